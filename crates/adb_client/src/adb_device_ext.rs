@@ -1,16 +1,163 @@
-use std::io::{Cursor, Read, Write};
+use std::collections::BTreeMap;
+use std::io::{self, Cursor, Read, Write};
+use std::ops::ControlFlow;
 use std::path::Path;
+use std::time::Duration;
 
 use image::{ImageBuffer, ImageFormat, Rgba};
 
-use crate::models::AdbStatResponse;
-use crate::{RebootType, Result};
+use crate::models::{
+    AdbStatResponse, BatteryStatus, DeviceInfo, RemoteEntry, ShellOutput, StorageVolume,
+};
+use crate::progress::{ProgressReader, ProgressWriter};
+use crate::shell_stream::{ChunkWriter, LineBuffer};
+use crate::utils::{
+    BugreportzLine, EXIT_STATUS_MARKER, parse_bugreportz_line, parse_getprop, shell_quote,
+    split_exit_status,
+};
+use crate::{
+    BackupOptions, InstallOptions, RebootType, RemoteEntryKind, RemoteWalk, Result, RustADBError,
+};
+
+/// Where [`ADBDeviceExt::screenrecord`] records to before pulling the video.
+const SCREENRECORD_PATH: &str = "/data/local/tmp/adb_client_screenrecord.mp4";
+/// Longest recording `screenrecord` accepts, in seconds.
+const SCREENRECORD_MAX_SECS: u64 = 180;
 
 /// Trait representing all features available on both [`crate::ADBServerDevice`] and [`crate::ADBUSBDevice`]
 pub trait ADBDeviceExt {
     /// Runs command in a shell on the device, and write its output and error streams into output.
     fn shell_command(&mut self, command: &[&str], output: &mut dyn Write) -> Result<()>;
 
+    /// Runs command in a shell on the device, handing its output to `on_chunk` as it arrives.
+    ///
+    /// More output is only requested from the device once `on_chunk` returns, so a slow
+    /// consumer holds the command back rather than letting output pile up in memory.
+    /// Returning [`ControlFlow::Break`] stops the command and returns `Ok(())`.
+    fn shell_command_stream(
+        &mut self,
+        command: &[&str],
+        on_chunk: &mut dyn FnMut(&[u8]) -> ControlFlow<()>,
+    ) -> Result<()> {
+        let mut writer = ChunkWriter::new(on_chunk);
+        match self.shell_command(command, &mut writer) {
+            Err(RustADBError::IOError(_)) if writer.stopped() => Ok(()),
+            result => result,
+        }
+    }
+
+    /// Same as [`ADBDeviceExt::shell_command_stream`], but output is handed to `on_line` one
+    /// line at a time, without its terminator. An unterminated last line is delivered when the
+    /// command ends.
+    fn shell_command_lines(
+        &mut self,
+        command: &[&str],
+        on_line: &mut dyn FnMut(&str) -> ControlFlow<()>,
+    ) -> Result<()> {
+        let mut lines = LineBuffer::default();
+        let mut stopped = false;
+        self.shell_command_stream(command, &mut |chunk| {
+            let flow = lines.push(chunk, &mut *on_line);
+            stopped = flow.is_break();
+            flow
+        })?;
+        if !stopped {
+            lines.finish(on_line);
+        }
+        Ok(())
+    }
+
+    /// Streams the device log to `on_line`, one entry per line in logcat's `threadtime`
+    /// format, until `on_line` returns [`ControlFlow::Break`].
+    ///
+    /// `filter_specs` are logcat filters such as `MediaProvider:V` or `*:S`; with none, every
+    /// entry at the default priority is shown. Logcat waits for new entries rather than ending,
+    /// so a break only takes effect once the next line arrives.
+    fn logcat(
+        &mut self,
+        filter_specs: &[&str],
+        on_line: &mut dyn FnMut(&str) -> ControlFlow<()>,
+    ) -> Result<()> {
+        // Specs like `*:S` must reach logcat without the shell globbing them.
+        let quoted: Vec<String> = filter_specs.iter().map(|spec| shell_quote(spec)).collect();
+        let mut command = vec!["exec", "logcat", "-v", "threadtime"];
+        command.extend(quoted.iter().map(String::as_str));
+        self.shell_command_lines(&command, on_line)
+    }
+
+    /// Reads the system property `name`, such as `ro.product.model`. Properties that aren't
+    /// set read as `None`, as do empty ones since `getprop` can't tell them apart.
+    fn get_prop(&mut self, name: &str) -> Result<Option<String>> {
+        let mut output = Vec::new();
+        self.shell_command(&["getprop", &shell_quote(name)], &mut output)?;
+        let value = String::from_utf8_lossy(&output);
+        let value = value.trim_end_matches(['\r', '\n']);
+        Ok((!value.is_empty()).then(|| value.to_string()))
+    }
+
+    /// Reads every system property, by name.
+    fn get_all_props(&mut self) -> Result<BTreeMap<String, String>> {
+        let mut output = Vec::new();
+        self.shell_command(&["getprop"], &mut output)?;
+        Ok(parse_getprop(&String::from_utf8_lossy(&output)))
+    }
+
+    /// Reads the serial, model, manufacturer, Android version, API level and ABIs from the
+    /// device's system properties, in one request.
+    fn device_info(&mut self) -> Result<DeviceInfo> {
+        Ok(DeviceInfo::from_props(&self.get_all_props()?))
+    }
+
+    /// Runs command in a shell on the device and collects its output, keeping standard error
+    /// apart and reporting the exit status when the device supports the shell v2 protocol.
+    ///
+    /// Other devices only give back the merged output, as with [`ADBDeviceExt::shell_command`].
+    fn shell_command_output(&mut self, command: &[&str]) -> Result<ShellOutput> {
+        let mut stdout = Vec::new();
+        self.shell_command(command, &mut stdout)?;
+        Ok(ShellOutput {
+            stdout,
+            ..ShellOutput::default()
+        })
+    }
+
+    /// Runs command in a shell on the device and returns what it printed, standard error
+    /// included. Fails with [`RustADBError::RemoteCommandFailed`] when it exits with a
+    /// non-zero status, which is echoed after the command so that it is known even on
+    /// devices without shell v2.
+    fn shell_command_checked(&mut self, command: &[&str]) -> Result<String> {
+        let status = format!("echo {EXIT_STATUS_MARKER}$?");
+        let mut checked = vec!["{"];
+        checked.extend_from_slice(command);
+        checked.extend([";", "}", "2>&1;", &status]);
+
+        let mut output = Vec::new();
+        self.shell_command(&checked, &mut output)?;
+        let output = String::from_utf8_lossy(&output);
+        match split_exit_status(&output) {
+            Some((printed, 0)) => Ok(printed.to_string()),
+            Some((printed, _)) => Err(RustADBError::RemoteCommandFailed(
+                command.join(" "),
+                printed.trim().to_string(),
+            )),
+            None => Err(RustADBError::RemoteCommandFailed(
+                command.join(" "),
+                "no exit status reported".into(),
+            )),
+        }
+    }
+
+    /// Calls the binder `service` (such as `package`, `activity` or `content`) with `args`,
+    /// like `cmd` on the device does.
+    ///
+    /// Devices advertising `abb` are reached through the Android Binder Bridge, without
+    /// starting a shell. Others run `cmd` through [`ADBDeviceExt::shell_command_output`].
+    fn binder_command_output(&mut self, service: &str, args: &[&str]) -> Result<ShellOutput> {
+        let mut command = vec!["cmd", service];
+        command.extend_from_slice(args);
+        self.shell_command_output(&command)
+    }
+
     /// Starts an interactive shell session on the device.
     /// Input data is read from reader and write to writer.
     fn shell(&mut self, reader: &mut dyn Read, writer: Box<dyn Write + Send>) -> Result<()>;
@@ -18,12 +165,102 @@ pub trait ADBDeviceExt {
     /// Display the stat information for a remote file
     fn stat(&mut self, remote_path: &str) -> Result<AdbStatResponse>;
 
+    /// List the directory at `remote_path` with the sync `LIST` request, leaving out `.` and
+    /// `..`. The [`RemoteEntry::path`] of each entry is its name within the directory.
+    fn list_dir(&mut self, remote_path: &str) -> Result<Vec<RemoteEntry>>;
+
+    /// Walk the tree below `remote_path` depth-first, yielding entries with paths relative to
+    /// it. See [`RemoteWalk`] for how unreadable directories are handled.
+    fn walk(&mut self, remote_path: &str) -> RemoteWalk<'_>
+    where
+        Self: Sized,
+    {
+        RemoteWalk::new(self, remote_path)
+    }
+
     /// Pull the remote file pointed to by `source` and write its contents into `output`
     fn pull(&mut self, source: &dyn AsRef<str>, output: &mut dyn Write) -> Result<()>;
 
+    /// Same as [`ADBDeviceExt::pull`], calling `on_progress` with the number of bytes written
+    /// to `output` so far as the file arrives.
+    fn pull_with_progress(
+        &mut self,
+        source: &dyn AsRef<str>,
+        output: &mut dyn Write,
+        on_progress: &mut dyn FnMut(u64),
+    ) -> Result<()> {
+        self.pull(source, &mut ProgressWriter::new(output, on_progress))
+    }
+
     /// Push `stream` to `path` on the device.
     fn push(&mut self, stream: &mut dyn Read, path: &dyn AsRef<str>) -> Result<()>;
 
+    /// Push `stream` to `path` on the device and set the remote file's modification time to
+    /// `mtime`, in seconds since the Unix epoch. A plain [`ADBDeviceExt::push`] sends no
+    /// timestamp.
+    ///
+    /// Devices unable to forward a timestamp fall back to a plain push.
+    fn push_with_mtime(
+        &mut self,
+        stream: &mut dyn Read,
+        path: &dyn AsRef<str>,
+        mtime: u32,
+    ) -> Result<()> {
+        let _ = mtime;
+        self.push(stream, path)
+    }
+
+    /// Same as [`ADBDeviceExt::push`], calling `on_progress` with the number of bytes of
+    /// `stream` sent so far after each chunk, for progress bars and throughput figures.
+    ///
+    /// Bytes are counted as they are handed to the transport, so the count may run a chunk
+    /// ahead of what the device received.
+    fn push_with_progress(
+        &mut self,
+        stream: &mut dyn Read,
+        path: &dyn AsRef<str>,
+        on_progress: &mut dyn FnMut(u64),
+    ) -> Result<()> {
+        self.push(&mut ProgressReader::new(stream, on_progress), path)
+    }
+
+    /// Deletes the file or symlink at `remote_path`. Fails with
+    /// [`RustADBError::RemoteCommandFailed`] carrying `rm`'s message when it is missing, is a
+    /// directory or can't be removed.
+    fn rm(&mut self, remote_path: &str) -> Result<()> {
+        self.shell_command_checked(&["rm", &shell_quote(remote_path)])
+            .map(drop)
+    }
+
+    /// Deletes the directory at `remote_path`, which has to be empty unless `recursive` is
+    /// set, in which case everything below it goes too. Fails with
+    /// [`RustADBError::RemoteCommandFailed`] when `remote_path` isn't a directory or
+    /// something in it can't be removed.
+    fn rm_dir(&mut self, remote_path: &str, recursive: bool) -> Result<()> {
+        if !recursive {
+            return self
+                .shell_command_checked(&["rmdir", &shell_quote(remote_path)])
+                .map(drop);
+        }
+        // `rm -r` would remove a file just as well.
+        if self.stat(remote_path)?.kind() != RemoteEntryKind::Directory {
+            return Err(RustADBError::RemoteCommandFailed(
+                format!("rm -r {remote_path}"),
+                format!("{remote_path}: Not a directory"),
+            ));
+        }
+        self.shell_command_checked(&["rm", "-r", &shell_quote(remote_path)])
+            .map(drop)
+    }
+
+    /// Moves or renames `from` to `to` on the device, replacing a file already at `to`. Fails
+    /// with [`RustADBError::RemoteCommandFailed`] carrying `mv`'s message, such as when
+    /// `from` is missing or the parent of `to` doesn't exist.
+    fn mv(&mut self, from: &str, to: &str) -> Result<()> {
+        self.shell_command_checked(&["mv", &shell_quote(from), &shell_quote(to)])
+            .map(drop)
+    }
+
     /// Reboot the device using given reboot type
     fn reboot(&mut self, reboot_type: RebootType) -> Result<()>;
 
@@ -41,8 +278,57 @@ pub trait ADBDeviceExt {
     /// Install an APK pointed to by `apk_path` on device.
     fn install(&mut self, apk_path: &dyn AsRef<Path>) -> Result<()>;
 
-    /// Uninstall the package `package` from device.
-    fn uninstall(&mut self, package: &str) -> Result<()>;
+    /// Install an APK of `size` bytes read from `apk`, passing `options` to the package
+    /// manager. Fails with the package manager's `Failure [REASON]` line when it rejects it.
+    fn install_from_reader(
+        &mut self,
+        apk: &mut dyn Read,
+        size: u64,
+        options: &InstallOptions,
+    ) -> Result<()>;
+
+    /// Install an app split across several APKs, such as a base APK and its configuration
+    /// splits, in one package manager session. The session is abandoned if any part fails.
+    fn install_multiple(&mut self, apk_paths: &[&Path], options: &InstallOptions) -> Result<()>;
+
+    /// Uninstall the package `package` from device, keeping its data and cache directories
+    /// when `keep_data` is set (`-k`). Fails with the package manager's `Failure [REASON]`
+    /// line, such as `Failure [DELETE_FAILED_INTERNAL_ERROR]` for an unknown package.
+    fn uninstall(&mut self, package: &str, keep_data: bool) -> Result<()>;
+
+    /// Restart adbd with root permissions, as `adb root` does, and connect again once it is
+    /// back. Does nothing when it already runs as root. Fails on production builds, where
+    /// adbd can't run as root.
+    fn root(&mut self) -> Result<()>;
+
+    /// Restart adbd without root permissions, as `adb unroot` does, and connect again once it
+    /// is back. Does nothing when it doesn't run as root.
+    fn unroot(&mut self) -> Result<()>;
+
+    /// Remount the system partitions read-write, as `adb remount` does. Needs adbd running
+    /// as root, see [`ADBDeviceExt::root`]. Returns what `remount` printed, which may ask for
+    /// a reboot before the change takes effect, e.g. after disabling verity.
+    fn remount(&mut self) -> Result<String>;
+
+    /// Send the OTA package at `package_path` to a device in recovery waiting for
+    /// `adb sideload`, as after [`RebootType::Sideload`]. The device requests the package block
+    /// by block, and starts installing it once it has all of them.
+    fn sideload(&mut self, package_path: &dyn AsRef<Path>) -> Result<()>;
+
+    /// Back up the data of `packages`, or of every app with [`BackupOptions::all`], writing
+    /// the `adb backup` archive to `output`. Blocks until the user confirms on the device,
+    /// where they may also set a password encrypting the archive. Apps that opt out of backup
+    /// are skipped, and recent Android versions leave out most apps' data.
+    fn backup(
+        &mut self,
+        packages: &[&str],
+        options: &BackupOptions,
+        output: &mut dyn Write,
+    ) -> Result<()>;
+
+    /// Restore an archive made by [`ADBDeviceExt::backup`], read from `input`. Blocks until
+    /// the user confirms on the device and the restore is done.
+    fn restore(&mut self, input: &mut dyn Read) -> Result<()>;
 
     /// Inner method requesting framebuffer from an Android device
     fn framebuffer_inner(&mut self) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>>;
@@ -65,6 +351,121 @@ pub trait ADBDeviceExt {
         Ok(vec.into_inner())
     }
 
+    /// Captures the screen as a PNG image.
+    ///
+    /// Devices reached directly run `screencap -p`, which is faster than encoding the raw
+    /// framebuffer here and handles rotated and multi-display screens. Others, and devices
+    /// where `screencap` gives no image, fall back to [`ADBDeviceExt::framebuffer_bytes`].
+    fn screencap(&mut self) -> Result<Vec<u8>> {
+        self.framebuffer_bytes()
+    }
+
+    /// Battery charge, charging state and temperature, as `dumpsys battery` reports them.
+    fn battery_status(&mut self) -> Result<BatteryStatus> {
+        let output = self.shell_command_checked(&["dumpsys", "battery"])?;
+        Ok(BatteryStatus::parse_dumpsys(&output))
+    }
+
+    /// Space on each mounted filesystem of the device that has a size, as `df -k` reports
+    /// it. Filesystems the shell user can't inspect are left out.
+    fn storage_info(&mut self) -> Result<Vec<StorageVolume>> {
+        let output = self.shell_command_output(&["df", "-k"])?;
+        let volumes = StorageVolume::parse_df(&output.stdout_lossy());
+        // `df` fails when some mounts are unreadable, while still listing the others.
+        if volumes.is_empty() && output.failed() {
+            return Err(RustADBError::RemoteCommandFailed(
+                "df -k".into(),
+                output.stderr_lossy().trim().to_string(),
+            ));
+        }
+        Ok(volumes)
+    }
+
+    /// Filesystem holding `remote_path`, following symlinks such as `/sdcard`, or `None`
+    /// when `df` doesn't report it, e.g. because the path does not exist.
+    fn storage_volume(&mut self, remote_path: &str) -> Result<Option<StorageVolume>> {
+        let output = self.shell_command_output(&["df", "-k", &shell_quote(remote_path)])?;
+        Ok(StorageVolume::parse_df(&output.stdout_lossy())
+            .into_iter()
+            .next())
+    }
+
+    /// Records the screen for `duration` and writes the resulting MP4 to `output`.
+    ///
+    /// `screenrecord` writes to a temporary file on the device, pulled once it ends and then
+    /// removed. Android caps recordings at three minutes, so longer durations are shortened.
+    fn screenrecord(&mut self, duration: Duration, output: &mut dyn Write) -> Result<()> {
+        let seconds = duration
+            .as_secs()
+            .clamp(1, SCREENRECORD_MAX_SECS)
+            .to_string();
+        let recorded = self.shell_command_output(&[
+            "screenrecord",
+            "--time-limit",
+            &seconds,
+            SCREENRECORD_PATH,
+        ])?;
+        if recorded.failed() {
+            return Err(RustADBError::ADBRequestFailed(format!(
+                "screenrecord failed: {}",
+                recorded.stderr_lossy().trim()
+            )));
+        }
+        let pulled = self.pull(&SCREENRECORD_PATH, output);
+        if let Err(error) = self.shell_command(&["rm", "-f", SCREENRECORD_PATH], &mut io::sink()) {
+            log::warn!("could not remove {SCREENRECORD_PATH}: {error}");
+        }
+        pulled
+    }
+
+    /// Captures a bugreport with `bugreportz` and writes the zip to `output`. Takes a few
+    /// minutes; the zip is removed from the device once pulled.
+    fn bugreport(&mut self, output: &mut dyn Write) -> Result<()> {
+        self.bugreport_with_progress(output, &mut |_, _| {})
+    }
+
+    /// Same as [`ADBDeviceExt::bugreport`], calling `on_progress` with how much of the report
+    /// is done out of its estimated total, as `bugreportz -p` reports it. The total may grow
+    /// as the report runs.
+    fn bugreport_with_progress(
+        &mut self,
+        output: &mut dyn Write,
+        on_progress: &mut dyn FnMut(u64, u64),
+    ) -> Result<()> {
+        let mut zip_path = None;
+        let mut failure = None;
+        self.shell_command_lines(&["bugreportz", "-p"], &mut |line| {
+            match parse_bugreportz_line(line) {
+                Some(BugreportzLine::Progress(done, total)) => on_progress(done, total),
+                Some(BugreportzLine::Ok(path)) => zip_path = Some(path.to_string()),
+                Some(BugreportzLine::Fail(reason)) => failure = Some(reason.to_string()),
+                None => log::debug!("bugreportz: {line}"),
+            }
+            ControlFlow::Continue(())
+        })?;
+        let zip_path = match (zip_path, failure) {
+            (Some(zip_path), _) => zip_path,
+            (None, Some(reason)) => {
+                return Err(RustADBError::ADBRequestFailed(format!(
+                    "bugreportz failed: {reason}"
+                )));
+            }
+            // Devices before Android 7 lack `bugreportz`, and before 8 its `-p` flag.
+            (None, None) => {
+                return Err(RustADBError::ADBRequestFailed(
+                    "bugreportz did not report a zip; the device may be too old".into(),
+                ));
+            }
+        };
+        let pulled = self.pull(&zip_path, output);
+        if let Err(error) =
+            self.shell_command(&["rm", "-f", &shell_quote(&zip_path)], &mut io::sink())
+        {
+            log::warn!("could not remove {zip_path}: {error}");
+        }
+        pulled
+    }
+
     /// Return a boxed instance representing this trait
     fn boxed(self) -> Box<dyn ADBDeviceExt>
     where
@@ -0,0 +1,139 @@
+use std::future::Future;
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_util::io::SyncIoBridge;
+
+use crate::device::ADBTransportMessage;
+use crate::{ADBDeviceExt, ADBMessageTransport, Result, RustADBError, ShellOutput};
+
+/// Runs `operation` on tokio's blocking pool: libusb and the sockets below every transport
+/// block until a transfer completes.
+async fn blocking<R: Send + 'static>(
+    operation: impl FnOnce() -> Result<R> + Send + 'static,
+) -> Result<R> {
+    tokio::task::spawn_blocking(operation)
+        .await
+        .map_err(|e| RustADBError::IOError(io::Error::other(e)))?
+}
+
+/// [`ADBMessageTransport`] usable from async code, each read or write running on tokio's
+/// blocking pool with its own handle on the transport.
+pub trait AsyncADBMessageTransport: Send {
+    /// Read a message using given timeout on the underlying transport
+    fn read_message_async(
+        &mut self,
+        read_timeout: Duration,
+    ) -> impl Future<Output = Result<ADBTransportMessage>> + Send;
+
+    /// Write a message using given timeout on the underlying transport
+    fn write_message_async(
+        &mut self,
+        message: ADBTransportMessage,
+        write_timeout: Duration,
+    ) -> impl Future<Output = Result<()>> + Send;
+}
+
+impl<T: ADBMessageTransport> AsyncADBMessageTransport for T {
+    async fn read_message_async(&mut self, read_timeout: Duration) -> Result<ADBTransportMessage> {
+        let mut transport = self.clone();
+        blocking(move || transport.read_message_with_timeout(read_timeout)).await
+    }
+
+    async fn write_message_async(
+        &mut self,
+        message: ADBTransportMessage,
+        write_timeout: Duration,
+    ) -> Result<()> {
+        let mut transport = self.clone();
+        blocking(move || transport.write_message_with_timeout(message, write_timeout)).await
+    }
+}
+
+/// Async handle on any [`ADBDeviceExt`], such as an [`crate::ADBUSBDevice`], so that one
+/// runtime can drive several devices without tying up its worker threads.
+///
+/// Operations run on tokio's blocking pool and are serialized, one at a time on the device,
+/// with no guarantee about which of several waiting ones runs first. Clones share the
+/// device.
+#[derive(Debug)]
+pub struct AsyncADBDevice<D> {
+    device: Arc<Mutex<D>>,
+}
+
+impl<D> Clone for AsyncADBDevice<D> {
+    fn clone(&self) -> Self {
+        Self {
+            device: self.device.clone(),
+        }
+    }
+}
+
+impl<D: ADBDeviceExt + Send + 'static> AsyncADBDevice<D> {
+    /// Wraps an already connected device
+    pub fn new(device: D) -> Self {
+        Self {
+            device: Arc::new(Mutex::new(device)),
+        }
+    }
+
+    /// Runs `operation` on the device from the blocking pool, for anything without an async
+    /// counterpart here.
+    pub async fn run<R, F>(&self, operation: F) -> Result<R>
+    where
+        R: Send + 'static,
+        F: FnOnce(&mut D) -> Result<R> + Send + 'static,
+    {
+        let device = self.device.clone();
+        blocking(move || operation(&mut device.lock().unwrap_or_else(PoisonError::into_inner)))
+            .await
+    }
+
+    /// Async [`ADBDeviceExt::shell_command`], writing the command's output to `output`.
+    pub async fn shell_command<W>(&self, command: Vec<String>, output: W) -> Result<()>
+    where
+        W: AsyncWrite + Unpin + Send + 'static,
+    {
+        let mut output = SyncIoBridge::new(output);
+        self.run(move |device| {
+            let command: Vec<&str> = command.iter().map(String::as_str).collect();
+            device.shell_command(&command, &mut output)?;
+            Ok(output.flush()?)
+        })
+        .await
+    }
+
+    /// Async [`ADBDeviceExt::shell_command_output`].
+    pub async fn shell_command_output(&self, command: Vec<String>) -> Result<ShellOutput> {
+        self.run(move |device| {
+            let command: Vec<&str> = command.iter().map(String::as_str).collect();
+            device.shell_command_output(&command)
+        })
+        .await
+    }
+
+    /// Async [`ADBDeviceExt::push`], uploading what `input` yields to `remote_path`.
+    pub async fn push<R>(&self, input: R, remote_path: String) -> Result<()>
+    where
+        R: AsyncRead + Unpin + Send + 'static,
+    {
+        let mut input = SyncIoBridge::new(input);
+        self.run(move |device| device.push(&mut input, &remote_path))
+            .await
+    }
+
+    /// Async [`ADBDeviceExt::pull`], downloading `remote_path` into `output`.
+    pub async fn pull<W>(&self, remote_path: String, output: W) -> Result<()>
+    where
+        W: AsyncWrite + Unpin + Send + 'static,
+    {
+        let mut output = SyncIoBridge::new(output);
+        self.run(move |device| {
+            device.pull(&remote_path, &mut output)?;
+            Ok(output.flush()?)
+        })
+        .await
+    }
+}
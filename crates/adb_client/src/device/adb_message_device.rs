@@ -1,13 +1,42 @@
+use super::sync_compression::SyncCompression;
+use super::sync_data_reader::SyncDataReader;
 use super::{ADBRsaKey, ADBTransportMessage, MessageCommand, models::MessageSubcommand};
-use crate::device::adb_transport_message::{AUTH_RSAPUBLICKEY, AUTH_SIGNATURE, AUTH_TOKEN};
-use crate::{ADBMessageTransport, AdbStatResponse, Result, RustADBError, constants::BUFFER_SIZE};
+use crate::device::adb_transport_message::{
+    AUTH_RSAPUBLICKEY, AUTH_SIGNATURE, AUTH_TOKEN, INITIAL_DELAYED_ACK_BYTES,
+};
+use crate::{
+    ADBMessageTransport, AdbStatResponse, ConnectionPhase, DeviceBanner, Result, RustADBError,
+    TransportTimeouts, constants::BUFFER_SIZE, models::STAT_V2_LEN,
+};
 use bincode::config::{Configuration, Fixint, LittleEndian, NoLimit};
-use byteorder::ReadBytesExt;
 use rand::Rng;
 use serde::Serialize;
 use serde::de::DeserializeOwned;
-use std::io::{Cursor, Read, Seek};
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+/// Banner of the `CNXN` we connect with, listing the features we support.
+pub(crate) const HOST_BANNER: &str =
+    concat!("host::", env!("CARGO_PKG_NAME"), ";features=delayed_ack\0");
+
+/// Largest message payload we accept, announced in our `CNXN`.
+pub(crate) const HOST_MAX_PAYLOAD: u32 = 1024 * 1024;
+
+/// Largest message payload of devices from before protocol version 2, and the least any
+/// device accepts.
+const LEGACY_MAX_PAYLOAD: u32 = 4096;
+
+/// Smallest chunk accepted for sync `DATA` packets.
+const MIN_SYNC_CHUNK_SIZE: usize = 1024;
+
+/// Largest chunk adbd accepts in one sync `DATA` packet, its `SYNC_DATA_MAX`.
+const SYNC_DATA_MAX: usize = 64 * 1024;
+
+/// Length of a sync packet header: its id and a length or argument.
+const SYNC_HEADER_LEN: usize = 8;
+
+/// How long [`ADBMessageDevice::abort_transaction`] waits for the device to confirm the
+/// close, once it stopped sending.
+const ABORT_DRAIN_TIMEOUT: Duration = Duration::from_secs(1);
 
 const BINCODE_CONFIG: Configuration<LittleEndian, Fixint, NoLimit> = bincode::config::legacy();
 
@@ -29,6 +58,17 @@ pub struct ADBMessageDevice<T: ADBMessageTransport> {
     transport: T,
     local_id: Option<u32>,
     remote_id: Option<u32>,
+    /// Largest chunk per sync `DATA` packet set by the caller, if any.
+    sync_chunk_size: Option<usize>,
+    /// What the device announced in its `CNXN`.
+    banner: DeviceBanner,
+    sync_compression_enabled: bool,
+    timeouts: TransportTimeouts,
+    /// Whether both sides support `delayed_ack`, so that `OKAY`s carry byte counts.
+    delayed_ack: bool,
+    /// Bytes the device can still take on the current stream before we wait for its
+    /// acknowledgements, with `delayed_ack`. Goes negative when a message overshoots.
+    send_window: Option<i64>,
 }
 
 impl<T: ADBMessageTransport> ADBMessageDevice<T> {
@@ -38,9 +78,95 @@ impl<T: ADBMessageTransport> ADBMessageDevice<T> {
             transport,
             local_id: None,
             remote_id: None,
+            sync_chunk_size: None,
+            banner: DeviceBanner::default(),
+            sync_compression_enabled: true,
+            timeouts: TransportTimeouts::default(),
+            delayed_ack: false,
+            send_window: None,
+        }
+    }
+
+    /// Records what the device announced in its `CNXN` message.
+    pub(crate) fn set_banner(&mut self, cnxn: ADBTransportMessage) {
+        let max_payload = cnxn.header().arg1();
+        self.banner = DeviceBanner::parse(&cnxn.into_payload(), max_payload);
+        self.delayed_ack = self.banner.has_feature("delayed_ack");
+        log::debug!("device banner: {:?}", self.banner);
+    }
+
+    pub(crate) fn banner(&self) -> &DeviceBanner {
+        &self.banner
+    }
+
+    /// Whether the device advertised `feature` when connecting.
+    pub(crate) fn has_feature(&self, feature: &str) -> bool {
+        self.banner.has_feature(feature)
+    }
+
+    /// Whether `OKAY`s on this connection carry the number of bytes they acknowledge.
+    pub(crate) fn delayed_ack(&self) -> bool {
+        self.delayed_ack
+    }
+
+    /// `OKAY` acknowledging `received` from the device on the stream `local_id`.
+    pub(crate) fn ack(
+        &self,
+        local_id: u32,
+        remote_id: u32,
+        received: &ADBTransportMessage,
+    ) -> ADBTransportMessage {
+        let acked = self.delayed_ack.then(|| received.header().data_length());
+        ADBTransportMessage::okay(local_id, remote_id, acked)
+    }
+
+    /// Bytes the device may send on a stream we open or accept before our first
+    /// acknowledgement, with `delayed_ack`.
+    pub(crate) fn receive_window(&self) -> Option<u32> {
+        self.delayed_ack.then_some(INITIAL_DELAYED_ACK_BYTES)
+    }
+
+    /// Makes room on the current stream for the bytes the device acknowledged in `okay`.
+    fn credit(&mut self, okay: &ADBTransportMessage) {
+        if let (Some(window), Some(acked)) = (&mut self.send_window, okay.acked_bytes()) {
+            *window += i64::from(acked);
         }
     }
 
+    /// Sets the largest payload of a sync `DATA` packet sent while pushing.
+    pub(crate) fn set_sync_chunk_size(&mut self, size: usize) {
+        self.sync_chunk_size = Some(size.clamp(MIN_SYNC_CHUNK_SIZE, BUFFER_SIZE));
+    }
+
+    /// Largest payload of a message sent to the device: what it announced in its `CNXN`,
+    /// within what we announced in ours.
+    pub(crate) fn max_payload(&self) -> usize {
+        let max_payload = self
+            .banner
+            .max_payload
+            .clamp(LEGACY_MAX_PAYLOAD, HOST_MAX_PAYLOAD);
+        max_payload as usize
+    }
+
+    pub(crate) fn timeouts(&self) -> TransportTimeouts {
+        self.timeouts
+    }
+
+    pub(crate) fn set_timeouts(&mut self, timeouts: TransportTimeouts) {
+        self.timeouts = timeouts;
+    }
+
+    /// Reads the next message, waiting up to the configured read timeout.
+    pub(crate) fn read_message(&mut self) -> Result<ADBTransportMessage> {
+        self.transport.read_message_with_timeout(self.timeouts.read)
+    }
+
+    /// Writes `message`, waiting up to the configured write timeout.
+    pub(crate) fn write_message(&mut self, message: ADBTransportMessage) -> Result<()> {
+        self.transport
+            .write_message_with_timeout(message, self.timeouts.write)
+    }
+
     pub(crate) fn get_transport(&mut self) -> &T {
         &self.transport
     }
@@ -49,48 +175,65 @@ impl<T: ADBMessageTransport> ADBMessageDevice<T> {
         &mut self.transport
     }
 
+    /// Answers the device's challenges by signing with `private_key`, then each of
+    /// `fallback_keys` in turn, and sends the public half of `private_key` for the user to
+    /// allow once the device rejected them all.
     pub(crate) fn auth_handshake(
         &mut self,
         message: ADBTransportMessage,
         private_key: &ADBRsaKey,
+        fallback_keys: &[ADBRsaKey],
+        on_phase: &mut dyn FnMut(ConnectionPhase),
     ) -> Result<()> {
         let mut next_message = Some(message);
+        let mut signing_keys = std::iter::once(private_key).chain(fallback_keys);
+        let mut read_timeout = self.timeouts.auth;
+
+        on_phase(ConnectionPhase::Authenticating);
 
         loop {
             let current_message = match next_message.take() {
                 Some(message) => message,
                 None => self
                     .get_transport_mut()
-                    .read_message_with_timeout(Duration::from_secs(10))?,
+                    .read_message_with_timeout(read_timeout)?,
             };
 
             match current_message.header().command() {
                 MessageCommand::Cnxn => {
                     log::info!(
                         "Authentication OK, device info {}",
-                        String::from_utf8(current_message.into_payload())?
+                        String::from_utf8_lossy(current_message.payload())
                     );
+                    self.set_banner(current_message);
+                    on_phase(ConnectionPhase::Connected);
                     return Ok(());
                 }
                 MessageCommand::Auth => match current_message.header().arg0() {
-                    AUTH_TOKEN => {
-                        log::debug!("Authentication challenge received (token)");
-                        let sign = private_key.sign(current_message.into_payload())?;
-                        let reply =
-                            ADBTransportMessage::new(MessageCommand::Auth, AUTH_SIGNATURE, 0, &sign);
-                        self.get_transport_mut().write_message(reply)?;
-                    }
+                    AUTH_TOKEN => match signing_keys.next() {
+                        Some(signing_key) => {
+                            log::debug!("Authentication challenge received (token)");
+                            let sign = signing_key.sign(current_message.into_payload())?;
+                            let reply = ADBTransportMessage::new(
+                                MessageCommand::Auth,
+                                AUTH_SIGNATURE,
+                                0,
+                                &sign,
+                            );
+                            self.write_message(reply)?;
+                        }
+                        None => {
+                            log::debug!("Signatures rejected, sending RSA public key");
+                            self.send_public_key(private_key)?;
+                            read_timeout = self.timeouts.user_approval;
+                            on_phase(ConnectionPhase::AwaitingUserApproval);
+                        }
+                    },
                     AUTH_RSAPUBLICKEY => {
                         log::debug!("Device requested RSA public key, sending it");
-                        let mut pubkey = private_key.android_pubkey_encode()?.into_bytes();
-                        pubkey.push(b'\0');
-                        let reply = ADBTransportMessage::new(
-                            MessageCommand::Auth,
-                            AUTH_RSAPUBLICKEY,
-                            0,
-                            &pubkey,
-                        );
-                        self.get_transport_mut().write_message(reply)?;
+                        self.send_public_key(private_key)?;
+                        read_timeout = self.timeouts.user_approval;
+                        on_phase(ConnectionPhase::AwaitingUserApproval);
                     }
                     other => {
                         return Err(RustADBError::ADBRequestFailed(format!(
@@ -119,18 +262,25 @@ impl<T: ADBMessageTransport> ADBMessageDevice<T> {
         }
     }
 
+    fn send_public_key(&mut self, private_key: &ADBRsaKey) -> Result<()> {
+        let mut pubkey = private_key.android_pubkey_encode()?.into_bytes();
+        pubkey.push(b'\0');
+        let reply = ADBTransportMessage::new(MessageCommand::Auth, AUTH_RSAPUBLICKEY, 0, &pubkey);
+        self.write_message(reply)
+    }
+
     /// Receive a message and acknowledge it by replying with an `OKAY` command
     pub(crate) fn recv_and_reply_okay(&mut self) -> Result<ADBTransportMessage> {
-        let message = self.transport.read_message()?;
+        let message = self.read_message()?;
         match message.header().command() {
             MessageCommand::Write | MessageCommand::Clse => {
-                self.transport.write_message(ADBTransportMessage::new(
-                    MessageCommand::Okay,
+                self.write_message(self.ack(
                     self.get_local_id()?,
                     self.get_remote_id()?,
-                    &[],
+                    &message,
                 ))?;
             }
+            MessageCommand::Okay => self.credit(&message),
             _ => {}
         }
         Ok(message)
@@ -141,23 +291,21 @@ impl<T: ADBMessageTransport> ADBMessageDevice<T> {
         &mut self,
         message: ADBTransportMessage,
     ) -> Result<ADBTransportMessage> {
-        self.transport.write_message(message)?;
+        self.write_message(message)?;
 
         loop {
-            let response = self.transport.read_message()?;
+            let response = self.read_message()?;
             match response.header().command() {
                 MessageCommand::Okay => {
+                    self.credit(&response);
                     return Ok(response);
                 }
                 MessageCommand::Write => {
-                    log::debug!(
-                        "ignoring unexpected WRTE while waiting for OKAY; acknowledging"
-                    );
-                    self.transport.write_message(ADBTransportMessage::new(
-                        MessageCommand::Okay,
+                    log::debug!("ignoring unexpected WRTE while waiting for OKAY; acknowledging");
+                    self.write_message(self.ack(
                         self.get_local_id()?,
                         self.get_remote_id()?,
-                        &[],
+                        &response,
                     ))?;
                 }
                 MessageCommand::Clse => {
@@ -173,113 +321,151 @@ impl<T: ADBMessageTransport> ADBMessageDevice<T> {
         }
     }
 
+    /// Sends a `WRTE` on the current stream. Without `delayed_ack`, waits for the device to
+    /// acknowledge it; with it, only waits when the device has no room left for more.
+    pub(crate) fn send_data(&mut self, message: ADBTransportMessage) -> Result<()> {
+        if self.send_window.is_none() {
+            self.send_and_expect_okay(message)?;
+            return Ok(());
+        }
+
+        while self.send_window.is_some_and(|window| window <= 0) {
+            let response = self.read_message()?;
+            match response.header().command() {
+                MessageCommand::Okay => self.credit(&response),
+                MessageCommand::Write => {
+                    log::debug!("ignoring unexpected WRTE while sending; acknowledging");
+                    self.write_message(self.ack(
+                        self.get_local_id()?,
+                        self.get_remote_id()?,
+                        &response,
+                    ))?;
+                }
+                MessageCommand::Clse => {
+                    return Err(RustADBError::ADBRequestFailed(
+                        "stream closed by the device".into(),
+                    ));
+                }
+                other => {
+                    return Err(RustADBError::WrongResponseReceived(
+                        other.to_string(),
+                        MessageCommand::Okay.to_string(),
+                    ));
+                }
+            }
+        }
+
+        let sent = i64::from(message.header().data_length());
+        self.write_message(message)?;
+        if let Some(window) = &mut self.send_window {
+            *window -= sent;
+        }
+        Ok(())
+    }
+
+    /// Writes the received file to `output`, decompressing it when the request
+    /// was a `RCV2` with `compression`.
     pub(crate) fn recv_file<W: std::io::Write>(
         &mut self,
         mut output: W,
+        compression: Option<SyncCompression>,
     ) -> std::result::Result<(), RustADBError> {
-        let mut len: Option<u64> = None;
-        loop {
-            let payload = self.recv_and_reply_okay()?.into_payload();
-            let mut rdr = Cursor::new(&payload);
-            while rdr.position() != payload.len() as u64 {
-                match len.take() {
-                    Some(0) | None => {
-                        rdr.seek_relative(4)?;
-                        len.replace(u64::from(rdr.read_u32::<byteorder::LittleEndian>()?));
-                    }
-                    Some(length) => {
-                        let remaining_bytes = payload.len() as u64 - rdr.position();
-                        if length < remaining_bytes {
-                            std::io::copy(&mut rdr.by_ref().take(length), &mut output)?;
-                        } else {
-                            std::io::copy(&mut rdr.take(remaining_bytes), &mut output)?;
-                            len.replace(length - remaining_bytes);
-                            // this payload is now exhausted
-                            break;
-                        }
-                    }
-                }
-            }
-            if Cursor::new(&payload[(payload.len() - 8)..(payload.len() - 4)])
-                .read_u32::<byteorder::LittleEndian>()?
-                == MessageSubcommand::Done as u32
-            {
-                break;
-            }
-        }
+        let mut data = SyncDataReader::new(self);
+        let copied = match compression {
+            Some(compression) => compression
+                .decoder(&mut data)
+                .and_then(|mut decoder| std::io::copy(&mut decoder, &mut output)),
+            None => std::io::copy(&mut data, &mut output),
+        };
+        // A decoder may stop at the end of its frame, before the closing `DONE`.
+        copied
+            .and_then(|_| std::io::copy(&mut data, &mut std::io::sink()))
+            .map_err(|error| match error.downcast::<RustADBError>() {
+                Ok(error) => error,
+                Err(error) => RustADBError::IOError(error),
+            })?;
         Ok(())
     }
 
+    /// Compression to use for the next push or pull, if the device supports any.
+    pub(crate) fn sync_compression(&self) -> Option<SyncCompression> {
+        if !self.sync_compression_enabled {
+            return None;
+        }
+        SyncCompression::negotiate(|feature| self.has_feature(feature))
+    }
+
+    /// Allows or forbids compressing file contents in pushes and pulls.
+    pub(crate) fn set_sync_compression(&mut self, enabled: bool) {
+        self.sync_compression_enabled = enabled;
+    }
+
+    /// Sends the contents of `reader` as sync `DATA` packets, then `DONE`. Packets are a
+    /// byte stream to adbd, so several go in each `WRTE`, up to the largest payload the
+    /// device accepts or a single chunk once a chunk size was set.
     pub(crate) fn push_file<R: std::io::Read>(
         &mut self,
         local_id: u32,
         remote_id: u32,
         mut reader: R,
+        mtime: u32,
     ) -> std::result::Result<(), RustADBError> {
-        let mut buffer = vec![0; BUFFER_SIZE].into_boxed_slice();
-        let amount_read = reader.read(&mut buffer)?;
-        let subcommand_data = MessageSubcommand::Data.with_arg(u32::try_from(amount_read)?);
+        let write_size = match self.sync_chunk_size {
+            Some(chunk_size) => (chunk_size + SYNC_HEADER_LEN).min(self.max_payload()),
+            None => self.max_payload(),
+        };
+        let chunk_size = (write_size - SYNC_HEADER_LEN).min(SYNC_DATA_MAX);
+        let mut buffer = vec![0; chunk_size].into_boxed_slice();
+        let mut serialized_message = Vec::with_capacity(write_size);
 
-        let mut serialized_message = bincode_serialize_to_vec(&subcommand_data)?;
-        serialized_message.append(&mut buffer[..amount_read].to_vec());
+        loop {
+            let size = read_full(&mut reader, &mut buffer)?;
+            if size > 0 {
+                let subcommand_data = MessageSubcommand::Data.with_arg(u32::try_from(size)?);
+                serialized_message.append(&mut bincode_serialize_to_vec(&subcommand_data)?);
+                serialized_message.extend_from_slice(&buffer[..size]);
+            }
 
+            let end_of_file = size < chunk_size;
+            if !serialized_message.is_empty()
+                && (end_of_file
+                    || serialized_message.len() + SYNC_HEADER_LEN + chunk_size > write_size)
+            {
+                let message = ADBTransportMessage::new(
+                    MessageCommand::Write,
+                    local_id,
+                    remote_id,
+                    &serialized_message,
+                );
+                self.send_data(message)?;
+                serialized_message.clear();
+            }
+            if end_of_file {
+                break;
+            }
+        }
+
+        let subcommand_data = MessageSubcommand::Done.with_arg(mtime);
         let message = ADBTransportMessage::new(
             MessageCommand::Write,
             local_id,
             remote_id,
-            &serialized_message,
+            &bincode_serialize_to_vec(&subcommand_data)?,
         );
+        self.send_data(message)?;
 
-        self.send_and_expect_okay(message)?;
-
+        // Command should end with a Write => Okay, but some devices shortcut by closing.
+        // With `delayed_ack`, acknowledgements of our last `DATA` come first.
         loop {
-            let mut buffer = vec![0; BUFFER_SIZE].into_boxed_slice();
-
-            match reader.read(&mut buffer) {
-                Ok(0) => {
-                    // Currently file mtime is not forwarded
-                    let subcommand_data = MessageSubcommand::Done.with_arg(0);
-
-                    let serialized_message = bincode_serialize_to_vec(&subcommand_data)?;
-                    let message = ADBTransportMessage::new(
-                        MessageCommand::Write,
-                        local_id,
-                        remote_id,
-                        &serialized_message,
-                    );
-
-                    self.send_and_expect_okay(message)?;
-
-                    // Command should end with a Write => Okay, but some devices shortcut by closing.
-                    let received = self.recv_and_reply_okay()?;
-                    match received.header().command() {
-                        MessageCommand::Write => return Ok(()),
-                        MessageCommand::Clse => return Ok(()),
-                        MessageCommand::Okay => continue,
-                        c => {
-                            return Err(RustADBError::ADBRequestFailed(format!(
-                                "Wrong command received {c}"
-                            )));
-                        }
-                    }
-                }
-                Ok(size) => {
-                    let subcommand_data = MessageSubcommand::Data.with_arg(u32::try_from(size)?);
-
-                    let mut serialized_message = bincode_serialize_to_vec(&subcommand_data)?;
-                    serialized_message.append(&mut buffer[..size].to_vec());
-
-                    let message = ADBTransportMessage::new(
-                        MessageCommand::Write,
-                        local_id,
-                        remote_id,
-                        &serialized_message,
-                    );
-
-                    self.send_and_expect_okay(message)?;
-                }
-                Err(e) => {
-                    return Err(RustADBError::IOError(e));
+            let received = self.recv_and_reply_okay()?;
+            match received.header().command() {
+                MessageCommand::Write => return Ok(()),
+                MessageCommand::Clse => return Ok(()),
+                MessageCommand::Okay => {}
+                c => {
+                    return Err(RustADBError::ADBRequestFailed(format!(
+                        "Wrong command received {c}"
+                    )));
                 }
             }
         }
@@ -290,8 +476,38 @@ impl<T: ADBMessageTransport> ADBMessageDevice<T> {
         Ok(())
     }
 
+    /// Runs `transaction` on a new sync stream, then ends it with `QUIT`. A transaction that
+    /// fails midway, e.g. because its reader or writer cancelled it, is aborted instead, so
+    /// the connection stays usable for the next one.
+    pub(crate) fn sync_transaction<R>(
+        &mut self,
+        transaction: impl FnOnce(&mut Self) -> Result<R>,
+    ) -> Result<R> {
+        self.begin_synchronization()?;
+        match transaction(self) {
+            Ok(value) => {
+                self.end_transaction()?;
+                Ok(value)
+            }
+            Err(error) => {
+                if let Err(abort_error) = self.abort_transaction() {
+                    log::warn!("could not abort sync transaction: {abort_error}");
+                }
+                Err(error)
+            }
+        }
+    }
+
+    /// Stats `remote_path` without following a final symlink, using `LST2` for
+    /// 64-bit sizes when the device supports `stat_v2`.
     pub(crate) fn stat_with_explicit_ids(&mut self, remote_path: &str) -> Result<AdbStatResponse> {
-        let stat_buffer = MessageSubcommand::Stat.with_arg(u32::try_from(remote_path.len())?);
+        let v2 = self.has_feature("stat_v2");
+        let subcommand = if v2 {
+            MessageSubcommand::Lst2
+        } else {
+            MessageSubcommand::Stat
+        };
+        let stat_buffer = subcommand.with_arg(u32::try_from(remote_path.len())?);
         let message = ADBTransportMessage::new(
             MessageCommand::Write,
             self.get_local_id()?,
@@ -305,11 +521,56 @@ impl<T: ADBMessageTransport> ADBMessageDevice<T> {
             self.get_remote_id()?,
             remote_path.as_bytes(),
         ))?;
-        let response = self.recv_and_reply_okay()?;
-        // Skip first 4 bytes as this is the literal "STAT".
+        let response = self.recv_and_reply_okay()?.into_payload();
+        // Skip first 4 bytes as this is the literal "STAT" or "LST2".
         // Interesting part starts right after
+        let fields = response.get(4..).unwrap_or_default();
+        if v2 {
+            let fields: [u8; STAT_V2_LEN] = fields
+                .get(..STAT_V2_LEN)
+                .and_then(|fields| fields.try_into().ok())
+                .ok_or(RustADBError::ConversionError)?;
+            Ok(fields.into())
+        } else {
+            let fields: [u8; 12] = fields
+                .get(..12)
+                .and_then(|fields| fields.try_into().ok())
+                .ok_or(RustADBError::ConversionError)?;
+            Ok(fields.into())
+        }
+    }
+
+    /// Abandons the current sync stream midway: asks the service to quit, closes the stream,
+    /// then drops whatever the device still sends on it until it confirms the close. Without
+    /// this, leftover `WRTE`s of a cancelled pull would be taken as replies to the next
+    /// command.
+    pub(crate) fn abort_transaction(&mut self) -> Result<()> {
+        let (local_id, remote_id) = (self.get_local_id()?, self.get_remote_id()?);
+        let quit_buffer = MessageSubcommand::Quit.with_arg(0u32);
+        self.write_message(ADBTransportMessage::new(
+            MessageCommand::Write,
+            local_id,
+            remote_id,
+            &bincode_serialize_to_vec(&quit_buffer)?,
+        ))?;
+        self.write_message(ADBTransportMessage::new(
+            MessageCommand::Clse,
+            local_id,
+            remote_id,
+            &[],
+        ))?;
+        self.send_window = None;
 
-        bincode_deserialize_from_slice(&response.into_payload()[4..])
+        loop {
+            let message = self
+                .transport
+                .read_message_with_timeout(ABORT_DRAIN_TIMEOUT)?;
+            let header = message.header();
+            if header.command() == MessageCommand::Clse && header.arg1() == local_id {
+                return Ok(());
+            }
+            log::debug!("dropping {} while aborting transaction", header.command());
+        }
     }
 
     pub(crate) fn end_transaction(&mut self) -> Result<()> {
@@ -320,25 +581,48 @@ impl<T: ADBMessageTransport> ADBMessageDevice<T> {
             self.get_remote_id()?,
             &bincode_serialize_to_vec(&quit_buffer)?,
         ))?;
-        let _discard_close = self.transport.read_message()?;
+        let _discard_close = self.read_message()?;
         Ok(())
     }
 
+    /// Collects what an `exec:` service writes until the device closes the stream.
+    pub(crate) fn read_exec_bytes(&mut self) -> Result<Vec<u8>> {
+        let mut output = Vec::new();
+        loop {
+            let message = self.recv_and_reply_okay()?;
+            match message.header().command() {
+                MessageCommand::Write => output.extend_from_slice(&message.into_payload()),
+                MessageCommand::Clse => break,
+                _ => {}
+            }
+        }
+        Ok(output)
+    }
+
+    /// [`ADBMessageDevice::read_exec_bytes`] as text, with invalid UTF-8 replaced.
+    pub(crate) fn read_exec_output(&mut self) -> Result<String> {
+        Ok(String::from_utf8_lossy(&self.read_exec_bytes()?).into_owned())
+    }
+
     pub(crate) fn open_session(&mut self, data: &[u8]) -> Result<ADBTransportMessage> {
         let mut rng = rand::rng();
 
-        let message = ADBTransportMessage::new(
-            MessageCommand::Open,
+        let message = ADBTransportMessage::open(
             rng.random(), // Our 'local-id'
-            0,
             data,
+            self.receive_window(),
         );
-        self.get_transport_mut().write_message(message)?;
+        self.write_message(message)?;
 
-        let response = self.get_transport_mut().read_message()?;
+        let response = self.read_message()?;
 
         self.local_id = Some(response.header().arg1());
         self.remote_id = Some(response.header().arg0());
+        // With `delayed_ack`, the device's `OKAY` tells how much it takes before acknowledging.
+        self.send_window = response
+            .acked_bytes()
+            .filter(|_| self.delayed_ack)
+            .map(i64::from);
 
         Ok(response)
     }
@@ -355,3 +639,46 @@ impl<T: ADBMessageTransport> ADBMessageDevice<T> {
         ))
     }
 }
+
+/// How long adbd gets to come back after restarting.
+const ADBD_RESTART_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Pause before each attempt to connect to adbd while it restarts.
+const ADBD_RESTART_POLL: Duration = Duration::from_millis(500);
+
+/// Longest each attempt waits for adbd to answer, so a half-started adbd is tried again.
+pub(crate) const ADBD_RESTART_CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Calls `connect` until it succeeds once adbd restarted, or fails with its last error after
+/// [`ADBD_RESTART_TIMEOUT`].
+pub(crate) fn wait_for_adbd(mut connect: impl FnMut() -> Result<()>) -> Result<()> {
+    let deadline = Instant::now() + ADBD_RESTART_TIMEOUT;
+    loop {
+        std::thread::sleep(ADBD_RESTART_POLL);
+        match connect() {
+            Ok(()) => return Ok(()),
+            Err(error) if Instant::now() < deadline => {
+                log::debug!("adbd not back yet: {error}");
+            }
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+/// Reads from `reader` until `buffer` is full or the reader is exhausted, returning how much
+/// was read.
+pub(crate) fn read_full<R: std::io::Read + ?Sized>(
+    reader: &mut R,
+    buffer: &mut [u8],
+) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match reader.read(&mut buffer[filled..]) {
+            Ok(0) => break,
+            Ok(read) => filled += read,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
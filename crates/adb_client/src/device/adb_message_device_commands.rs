@@ -1,6 +1,10 @@
-use crate::{ADBDeviceExt, ADBMessageTransport, RebootType, Result, models::AdbStatResponse};
+use crate::{
+    ADBDeviceExt, ADBMessageTransport, RebootType, RemoteEntry, Result, ShellOutput,
+    models::AdbStatResponse,
+};
 use std::{
     io::{Read, Write},
+    ops::ControlFlow,
     path::Path,
 };
 
@@ -11,6 +15,22 @@ impl<T: ADBMessageTransport> ADBDeviceExt for ADBMessageDevice<T> {
         self.shell_command(command, output)
     }
 
+    fn shell_command_stream(
+        &mut self,
+        command: &[&str],
+        on_chunk: &mut dyn FnMut(&[u8]) -> ControlFlow<()>,
+    ) -> Result<()> {
+        self.shell_command_stream(command, on_chunk)
+    }
+
+    fn shell_command_output(&mut self, command: &[&str]) -> Result<ShellOutput> {
+        self.shell_command_output(command)
+    }
+
+    fn binder_command_output(&mut self, service: &str, args: &[&str]) -> Result<ShellOutput> {
+        self.binder_command_output(service, args)
+    }
+
     fn shell(&mut self, reader: &mut dyn Read, writer: Box<dyn Write + Send>) -> Result<()> {
         self.shell(reader, writer)
     }
@@ -19,6 +39,10 @@ impl<T: ADBMessageTransport> ADBDeviceExt for ADBMessageDevice<T> {
         self.stat(remote_path)
     }
 
+    fn list_dir(&mut self, remote_path: &str) -> Result<Vec<RemoteEntry>> {
+        self.list_dir(remote_path)
+    }
+
     fn pull(&mut self, source: &dyn AsRef<str>, output: &mut dyn Write) -> Result<()> {
         self.pull(source, output)
     }
@@ -27,6 +51,15 @@ impl<T: ADBMessageTransport> ADBDeviceExt for ADBMessageDevice<T> {
         self.push(stream, path)
     }
 
+    fn push_with_mtime(
+        &mut self,
+        stream: &mut dyn Read,
+        path: &dyn AsRef<str>,
+        mtime: u32,
+    ) -> Result<()> {
+        self.push_with_mtime(stream, path, mtime)
+    }
+
     fn reboot(&mut self, reboot_type: RebootType) -> Result<()> {
         self.reboot(reboot_type)
     }
@@ -35,8 +68,59 @@ impl<T: ADBMessageTransport> ADBDeviceExt for ADBMessageDevice<T> {
         self.install(apk_path)
     }
 
-    fn uninstall(&mut self, package: &str) -> Result<()> {
-        self.uninstall(package)
+    fn install_from_reader(
+        &mut self,
+        apk: &mut dyn Read,
+        size: u64,
+        options: &crate::InstallOptions,
+    ) -> Result<()> {
+        self.install_from_reader(apk, size, options)
+    }
+
+    fn install_multiple(
+        &mut self,
+        apk_paths: &[&Path],
+        options: &crate::InstallOptions,
+    ) -> Result<()> {
+        self.install_multiple(apk_paths, options)
+    }
+
+    fn uninstall(&mut self, package: &str, keep_data: bool) -> Result<()> {
+        self.uninstall(package, keep_data)
+    }
+
+    // Without a way to connect again, adbd restarting leaves the connection unusable.
+    fn root(&mut self) -> Result<()> {
+        self.restart_adbd("root:").map(|_| ())
+    }
+
+    fn unroot(&mut self) -> Result<()> {
+        self.restart_adbd("unroot:").map(|_| ())
+    }
+
+    fn remount(&mut self) -> Result<String> {
+        self.remount()
+    }
+
+    fn sideload(&mut self, package_path: &dyn AsRef<Path>) -> Result<()> {
+        self.sideload(package_path)
+    }
+
+    fn backup(
+        &mut self,
+        packages: &[&str],
+        options: &crate::BackupOptions,
+        output: &mut dyn Write,
+    ) -> Result<()> {
+        self.backup(packages, options, output)
+    }
+
+    fn restore(&mut self, input: &mut dyn Read) -> Result<()> {
+        self.restore(input)
+    }
+
+    fn screencap(&mut self) -> Result<Vec<u8>> {
+        self.screencap()
     }
 
     fn framebuffer_inner(&mut self) -> Result<image::ImageBuffer<image::Rgba<u8>, Vec<u8>>> {
@@ -1,17 +1,28 @@
+use std::io::Read;
 use std::io::Write;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::ops::ControlFlow;
 use std::path::{Path, PathBuf};
-use std::{io::Read, net::SocketAddr};
 
-use super::adb_message_device::ADBMessageDevice;
+use super::adb_message_device::{
+    ADBD_RESTART_CONNECT_TIMEOUT, ADBMessageDevice, HOST_BANNER, HOST_MAX_PAYLOAD, wait_for_adbd,
+};
 use super::models::MessageCommand;
-use super::{ADBRsaKey, ADBTransportMessage, get_default_adb_key_path};
-use crate::device::adb_usb_device::read_adb_private_key;
-use crate::{ADBDeviceExt, ADBMessageTransport, ADBTransport, Result, TcpTransport};
+use super::pairing;
+use super::{
+    ADBRsaKey, ADBShellSession, ADBStreamManager, ADBTransportMessage, get_default_adb_key_path,
+};
+use crate::{
+    ADBDeviceExt, ADBMessageTransport, ADBTransport, DeviceBanner, Result, ShellWindowSize,
+    TcpTransport, TransportTimeouts,
+};
 
 /// Represent a device reached and available over USB.
 #[derive(Debug)]
 pub struct ADBTcpDevice {
     private_key: ADBRsaKey,
+    /// Keys whose signatures are offered after `private_key`'s.
+    fallback_keys: Vec<ADBRsaKey>,
     inner: ADBMessageDevice<TcpTransport>,
 }
 
@@ -26,19 +37,28 @@ impl ADBTcpDevice {
         address: SocketAddr,
         private_key_path: PathBuf,
     ) -> Result<Self> {
-        let private_key = if let Some(private_key) = read_adb_private_key(&private_key_path)? {
-            private_key
-        } else {
-            log::warn!(
-                "No private key found at path {}. Using a temporary random one.",
-                private_key_path.display()
-            );
-            ADBRsaKey::new_random()?
-        };
+        Self::new_with_fallback_keys(address, private_key_path, Vec::new())
+    }
+
+    /// Same as [`ADBTcpDevice::new_with_custom_private_key`], with the device's challenges
+    /// signed by the key at `private_key_path`, then by each of `fallback_keys` and the keys
+    /// in `ADB_VENDOR_KEYS`, so a device that already allowed any of them connects without
+    /// prompting. Otherwise, the user is asked to allow the key at `private_key_path`.
+    pub fn new_with_fallback_keys(
+        address: SocketAddr,
+        private_key_path: PathBuf,
+        mut fallback_keys: Vec<ADBRsaKey>,
+    ) -> Result<Self> {
+        let private_key = ADBRsaKey::load_or_generate(&private_key_path)?;
+        fallback_keys.extend(ADBRsaKey::load_vendor_keys());
 
         let mut device = Self {
             private_key,
-            inner: ADBMessageDevice::new(TcpTransport::new(address)?),
+            fallback_keys,
+            inner: ADBMessageDevice::new(TcpTransport::new_with_custom_private_key(
+                address,
+                private_key_path,
+            )?),
         };
 
         device.connect()?;
@@ -46,6 +66,23 @@ impl ADBTcpDevice {
         Ok(device)
     }
 
+    /// Pairs with a device through wireless debugging, using the pairing code and the
+    /// pairing `address` it shows, which differs from the one to connect to afterwards.
+    /// Once paired, the device accepts the default ADB key without prompting.
+    pub fn pair(address: SocketAddr, code: &str) -> Result<()> {
+        Self::pair_with_custom_private_key(address, code, &get_default_adb_key_path()?)
+    }
+
+    /// Pairs with a device through wireless debugging so it accepts the key at
+    /// `private_key_path`, which is generated if missing.
+    pub fn pair_with_custom_private_key(
+        address: SocketAddr,
+        code: &str,
+        private_key_path: &Path,
+    ) -> Result<()> {
+        pairing::pair(address, code, private_key_path)
+    }
+
     /// Send initial connect
     pub fn connect(&mut self) -> Result<()> {
         self.get_transport_mut().connect()?;
@@ -53,30 +90,50 @@ impl ADBTcpDevice {
         let message = ADBTransportMessage::new(
             MessageCommand::Cnxn,
             0x0100_0000,
-            1_048_576,
-            format!("host::{}\0", env!("CARGO_PKG_NAME")).as_bytes(),
+            HOST_MAX_PAYLOAD,
+            HOST_BANNER.as_bytes(),
         );
 
-        self.get_transport_mut().write_message(message)?;
+        self.inner.write_message(message)?;
 
-        let message = self.get_transport_mut().read_message()?;
+        let connect_timeout = self.inner.timeouts().connect;
+        let message = self
+            .get_transport_mut()
+            .read_message_with_timeout(connect_timeout)?;
 
         // Check if a client is requesting a secure connection and upgrade it if necessary
         match message.header().command() {
             MessageCommand::Stls => {
-                self.get_transport_mut()
-                    .write_message(ADBTransportMessage::new(MessageCommand::Stls, 1, 0, &[]))?;
+                self.inner.write_message(ADBTransportMessage::new(
+                    MessageCommand::Stls,
+                    1,
+                    0,
+                    &[],
+                ))?;
                 self.get_transport_mut().upgrade_connection()?;
                 log::debug!("Connection successfully upgraded from TCP to TLS");
+                // The TLS handshake authenticates us; the device then confirms the connection
+                // over it, with the banner listing its features.
+                let message = self
+                    .get_transport_mut()
+                    .read_message_with_timeout(connect_timeout)?;
+                message.assert_command(MessageCommand::Cnxn)?;
+                self.inner.set_banner(message);
                 Ok(())
             }
             MessageCommand::Cnxn => {
                 log::debug!("Unencrypted connection established");
+                self.inner.set_banner(message);
                 Ok(())
             }
             MessageCommand::Auth => {
                 log::debug!("Authentication required");
-                self.inner.auth_handshake(message, &self.private_key)
+                self.inner.auth_handshake(
+                    message,
+                    &self.private_key,
+                    &self.fallback_keys,
+                    &mut |_| {},
+                )
             }
             _ => Err(crate::RustADBError::WrongResponseReceived(
                 "Expected CNXN, STLS or AUTH command".to_string(),
@@ -85,6 +142,98 @@ impl ADBTcpDevice {
         }
     }
 
+    /// Key this host authenticates with, e.g. to show its [`ADBRsaKey::fingerprint`] next
+    /// to the one on the device's prompt.
+    pub fn private_key(&self) -> &ADBRsaKey {
+        &self.private_key
+    }
+
+    /// Sets the keys offered after [`ADBTcpDevice::private_key`] when connecting again.
+    pub fn set_fallback_keys(&mut self, fallback_keys: Vec<ADBRsaKey>) {
+        self.fallback_keys = fallback_keys;
+    }
+
+    /// What the device announced about itself when the connection was established.
+    pub fn banner(&self) -> &DeviceBanner {
+        self.inner.banner()
+    }
+
+    /// How long the device is waited on for each operation.
+    pub fn timeouts(&self) -> TransportTimeouts {
+        self.inner.timeouts()
+    }
+
+    /// Sets how long the device is waited on for each operation, from the next one on,
+    /// including the handshake of the next [`ADBTcpDevice::connect`].
+    pub fn set_timeouts(&mut self, timeouts: TransportTimeouts) {
+        self.inner.set_timeouts(timeouts);
+    }
+
+    /// Runs `root:` or `unroot:`, then connects again once adbd restarted and listens on
+    /// the same address.
+    fn restart_adbd(&mut self, service: &str) -> Result<()> {
+        if !self.inner.restart_adbd(service)? {
+            return Ok(());
+        }
+        log::info!("adbd restarting, connecting again once it is back");
+        let _ = self.get_transport_mut().disconnect();
+
+        let timeouts = self.inner.timeouts();
+        self.inner.set_timeouts(TransportTimeouts {
+            connect: timeouts.connect.min(ADBD_RESTART_CONNECT_TIMEOUT),
+            ..timeouts
+        });
+        let result = wait_for_adbd(|| self.connect());
+        self.inner.set_timeouts(timeouts);
+        result
+    }
+
+    /// Starts `command` in a shell session kept open for input and output, or an interactive
+    /// shell when `command` is empty. With a `window_size`, it runs in a pseudo-terminal.
+    pub fn shell_session(
+        &mut self,
+        command: &str,
+        window_size: Option<ShellWindowSize>,
+    ) -> Result<ADBShellSession<'_, TcpTransport>> {
+        ADBShellSession::open(&mut self.inner, command, window_size)
+    }
+
+    /// Opens several streams over this connection to use at once, such as a shell next to a
+    /// sync transfer.
+    pub fn streams(&mut self) -> ADBStreamManager<'_, TcpTransport> {
+        ADBStreamManager::new(&mut self.inner)
+    }
+
+    /// Forwards connections accepted on `listener` to `remote` on the device, such as
+    /// `tcp:8080` or `localabstract:chrome_devtools_remote`, like `adb forward`. Blocks while
+    /// serving them one at a time, since each holds the device until it closes.
+    pub fn forward(&mut self, listener: &TcpListener, remote: &str) -> Result<()> {
+        self.inner.forward(listener, remote)
+    }
+
+    /// Relays a single connection to `remote` on the device until either side closes it.
+    pub fn forward_connection(&mut self, stream: TcpStream, remote: &str) -> Result<()> {
+        self.inner.forward_connection(stream, remote)
+    }
+
+    /// Asks the device to listen on `remote`, such as `tcp:8080`, and pass connections back
+    /// to `local` on this host, like `adb reverse`. They are only served while
+    /// [`Self::serve_reverse`] runs.
+    pub fn reverse(&mut self, remote: &str, local: &str) -> Result<()> {
+        self.inner.reverse(remote, local)
+    }
+
+    /// Removes every reverse rule set on the device.
+    pub fn reverse_remove_all(&mut self) -> Result<()> {
+        self.inner.reverse_remove_all()
+    }
+
+    /// Relays the connections the device opens for reverse rules to their `tcp:` targets on
+    /// this host, one at a time. Blocks until the connection to the device fails.
+    pub fn serve_reverse(&mut self) -> Result<()> {
+        self.inner.serve_reverse()
+    }
+
     #[inline]
     fn get_transport_mut(&mut self) -> &mut TcpTransport {
         self.inner.get_transport_mut()
@@ -97,6 +246,29 @@ impl ADBDeviceExt for ADBTcpDevice {
         self.inner.shell_command(command, output)
     }
 
+    #[inline]
+    fn shell_command_stream(
+        &mut self,
+        command: &[&str],
+        on_chunk: &mut dyn FnMut(&[u8]) -> ControlFlow<()>,
+    ) -> Result<()> {
+        self.inner.shell_command_stream(command, on_chunk)
+    }
+
+    #[inline]
+    fn shell_command_output(&mut self, command: &[&str]) -> Result<crate::ShellOutput> {
+        self.inner.shell_command_output(command)
+    }
+
+    #[inline]
+    fn binder_command_output(
+        &mut self,
+        service: &str,
+        args: &[&str],
+    ) -> Result<crate::ShellOutput> {
+        self.inner.binder_command_output(service, args)
+    }
+
     #[inline]
     fn shell(&mut self, reader: &mut dyn Read, writer: Box<dyn Write + Send>) -> Result<()> {
         self.inner.shell(reader, writer)
@@ -107,6 +279,11 @@ impl ADBDeviceExt for ADBTcpDevice {
         self.inner.stat(remote_path)
     }
 
+    #[inline]
+    fn list_dir(&mut self, remote_path: &str) -> Result<Vec<crate::RemoteEntry>> {
+        self.inner.list_dir(remote_path)
+    }
+
     #[inline]
     fn pull(&mut self, source: &dyn AsRef<str>, output: &mut dyn Write) -> Result<()> {
         self.inner.pull(source, output)
@@ -117,6 +294,16 @@ impl ADBDeviceExt for ADBTcpDevice {
         self.inner.push(stream, path)
     }
 
+    #[inline]
+    fn push_with_mtime(
+        &mut self,
+        stream: &mut dyn Read,
+        path: &dyn AsRef<str>,
+        mtime: u32,
+    ) -> Result<()> {
+        self.inner.push_with_mtime(stream, path, mtime)
+    }
+
     #[inline]
     fn reboot(&mut self, reboot_type: crate::RebootType) -> Result<()> {
         self.inner.reboot(reboot_type)
@@ -128,8 +315,67 @@ impl ADBDeviceExt for ADBTcpDevice {
     }
 
     #[inline]
-    fn uninstall(&mut self, package: &str) -> Result<()> {
-        self.inner.uninstall(package)
+    fn install_from_reader(
+        &mut self,
+        apk: &mut dyn Read,
+        size: u64,
+        options: &crate::InstallOptions,
+    ) -> Result<()> {
+        self.inner.install_from_reader(apk, size, options)
+    }
+
+    #[inline]
+    fn install_multiple(
+        &mut self,
+        apk_paths: &[&Path],
+        options: &crate::InstallOptions,
+    ) -> Result<()> {
+        self.inner.install_multiple(apk_paths, options)
+    }
+
+    #[inline]
+    fn uninstall(&mut self, package: &str, keep_data: bool) -> Result<()> {
+        self.inner.uninstall(package, keep_data)
+    }
+
+    #[inline]
+    fn root(&mut self) -> Result<()> {
+        self.restart_adbd("root:")
+    }
+
+    #[inline]
+    fn unroot(&mut self) -> Result<()> {
+        self.restart_adbd("unroot:")
+    }
+
+    #[inline]
+    fn remount(&mut self) -> Result<String> {
+        self.inner.remount()
+    }
+
+    #[inline]
+    fn sideload(&mut self, package_path: &dyn AsRef<Path>) -> Result<()> {
+        self.inner.sideload(package_path)
+    }
+
+    #[inline]
+    fn backup(
+        &mut self,
+        packages: &[&str],
+        options: &crate::BackupOptions,
+        output: &mut dyn Write,
+    ) -> Result<()> {
+        self.inner.backup(packages, options, output)
+    }
+
+    #[inline]
+    fn restore(&mut self, input: &mut dyn Read) -> Result<()> {
+        self.inner.restore(input)
+    }
+
+    #[inline]
+    fn screencap(&mut self) -> Result<Vec<u8>> {
+        self.inner.screencap()
     }
 
     #[inline]
@@ -8,6 +8,10 @@ pub const AUTH_TOKEN: u32 = 1;
 pub const AUTH_SIGNATURE: u32 = 2;
 pub const AUTH_RSAPUBLICKEY: u32 = 3;
 
+/// Bytes a stream may be sent before the receiver acknowledges any, when the connection
+/// uses `delayed_ack`. Matches adbd.
+pub(crate) const INITIAL_DELAYED_ACK_BYTES: u32 = 32 * 1024 * 1024;
+
 #[derive(Debug)]
 pub struct ADBTransportMessage {
     header: ADBTransportMessageHeader,
@@ -79,6 +83,37 @@ impl ADBTransportMessage {
         }
     }
 
+    /// `OKAY` on a stream. With `delayed_ack`, it carries the number of bytes `acked`, which
+    /// the peer may send again.
+    pub(crate) fn okay(local_id: u32, remote_id: u32, acked: Option<u32>) -> Self {
+        match acked {
+            Some(acked) => Self::new(
+                MessageCommand::Okay,
+                local_id,
+                remote_id,
+                &acked.to_le_bytes(),
+            ),
+            None => Self::new(MessageCommand::Okay, local_id, remote_id, &[]),
+        }
+    }
+
+    /// `OPEN` of `service`. With `delayed_ack`, it grants the device `window` bytes to send
+    /// before waiting for our acknowledgements.
+    pub(crate) fn open(local_id: u32, service: &[u8], window: Option<u32>) -> Self {
+        Self::new(
+            MessageCommand::Open,
+            local_id,
+            window.unwrap_or_default(),
+            service,
+        )
+    }
+
+    /// Bytes acknowledged by an `OKAY` from a peer using `delayed_ack`.
+    pub(crate) fn acked_bytes(&self) -> Option<u32> {
+        let acked: [u8; 4] = self.payload.as_slice().try_into().ok()?;
+        Some(u32::from_le_bytes(acked))
+    }
+
     pub fn from_header_and_payload(header: ADBTransportMessageHeader, payload: Vec<u8>) -> Self {
         Self { header, payload }
     }
@@ -2,36 +2,30 @@ use rusb::Device;
 use rusb::DeviceDescriptor;
 use rusb::UsbContext;
 use rusb::constants::LIBUSB_CLASS_VENDOR_SPEC;
-use std::fs::read_to_string;
 use std::io::Read;
 use std::io::Write;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::ops::ControlFlow;
 use std::path::Path;
 use std::path::PathBuf;
+use std::time::{Duration, Instant};
 
-use super::adb_message_device::ADBMessageDevice;
+use super::adb_message_device::{
+    ADBD_RESTART_CONNECT_TIMEOUT, ADBMessageDevice, HOST_BANNER, HOST_MAX_PAYLOAD, wait_for_adbd,
+};
 use super::models::MessageCommand;
-use super::{ADBRsaKey, ADBTransportMessage};
+use super::{ADBRsaKey, ADBShellSession, ADBStreamManager, ADBTransportMessage};
 use crate::ADBDeviceExt;
 use crate::ADBMessageTransport;
 use crate::ADBTransport;
-use crate::{Result, RustADBError, USBTransport};
-
-pub fn read_adb_private_key<P: AsRef<Path>>(private_key_path: P) -> Result<Option<ADBRsaKey>> {
-    // Try to read the private key file from given path
-    // If the file is not found, return None
-    // If there is another error while reading the file, return this error
-    // Else, return the private key content
-    let pk = match read_to_string(private_key_path.as_ref()) {
-        Ok(pk) => pk,
-        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
-        Err(e) => return Err(e.into()),
-    };
-
-    match ADBRsaKey::new_from_pkcs8(&pk) {
-        Ok(pk) => Ok(Some(pk)),
-        Err(e) => Err(e),
-    }
-}
+use crate::{
+    ConnectionPhase, DeviceBanner, Result, RustADBError, ShellWindowSize, TransportTimeouts,
+    USBTransport,
+};
+
+/// How long the device gets to answer a keepalive ping before the connection is considered
+/// stale.
+const KEEPALIVE_PING_TIMEOUT: Duration = Duration::from_secs(5);
 
 /// Search for adb devices with known interface class and subclass values
 pub fn search_adb_devices() -> Result<Option<(u16, u16)>> {
@@ -90,6 +84,7 @@ pub fn is_adb_device<T: UsbContext>(device: &Device<T>, des: &DeviceDescriptor)
     false
 }
 
+/// Path of the private key `adb` uses by default, `~/.android/adbkey`
 pub fn get_default_adb_key_path() -> Result<PathBuf> {
     std::env::home_dir()
         .map(|home| home.join(".android").join("adbkey"))
@@ -100,7 +95,11 @@ pub fn get_default_adb_key_path() -> Result<PathBuf> {
 #[derive(Debug)]
 pub struct ADBUSBDevice {
     private_key: ADBRsaKey,
+    /// Keys whose signatures are offered after `private_key`'s.
+    fallback_keys: Vec<ADBRsaKey>,
     inner: ADBMessageDevice<USBTransport>,
+    keepalive: Option<Duration>,
+    last_activity: Instant,
 }
 
 impl ADBUSBDevice {
@@ -109,48 +108,126 @@ impl ADBUSBDevice {
         Self::new_with_custom_private_key(vendor_id, product_id, get_default_adb_key_path()?)
     }
 
+    /// Instantiate a new [`ADBUSBDevice`], reporting each [`ConnectionPhase`] of the handshake to `on_phase`
+    pub fn new_with_progress(
+        vendor_id: u16,
+        product_id: u16,
+        on_phase: &mut dyn FnMut(ConnectionPhase),
+    ) -> Result<Self> {
+        Self::new_from_transport_inner(
+            USBTransport::new(vendor_id, product_id)?,
+            &get_default_adb_key_path()?,
+            TransportTimeouts::default(),
+            on_phase,
+        )
+    }
+
+    /// Instantiate a new [`ADBUSBDevice`] for the device with the given USB serial number, as
+    /// shown by `adb devices`, when several devices share `vendor_id` and `product_id`.
+    pub fn new_with_serial(vendor_id: u16, product_id: u16, serial: &str) -> Result<Self> {
+        Self::new_from_transport_inner(
+            USBTransport::new_with_serial(vendor_id, product_id, serial)?,
+            &get_default_adb_key_path()?,
+            TransportTimeouts::default(),
+            &mut |_| {},
+        )
+    }
+
     /// Instantiate a new [`ADBUSBDevice`] using a custom private key path
     pub fn new_with_custom_private_key(
         vendor_id: u16,
         product_id: u16,
         private_key_path: PathBuf,
     ) -> Result<Self> {
-        Self::new_from_transport_inner(USBTransport::new(vendor_id, product_id)?, &private_key_path)
+        Self::new_from_transport_inner(
+            USBTransport::new(vendor_id, product_id)?,
+            &private_key_path,
+            TransportTimeouts::default(),
+            &mut |_| {},
+        )
     }
 
     /// Instantiate a new [`ADBUSBDevice`] from a [`USBTransport`] and an optional private key path.
     pub fn new_from_transport(
         transport: USBTransport,
         private_key_path: Option<PathBuf>,
+    ) -> Result<Self> {
+        Self::new_from_transport_with_progress(transport, private_key_path, &mut |_| {})
+    }
+
+    /// Same as [`ADBUSBDevice::new_from_transport`], reporting each [`ConnectionPhase`] of the handshake to `on_phase`
+    pub fn new_from_transport_with_progress(
+        transport: USBTransport,
+        private_key_path: Option<PathBuf>,
+        on_phase: &mut dyn FnMut(ConnectionPhase),
+    ) -> Result<Self> {
+        Self::new_from_transport_with_timeouts(
+            transport,
+            private_key_path,
+            TransportTimeouts::default(),
+            on_phase,
+        )
+    }
+
+    /// Same as [`ADBUSBDevice::new_from_transport_with_progress`], waiting on the device as
+    /// long as `timeouts` allow, starting with the handshake.
+    pub fn new_from_transport_with_timeouts(
+        transport: USBTransport,
+        private_key_path: Option<PathBuf>,
+        timeouts: TransportTimeouts,
+        on_phase: &mut dyn FnMut(ConnectionPhase),
     ) -> Result<Self> {
         let private_key_path = match private_key_path {
             Some(private_key_path) => private_key_path,
             None => get_default_adb_key_path()?,
         };
 
-        Self::new_from_transport_inner(transport, &private_key_path)
+        Self::new_from_transport_inner(transport, &private_key_path, timeouts, on_phase)
+    }
+
+    /// Same as [`ADBUSBDevice::new_from_transport_with_timeouts`], with the device's
+    /// challenges signed by `private_key`, then by each of `fallback_keys` and the keys in
+    /// `ADB_VENDOR_KEYS`, so a device that already allowed any of them connects without
+    /// prompting. Otherwise, the user is asked to allow `private_key`.
+    pub fn new_from_transport_with_keys(
+        transport: USBTransport,
+        private_key: ADBRsaKey,
+        mut fallback_keys: Vec<ADBRsaKey>,
+        timeouts: TransportTimeouts,
+        on_phase: &mut dyn FnMut(ConnectionPhase),
+    ) -> Result<Self> {
+        fallback_keys.extend(ADBRsaKey::load_vendor_keys());
+        Self::connect_new(transport, private_key, fallback_keys, timeouts, on_phase)
     }
 
     fn new_from_transport_inner(
         transport: USBTransport,
-        private_key_path: &PathBuf,
+        private_key_path: &Path,
+        timeouts: TransportTimeouts,
+        on_phase: &mut dyn FnMut(ConnectionPhase),
     ) -> Result<Self> {
-        let private_key = if let Some(private_key) = read_adb_private_key(private_key_path)? {
-            private_key
-        } else {
-            log::warn!(
-                "No private key found at path {}. Using a temporary random one.",
-                private_key_path.display()
-            );
-            ADBRsaKey::new_random()?
-        };
+        let private_key = ADBRsaKey::load_or_generate(private_key_path)?;
+        let fallback_keys = ADBRsaKey::load_vendor_keys();
+        Self::connect_new(transport, private_key, fallback_keys, timeouts, on_phase)
+    }
 
+    fn connect_new(
+        transport: USBTransport,
+        private_key: ADBRsaKey,
+        fallback_keys: Vec<ADBRsaKey>,
+        timeouts: TransportTimeouts,
+        on_phase: &mut dyn FnMut(ConnectionPhase),
+    ) -> Result<Self> {
         let mut s = Self {
             private_key,
+            fallback_keys,
             inner: ADBMessageDevice::new(transport),
+            keepalive: None,
+            last_activity: Instant::now(),
         };
+        s.inner.set_timeouts(timeouts);
 
-        s.connect()?;
+        s.connect_with_progress(on_phase)?;
 
         Ok(s)
     }
@@ -174,27 +251,48 @@ impl ADBUSBDevice {
 
     /// Send initial connect
     pub fn connect(&mut self) -> Result<()> {
+        self.connect_with_progress(&mut |_| {})
+    }
+
+    /// Send initial connect, reporting each [`ConnectionPhase`] of the handshake to `on_phase`
+    pub fn connect_with_progress(
+        &mut self,
+        on_phase: &mut dyn FnMut(ConnectionPhase),
+    ) -> Result<()> {
         self.get_transport_mut().connect()?;
 
         let message = ADBTransportMessage::new(
             MessageCommand::Cnxn,
             0x0100_0000,
-            1_048_576,
-            format!("host::{}\0", env!("CARGO_PKG_NAME")).as_bytes(),
+            HOST_MAX_PAYLOAD,
+            HOST_BANNER.as_bytes(),
         );
 
-        self.get_transport_mut().write_message(message)?;
+        self.inner.write_message(message)?;
+        on_phase(ConnectionPhase::Connecting);
 
+        let connect_timeout = self.inner.timeouts().connect;
         loop {
-            let message = self.get_transport_mut().read_message()?;
+            let message = self
+                .get_transport_mut()
+                .read_message_with_timeout(connect_timeout)?;
 
             match message.header().command() {
                 // If the device returned CNXN instead of AUTH it does not require authentication,
                 // so we can skip the auth steps.
-                MessageCommand::Cnxn => return Ok(()),
+                MessageCommand::Cnxn => {
+                    self.inner.set_banner(message);
+                    on_phase(ConnectionPhase::Connected);
+                    return Ok(());
+                }
                 MessageCommand::Auth => {
                     message.assert_command(MessageCommand::Auth)?;
-                    return self.inner.auth_handshake(message, &self.private_key);
+                    return self.inner.auth_handshake(
+                        message,
+                        &self.private_key,
+                        &self.fallback_keys,
+                        on_phase,
+                    );
                 }
                 MessageCommand::Clse => {
                     log::debug!(
@@ -221,6 +319,184 @@ impl ADBUSBDevice {
         }
     }
 
+    /// Drops the current connection, releasing the USB interface, then claims it again and
+    /// repeats the handshake on the same USB device, e.g. after a transfer failed mid-way and
+    /// left the session in an unknown state.
+    pub fn reconnect(&mut self) -> Result<()> {
+        self.reconnect_with_reset(false)
+    }
+
+    /// Same as [`ADBUSBDevice::reconnect`], resetting the USB port in between when
+    /// `reset_port` is set. Recovers from babble or stall errors that a plain reconnect
+    /// doesn't, at the cost of the device briefly dropping off the bus.
+    pub fn reconnect_with_reset(&mut self, reset_port: bool) -> Result<()> {
+        self.get_transport_mut().disconnect()?;
+        if reset_port {
+            log::info!("resetting USB port before reconnecting");
+            self.get_transport_mut().reset_port()?;
+        }
+        self.connect()
+    }
+
+    /// Sets the largest chunk sent per sync `DATA` packet when pushing, clamped between 1 KiB
+    /// and 64 KiB, and sends one chunk per message from then on. By default, 64 KiB chunks
+    /// are packed into messages as large as the device accepts. Smaller chunks help pushes
+    /// complete over marginal cables and hubs that fail on large bulk transfers.
+    pub fn set_sync_chunk_size(&mut self, size: usize) {
+        self.inner.set_sync_chunk_size(size);
+    }
+
+    /// Allows or forbids compressing file contents during pushes and pulls. Enabled by
+    /// default, and only used when the device advertises `sendrecv_v2` with brotli, LZ4
+    /// or zstd. Worth turning off for libraries of already compressed media on fast links.
+    pub fn set_sync_compression(&mut self, enabled: bool) {
+        self.inner.set_sync_compression(enabled);
+    }
+
+    /// Key this host authenticates with, e.g. to show its [`ADBRsaKey::fingerprint`] next
+    /// to the one on the device's prompt.
+    pub fn private_key(&self) -> &ADBRsaKey {
+        &self.private_key
+    }
+
+    /// Sets the keys offered after [`ADBUSBDevice::private_key`] when connecting again, such
+    /// as on [`ADBUSBDevice::reconnect`].
+    pub fn set_fallback_keys(&mut self, fallback_keys: Vec<ADBRsaKey>) {
+        self.fallback_keys = fallback_keys;
+    }
+
+    /// What the device announced about itself when the connection was established.
+    pub fn banner(&self) -> &DeviceBanner {
+        self.inner.banner()
+    }
+
+    /// How long the device is waited on for each operation.
+    pub fn timeouts(&self) -> TransportTimeouts {
+        self.inner.timeouts()
+    }
+
+    /// Sets how long the device is waited on for each operation, from the next one on. The
+    /// `connect` and `auth` timeouts apply to the next [`ADBUSBDevice::reconnect`]; use
+    /// [`ADBUSBDevice::new_from_transport_with_timeouts`] for the first handshake.
+    pub fn set_timeouts(&mut self, timeouts: TransportTimeouts) {
+        self.inner.set_timeouts(timeouts);
+    }
+
+    /// Pings the device before the next operation once the connection was idle for
+    /// `interval`, reconnecting transparently when it went stale meanwhile, such as during
+    /// long work on the host or while the device slept. Off with `None`, the default.
+    pub fn set_keepalive(&mut self, interval: Option<Duration>) {
+        self.keepalive = interval;
+    }
+
+    /// Checks the connection still works after being idle longer than the keepalive
+    /// interval, with a cheap `stat`, and reconnects otherwise.
+    fn keep_alive(&mut self) -> Result<()> {
+        let idle = self.last_activity.elapsed();
+        self.last_activity = Instant::now();
+        if self.keepalive.is_none_or(|interval| idle < interval) {
+            return Ok(());
+        }
+
+        let timeouts = self.inner.timeouts();
+        self.inner.set_timeouts(TransportTimeouts {
+            read: timeouts.read.min(KEEPALIVE_PING_TIMEOUT),
+            ..timeouts
+        });
+        let ping = self.inner.stat("/");
+        self.inner.set_timeouts(timeouts);
+
+        if let Err(e) = ping {
+            log::info!("connection idle for {idle:?} went stale ({e}), reconnecting");
+            self.reconnect()?;
+        }
+        Ok(())
+    }
+
+    /// Runs `root:` or `unroot:`, then connects again once adbd restarted. The device drops
+    /// off the bus meanwhile, and comes back as a new USB device on the same port.
+    fn restart_adbd(&mut self, service: &str) -> Result<()> {
+        self.keep_alive()?;
+        if !self.inner.restart_adbd(service)? {
+            return Ok(());
+        }
+        log::info!("adbd restarting, connecting again once it is back");
+        let _ = self.get_transport_mut().disconnect();
+
+        let timeouts = self.inner.timeouts();
+        self.inner.set_timeouts(TransportTimeouts {
+            connect: timeouts.connect.min(ADBD_RESTART_CONNECT_TIMEOUT),
+            ..timeouts
+        });
+        let result = wait_for_adbd(|| {
+            self.get_transport_mut().rediscover()?;
+            self.connect()
+        });
+        self.inner.set_timeouts(timeouts);
+        result
+    }
+
+    /// Starts `command` in a shell session kept open for input and output, or an interactive
+    /// shell when `command` is empty. With a `window_size`, it runs in a pseudo-terminal.
+    pub fn shell_session(
+        &mut self,
+        command: &str,
+        window_size: Option<ShellWindowSize>,
+    ) -> Result<ADBShellSession<'_, USBTransport>> {
+        self.keep_alive()?;
+        ADBShellSession::open(&mut self.inner, command, window_size)
+    }
+
+    /// Opens several streams over this connection to use at once, such as a shell next to a
+    /// sync transfer, without claiming the USB interface again.
+    pub fn streams(&mut self) -> Result<ADBStreamManager<'_, USBTransport>> {
+        self.keep_alive()?;
+        Ok(ADBStreamManager::new(&mut self.inner))
+    }
+
+    /// Forwards connections accepted on `listener` to `remote` on the device, such as
+    /// `tcp:8080` or `localabstract:chrome_devtools_remote`, like `adb forward`. Blocks while
+    /// serving them one at a time, since each holds the device until it closes.
+    pub fn forward(&mut self, listener: &TcpListener, remote: &str) -> Result<()> {
+        self.keep_alive()?;
+        self.inner.forward(listener, remote)
+    }
+
+    /// Relays a single connection to `remote` on the device until either side closes it.
+    pub fn forward_connection(&mut self, stream: TcpStream, remote: &str) -> Result<()> {
+        self.keep_alive()?;
+        self.inner.forward_connection(stream, remote)
+    }
+
+    /// Asks the device to listen on `remote`, such as `tcp:8080`, and pass connections back
+    /// to `local` on this host, like `adb reverse`. They are only served while
+    /// [`Self::serve_reverse`] runs.
+    pub fn reverse(&mut self, remote: &str, local: &str) -> Result<()> {
+        self.keep_alive()?;
+        self.inner.reverse(remote, local)
+    }
+
+    /// Removes every reverse rule set on the device.
+    pub fn reverse_remove_all(&mut self) -> Result<()> {
+        self.keep_alive()?;
+        self.inner.reverse_remove_all()
+    }
+
+    /// Relays the connections the device opens for reverse rules to their `tcp:` targets on
+    /// this host, one at a time. Blocks until the connection to the device fails.
+    pub fn serve_reverse(&mut self) -> Result<()> {
+        self.keep_alive()?;
+        self.inner.serve_reverse()
+    }
+
+    /// Restarts adbd listening on TCP `port` as well, like `adb tcpip`, and returns the
+    /// device's Wi-Fi address to reach it at with [`crate::ADBTcpDevice`]. The device drops
+    /// off USB while adbd restarts, so this device has to be reconnected to be used again.
+    pub fn enable_tcpip(&mut self, port: u16) -> Result<SocketAddr> {
+        self.keep_alive()?;
+        self.inner.enable_tcpip(port)
+    }
+
     #[inline]
     fn get_transport_mut(&mut self) -> &mut USBTransport {
         self.inner.get_transport_mut()
@@ -230,46 +506,164 @@ impl ADBUSBDevice {
 impl ADBDeviceExt for ADBUSBDevice {
     #[inline]
     fn shell_command(&mut self, command: &[&str], output: &mut dyn Write) -> Result<()> {
+        self.keep_alive()?;
         self.inner.shell_command(command, output)
     }
 
+    #[inline]
+    fn shell_command_stream(
+        &mut self,
+        command: &[&str],
+        on_chunk: &mut dyn FnMut(&[u8]) -> ControlFlow<()>,
+    ) -> Result<()> {
+        self.keep_alive()?;
+        self.inner.shell_command_stream(command, on_chunk)
+    }
+
+    #[inline]
+    fn shell_command_output(&mut self, command: &[&str]) -> Result<crate::ShellOutput> {
+        self.keep_alive()?;
+        self.inner.shell_command_output(command)
+    }
+
+    #[inline]
+    fn binder_command_output(
+        &mut self,
+        service: &str,
+        args: &[&str],
+    ) -> Result<crate::ShellOutput> {
+        self.keep_alive()?;
+        self.inner.binder_command_output(service, args)
+    }
+
     #[inline]
     fn shell<'a>(&mut self, reader: &mut dyn Read, writer: Box<dyn Write + Send>) -> Result<()> {
+        self.keep_alive()?;
         self.inner.shell(reader, writer)
     }
 
     #[inline]
     fn stat(&mut self, remote_path: &str) -> Result<crate::AdbStatResponse> {
+        self.keep_alive()?;
         self.inner.stat(remote_path)
     }
 
+    #[inline]
+    fn list_dir(&mut self, remote_path: &str) -> Result<Vec<crate::RemoteEntry>> {
+        self.keep_alive()?;
+        self.inner.list_dir(remote_path)
+    }
+
     #[inline]
     fn pull(&mut self, source: &dyn AsRef<str>, output: &mut dyn Write) -> Result<()> {
+        self.keep_alive()?;
         self.inner.pull(source, output)
     }
 
     #[inline]
     fn push(&mut self, stream: &mut dyn Read, path: &dyn AsRef<str>) -> Result<()> {
+        self.keep_alive()?;
         self.inner.push(stream, path)
     }
 
+    #[inline]
+    fn push_with_mtime(
+        &mut self,
+        stream: &mut dyn Read,
+        path: &dyn AsRef<str>,
+        mtime: u32,
+    ) -> Result<()> {
+        self.keep_alive()?;
+        self.inner.push_with_mtime(stream, path, mtime)
+    }
+
     #[inline]
     fn reboot(&mut self, reboot_type: crate::RebootType) -> Result<()> {
+        self.keep_alive()?;
         self.inner.reboot(reboot_type)
     }
 
     #[inline]
     fn install(&mut self, apk_path: &dyn AsRef<Path>) -> Result<()> {
+        self.keep_alive()?;
         self.inner.install(apk_path)
     }
 
     #[inline]
-    fn uninstall(&mut self, package: &str) -> Result<()> {
-        self.inner.uninstall(package)
+    fn install_from_reader(
+        &mut self,
+        apk: &mut dyn Read,
+        size: u64,
+        options: &crate::InstallOptions,
+    ) -> Result<()> {
+        self.keep_alive()?;
+        self.inner.install_from_reader(apk, size, options)
+    }
+
+    #[inline]
+    fn install_multiple(
+        &mut self,
+        apk_paths: &[&Path],
+        options: &crate::InstallOptions,
+    ) -> Result<()> {
+        self.keep_alive()?;
+        self.inner.install_multiple(apk_paths, options)
+    }
+
+    #[inline]
+    fn uninstall(&mut self, package: &str, keep_data: bool) -> Result<()> {
+        self.keep_alive()?;
+        self.inner.uninstall(package, keep_data)
+    }
+
+    #[inline]
+    fn root(&mut self) -> Result<()> {
+        self.restart_adbd("root:")
+    }
+
+    #[inline]
+    fn unroot(&mut self) -> Result<()> {
+        self.restart_adbd("unroot:")
+    }
+
+    #[inline]
+    fn remount(&mut self) -> Result<String> {
+        self.keep_alive()?;
+        self.inner.remount()
+    }
+
+    #[inline]
+    fn sideload(&mut self, package_path: &dyn AsRef<Path>) -> Result<()> {
+        self.keep_alive()?;
+        self.inner.sideload(package_path)
+    }
+
+    #[inline]
+    fn backup(
+        &mut self,
+        packages: &[&str],
+        options: &crate::BackupOptions,
+        output: &mut dyn Write,
+    ) -> Result<()> {
+        self.keep_alive()?;
+        self.inner.backup(packages, options, output)
+    }
+
+    #[inline]
+    fn restore(&mut self, input: &mut dyn Read) -> Result<()> {
+        self.keep_alive()?;
+        self.inner.restore(input)
+    }
+
+    #[inline]
+    fn screencap(&mut self) -> Result<Vec<u8>> {
+        self.keep_alive()?;
+        self.inner.screencap()
     }
 
     #[inline]
     fn framebuffer_inner(&mut self) -> Result<image::ImageBuffer<image::Rgba<u8>, Vec<u8>>> {
+        self.keep_alive()?;
         self.inner.framebuffer_inner()
     }
 }
@@ -0,0 +1,59 @@
+use crate::{
+    ADBMessageTransport, Result, ShellOutput, device::adb_message_device::ADBMessageDevice,
+};
+
+/// Service request for `abb:` or `abb_exec:`, which take the binder service and its
+/// arguments NUL-separated instead of as a shell command line.
+fn abb_request(prefix: &str, service: &str, args: &[&str]) -> Vec<u8> {
+    let mut request = format!("{prefix}{service}");
+    for arg in args {
+        request.push('\0');
+        request.push_str(arg);
+    }
+    request.push('\0');
+    request.into_bytes()
+}
+
+impl<T: ADBMessageTransport> ADBMessageDevice<T> {
+    /// Calls binder `service` with `args` through the Android Binder Bridge (`abb:`) when the
+    /// device advertises it, skipping the shell and `cmd` process. Others run `cmd` in a shell.
+    pub(crate) fn binder_command_output(
+        &mut self,
+        service: &str,
+        args: &[&str],
+    ) -> Result<ShellOutput> {
+        if self.has_feature("abb") {
+            return self.shell_v2_service_output(&abb_request("abb:", service, args));
+        }
+        let mut command = vec!["cmd", service];
+        command.extend_from_slice(args);
+        self.shell_command_output(&command)
+    }
+
+    /// Opens a raw stream to binder `service`, through `abb_exec:` when advertised or
+    /// `exec:cmd` otherwise. Returns `false` without opening anything when the device has
+    /// neither, which is the case before Android 7.
+    pub(crate) fn open_binder_exec(&mut self, service: &str, args: &[&str]) -> Result<bool> {
+        if self.has_feature("abb_exec") {
+            self.open_session(&abb_request("abb_exec:", service, args))?;
+        } else if self.has_feature("cmd") {
+            self.open_session(format!("exec:cmd {service} {}\0", args.join(" ")).as_bytes())?;
+        } else {
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    /// Runs the package manager with `args` and returns its output, through `pm` in a
+    /// shell on devices without `cmd`.
+    pub(crate) fn package_command(&mut self, args: &[&str]) -> Result<String> {
+        if self.open_binder_exec("package", args)? {
+            return self.read_exec_output();
+        }
+        let mut command = vec!["pm"];
+        command.extend_from_slice(args);
+        let mut output = Vec::new();
+        self.shell_command(&command, &mut output)?;
+        Ok(String::from_utf8_lossy(&output).into_owned())
+    }
+}
@@ -0,0 +1,71 @@
+use std::io::{Read, Write};
+
+use crate::{
+    ADBMessageTransport, BackupOptions, Result,
+    device::{
+        ADBTransportMessage, MessageCommand,
+        adb_message_device::{ADBMessageDevice, read_full},
+    },
+};
+
+/// Zeroed tar blocks sent after a restore archive, marking its end even when it is truncated.
+const RESTORE_END_MARKER: [u8; 1024] = [0; 1024];
+
+impl<T: ADBMessageTransport> ADBMessageDevice<T> {
+    pub(crate) fn backup(
+        &mut self,
+        packages: &[&str],
+        options: &BackupOptions,
+        output: &mut dyn Write,
+    ) -> Result<()> {
+        let service = format!("backup:{}\0", options.args(packages));
+        self.open_session(service.as_bytes())?
+            .assert_command(MessageCommand::Okay)?;
+
+        // Nothing comes until the user confirms on the device, then the archive follows
+        // until `bu` exits.
+        loop {
+            let message = self.recv_and_reply_okay()?;
+            match message.header().command() {
+                MessageCommand::Write => output.write_all(&message.into_payload())?,
+                MessageCommand::Clse => break,
+                _ => {}
+            }
+        }
+        output.flush()?;
+        Ok(())
+    }
+
+    pub(crate) fn restore(&mut self, input: &mut dyn Read) -> Result<()> {
+        self.open_session(b"restore:\0")?
+            .assert_command(MessageCommand::Okay)?;
+
+        let (local_id, remote_id) = (self.get_local_id()?, self.get_remote_id()?);
+        let mut buffer = vec![0; self.max_payload()].into_boxed_slice();
+        loop {
+            let size = read_full(input, &mut buffer)?;
+            if size == 0 {
+                break;
+            }
+            let message = ADBTransportMessage::new(
+                MessageCommand::Write,
+                local_id,
+                remote_id,
+                &buffer[..size],
+            );
+            self.send_data(message)?;
+        }
+        self.send_data(ADBTransportMessage::new(
+            MessageCommand::Write,
+            local_id,
+            remote_id,
+            &RESTORE_END_MARKER,
+        ))?;
+
+        // `bu` exits once the restore is done; closing earlier would interrupt it.
+        let output = self.read_exec_output()?;
+        log::debug!("bu restore: {}", output.trim());
+        log::info!("Backup successfully restored");
+        Ok(())
+    }
+}
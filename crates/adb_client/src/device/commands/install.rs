@@ -1,11 +1,21 @@
-use std::{fs::File, path::Path};
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::Path,
+};
 
 use crate::{
-    ADBMessageTransport, Result,
+    ADBMessageTransport, InstallOptions, Result,
     device::{MessageWriter, adb_message_device::ADBMessageDevice},
-    utils::check_extension_is_apk,
+    utils::{
+        SplitApk, check_extension_is_apk, check_package_manager_output, open_split_apks,
+        parse_install_session,
+    },
 };
 
+/// Where devices without `cmd` get the APK pushed for `pm install`.
+const LEGACY_INSTALL_PATH: &str = "/data/local/tmp/adb_client_install.apk";
+
 impl<T: ADBMessageTransport> ADBMessageDevice<T> {
     pub(crate) fn install(&mut self, apk_path: &dyn AsRef<Path>) -> Result<()> {
         let mut apk_file = File::open(apk_path)?;
@@ -14,27 +24,109 @@ impl<T: ADBMessageTransport> ADBMessageDevice<T> {
 
         let file_size = apk_file.metadata()?.len();
 
-        self.open_session(format!("exec:cmd package 'install' -S {file_size}\0").as_bytes())?;
+        self.install_from_reader(&mut apk_file, file_size, &InstallOptions::default())?;
+        log::info!(
+            "APK file {} successfully installed",
+            apk_path.as_ref().display()
+        );
+        Ok(())
+    }
+
+    /// Streams the APK to the package manager through `abb_exec` or `cmd`. Devices with
+    /// neither (before Android 7) get it pushed to a temporary file for `pm install` instead.
+    pub(crate) fn install_from_reader(
+        &mut self,
+        apk: &mut dyn Read,
+        size: u64,
+        options: &InstallOptions,
+    ) -> Result<()> {
+        let size = size.to_string();
+        let mut args = vec!["install"];
+        args.extend(options.flags());
+        args.extend(["-S", size.as_str()]);
+        if !self.open_binder_exec("package", &args)? {
+            return self.install_with_pm(apk, options);
+        }
 
         let transport = self.get_transport().clone();
 
         let mut writer = MessageWriter::new(transport, self.get_local_id()?, self.get_remote_id()?);
 
-        std::io::copy(&mut apk_file, &mut writer)?;
+        io::copy(apk, &mut writer)?;
+
+        check_package_manager_output(&self.read_exec_output()?)
+    }
+
+    fn install_with_pm(&mut self, apk: &mut dyn Read, options: &InstallOptions) -> Result<()> {
+        self.push(apk, LEGACY_INSTALL_PATH)?;
+        let mut args = vec!["install"];
+        args.extend(options.flags());
+        args.push(LEGACY_INSTALL_PATH);
+        let installed = self.package_command(&args);
+        self.remove_legacy_install_file();
+        check_package_manager_output(&installed?)
+    }
+
+    /// Installs the APKs in one session: `install-create`, an `install-write` per APK, then
+    /// `install-commit`, abandoning the session if any step fails.
+    pub(crate) fn install_multiple(
+        &mut self,
+        apk_paths: &[&Path],
+        options: &InstallOptions,
+    ) -> Result<()> {
+        let mut apks = open_split_apks(apk_paths)?;
+        let total_size = apks.iter().map(|apk| apk.size).sum::<u64>().to_string();
 
-        let final_status = self.get_transport_mut().read_message()?;
+        let mut args = vec!["install-create"];
+        args.extend(options.flags());
+        args.extend(["-S", total_size.as_str()]);
+        let session = parse_install_session(&self.package_command(&args)?)?;
 
-        match final_status.into_payload().as_slice() {
-            b"Success\n" => {
-                log::info!(
-                    "APK file {} successfully installed",
-                    apk_path.as_ref().display()
-                );
-                Ok(())
+        let result = self.write_and_commit(session, &mut apks);
+        if result.is_err() {
+            let session = session.to_string();
+            if let Err(error) = self.package_command(&["install-abandon", &session]) {
+                log::warn!("could not abandon install session {session}: {error}");
             }
-            d => Err(crate::RustADBError::ADBRequestFailed(String::from_utf8(
-                d.to_vec(),
-            )?)),
+        }
+        result
+    }
+
+    fn write_and_commit(&mut self, session: u32, apks: &mut [SplitApk]) -> Result<()> {
+        let session = session.to_string();
+        for apk in apks {
+            let size = apk.size.to_string();
+            let args: &[&str] = &["install-write", "-S", &size, &session, &apk.name, "-"];
+            let output = if self.open_binder_exec("package", args)? {
+                let transport = self.get_transport().clone();
+                let mut writer =
+                    MessageWriter::new(transport, self.get_local_id()?, self.get_remote_id()?);
+                io::copy(&mut apk.file, &mut writer)?;
+                self.read_exec_output()?
+            } else {
+                // `pm install-write` only reads from a file on older devices.
+                self.push(&mut apk.file, LEGACY_INSTALL_PATH)?;
+                let args: &[&str] = &[
+                    "install-write",
+                    "-S",
+                    &size,
+                    &session,
+                    &apk.name,
+                    LEGACY_INSTALL_PATH,
+                ];
+                let output = self.package_command(args);
+                self.remove_legacy_install_file();
+                output?
+            };
+            check_package_manager_output(&output)?;
+        }
+        check_package_manager_output(&self.package_command(&["install-commit", &session])?)
+    }
+
+    fn remove_legacy_install_file(&mut self) {
+        if let Err(error) = self.shell_command(&["rm", "-f", LEGACY_INSTALL_PATH], &mut io::sink())
+        {
+            log::warn!("could not remove {LEGACY_INSTALL_PATH}: {error}");
         }
     }
 }
@@ -0,0 +1,249 @@
+use byteorder::{ByteOrder, LittleEndian};
+
+use crate::{
+    ADBMessageTransport, RemoteEntry, Result, RustADBError,
+    device::{
+        ADBTransportMessage, MessageCommand, MessageSubcommand,
+        adb_message_device::{self, ADBMessageDevice},
+    },
+};
+
+/// Directory entry records of a `LIST` (v1) or `LIS2` (v2) reply.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DentFormat {
+    /// `DENT`: id, mode, size, mtime and name length, each 32 bits.
+    V1,
+    /// `DNT2`: id, error, dev, ino, mode, nlink, uid, gid, size, atime, mtime, ctime
+    /// and name length, with 64-bit sizes and times.
+    V2,
+}
+
+impl DentFormat {
+    fn request(self) -> MessageSubcommand {
+        match self {
+            DentFormat::V1 => MessageSubcommand::List,
+            DentFormat::V2 => MessageSubcommand::Lis2,
+        }
+    }
+
+    fn id(self) -> MessageSubcommand {
+        match self {
+            DentFormat::V1 => MessageSubcommand::Dent,
+            DentFormat::V2 => MessageSubcommand::Dnt2,
+        }
+    }
+
+    /// Bytes before the name.
+    fn header_len(self) -> usize {
+        match self {
+            DentFormat::V1 => 20,
+            DentFormat::V2 => 76,
+        }
+    }
+
+    /// Entry described by a complete `header`, or `None` when the device
+    /// couldn't `lstat` it.
+    fn entry(self, header: &[u8], name: &str) -> Option<RemoteEntry> {
+        match self {
+            DentFormat::V1 => Some(RemoteEntry::new(
+                name,
+                LittleEndian::read_u32(&header[4..8]),
+                u64::from(LittleEndian::read_u32(&header[8..12])),
+                u64::from(LittleEndian::read_u32(&header[12..16])),
+            )),
+            DentFormat::V2 => {
+                let error = LittleEndian::read_u32(&header[4..8]);
+                if error != 0 {
+                    log::debug!("skipping {name}: lstat failed with errno {error}");
+                    return None;
+                }
+                Some(
+                    RemoteEntry::new(
+                        name,
+                        LittleEndian::read_u32(&header[24..28]),
+                        LittleEndian::read_u64(&header[40..48]),
+                        u64::try_from(LittleEndian::read_i64(&header[56..64])).unwrap_or_default(),
+                    )
+                    .with_owner(
+                        LittleEndian::read_u32(&header[32..36]),
+                        LittleEndian::read_u32(&header[36..40]),
+                    ),
+                )
+            }
+        }
+    }
+}
+
+impl<T: ADBMessageTransport> ADBMessageDevice<T> {
+    pub(crate) fn list_dir(&mut self, remote_path: &str) -> Result<Vec<RemoteEntry>> {
+        let format = if self.has_feature("ls_v2") {
+            DentFormat::V2
+        } else {
+            DentFormat::V1
+        };
+        self.sync_transaction(|device| {
+            let list_buffer = format.request().with_arg(u32::try_from(remote_path.len())?);
+            device.send_and_expect_okay(ADBTransportMessage::new(
+                MessageCommand::Write,
+                device.get_local_id()?,
+                device.get_remote_id()?,
+                &adb_message_device::bincode_serialize_to_vec(&list_buffer)?,
+            ))?;
+            device.send_and_expect_okay(ADBTransportMessage::new(
+                MessageCommand::Write,
+                device.get_local_id()?,
+                device.get_remote_id()?,
+                remote_path.as_bytes(),
+            ))?;
+
+            // Records are not aligned to messages, so a record cut at the end of one
+            // payload is completed by the next.
+            let mut pending = Vec::new();
+            let mut entries = Vec::new();
+            loop {
+                let message = device.recv_and_reply_okay()?;
+                match message.header().command() {
+                    MessageCommand::Write => {}
+                    MessageCommand::Clse => {
+                        return Err(RustADBError::ADBRequestFailed(format!(
+                            "connection closed while listing {remote_path}"
+                        )));
+                    }
+                    _ => continue,
+                }
+                pending.extend_from_slice(&message.into_payload());
+                if parse_dents(format, &mut pending, &mut entries)? {
+                    break;
+                }
+            }
+
+            Ok(entries)
+        })
+    }
+}
+
+/// Moves the complete records at the front of `pending` into `entries`, leaving a
+/// partial record behind for the next payload. Returns `true` once `DONE` is reached.
+/// `.` and `..` are left out.
+fn parse_dents(
+    format: DentFormat,
+    pending: &mut Vec<u8>,
+    entries: &mut Vec<RemoteEntry>,
+) -> Result<bool> {
+    let header_len = format.header_len();
+    let mut offset = 0;
+    let done = loop {
+        let record = &pending[offset..];
+        if record.len() < 8 {
+            break false;
+        }
+        let id = LittleEndian::read_u32(&record[0..4]);
+        if id == MessageSubcommand::Done as u32 {
+            break true;
+        }
+        if id == MessageSubcommand::Fail as u32 {
+            let len = LittleEndian::read_u32(&record[4..8]) as usize;
+            if record.len() < 8 + len {
+                break false;
+            }
+            return Err(RustADBError::ADBRequestFailed(
+                String::from_utf8_lossy(&record[8..8 + len]).into_owned(),
+            ));
+        }
+        if id != format.id() as u32 {
+            return Err(RustADBError::WrongResponseReceived(
+                String::from_utf8_lossy(&record[0..4]).into_owned(),
+                format!("{:?}", format.id()).to_uppercase(),
+            ));
+        }
+        if record.len() < header_len {
+            break false;
+        }
+        let name_len = LittleEndian::read_u32(&record[header_len - 4..header_len]) as usize;
+        if record.len() < header_len + name_len {
+            break false;
+        }
+
+        let name = String::from_utf8_lossy(&record[header_len..header_len + name_len]);
+        if name != "." && name != ".." {
+            entries.extend(format.entry(&record[..header_len], &name));
+        }
+        offset += header_len + name_len;
+    };
+    pending.drain(..offset);
+    Ok(done)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dent(id: &[u8; 4], mode: u32, size: u32, mtime: u32, name: &str) -> Vec<u8> {
+        let mut record = id.to_vec();
+        for field in [mode, size, mtime, name.len() as u32] {
+            record.extend_from_slice(&field.to_le_bytes());
+        }
+        record.extend_from_slice(name.as_bytes());
+        record
+    }
+
+    #[test]
+    fn records_split_across_payloads() {
+        let mut stream = dent(b"DENT", 0o040_771, 4096, 10, ".");
+        stream.extend(dent(b"DENT", 0o100_644, 12, 20, "notes.txt"));
+        stream.extend(dent(b"DENT", 0o040_771, 4096, 30, "DCIM"));
+        stream.extend(dent(b"DONE", 0, 0, 0, ""));
+
+        let (first, second) = stream.split_at(40);
+        let mut pending = first.to_vec();
+        let mut entries = Vec::new();
+        assert!(!parse_dents(DentFormat::V1, &mut pending, &mut entries).unwrap());
+        pending.extend_from_slice(second);
+        assert!(parse_dents(DentFormat::V1, &mut pending, &mut entries).unwrap());
+
+        assert_eq!(
+            entries,
+            vec![
+                RemoteEntry::new("notes.txt", 0o100_644, 12, 20),
+                RemoteEntry::new("DCIM", 0o040_771, 4096, 30),
+            ]
+        );
+    }
+
+    /// `AID_MEDIA_RW`, owner of files on shared storage.
+    const MEDIA_RW: u32 = 1023;
+
+    fn dent_v2(id: &[u8; 4], error: u32, mode: u32, size: u64, mtime: i64, name: &str) -> Vec<u8> {
+        let mut record = id.to_vec();
+        record.extend_from_slice(&error.to_le_bytes());
+        record.extend_from_slice(&[0; 16]); // dev, ino
+        record.extend_from_slice(&mode.to_le_bytes());
+        record.extend_from_slice(&1u32.to_le_bytes()); // nlink
+        record.extend_from_slice(&MEDIA_RW.to_le_bytes()); // uid
+        record.extend_from_slice(&MEDIA_RW.to_le_bytes()); // gid
+        record.extend_from_slice(&size.to_le_bytes());
+        record.extend_from_slice(&[0; 8]); // atime
+        record.extend_from_slice(&mtime.to_le_bytes());
+        record.extend_from_slice(&[0; 8]); // ctime
+        record.extend_from_slice(&(name.len() as u32).to_le_bytes());
+        record.extend_from_slice(name.as_bytes());
+        record
+    }
+
+    #[test]
+    fn v2_records_keep_large_sizes() {
+        let mut stream = dent_v2(b"DNT2", 0, 0o100_644, 5 << 30, 40, "movie.mkv");
+        stream.extend(dent_v2(b"DNT2", 13, 0, 0, 0, "private"));
+        stream.extend(dent_v2(b"DONE", 0, 0, 0, 0, ""));
+
+        let mut entries = Vec::new();
+        assert!(parse_dents(DentFormat::V2, &mut stream, &mut entries).unwrap());
+        assert_eq!(
+            entries,
+            vec![
+                RemoteEntry::new("movie.mkv", 0o100_644, 5 << 30, 40)
+                    .with_owner(MEDIA_RW, MEDIA_RW)
+            ]
+        );
+    }
+}
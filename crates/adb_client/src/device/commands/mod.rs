@@ -1,8 +1,15 @@
+mod abb;
+mod backup;
 mod framebuffer;
 mod install;
+mod list;
 mod pull;
 mod push;
 mod reboot;
+mod root;
+mod screencap;
 mod shell;
+mod sideload;
 mod stat;
+mod tcpip;
 mod uninstall;
@@ -1,7 +1,7 @@
 use std::io::Write;
 
 use crate::{
-    ADBMessageTransport, Result, RustADBError,
+    ADBMessageTransport, Result,
     device::{
         ADBTransportMessage, MessageCommand,
         adb_message_device::{self, ADBMessageDevice},
@@ -11,42 +11,51 @@ use crate::{
 
 impl<T: ADBMessageTransport> ADBMessageDevice<T> {
     pub(crate) fn pull<A: AsRef<str>, W: Write>(&mut self, source: A, output: W) -> Result<()> {
-        self.begin_synchronization()?;
-        let source = source.as_ref();
-
-        let adb_stat_response = self.stat_with_explicit_ids(source)?;
-
-        if adb_stat_response.file_perm == 0 {
-            return Err(RustADBError::UnknownResponseType(
-                "mode is 0: source file does not exist".to_string(),
-            ));
-        }
-
-        let local_id = self.get_local_id()?;
-        let remote_id = self.get_remote_id()?;
-
-        self.get_transport_mut().write_message_with_timeout(
-            ADBTransportMessage::new(MessageCommand::Okay, local_id, remote_id, &[]),
-            std::time::Duration::from_secs(4),
-        )?;
-
-        let recv_buffer = MessageSubcommand::Recv.with_arg(u32::try_from(source.len())?);
-        let recv_buffer = adb_message_device::bincode_serialize_to_vec(&recv_buffer)?;
-        self.send_and_expect_okay(ADBTransportMessage::new(
-            MessageCommand::Write,
-            self.get_local_id()?,
-            self.get_remote_id()?,
-            &recv_buffer,
-        ))?;
-        self.send_and_expect_okay(ADBTransportMessage::new(
-            MessageCommand::Write,
-            self.get_local_id()?,
-            self.get_remote_id()?,
-            source.as_bytes(),
-        ))?;
-
-        self.recv_file(output)?;
-        self.end_transaction()?;
-        Ok(())
+        let compression = self.sync_compression();
+        self.sync_transaction(|device| {
+            let source = source.as_ref();
+
+            device.stat_with_explicit_ids(source)?.into_result(source)?;
+
+            let local_id = device.get_local_id()?;
+            let remote_id = device.get_remote_id()?;
+            let acked = device.delayed_ack().then_some(0);
+
+            device.get_transport_mut().write_message_with_timeout(
+                ADBTransportMessage::okay(local_id, remote_id, acked),
+                std::time::Duration::from_secs(4),
+            )?;
+
+            let subcommand = if compression.is_some() {
+                MessageSubcommand::Rcv2
+            } else {
+                MessageSubcommand::Recv
+            };
+            let recv_buffer = subcommand.with_arg(u32::try_from(source.len())?);
+            let recv_buffer = adb_message_device::bincode_serialize_to_vec(&recv_buffer)?;
+            device.send_and_expect_okay(ADBTransportMessage::new(
+                MessageCommand::Write,
+                device.get_local_id()?,
+                device.get_remote_id()?,
+                &recv_buffer,
+            ))?;
+            device.send_and_expect_okay(ADBTransportMessage::new(
+                MessageCommand::Write,
+                device.get_local_id()?,
+                device.get_remote_id()?,
+                source.as_bytes(),
+            ))?;
+            if let Some(compression) = compression {
+                let setup = MessageSubcommand::Rcv2.with_arg(compression.flag());
+                device.send_and_expect_okay(ADBTransportMessage::new(
+                    MessageCommand::Write,
+                    device.get_local_id()?,
+                    device.get_remote_id()?,
+                    &adb_message_device::bincode_serialize_to_vec(&setup)?,
+                ))?;
+            }
+
+            device.recv_file(output, compression)
+        })
     }
 }
@@ -8,27 +8,62 @@ use crate::{
     },
 };
 
+/// Mode pushed files are created with, before the device's umask.
+const PUSH_MODE: u32 = 0o777;
+
 impl<T: ADBMessageTransport> ADBMessageDevice<T> {
     pub(crate) fn push<R: Read, A: AsRef<str>>(&mut self, stream: R, path: A) -> Result<()> {
-        self.begin_synchronization()?;
-
-        let path_header = format!("{},0777", path.as_ref());
-
-        let send_buffer = MessageSubcommand::Send.with_arg(u32::try_from(path_header.len())?);
-        let mut send_buffer = adb_message_device::bincode_serialize_to_vec(&send_buffer)?;
-        send_buffer.append(&mut path_header.as_bytes().to_vec());
-
-        self.send_and_expect_okay(ADBTransportMessage::new(
-            MessageCommand::Write,
-            self.get_local_id()?,
-            self.get_remote_id()?,
-            &send_buffer,
-        ))?;
+        self.push_with_mtime(stream, path, 0)
+    }
 
-        self.push_file(self.get_local_id()?, self.get_remote_id()?, stream)?;
+    /// Same as `push`, with `mtime` sent in the closing `DONE` so adbd stamps the file with it.
+    pub(crate) fn push_with_mtime<R: Read, A: AsRef<str>>(
+        &mut self,
+        stream: R,
+        path: A,
+        mtime: u32,
+    ) -> Result<()> {
+        let compression = self.sync_compression();
+        self.sync_transaction(|device| {
+            let send_buffer = match compression {
+                // SND2 carries the mode in its setup packet rather than after the path.
+                Some(compression) => {
+                    let path = path.as_ref();
+                    let send_buffer = MessageSubcommand::Snd2.with_arg(u32::try_from(path.len())?);
+                    let mut send_buffer =
+                        adb_message_device::bincode_serialize_to_vec(&send_buffer)?;
+                    send_buffer.extend_from_slice(path.as_bytes());
+                    let setup = MessageSubcommand::Snd2.with_arg(PUSH_MODE);
+                    send_buffer.append(&mut adb_message_device::bincode_serialize_to_vec(&setup)?);
+                    send_buffer.extend_from_slice(&compression.flag().to_le_bytes());
+                    send_buffer
+                }
+                None => {
+                    let path_header = format!("{},0{PUSH_MODE:o}", path.as_ref());
+                    let send_buffer =
+                        MessageSubcommand::Send.with_arg(u32::try_from(path_header.len())?);
+                    let mut send_buffer =
+                        adb_message_device::bincode_serialize_to_vec(&send_buffer)?;
+                    send_buffer.append(&mut path_header.as_bytes().to_vec());
+                    send_buffer
+                }
+            };
 
-        self.end_transaction()?;
+            device.send_and_expect_okay(ADBTransportMessage::new(
+                MessageCommand::Write,
+                device.get_local_id()?,
+                device.get_remote_id()?,
+                &send_buffer,
+            ))?;
 
-        Ok(())
+            let (local_id, remote_id) = (device.get_local_id()?, device.get_remote_id()?);
+            match compression {
+                Some(compression) => {
+                    let stream = compression.encoder(stream)?;
+                    device.push_file(local_id, remote_id, stream, mtime)
+                }
+                None => device.push_file(local_id, remote_id, stream, mtime),
+            }
+        })
     }
 }
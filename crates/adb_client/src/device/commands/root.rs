@@ -0,0 +1,36 @@
+use crate::{
+    ADBMessageTransport, Result,
+    device::{MessageCommand, adb_message_device::ADBMessageDevice},
+    utils::{check_remount_output, parse_adbd_restart},
+};
+
+impl<T: ADBMessageTransport> ADBMessageDevice<T> {
+    /// Runs `root:` or `unroot:`, returning whether adbd is restarting. The connection is
+    /// unusable then, until connecting again once adbd is back.
+    pub(crate) fn restart_adbd(&mut self, service: &str) -> Result<bool> {
+        self.open_session(format!("{service}\0").as_bytes())?;
+
+        let mut output = Vec::new();
+        loop {
+            match self.recv_and_reply_okay() {
+                Ok(message) => match message.header().command() {
+                    MessageCommand::Write => output.extend_from_slice(&message.into_payload()),
+                    MessageCommand::Clse => break,
+                    _ => {}
+                },
+                // adbd may exit before closing the stream.
+                Err(error) if output.starts_with(b"restarting") => {
+                    log::debug!("connection lost while adbd restarts: {error}");
+                    break;
+                }
+                Err(error) => return Err(error),
+            }
+        }
+        parse_adbd_restart(&String::from_utf8_lossy(&output))
+    }
+
+    pub(crate) fn remount(&mut self) -> Result<String> {
+        self.open_session(b"remount:\0")?;
+        check_remount_output(&self.read_exec_output()?)
+    }
+}
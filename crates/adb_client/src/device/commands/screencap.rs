@@ -0,0 +1,23 @@
+use crate::{
+    ADBDeviceExt, ADBMessageTransport, Result,
+    device::{MessageCommand, adb_message_device::ADBMessageDevice},
+};
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+impl<T: ADBMessageTransport> ADBMessageDevice<T> {
+    /// Runs `screencap -p` through `exec:`, which passes its binary output through untouched
+    /// where `shell:` may translate newlines. Recovery and some emulators have no working
+    /// `screencap`, so anything but a PNG falls back to the framebuffer service.
+    pub(crate) fn screencap(&mut self) -> Result<Vec<u8>> {
+        let response = self.open_session(b"exec:screencap -p\0")?;
+        if response.header().command() == MessageCommand::Okay {
+            let png = self.read_exec_bytes()?;
+            if png.starts_with(PNG_SIGNATURE) {
+                return Ok(png);
+            }
+        }
+        log::debug!("screencap gave no PNG, reading the framebuffer instead");
+        self.framebuffer_bytes()
+    }
+}
@@ -1,15 +1,39 @@
 use std::io::{ErrorKind, Read, Write};
+use std::ops::ControlFlow;
 
-use crate::Result;
-use crate::device::ShellMessageWriter;
+use crate::device::{ShellMessageWriter, shell_v2};
 use crate::{
     ADBMessageTransport, RustADBError,
     device::{ADBMessageDevice, ADBTransportMessage, MessageCommand},
 };
+use crate::{Result, ShellOutput};
 
 impl<T: ADBMessageTransport> ADBMessageDevice<T> {
     /// Runs 'command' in a shell on the device, and write its output and error streams into output.
     pub(crate) fn shell_command(&mut self, command: &[&str], output: &mut dyn Write) -> Result<()> {
+        self.shell_command_with(command, &mut |chunk| {
+            output.write_all(chunk)?;
+            Ok(ControlFlow::Continue(()))
+        })
+    }
+
+    /// Runs 'command' in a shell on the device, handing output chunks to `on_chunk` as they arrive.
+    pub(crate) fn shell_command_stream(
+        &mut self,
+        command: &[&str],
+        on_chunk: &mut dyn FnMut(&[u8]) -> ControlFlow<()>,
+    ) -> Result<()> {
+        self.shell_command_with(command, &mut |chunk| Ok(on_chunk(chunk)))
+    }
+
+    /// Each chunk is only acknowledged once `on_chunk` returns, which is what keeps adbd from
+    /// sending the next one (or, with `delayed_ack`, from filling its window again). On [`ControlFlow::Break`] the stream is closed and chunks still in
+    /// flight are dropped.
+    fn shell_command_with(
+        &mut self,
+        command: &[&str],
+        on_chunk: &mut dyn FnMut(&[u8]) -> Result<ControlFlow<()>>,
+    ) -> Result<()> {
         let response = self.open_session(format!("shell:{}\0", command.join(" "),).as_bytes())?;
 
         if response.header().command() != MessageCommand::Okay {
@@ -22,26 +46,40 @@ impl<T: ADBMessageTransport> ADBMessageDevice<T> {
         let local_id = self.get_local_id()?;
         let remote_id = self.get_remote_id()?;
 
+        let mut closing = false;
         loop {
-            let response = self.get_transport_mut().read_message()?;
+            let response = self.read_message()?;
             match response.header().command() {
+                MessageCommand::Write if closing => {
+                    // sent before the device saw our close
+                }
                 MessageCommand::Write => {
-                    output.write_all(&response.into_payload())?;
-                    let ack =
-                        ADBTransportMessage::new(MessageCommand::Okay, local_id, remote_id, &[]);
-                    self.get_transport_mut().write_message(ack)?;
+                    if on_chunk(response.payload())?.is_break() {
+                        let close = ADBTransportMessage::new(
+                            MessageCommand::Clse,
+                            local_id,
+                            remote_id,
+                            &[],
+                        );
+                        self.write_message(close)?;
+                        closing = true;
+                        continue;
+                    }
+                    self.write_message(self.ack(local_id, remote_id, &response))?;
                 }
                 MessageCommand::Okay => {
                     // nothing to do, device acknowledged a previous write
                 }
                 MessageCommand::Clse => {
-                    let close = ADBTransportMessage::new(
-                        MessageCommand::Clse,
-                        local_id,
-                        remote_id,
-                        &[],
-                    );
-                    self.get_transport_mut().write_message(close)?;
+                    if !closing {
+                        let close = ADBTransportMessage::new(
+                            MessageCommand::Clse,
+                            local_id,
+                            remote_id,
+                            &[],
+                        );
+                        self.write_message(close)?;
+                    }
                     break;
                 }
                 other => {
@@ -56,6 +94,63 @@ impl<T: ADBMessageTransport> ADBMessageDevice<T> {
         Ok(())
     }
 
+    /// Runs 'command' with the shell v2 protocol, which keeps stderr apart and reports the exit
+    /// status. Devices without `shell_v2` get a plain `shell:` and only merged output back.
+    pub(crate) fn shell_command_output(&mut self, command: &[&str]) -> Result<ShellOutput> {
+        if !self.has_feature("shell_v2") {
+            let mut stdout = Vec::new();
+            self.shell_command(command, &mut stdout)?;
+            return Ok(ShellOutput {
+                stdout,
+                ..ShellOutput::default()
+            });
+        }
+
+        self.shell_v2_service_output(format!("shell,v2,raw:{}\0", command.join(" ")).as_bytes())
+    }
+
+    /// Opens `service`, which must speak the shell v2 protocol, and collects its output.
+    pub(crate) fn shell_v2_service_output(&mut self, service: &[u8]) -> Result<ShellOutput> {
+        let response = self.open_session(service)?;
+        if response.header().command() != MessageCommand::Okay {
+            return Err(RustADBError::ADBRequestFailed(format!(
+                "wrong command {}",
+                response.header().command()
+            )));
+        }
+
+        let local_id = self.get_local_id()?;
+        let remote_id = self.get_remote_id()?;
+
+        let mut pending = Vec::new();
+        let mut output = ShellOutput::default();
+        loop {
+            let response = self.read_message()?;
+            match response.header().command() {
+                MessageCommand::Write => {
+                    self.write_message(self.ack(local_id, remote_id, &response))?;
+                    pending.extend_from_slice(&response.into_payload());
+                    parse_shell_v2(&mut pending, &mut output);
+                }
+                MessageCommand::Okay => {}
+                MessageCommand::Clse => {
+                    let close =
+                        ADBTransportMessage::new(MessageCommand::Clse, local_id, remote_id, &[]);
+                    self.write_message(close)?;
+                    break;
+                }
+                other => {
+                    return Err(RustADBError::WrongResponseReceived(
+                        other.to_string(),
+                        MessageCommand::Write.to_string(),
+                    ));
+                }
+            }
+        }
+
+        Ok(output)
+    }
+
     /// Starts an interactive shell session on the device.
     /// Input data is read from [reader] and write to [writer].
     pub(crate) fn shell(
@@ -66,6 +161,7 @@ impl<T: ADBMessageTransport> ADBMessageDevice<T> {
         self.open_session(b"shell:\0")?;
 
         let mut transport = self.get_transport().clone();
+        let delayed_ack = self.delayed_ack();
 
         let local_id = self.get_local_id()?;
         let remote_id = self.get_remote_id()?;
@@ -76,8 +172,8 @@ impl<T: ADBMessageTransport> ADBMessageDevice<T> {
                 let message = transport.read_message()?;
 
                 // Acknowledge for more data
-                let response =
-                    ADBTransportMessage::new(MessageCommand::Okay, local_id, remote_id, &[]);
+                let acked = delayed_ack.then(|| message.header().data_length());
+                let response = ADBTransportMessage::okay(local_id, remote_id, acked);
                 transport.write_message(response)?;
 
                 match message.header().command() {
@@ -105,3 +201,43 @@ impl<T: ADBMessageTransport> ADBMessageDevice<T> {
         Ok(())
     }
 }
+
+/// Moves the complete packets at the front of `pending` into `output`.
+fn parse_shell_v2(pending: &mut Vec<u8>, output: &mut ShellOutput) {
+    while let Some((id, data)) = shell_v2::take_packet(pending) {
+        match id {
+            shell_v2::STDOUT => output.stdout.extend(data),
+            shell_v2::STDERR => output.stderr.extend(data),
+            shell_v2::EXIT => output.exit_code = data.first().copied(),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packets_split_across_payloads() {
+        let mut stream = shell_v2::packet(shell_v2::STDOUT, b"created\n").unwrap();
+        stream.extend(
+            shell_v2::packet(shell_v2::STDERR, b"mkdir: '/sdcard/x': Permission denied\n").unwrap(),
+        );
+        stream.extend(shell_v2::packet(shell_v2::EXIT, &[1]).unwrap());
+
+        let (first, second) = stream.split_at(20);
+        let mut pending = first.to_vec();
+        let mut output = ShellOutput::default();
+        parse_shell_v2(&mut pending, &mut output);
+        assert!(output.stderr.is_empty());
+        pending.extend_from_slice(second);
+        parse_shell_v2(&mut pending, &mut output);
+
+        assert!(pending.is_empty());
+        assert_eq!(output.stdout, b"created\n");
+        assert_eq!(output.stderr, b"mkdir: '/sdcard/x': Permission denied\n");
+        assert_eq!(output.exit_code, Some(1));
+        assert!(output.failed());
+    }
+}
@@ -0,0 +1,62 @@
+use std::{
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+    path::Path,
+};
+
+use crate::{
+    ADBMessageTransport, Result, RustADBError,
+    device::{ADBTransportMessage, MessageCommand, adb_message_device::ADBMessageDevice},
+    utils::{SIDELOAD_BLOCK_SIZE, parse_sideload_request},
+};
+
+impl<T: ADBMessageTransport> ADBMessageDevice<T> {
+    /// Serves the package through `sideload-host:`, where the device asks for each block it
+    /// wants by number rather than receiving the package as a stream.
+    pub(crate) fn sideload(&mut self, package_path: &dyn AsRef<Path>) -> Result<()> {
+        let mut package = File::open(package_path)?;
+        let size = package.metadata()?.len();
+
+        self.open_session(format!("sideload-host:{size}:{SIDELOAD_BLOCK_SIZE}\0").as_bytes())?
+            .assert_command(MessageCommand::Okay)?;
+
+        let (local_id, remote_id) = (self.get_local_id()?, self.get_remote_id()?);
+        let max_payload = self.max_payload();
+        let mut block = Vec::new();
+        loop {
+            let message = self.recv_and_reply_okay()?;
+            match message.header().command() {
+                MessageCommand::Write => {}
+                MessageCommand::Clse => {
+                    return Err(RustADBError::ADBRequestFailed(
+                        "device closed the sideload before receiving the whole package".into(),
+                    ));
+                }
+                _ => continue,
+            }
+            let Some(range) = parse_sideload_request(&message.into_payload(), size)? else {
+                break;
+            };
+
+            package.seek(SeekFrom::Start(range.start))?;
+            block.resize(usize::try_from(range.end - range.start)?, 0);
+            package.read_exact(&mut block)?;
+            for chunk in block.chunks(max_payload) {
+                self.send_data(ADBTransportMessage::new(
+                    MessageCommand::Write,
+                    local_id,
+                    remote_id,
+                    chunk,
+                ))?;
+            }
+        }
+
+        // Recovery installs the package after closing the stream.
+        while self.recv_and_reply_okay()?.header().command() != MessageCommand::Clse {}
+        log::info!(
+            "package {} successfully sideloaded",
+            package_path.as_ref().display()
+        );
+        Ok(())
+    }
+}
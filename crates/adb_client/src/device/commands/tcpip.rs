@@ -0,0 +1,32 @@
+use std::net::SocketAddr;
+
+use crate::{
+    ADBMessageTransport, Result, RustADBError,
+    device::{MessageCommand, adb_message_device::ADBMessageDevice},
+    utils::parse_wifi_address,
+};
+
+impl<T: ADBMessageTransport> ADBMessageDevice<T> {
+    /// Restarts adbd listening on TCP `port` and returns the address to reach it at. The
+    /// address is looked up first, since the current connection drops once adbd restarts.
+    pub(crate) fn enable_tcpip(&mut self, port: u16) -> Result<SocketAddr> {
+        let mut routes = Vec::new();
+        self.shell_command(&["ip", "route"], &mut routes)?;
+        let address = parse_wifi_address(&String::from_utf8_lossy(&routes)).ok_or(
+            RustADBError::ADBRequestFailed("device has no network address, is Wi-Fi on?".into()),
+        )?;
+
+        self.open_session(format!("tcpip:{port}\0").as_bytes())?
+            .assert_command(MessageCommand::Okay)?;
+
+        // adbd answers "restarting in TCP mode port: <port>" right before restarting, so
+        // its stream may never be closed.
+        let reply = self.recv_and_reply_okay()?;
+        let reply = String::from_utf8_lossy(reply.payload());
+        if !reply.starts_with("restarting") {
+            return Err(RustADBError::ADBRequestFailed(reply.trim().to_string()));
+        }
+
+        Ok(SocketAddr::new(address, port))
+    }
+}
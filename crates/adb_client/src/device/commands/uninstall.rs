@@ -1,19 +1,17 @@
-use crate::{ADBMessageTransport, Result, device::adb_message_device::ADBMessageDevice};
+use crate::{
+    ADBMessageTransport, Result, device::adb_message_device::ADBMessageDevice,
+    utils::check_package_manager_output,
+};
 
 impl<T: ADBMessageTransport> ADBMessageDevice<T> {
-    pub(crate) fn uninstall(&mut self, package_name: &str) -> Result<()> {
-        self.open_session(format!("exec:cmd package 'uninstall' {package_name}\0").as_bytes())?;
-
-        let final_status = self.get_transport_mut().read_message()?;
-
-        match final_status.into_payload().as_slice() {
-            b"Success\n" => {
-                log::info!("Package {package_name} successfully uninstalled");
-                Ok(())
-            }
-            d => Err(crate::RustADBError::ADBRequestFailed(String::from_utf8(
-                d.to_vec(),
-            )?)),
-        }
+    pub(crate) fn uninstall(&mut self, package_name: &str, keep_data: bool) -> Result<()> {
+        let args: &[&str] = if keep_data {
+            &["uninstall", "-k", package_name]
+        } else {
+            &["uninstall", package_name]
+        };
+        check_package_manager_output(&self.package_command(args)?)?;
+        log::info!("Package {package_name} successfully uninstalled");
+        Ok(())
     }
 }
@@ -0,0 +1,205 @@
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use rusb::{Context, Device, Hotplug, HotplugBuilder, UsbContext};
+
+use super::is_adb_device;
+use crate::Result;
+
+/// How often the watcher checks whether it was stopped, and how often devices are listed
+/// where libusb cannot report hotplug events (Windows).
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Identifies an ADB device by its USB IDs and where it is attached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct UsbDeviceId {
+    /// USB vendor ID
+    pub vendor_id: u16,
+    /// USB product ID
+    pub product_id: u16,
+    /// Bus the device is attached to
+    pub bus_number: u8,
+    /// Address of the device on its bus, which changes each time it is plugged in
+    pub address: u8,
+}
+
+impl UsbDeviceId {
+    fn of<T: UsbContext>(device: &Device<T>) -> Option<Self> {
+        let descriptor = device.device_descriptor().ok()?;
+        is_adb_device(device, &descriptor).then(|| Self {
+            vendor_id: descriptor.vendor_id(),
+            product_id: descriptor.product_id(),
+            bus_number: device.bus_number(),
+            address: device.address(),
+        })
+    }
+
+    fn is_at<T: UsbContext>(&self, device: &Device<T>) -> bool {
+        (self.bus_number, self.address) == (device.bus_number(), device.address())
+    }
+}
+
+/// Change reported by [`DeviceWatcher`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceEvent {
+    /// An ADB device was plugged in, or was already attached when watching started
+    Connected(UsbDeviceId),
+    /// An ADB device was unplugged, or left ADB mode
+    Disconnected(UsbDeviceId),
+}
+
+/// ADB devices attached so far, turning arrivals and departures into events.
+struct Tracker {
+    attached: HashSet<UsbDeviceId>,
+    sender: Sender<DeviceEvent>,
+    stop: Arc<AtomicBool>,
+}
+
+impl Tracker {
+    fn send(&self, event: DeviceEvent) {
+        // Nobody listens anymore, so there is no point in watching.
+        if self.sender.send(event).is_err() {
+            self.stop.store(true, Ordering::Relaxed);
+        }
+    }
+
+    fn arrived(&mut self, id: UsbDeviceId) {
+        if self.attached.insert(id) {
+            self.send(DeviceEvent::Connected(id));
+        }
+    }
+
+    fn left<T: UsbContext>(&mut self, device: &Device<T>) {
+        // Descriptors can't always be read from a device that is gone, so it is matched by
+        // where it was attached.
+        let left = self.attached.iter().copied().find(|id| id.is_at(device));
+        if let Some(id) = left {
+            self.attached.remove(&id);
+            self.send(DeviceEvent::Disconnected(id));
+        }
+    }
+
+    /// Reconciles with the devices currently listed, for platforms without hotplug events.
+    fn refresh(&mut self) -> Result<()> {
+        let current: HashSet<UsbDeviceId> = rusb::devices()?
+            .iter()
+            .filter_map(|device| UsbDeviceId::of(&device))
+            .collect();
+        let left: Vec<UsbDeviceId> = self.attached.difference(&current).copied().collect();
+        for id in left {
+            self.attached.remove(&id);
+            self.send(DeviceEvent::Disconnected(id));
+        }
+        for id in current {
+            self.arrived(id);
+        }
+        Ok(())
+    }
+}
+
+struct HotplugHandler(Arc<Mutex<Tracker>>);
+
+impl Hotplug<Context> for HotplugHandler {
+    fn device_arrived(&mut self, device: Device<Context>) {
+        if let Some(id) = UsbDeviceId::of(&device) {
+            self.0
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .arrived(id);
+        }
+    }
+
+    fn device_left(&mut self, device: Device<Context>) {
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .left(&device);
+    }
+}
+
+/// Watches for ADB devices being plugged in and unplugged, using libusb hotplug events
+/// where available and listing devices periodically elsewhere.
+#[derive(Debug, Default)]
+pub struct DeviceWatcher {
+    stop: Arc<AtomicBool>,
+    thread_handle: Option<JoinHandle<Result<()>>>,
+}
+
+impl DeviceWatcher {
+    /// Instantiate a new watcher, not watching yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start watching on a new thread, sending a [`DeviceEvent`] to `sender` for each change.
+    /// Devices already attached are reported as connected first.
+    ///
+    /// Watching stops with [`DeviceWatcher::stop`], when the watcher is dropped, or when the
+    /// receiving end of `sender` is.
+    pub fn start(&mut self, sender: Sender<DeviceEvent>) -> Result<()> {
+        if let Err(e) = self.stop_thread() {
+            log::warn!("previous device watch failed: {e}");
+        }
+        let stop = Arc::new(AtomicBool::new(false));
+        self.stop = stop.clone();
+        let tracker = Arc::new(Mutex::new(Tracker {
+            attached: HashSet::new(),
+            sender,
+            stop: stop.clone(),
+        }));
+
+        let handle: JoinHandle<Result<()>> = if rusb::has_hotplug() {
+            let context = Context::new()?;
+            let handler: Box<dyn Hotplug<Context>> = Box::new(HotplugHandler(tracker));
+            let registration = HotplugBuilder::new()
+                .enumerate(true)
+                .register(&context, handler)?;
+
+            std::thread::spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    context.handle_events(Some(POLL_INTERVAL))?;
+                }
+                drop(registration);
+                Ok(())
+            })
+        } else {
+            std::thread::spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    tracker
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .refresh()?;
+                    std::thread::sleep(POLL_INTERVAL);
+                }
+                Ok(())
+            })
+        };
+
+        self.thread_handle = Some(handle);
+        Ok(())
+    }
+
+    /// Stop watching, returning the error that ended watching early, if any.
+    pub fn stop(&mut self) -> Result<()> {
+        self.stop_thread()
+    }
+
+    fn stop_thread(&mut self) -> Result<()> {
+        self.stop.store(true, Ordering::Relaxed);
+        match self.thread_handle.take() {
+            Some(handle) => handle.join().unwrap_or(Ok(())),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for DeviceWatcher {
+    fn drop(&mut self) {
+        // Best effort here
+        let _ = self.stop_thread();
+    }
+}
@@ -4,9 +4,18 @@ mod adb_tcp_device;
 mod adb_transport_message;
 mod adb_usb_device;
 mod commands;
+mod device_watcher;
 mod message_writer;
 mod models;
+mod pairing;
+mod port_forward;
 mod shell_message_writer;
+mod shell_session;
+mod shell_v2;
+mod spake2;
+mod stream_manager;
+mod sync_compression;
+mod sync_data_reader;
 
 use adb_message_device::ADBMessageDevice;
 pub use adb_tcp_device::ADBTcpDevice;
@@ -14,6 +23,9 @@ pub use adb_transport_message::{ADBTransportMessage, ADBTransportMessageHeader};
 pub use adb_usb_device::{
     ADBUSBDevice, get_default_adb_key_path, is_adb_device, search_adb_devices,
 };
+pub use device_watcher::{DeviceEvent, DeviceWatcher, UsbDeviceId};
 pub use message_writer::MessageWriter;
 pub use models::{ADBRsaKey, MessageCommand, MessageSubcommand};
 pub use shell_message_writer::ShellMessageWriter;
+pub use shell_session::ADBShellSession;
+pub use stream_manager::{ADBStream, ADBStreamManager};
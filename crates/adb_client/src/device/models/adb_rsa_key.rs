@@ -1,14 +1,22 @@
 use crate::{Result, RustADBError};
 use base64::{Engine, engine::general_purpose::STANDARD};
+use md5::{Digest, Md5};
 use num_bigint::{BigUint, ModInverse};
 use num_traits::FromPrimitive;
 use num_traits::cast::ToPrimitive;
-use rsa::pkcs8::DecodePrivateKey;
+use rsa::pkcs8::{DecodePrivateKey, EncodePrivateKey, LineEnding};
 use rsa::traits::PublicKeyParts;
 use rsa::{Pkcs1v15Sign, RsaPrivateKey};
 use std::fmt::Write;
+use std::fs;
+use std::io::{self, Write as _};
+use std::path::Path;
 
 const ADB_PRIVATE_KEY_SIZE: usize = 2048;
+/// Variable listing extra keys to authenticate with, as understood by `adb`.
+const ADB_VENDOR_KEYS: &str = "ADB_VENDOR_KEYS";
+/// Extension of the keys picked up from directories listed in [`ADB_VENDOR_KEYS`].
+const VENDOR_KEY_EXTENSION: &str = "adb_key";
 const ANDROID_PUBKEY_MODULUS_SIZE_WORDS: u32 = 64;
 
 #[repr(C)]
@@ -44,25 +52,135 @@ impl ADBRsaInternalPublicKey {
     }
 }
 
+/// RSA key pair this host authenticates with, which devices remember once the user allows
+/// debugging from it. Stored like `adb` does, e.g. in `~/.android/adbkey` and `adbkey.pub`.
 #[derive(Debug, Clone)]
 pub struct ADBRsaKey {
     private_key: RsaPrivateKey,
 }
 
 impl ADBRsaKey {
+    /// Generates a new key pair
     pub fn new_random() -> Result<Self> {
         Ok(Self {
             private_key: RsaPrivateKey::new(&mut rsa::rand_core::OsRng, ADB_PRIVATE_KEY_SIZE)?,
         })
     }
 
+    /// Parses a private key in PKCS#8 PEM, as found in `adbkey`
     pub fn new_from_pkcs8(pkcs8_content: &str) -> Result<Self> {
         Ok(ADBRsaKey {
             private_key: RsaPrivateKey::from_pkcs8_pem(pkcs8_content)?,
         })
     }
 
+    /// Reads the private key at `path`, or returns `None` if there is no such file.
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        match fs::read_to_string(path) {
+            Ok(pk) => Self::new_from_pkcs8(&pk).map(Some),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Reads the private key at `path`, generating and saving a new key pair there if there
+    /// is none yet.
+    pub fn load_or_generate(path: &Path) -> Result<Self> {
+        if let Some(private_key) = Self::load(path)? {
+            return Ok(private_key);
+        }
+
+        log::info!(
+            "No private key found at path {}. Generating a new one.",
+            path.display()
+        );
+        let private_key = Self::new_random()?;
+        private_key.save(path)?;
+        Ok(private_key)
+    }
+
+    /// Loads the keys listed in `ADB_VENDOR_KEYS`, like `adb`: key files, or directories
+    /// whose `*.adb_key` files are loaded, separated as in `PATH`. Keys that cannot be read
+    /// are skipped.
+    pub fn load_vendor_keys() -> Vec<Self> {
+        let Some(paths) = std::env::var_os(ADB_VENDOR_KEYS) else {
+            return Vec::new();
+        };
+
+        let mut key_paths = Vec::new();
+        for path in std::env::split_paths(&paths) {
+            match fs::read_dir(&path) {
+                Ok(entries) => {
+                    for entry in entries.flatten() {
+                        let path = entry.path();
+                        if path
+                            .extension()
+                            .is_some_and(|extension| extension == VENDOR_KEY_EXTENSION)
+                        {
+                            key_paths.push(path);
+                        }
+                    }
+                }
+                Err(_) => key_paths.push(path),
+            }
+        }
+
+        key_paths
+            .into_iter()
+            .filter_map(|path| match Self::load(&path) {
+                Ok(Some(key)) => Some(key),
+                Ok(None) => None,
+                Err(e) => {
+                    log::warn!("skipping vendor key {}: {e}", path.display());
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Writes the private key to `path`, readable by the current user only, and the public
+    /// key next to it with a `.pub` extension added, like `adb keygen`.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        options
+            .open(path)?
+            .write_all(self.to_pkcs8_pem()?.as_bytes())?;
+
+        let mut public_key_path = path.as_os_str().to_owned();
+        public_key_path.push(".pub");
+        fs::write(public_key_path, self.android_pubkey_encode()? + "\n")?;
+        Ok(())
+    }
+
+    /// Private key in PKCS#8 PEM
+    pub fn to_pkcs8_pem(&self) -> Result<String> {
+        Ok(self.private_key.to_pkcs8_pem(LineEnding::LF)?.to_string())
+    }
+
+    /// Fingerprint of the public key as devices show it when asking whether to allow
+    /// debugging, e.g. `4E:1B:...:9C`: the MD5 of the key in adb's format.
+    pub fn fingerprint(&self) -> Result<String> {
+        let digest = Md5::digest(self.android_pubkey_bytes()?);
+        Ok(digest
+            .iter()
+            .map(|byte| format!("{byte:02X}"))
+            .collect::<Vec<_>>()
+            .join(":"))
+    }
+
+    /// Public key in adb's format, as sent to devices and stored in `adbkey.pub`
     pub fn android_pubkey_encode(&self) -> Result<String> {
+        Self::encode_public_key(self.android_pubkey_bytes()?)
+    }
+
+    fn android_pubkey_bytes(&self) -> Result<Vec<u8>> {
         // Helped from project: https://github.com/hajifkd/webadb
         // Source code: https://android.googlesource.com/platform/system/core/+/refs/heads/main/libcrypto_utils/android_pubkey.cpp
         // Useful function `android_pubkey_encode()`
@@ -93,7 +211,7 @@ impl ADBRsaKey {
             .to_u32()
             .ok_or(RustADBError::ConversionError)?;
 
-        Self::encode_public_key(adb_rsa_pubkey.into_bytes())
+        Ok(adb_rsa_pubkey.into_bytes())
     }
 
     fn encode_public_key(pub_key: Vec<u8>) -> Result<String> {
@@ -105,6 +223,7 @@ impl ADBRsaKey {
         Ok(encoded)
     }
 
+    /// Signs an `AUTH` token sent by a device
     pub fn sign(&self, msg: impl AsRef<[u8]>) -> Result<Vec<u8>> {
         Ok(self
             .private_key
@@ -32,6 +32,12 @@ pub enum MessageSubcommand {
     Done = 0x454E_4F44,
     Data = 0x4154_4144,
     List = 0x5453_494C,
+    Dent = 0x544E_4544,
+    Lst2 = 0x3254_534C,
+    Lis2 = 0x3253_494C,
+    Dnt2 = 0x3254_4E44,
+    Snd2 = 0x3244_4E53,
+    Rcv2 = 0x3256_4352,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
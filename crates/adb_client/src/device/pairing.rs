@@ -0,0 +1,184 @@
+use std::io::{ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes128Gcm, Nonce};
+use hkdf::Hkdf;
+use rustls::{ClientConnection, StreamOwned};
+use sha2::Sha256;
+
+use super::ADBRsaKey;
+use super::spake2::{Spake2, Spake2Role};
+use crate::transports::tls_client_config;
+use crate::{Result, RustADBError};
+
+// Source code: https://android.googlesource.com/platform/packages/modules/adb/+/refs/heads/main/pairing_connection/pairing_connection.cpp
+const PAIRING_PACKET_VERSION: u8 = 1;
+const SPAKE2_MSG: u8 = 0;
+const PEER_INFO: u8 = 1;
+/// Size of the peer info exchanged once both sides share a key, including its type byte.
+const PEER_INFO_SIZE: usize = 8192;
+/// Peer info type carrying an ADB public key.
+const ADB_RSA_PUB_KEY: u8 = 0;
+/// Largest packet payload adbd sends, an encrypted peer info leaving plenty of room.
+const MAX_PAYLOAD_SIZE: usize = 2 * PEER_INFO_SIZE;
+
+const EXPORTED_KEY_LABEL: &[u8] = b"adb-label\0";
+const EXPORTED_KEY_SIZE: usize = 64;
+const CLIENT_NAME: &[u8] = b"adb pair client\0";
+const SERVER_NAME: &[u8] = b"adb pair server\0";
+const AES_KEY_INFO: &[u8] = b"adb pairing_auth aes-128-gcm key";
+
+/// How long adbd is waited on for each step. It answers right away unless something is wrong.
+const PAIRING_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Pairs with the wireless debugging pairing service at `address` using the code shown on
+/// the device, so that adbd trusts the key at `private_key_path` from then on. The key is
+/// generated and saved there if the file does not exist yet.
+pub(crate) fn pair(address: SocketAddr, code: &str, private_key_path: &Path) -> Result<()> {
+    let private_key = ADBRsaKey::load_or_generate(private_key_path)?;
+
+    let stream = TcpStream::connect(address)?;
+    stream.set_read_timeout(Some(PAIRING_TIMEOUT))?;
+    stream.set_write_timeout(Some(PAIRING_TIMEOUT))?;
+
+    let client_config = Arc::new(tls_client_config(&private_key.to_pkcs8_pem()?)?);
+    let connection = ClientConnection::new(client_config, address.ip().into())?;
+    let mut tls = StreamOwned::new(connection, stream);
+    while tls.conn.is_handshaking() {
+        tls.conn.complete_io(&mut tls.sock)?;
+    }
+
+    // Binding the password to this TLS session keeps the exchange from being relayed.
+    let exported_key =
+        tls.conn
+            .export_keying_material([0; EXPORTED_KEY_SIZE], EXPORTED_KEY_LABEL, None)?;
+    let mut password = code.as_bytes().to_vec();
+    password.extend_from_slice(&exported_key);
+
+    let (spake2, message) = Spake2::start(Spake2Role::Alice, CLIENT_NAME, SERVER_NAME, &password)?;
+    write_packet(&mut tls, SPAKE2_MSG, &message)?;
+    let their_message = read_packet(&mut tls, SPAKE2_MSG)?;
+    let mut cipher = PairingCipher::new(&spake2.finish(&their_message)?)?;
+
+    let public_key = private_key.android_pubkey_encode()?;
+    // Keep at least one trailing NUL, adbd reads the key as a C string.
+    if public_key.len() >= PEER_INFO_SIZE - 1 {
+        return Err(RustADBError::ConversionError);
+    }
+    let mut peer_info = vec![0; PEER_INFO_SIZE];
+    peer_info[0] = ADB_RSA_PUB_KEY;
+    peer_info[1..=public_key.len()].copy_from_slice(public_key.as_bytes());
+    write_packet(&mut tls, PEER_INFO, &cipher.encrypt(&peer_info)?)?;
+
+    // adbd hangs up instead of answering when it cannot decrypt our peer info, which only
+    // happens when both sides derived different keys from different codes.
+    let their_peer_info = match read_packet(&mut tls, PEER_INFO) {
+        Err(RustADBError::IOError(e))
+            if matches!(
+                e.kind(),
+                ErrorKind::UnexpectedEof
+                    | ErrorKind::ConnectionReset
+                    | ErrorKind::ConnectionAborted
+            ) =>
+        {
+            return Err(RustADBError::PairingFailed(
+                "device rejected the pairing code".into(),
+            ));
+        }
+        result => result?,
+    };
+    cipher.decrypt(&their_peer_info)?;
+
+    tls.conn.send_close_notify();
+    let _ = tls.conn.complete_io(&mut tls.sock);
+
+    Ok(())
+}
+
+fn write_packet(stream: &mut impl Write, packet_type: u8, payload: &[u8]) -> Result<()> {
+    let size = u32::try_from(payload.len())?;
+    let mut packet = vec![PAIRING_PACKET_VERSION, packet_type];
+    packet.extend_from_slice(&size.to_be_bytes());
+    packet.extend_from_slice(payload);
+
+    stream.write_all(&packet)?;
+    stream.flush()?;
+    Ok(())
+}
+
+fn read_packet(stream: &mut impl Read, expected_type: u8) -> Result<Vec<u8>> {
+    let mut header = [0; 6];
+    stream.read_exact(&mut header)?;
+    let [version, packet_type, size @ ..] = header;
+
+    if version != PAIRING_PACKET_VERSION {
+        return Err(RustADBError::PairingFailed(format!(
+            "unsupported pairing packet version {version}"
+        )));
+    }
+    if packet_type != expected_type {
+        return Err(RustADBError::PairingFailed(format!(
+            "expected pairing packet type {expected_type}, got {packet_type}"
+        )));
+    }
+    let size = usize::try_from(u32::from_be_bytes(size))?;
+    if size > MAX_PAYLOAD_SIZE {
+        return Err(RustADBError::PairingFailed(format!(
+            "pairing packet too large: {size} bytes"
+        )));
+    }
+
+    let mut payload = vec![0; size];
+    stream.read_exact(&mut payload)?;
+    Ok(payload)
+}
+
+/// AES-128-GCM keyed from the SPAKE2 key material, with separate nonce counters per
+/// direction like adbd's.
+struct PairingCipher {
+    cipher: Aes128Gcm,
+    encrypt_sequence: u64,
+    decrypt_sequence: u64,
+}
+
+impl PairingCipher {
+    fn new(key_material: &[u8]) -> Result<Self> {
+        let mut key = [0; 16];
+        Hkdf::<Sha256>::new(None, key_material)
+            .expand(AES_KEY_INFO, &mut key)
+            .map_err(|_| RustADBError::ConversionError)?;
+        let cipher = Aes128Gcm::new_from_slice(&key).map_err(|_| RustADBError::ConversionError)?;
+
+        Ok(Self {
+            cipher,
+            encrypt_sequence: 0,
+            decrypt_sequence: 0,
+        })
+    }
+
+    fn encrypt(&mut self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let nonce = Self::nonce(self.encrypt_sequence);
+        self.encrypt_sequence += 1;
+        self.cipher
+            .encrypt(&Nonce::from(nonce), plaintext)
+            .map_err(|_| RustADBError::PairingFailed("cannot encrypt peer info".into()))
+    }
+
+    fn decrypt(&mut self, ciphertext: &[u8]) -> Result<Vec<u8>> {
+        let nonce = Self::nonce(self.decrypt_sequence);
+        self.decrypt_sequence += 1;
+        self.cipher
+            .decrypt(&Nonce::from(nonce), ciphertext)
+            .map_err(|_| RustADBError::PairingFailed("cannot decrypt device peer info".into()))
+    }
+
+    fn nonce(sequence: u64) -> [u8; 12] {
+        let mut nonce = [0; 12];
+        nonce[..8].copy_from_slice(&sequence.to_le_bytes());
+        nonce
+    }
+}
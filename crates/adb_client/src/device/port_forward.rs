@@ -0,0 +1,236 @@
+use std::io::{ErrorKind, Read, Write};
+use std::net::{Ipv4Addr, Shutdown, TcpListener, TcpStream};
+use std::time::Duration;
+
+use super::adb_message_device::ADBMessageDevice;
+use super::shell_session::is_timeout;
+use super::{ADBTransportMessage, MessageCommand};
+use crate::{ADBMessageTransport, Result, RustADBError};
+
+/// How long each side is waited on before checking the other. Bounds the latency the
+/// relay adds in either direction.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+/// How long closing waits for the device to close its end of the stream.
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(2);
+/// How long [`ADBMessageDevice::serve_reverse`] waits for the device between checks.
+const REVERSE_WAIT: Duration = Duration::from_secs(60);
+
+impl<T: ADBMessageTransport> ADBMessageDevice<T> {
+    /// Serves connections accepted on `listener` by relaying each to `remote` on the device,
+    /// one after the other, until accepting fails.
+    ///
+    /// A connection the device refuses, usually because nothing listens on `remote`, is
+    /// closed and the next one accepted; any other error ends forwarding.
+    pub(crate) fn forward(&mut self, listener: &TcpListener, remote: &str) -> Result<()> {
+        for stream in listener.incoming() {
+            match self.forward_connection(stream?, remote) {
+                Err(RustADBError::ADBRequestFailed(reason)) => {
+                    log::warn!("forwarded connection to {remote} failed: {reason}");
+                }
+                result => result?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Relays `stream` to `remote` on the device, such as `tcp:8080` or
+    /// `localabstract:chrome_devtools_remote`, until either side closes it.
+    pub(crate) fn forward_connection(&mut self, stream: TcpStream, remote: &str) -> Result<()> {
+        let response = self.open_session(format!("{remote}\0").as_bytes())?;
+        if response.header().command() != MessageCommand::Okay {
+            return Err(RustADBError::ADBRequestFailed(format!(
+                "device refused connection to {remote}"
+            )));
+        }
+        let local_id = self.get_local_id()?;
+        let remote_id = self.get_remote_id()?;
+        self.relay(stream, local_id, remote_id)
+    }
+
+    /// Asks the device to listen on `remote`, such as `tcp:8080`, and hand connections to
+    /// us for `local` on this host, like `adb reverse`. Connections only reach `local`
+    /// while [`ADBMessageDevice::serve_reverse`] runs.
+    pub(crate) fn reverse(&mut self, remote: &str, local: &str) -> Result<()> {
+        self.reverse_request(&format!("reverse:forward:{remote};{local}"))
+    }
+
+    /// Removes every reverse rule set on the device.
+    pub(crate) fn reverse_remove_all(&mut self) -> Result<()> {
+        self.reverse_request("reverse:killforward-all")
+    }
+
+    fn reverse_request(&mut self, service: &str) -> Result<()> {
+        let response = self.open_session(format!("{service}\0").as_bytes())?;
+        if response.header().command() != MessageCommand::Okay {
+            return Err(RustADBError::ADBRequestFailed(format!(
+                "device refused {service}"
+            )));
+        }
+        // adbd answers like the host server does: `OKAY`, or `FAIL` and a hex length.
+        let reply = self.read_exec_bytes()?;
+        match reply.get(..4) {
+            Some(b"OKAY") => Ok(()),
+            Some(b"FAIL") => Err(RustADBError::ADBRequestFailed(
+                String::from_utf8_lossy(reply.get(8..).unwrap_or_default()).into_owned(),
+            )),
+            _ => Err(RustADBError::WrongResponseReceived(
+                String::from_utf8_lossy(&reply).into_owned(),
+                "OKAY".to_string(),
+            )),
+        }
+    }
+
+    /// Accepts the connections the device opens for reverse rules, relaying each to its
+    /// local target one after the other. Only `tcp:PORT` targets are served; others are
+    /// refused. Blocks until the connection to the device fails.
+    pub(crate) fn serve_reverse(&mut self) -> Result<()> {
+        loop {
+            let message = match self
+                .get_transport_mut()
+                .read_message_with_timeout(REVERSE_WAIT)
+            {
+                Ok(message) => message,
+                Err(error) if is_timeout(&error) => continue,
+                Err(error) => return Err(error),
+            };
+            if message.header().command() != MessageCommand::Open {
+                continue;
+            }
+            let remote_id = message.header().arg0();
+            let target = String::from_utf8_lossy(message.payload())
+                .trim_end_matches('\0')
+                .to_string();
+            let stream = match connect_local(&target) {
+                Ok(stream) => stream,
+                Err(error) => {
+                    log::warn!("refusing reverse connection to {target}: {error}");
+                    self.get_transport_mut()
+                        .write_message(ADBTransportMessage::new(
+                            MessageCommand::Clse,
+                            0,
+                            remote_id,
+                            &[],
+                        ))?;
+                    continue;
+                }
+            };
+            let local_id = rand::random::<u32>().max(1);
+            let accept = ADBTransportMessage::okay(local_id, remote_id, self.receive_window());
+            self.get_transport_mut().write_message(accept)?;
+            self.relay(stream, local_id, remote_id)?;
+        }
+    }
+
+    /// Copies data both ways between `stream` and the open device stream until either side
+    /// closes it. A failing local socket is treated as closed; only device errors are
+    /// returned.
+    fn relay(&mut self, mut stream: TcpStream, local_id: u32, remote_id: u32) -> Result<()> {
+        stream.set_read_timeout(Some(POLL_INTERVAL))?;
+
+        let mut buffer = vec![0; self.max_payload()];
+        // Whether our last `WRTE` hasn't been acknowledged yet; a stream carries one at a time.
+        let mut awaiting_ack = false;
+        loop {
+            if !awaiting_ack {
+                match stream.read(&mut buffer) {
+                    Ok(0) => return self.close_forwarded(local_id, remote_id),
+                    Ok(read) => {
+                        self.get_transport_mut()
+                            .write_message(ADBTransportMessage::new(
+                                MessageCommand::Write,
+                                local_id,
+                                remote_id,
+                                &buffer[..read],
+                            ))?;
+                        awaiting_ack = true;
+                    }
+                    Err(error)
+                        if matches!(error.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+                    Err(error) => {
+                        log::debug!("local end of forwarded stream failed: {error}");
+                        return self.close_forwarded(local_id, remote_id);
+                    }
+                }
+            }
+
+            let message = match self
+                .get_transport_mut()
+                .read_message_with_timeout(POLL_INTERVAL)
+            {
+                Ok(message) => message,
+                Err(error) if is_timeout(&error) => continue,
+                Err(error) => return Err(error),
+            };
+            match message.header().command() {
+                MessageCommand::Write => {
+                    if let Err(error) = stream.write_all(message.payload()) {
+                        log::debug!("local end of forwarded stream failed: {error}");
+                        return self.close_forwarded(local_id, remote_id);
+                    }
+                    let ack = self.ack(local_id, remote_id, &message);
+                    self.get_transport_mut().write_message(ack)?;
+                }
+                MessageCommand::Okay => awaiting_ack = false,
+                MessageCommand::Clse => {
+                    self.get_transport_mut()
+                        .write_message(ADBTransportMessage::new(
+                            MessageCommand::Clse,
+                            local_id,
+                            remote_id,
+                            &[],
+                        ))?;
+                    let _ = stream.shutdown(Shutdown::Both);
+                    return Ok(());
+                }
+                MessageCommand::Open => {
+                    // A reverse connection opened while this one is busy.
+                    self.get_transport_mut()
+                        .write_message(ADBTransportMessage::new(
+                            MessageCommand::Clse,
+                            0,
+                            message.header().arg0(),
+                            &[],
+                        ))?;
+                }
+                other => {
+                    return Err(RustADBError::WrongResponseReceived(
+                        other.to_string(),
+                        MessageCommand::Write.to_string(),
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Sends our `CLSE` and waits briefly for the device's, so no message of this stream
+    /// is left behind for the next request.
+    fn close_forwarded(&mut self, local_id: u32, remote_id: u32) -> Result<()> {
+        self.get_transport_mut()
+            .write_message(ADBTransportMessage::new(
+                MessageCommand::Clse,
+                local_id,
+                remote_id,
+                &[],
+            ))?;
+        loop {
+            match self
+                .get_transport_mut()
+                .read_message_with_timeout(CLOSE_TIMEOUT)
+            {
+                Ok(message) if message.header().command() == MessageCommand::Clse => return Ok(()),
+                Ok(_) => {}
+                Err(error) if is_timeout(&error) => return Ok(()),
+                Err(error) => return Err(error),
+            }
+        }
+    }
+}
+
+/// Connects to the host end of a reverse rule.
+fn connect_local(target: &str) -> Result<TcpStream> {
+    let port = target
+        .strip_prefix("tcp:")
+        .and_then(|port| port.parse::<u16>().ok())
+        .ok_or_else(|| RustADBError::ADBRequestFailed(format!("unsupported target {target}")))?;
+    Ok(TcpStream::connect((Ipv4Addr::LOCALHOST, port))?)
+}
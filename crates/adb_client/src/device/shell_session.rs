@@ -0,0 +1,266 @@
+use std::collections::VecDeque;
+use std::io::ErrorKind;
+use std::time::Duration;
+
+use super::adb_message_device::ADBMessageDevice;
+use super::{ADBTransportMessage, MessageCommand, shell_v2};
+use crate::{ADBMessageTransport, Result, RustADBError, ShellSessionOutput, ShellWindowSize};
+
+/// How long a write waits for the device to acknowledge the previous one.
+const WRITE_ACK_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long closing waits for the device to close its end of the stream.
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(2);
+/// `TERM` given to shells running in a pseudo-terminal.
+const PTY_TERM: &str = "xterm-256color";
+
+/// Shell running on the device over one persistent stream, fed with
+/// [`ADBShellSession::write_stdin`] and drained with [`ADBShellSession::read_output`].
+///
+/// The session borrows its device, since no other request can share the connection while
+/// the stream is open. Dropping it closes the stream.
+///
+/// Devices advertising `shell_v2` keep standard error apart, report the exit status and
+/// accept window size changes. Others only offer a plain stream of merged output.
+#[derive(Debug)]
+pub struct ADBShellSession<'a, T: ADBMessageTransport> {
+    device: &'a mut ADBMessageDevice<T>,
+    local_id: u32,
+    remote_id: u32,
+    v2: bool,
+    /// Received shell v2 bytes not forming a complete packet yet.
+    pending: Vec<u8>,
+    /// Output received while waiting for something else, returned first.
+    queued: VecDeque<ShellSessionOutput>,
+    /// Whether our last `WRTE` hasn't been acknowledged yet.
+    awaiting_ack: bool,
+    exit_code: Option<u8>,
+    closed: bool,
+}
+
+impl<'a, T: ADBMessageTransport> ADBShellSession<'a, T> {
+    /// Starts `command`, or an interactive shell when it's empty. With a `window_size`, the
+    /// shell runs in a pseudo-terminal of that size.
+    pub(crate) fn open(
+        device: &'a mut ADBMessageDevice<T>,
+        command: &str,
+        window_size: Option<ShellWindowSize>,
+    ) -> Result<Self> {
+        let v2 = device.has_feature("shell_v2");
+        let service = match (v2, window_size) {
+            (true, Some(_)) => format!("shell,v2,TERM={PTY_TERM},pty:{command}\0"),
+            (true, None) => format!("shell,v2,raw:{command}\0"),
+            (false, _) => format!("shell:{command}\0"),
+        };
+        let response = device.open_session(service.as_bytes())?;
+        if response.header().command() != MessageCommand::Okay {
+            return Err(RustADBError::ADBRequestFailed(format!(
+                "wrong command {}",
+                response.header().command()
+            )));
+        }
+
+        let mut session = Self {
+            local_id: device.get_local_id()?,
+            remote_id: device.get_remote_id()?,
+            device,
+            v2,
+            pending: Vec::new(),
+            queued: VecDeque::new(),
+            awaiting_ack: false,
+            exit_code: None,
+            closed: false,
+        };
+        match window_size {
+            Some(size) if v2 => session.resize(size)?,
+            Some(_) => log::debug!("device lacks shell_v2, ignoring the window size"),
+            None => {}
+        }
+        Ok(session)
+    }
+
+    /// Sends `data` to the shell's standard input.
+    pub fn write_stdin(&mut self, data: &[u8]) -> Result<()> {
+        if self.v2 {
+            self.send(&shell_v2::packet(shell_v2::STDIN, data)?)
+        } else {
+            self.send(data)
+        }
+    }
+
+    /// Closes the shell's standard input, so commands reading it see its end.
+    /// Needs `shell_v2`.
+    pub fn close_stdin(&mut self) -> Result<()> {
+        if !self.v2 {
+            return Err(RustADBError::ADBShellNotSupported);
+        }
+        self.send(&shell_v2::packet(shell_v2::CLOSE_STDIN, &[])?)
+    }
+
+    /// Tells a shell running in a pseudo-terminal that the terminal was resized.
+    /// Needs `shell_v2`.
+    pub fn resize(&mut self, size: ShellWindowSize) -> Result<()> {
+        if !self.v2 {
+            return Err(RustADBError::ADBShellNotSupported);
+        }
+        let data = size.to_packet_data();
+        self.send(&shell_v2::packet(
+            shell_v2::WINDOW_SIZE_CHANGE,
+            data.as_bytes(),
+        )?)
+    }
+
+    /// Waits up to `timeout` for output, returning `None` when there is none yet. Once the
+    /// shell has ended, every call returns [`ShellSessionOutput::Exited`].
+    pub fn read_output(&mut self, timeout: Duration) -> Result<Option<ShellSessionOutput>> {
+        loop {
+            if let Some(output) = self.queued.pop_front() {
+                return Ok(Some(output));
+            }
+            if self.closed {
+                return Ok(Some(ShellSessionOutput::Exited(self.exit_code)));
+            }
+            if !self.receive(timeout)? {
+                return Ok(None);
+            }
+        }
+    }
+
+    /// Exit status of the shell, once it has ended on a device with `shell_v2`.
+    pub fn exit_code(&self) -> Option<u8> {
+        self.exit_code
+    }
+
+    /// Closes the stream, ending the shell if it is still running, and returns its exit
+    /// status when known.
+    pub fn close(mut self) -> Result<Option<u8>> {
+        self.shutdown()?;
+        Ok(self.exit_code)
+    }
+
+    fn send(&mut self, data: &[u8]) -> Result<()> {
+        // A stream carries one unacknowledged write at a time.
+        while self.awaiting_ack && !self.closed {
+            if !self.receive(WRITE_ACK_TIMEOUT)? {
+                return Err(RustADBError::ADBRequestFailed(
+                    "device did not acknowledge shell input".into(),
+                ));
+            }
+        }
+        if self.closed {
+            return Err(RustADBError::ADBRequestFailed(
+                "shell session closed".into(),
+            ));
+        }
+        self.device
+            .get_transport_mut()
+            .write_message(ADBTransportMessage::new(
+                MessageCommand::Write,
+                self.local_id,
+                self.remote_id,
+                data,
+            ))?;
+        self.awaiting_ack = true;
+        Ok(())
+    }
+
+    /// Handles one message from the device, returning `false` if none came within `timeout`.
+    fn receive(&mut self, timeout: Duration) -> Result<bool> {
+        let message = match self
+            .device
+            .get_transport_mut()
+            .read_message_with_timeout(timeout)
+        {
+            Ok(message) => message,
+            Err(error) if is_timeout(&error) => return Ok(false),
+            Err(error) => return Err(error),
+        };
+        match message.header().command() {
+            MessageCommand::Write => {
+                let ack = self.device.ack(self.local_id, self.remote_id, &message);
+                self.device.get_transport_mut().write_message(ack)?;
+                let payload = message.into_payload();
+                if !self.v2 {
+                    self.queued.push_back(ShellSessionOutput::Stdout(payload));
+                    return Ok(true);
+                }
+                self.pending.extend_from_slice(&payload);
+                while let Some((id, data)) = shell_v2::take_packet(&mut self.pending) {
+                    match id {
+                        shell_v2::STDOUT => self.queued.push_back(ShellSessionOutput::Stdout(data)),
+                        shell_v2::STDERR => self.queued.push_back(ShellSessionOutput::Stderr(data)),
+                        shell_v2::EXIT => self.exit_code = data.first().copied(),
+                        _ => {}
+                    }
+                }
+            }
+            MessageCommand::Okay => self.awaiting_ack = false,
+            MessageCommand::Clse => {
+                if !self.closed {
+                    self.reply(MessageCommand::Clse)?;
+                }
+                self.closed = true;
+            }
+            other => {
+                return Err(RustADBError::WrongResponseReceived(
+                    other.to_string(),
+                    MessageCommand::Write.to_string(),
+                ));
+            }
+        }
+        Ok(true)
+    }
+
+    fn reply(&mut self, command: MessageCommand) -> Result<()> {
+        self.device
+            .get_transport_mut()
+            .write_message(ADBTransportMessage::new(
+                command,
+                self.local_id,
+                self.remote_id,
+                &[],
+            ))
+    }
+
+    /// Sends our `CLSE` and waits briefly for the device's, so no message of this stream
+    /// is left behind for the next request.
+    fn shutdown(&mut self) -> Result<()> {
+        if self.closed {
+            return Ok(());
+        }
+        self.reply(MessageCommand::Clse)?;
+        self.closed = true;
+        loop {
+            let message = match self
+                .device
+                .get_transport_mut()
+                .read_message_with_timeout(CLOSE_TIMEOUT)
+            {
+                Ok(message) => message,
+                Err(error) if is_timeout(&error) => return Ok(()),
+                Err(error) => return Err(error),
+            };
+            if message.header().command() == MessageCommand::Clse {
+                return Ok(());
+            }
+        }
+    }
+}
+
+impl<T: ADBMessageTransport> Drop for ADBShellSession<'_, T> {
+    fn drop(&mut self) {
+        if let Err(error) = self.shutdown() {
+            log::debug!("error closing shell session: {error}");
+        }
+    }
+}
+
+/// Whether `error` is a read giving up after its timeout, rather than a failure.
+pub(crate) fn is_timeout(error: &RustADBError) -> bool {
+    match error {
+        RustADBError::UsbError(rusb::Error::Timeout) => true,
+        RustADBError::IOError(error) => {
+            matches!(error.kind(), ErrorKind::TimedOut | ErrorKind::WouldBlock)
+        }
+        _ => false,
+    }
+}
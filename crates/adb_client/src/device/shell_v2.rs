@@ -0,0 +1,37 @@
+//! Framing of the shell v2 protocol (`shell,v2:` services), which multiplexes stdin,
+//! stdout, stderr, the exit status and window size changes over one stream.
+
+use byteorder::{ByteOrder, LittleEndian};
+
+use crate::Result;
+
+/// Packet ids, as in adbd's `shell_protocol.h`.
+pub(crate) const STDIN: u8 = 0;
+pub(crate) const STDOUT: u8 = 1;
+pub(crate) const STDERR: u8 = 2;
+pub(crate) const EXIT: u8 = 3;
+pub(crate) const CLOSE_STDIN: u8 = 4;
+pub(crate) const WINDOW_SIZE_CHANGE: u8 = 5;
+
+/// Bytes before the data of a packet: id and data length.
+const HEADER_LEN: usize = 5;
+
+/// Frames `data` as one packet.
+pub(crate) fn packet(id: u8, data: &[u8]) -> Result<Vec<u8>> {
+    let mut packet = Vec::with_capacity(HEADER_LEN + data.len());
+    packet.push(id);
+    packet.extend_from_slice(&u32::try_from(data.len())?.to_le_bytes());
+    packet.extend_from_slice(data);
+    Ok(packet)
+}
+
+/// Takes the first complete packet off `pending`. Packets are not aligned to messages,
+/// so a partial one is left in place to be completed by the next payload.
+pub(crate) fn take_packet(pending: &mut Vec<u8>) -> Option<(u8, Vec<u8>)> {
+    let header = pending.get(..HEADER_LEN)?;
+    let id = header[0];
+    let len = LittleEndian::read_u32(&header[1..HEADER_LEN]) as usize;
+    let data = pending.get(HEADER_LEN..HEADER_LEN + len)?.to_vec();
+    pending.drain(..HEADER_LEN + len);
+    Some((id, data))
+}
@@ -0,0 +1,157 @@
+use curve25519_dalek::edwards::{CompressedEdwardsY, EdwardsPoint};
+use curve25519_dalek::scalar::Scalar;
+use rsa::rand_core::{OsRng, RngCore};
+use sha2::{Digest, Sha512};
+
+use crate::{Result, RustADBError};
+
+/// BoringSSL's M point, derived from the SHA-256 hash of "edwards25519 point generation seed (M)".
+const M_POINT: [u8; 32] = [
+    0x5a, 0xda, 0x7e, 0x4b, 0xf6, 0xdd, 0xd9, 0xad, 0xb6, 0x62, 0x6d, 0x32, 0x13, 0x1c, 0x6b, 0x5c,
+    0x51, 0xa1, 0xe3, 0x47, 0xa3, 0x47, 0x8f, 0x53, 0xcf, 0xcf, 0x44, 0x1b, 0x88, 0xee, 0xd1, 0x2e,
+];
+/// BoringSSL's N point, derived from the SHA-256 hash of "edwards25519 point generation seed (N)".
+const N_POINT: [u8; 32] = [
+    0x10, 0xe3, 0xdf, 0x0a, 0xe3, 0x7d, 0x8e, 0x7a, 0x99, 0xb5, 0xfe, 0x74, 0xb4, 0x46, 0x72, 0x10,
+    0x3d, 0xbd, 0xdc, 0xbd, 0x06, 0xaf, 0x68, 0x0d, 0x71, 0x32, 0x9a, 0x11, 0x69, 0x3b, 0xc7, 0x78,
+];
+
+/// Size of the messages exchanged by both sides.
+pub(crate) const SPAKE2_MESSAGE_SIZE: usize = 32;
+
+/// Side of the exchange, deciding which of M and N masks our message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Spake2Role {
+    Alice,
+    /// adbd's side, only taken by tests.
+    #[cfg_attr(not(test), allow(dead_code))]
+    Bob,
+}
+
+/// SPAKE2 over Ed25519, compatible with the BoringSSL implementation adbd's pairing server uses.
+// Source code: https://boringssl.googlesource.com/boringssl/+/refs/heads/main/crypto/curve25519/spake25519.cc
+#[derive(Debug)]
+pub(crate) struct Spake2 {
+    role: Spake2Role,
+    my_name: Vec<u8>,
+    their_name: Vec<u8>,
+    private_key: Scalar,
+    password_scalar: Scalar,
+    password_hash: [u8; 64],
+    my_message: [u8; SPAKE2_MESSAGE_SIZE],
+}
+
+impl Spake2 {
+    /// Starts an exchange authenticated by `password`, returning the context and the message
+    /// to send to the peer.
+    pub(crate) fn start(
+        role: Spake2Role,
+        my_name: &[u8],
+        their_name: &[u8],
+        password: &[u8],
+    ) -> Result<(Self, [u8; SPAKE2_MESSAGE_SIZE])> {
+        let mut private_bytes = [0; 64];
+        OsRng.fill_bytes(&mut private_bytes);
+        let private_key = Scalar::from_bytes_mod_order_wide(&private_bytes);
+
+        let mut password_hash = [0; 64];
+        password_hash.copy_from_slice(&Sha512::digest(password));
+        let password_scalar = Scalar::from_bytes_mod_order_wide(&password_hash);
+
+        // BoringSSL multiplies its private key by the cofactor, so the public point is B * 8r.
+        let public = EdwardsPoint::mul_base(&(private_key * Scalar::from(8_u8)));
+        let mask = match role {
+            Spake2Role::Alice => mask_point(&M_POINT, &password_scalar)?,
+            Spake2Role::Bob => mask_point(&N_POINT, &password_scalar)?,
+        };
+        let my_message = (public + mask).compress().to_bytes();
+
+        Ok((
+            Self {
+                role,
+                my_name: my_name.to_vec(),
+                their_name: their_name.to_vec(),
+                private_key,
+                password_scalar,
+                password_hash,
+                my_message,
+            },
+            my_message,
+        ))
+    }
+
+    /// Derives the 64 bytes of key material shared with the peer from its message. Both sides
+    /// only end up with the same key if they used the same password.
+    pub(crate) fn finish(self, their_message: &[u8]) -> Result<[u8; 64]> {
+        let their_message: [u8; SPAKE2_MESSAGE_SIZE] = their_message.try_into()?;
+        let their_point = CompressedEdwardsY(their_message)
+            .decompress()
+            .ok_or_else(|| RustADBError::PairingFailed("invalid SPAKE2 message".into()))?;
+
+        let their_mask = match self.role {
+            Spake2Role::Alice => mask_point(&N_POINT, &self.password_scalar)?,
+            Spake2Role::Bob => mask_point(&M_POINT, &self.password_scalar)?,
+        };
+        let shared = ((their_point - their_mask) * self.private_key).mul_by_cofactor();
+
+        let mut hasher = Sha512::new();
+        let (first_name, second_name, first_message, second_message) = match self.role {
+            Spake2Role::Alice => (
+                &self.my_name,
+                &self.their_name,
+                &self.my_message[..],
+                &their_message[..],
+            ),
+            Spake2Role::Bob => (
+                &self.their_name,
+                &self.my_name,
+                &their_message[..],
+                &self.my_message[..],
+            ),
+        };
+        for field in [
+            first_name.as_slice(),
+            second_name.as_slice(),
+            first_message,
+            second_message,
+            shared.compress().as_bytes(),
+            &self.password_hash,
+        ] {
+            hasher.update((field.len() as u64).to_le_bytes());
+            hasher.update(field);
+        }
+
+        let mut key = [0; 64];
+        key.copy_from_slice(&hasher.finalize());
+        Ok(key)
+    }
+}
+
+/// Multiplies the mask point by the password scalar the way BoringSSL does: it first adds
+/// multiples of the group order to make the scalar divisible by the cofactor, which also
+/// clears the mask's small-order component.
+fn mask_point(point: &[u8; 32], password_scalar: &Scalar) -> Result<EdwardsPoint> {
+    let point = CompressedEdwardsY(*point)
+        .decompress()
+        .ok_or_else(|| RustADBError::PairingFailed("invalid SPAKE2 mask point".into()))?;
+    Ok((point * (password_scalar * Scalar::from(8_u8).invert())).mul_by_cofactor())
+}
+
+#[test]
+fn spake2_agrees_on_shared_password() {
+    let (alice, alice_message) =
+        Spake2::start(Spake2Role::Alice, b"alice", b"bob", b"123456").unwrap();
+    let (bob, bob_message) = Spake2::start(Spake2Role::Bob, b"bob", b"alice", b"123456").unwrap();
+    assert_eq!(
+        alice.finish(&bob_message).unwrap(),
+        bob.finish(&alice_message).unwrap()
+    );
+
+    let (alice, alice_message) =
+        Spake2::start(Spake2Role::Alice, b"alice", b"bob", b"123456").unwrap();
+    let (bob, bob_message) = Spake2::start(Spake2Role::Bob, b"bob", b"alice", b"654321").unwrap();
+    assert_ne!(
+        alice.finish(&bob_message).unwrap(),
+        bob.finish(&alice_message).unwrap()
+    );
+}
@@ -0,0 +1,272 @@
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, ErrorKind};
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Condvar, Mutex, MutexGuard, PoisonError, TryLockError};
+use std::time::{Duration, Instant};
+
+use rand::Rng;
+
+use super::adb_message_device::ADBMessageDevice;
+use super::adb_transport_message::INITIAL_DELAYED_ACK_BYTES;
+use super::shell_session::is_timeout;
+use super::{ADBTransportMessage, MessageCommand};
+use crate::{ADBMessageTransport, Result, RustADBError, TransportTimeouts};
+
+/// How long the thread reading for everyone holds the transport before letting others look
+/// at what arrived.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Messages received for one stream and not consumed yet.
+#[derive(Debug, Default)]
+struct Inbox {
+    messages: VecDeque<ADBTransportMessage>,
+}
+
+/// Several streams opened over the connection of one device and used at the same time,
+/// e.g. a sync transfer on one thread while a shell reports progress on another.
+///
+/// Messages from the device are routed to their stream by id: whichever stream waits for
+/// a message reads the transport for all of them, queueing messages addressed to others.
+/// The manager borrows its device, whose own requests would take messages meant for the
+/// streams, and can be shared between scoped threads.
+#[derive(Debug)]
+pub struct ADBStreamManager<'a, T: ADBMessageTransport> {
+    reader: Mutex<T>,
+    writer: Mutex<T>,
+    inboxes: Mutex<HashMap<u32, Inbox>>,
+    arrived: Condvar,
+    next_local_id: AtomicU32,
+    timeouts: TransportTimeouts,
+    delayed_ack: bool,
+    max_payload: usize,
+    /// Holds on to the device while streams are open.
+    device: PhantomData<&'a mut ()>,
+}
+
+impl<'a, T: ADBMessageTransport> ADBStreamManager<'a, T> {
+    pub(crate) fn new(device: &'a mut ADBMessageDevice<T>) -> Self {
+        let transport = device.get_transport().clone();
+        Self {
+            reader: Mutex::new(transport.clone()),
+            writer: Mutex::new(transport),
+            inboxes: Mutex::new(HashMap::new()),
+            arrived: Condvar::new(),
+            // 0 is never a valid id.
+            next_local_id: AtomicU32::new(rand::rng().random_range(1..u32::MAX / 2)),
+            timeouts: device.timeouts(),
+            delayed_ack: device.delayed_ack(),
+            max_payload: device.max_payload(),
+            device: PhantomData,
+        }
+    }
+
+    /// Opens a stream to `service` on the device, such as `shell:ls` or `sync:`.
+    pub fn open(&self, service: &str) -> Result<ADBStream<'_, 'a, T>> {
+        let local_id = self.next_local_id.fetch_add(1, Ordering::Relaxed);
+        self.inboxes().insert(local_id, Inbox::default());
+
+        let mut service = service.as_bytes().to_vec();
+        service.push(b'\0');
+        let window = self.delayed_ack.then_some(INITIAL_DELAYED_ACK_BYTES);
+        let opened = self
+            .send(ADBTransportMessage::open(local_id, &service, window))
+            .and_then(|()| self.receive(local_id));
+        let response = match opened {
+            Ok(response) => response,
+            Err(e) => {
+                self.inboxes().remove(&local_id);
+                return Err(e);
+            }
+        };
+
+        match response.header().command() {
+            MessageCommand::Okay => Ok(ADBStream {
+                manager: self,
+                local_id,
+                remote_id: response.header().arg0(),
+                received: VecDeque::new(),
+                closed: false,
+            }),
+            command => {
+                self.inboxes().remove(&local_id);
+                Err(RustADBError::ADBRequestFailed(format!(
+                    "cannot open {}: device answered {command}",
+                    String::from_utf8_lossy(&service[..service.len() - 1])
+                )))
+            }
+        }
+    }
+
+    fn inboxes(&self) -> MutexGuard<'_, HashMap<u32, Inbox>> {
+        self.inboxes.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn send(&self, message: ADBTransportMessage) -> Result<()> {
+        self.writer
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .write_message_with_timeout(message, self.timeouts.write)
+    }
+
+    /// Waits for the next message addressed to the stream `local_id`, reading the transport
+    /// unless another stream already is.
+    fn receive(&self, local_id: u32) -> Result<ADBTransportMessage> {
+        // The default read timeout is too far away to be represented.
+        let deadline = Instant::now().checked_add(self.timeouts.read);
+        loop {
+            if let Some(message) = self.take(local_id) {
+                return Ok(message);
+            }
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return Err(RustADBError::IOError(io::Error::new(
+                    ErrorKind::TimedOut,
+                    "no message received for stream",
+                )));
+            }
+
+            let mut reader = match self.reader.try_lock() {
+                Ok(reader) => reader,
+                Err(TryLockError::Poisoned(e)) => e.into_inner(),
+                Err(TryLockError::WouldBlock) => {
+                    let inboxes = self.inboxes();
+                    if inboxes
+                        .get(&local_id)
+                        .is_none_or(|inbox| inbox.messages.is_empty())
+                    {
+                        let _ = self.arrived.wait_timeout(inboxes, POLL_INTERVAL);
+                    }
+                    continue;
+                }
+            };
+            match reader.read_message_with_timeout(POLL_INTERVAL) {
+                Ok(message) => self.route(message),
+                Err(e) if is_timeout(&e) => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    fn take(&self, local_id: u32) -> Option<ADBTransportMessage> {
+        self.inboxes()
+            .get_mut(&local_id)
+            .and_then(|inbox| inbox.messages.pop_front())
+    }
+
+    /// Queues `message` for the stream it's addressed to, dropping it if that stream is gone.
+    fn route(&self, message: ADBTransportMessage) {
+        let local_id = message.header().arg1();
+        match self.inboxes().get_mut(&local_id) {
+            Some(inbox) => {
+                inbox.messages.push_back(message);
+                self.arrived.notify_all();
+            }
+            None => log::debug!(
+                "dropping {} for closed stream {local_id}",
+                message.header().command()
+            ),
+        }
+    }
+}
+
+/// A stream opened with [`ADBStreamManager::open`], closed when dropped.
+#[derive(Debug)]
+pub struct ADBStream<'m, 'a, T: ADBMessageTransport> {
+    manager: &'m ADBStreamManager<'a, T>,
+    local_id: u32,
+    remote_id: u32,
+    /// Payloads received while waiting for an acknowledgement, returned first.
+    received: VecDeque<Vec<u8>>,
+    closed: bool,
+}
+
+impl<T: ADBMessageTransport> ADBStream<'_, '_, T> {
+    /// Sends `data` to the service, waiting for the device to acknowledge each message.
+    pub fn write_all(&mut self, data: &[u8]) -> Result<()> {
+        for chunk in data.chunks(self.manager.max_payload) {
+            if self.closed {
+                return Err(RustADBError::ADBRequestFailed(
+                    "stream closed by the device".into(),
+                ));
+            }
+            self.manager.send(ADBTransportMessage::new(
+                MessageCommand::Write,
+                self.local_id,
+                self.remote_id,
+                chunk,
+            ))?;
+            loop {
+                let message = self.manager.receive(self.local_id)?;
+                match message.header().command() {
+                    MessageCommand::Okay => break,
+                    MessageCommand::Write => {
+                        self.acknowledge(&message)?;
+                        self.received.push_back(message.into_payload());
+                    }
+                    MessageCommand::Clse => {
+                        self.closed = true;
+                        return Err(RustADBError::ADBRequestFailed(
+                            "stream closed by the device".into(),
+                        ));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Waits for the next payload the service sends, or `None` once it closed the stream.
+    pub fn read(&mut self) -> Result<Option<Vec<u8>>> {
+        if let Some(payload) = self.received.pop_front() {
+            return Ok(Some(payload));
+        }
+        while !self.closed {
+            let message = self.manager.receive(self.local_id)?;
+            match message.header().command() {
+                MessageCommand::Write => {
+                    self.acknowledge(&message)?;
+                    return Ok(Some(message.into_payload()));
+                }
+                MessageCommand::Clse => self.closed = true,
+                _ => {}
+            }
+        }
+        Ok(None)
+    }
+
+    /// Collects everything the service sends until it closes the stream.
+    pub fn read_to_end(&mut self) -> Result<Vec<u8>> {
+        let mut output = Vec::new();
+        while let Some(payload) = self.read()? {
+            output.extend_from_slice(&payload);
+        }
+        Ok(output)
+    }
+
+    fn acknowledge(&self, received: &ADBTransportMessage) -> Result<()> {
+        let acked = self
+            .manager
+            .delayed_ack
+            .then(|| received.header().data_length());
+        self.manager.send(ADBTransportMessage::okay(
+            self.local_id,
+            self.remote_id,
+            acked,
+        ))
+    }
+}
+
+impl<T: ADBMessageTransport> Drop for ADBStream<'_, '_, T> {
+    fn drop(&mut self) {
+        // Messages the device still sends for this stream are dropped once it is forgotten.
+        self.manager.inboxes().remove(&self.local_id);
+        // Best effort here
+        let _ = self.manager.send(ADBTransportMessage::new(
+            MessageCommand::Clse,
+            self.local_id,
+            self.remote_id,
+            &[],
+        ));
+    }
+}
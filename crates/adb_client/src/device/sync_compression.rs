@@ -0,0 +1,125 @@
+use std::io::{self, Read, Write};
+
+use lz4_flex::frame::{FrameDecoder, FrameEncoder};
+
+/// Bytes read from the source at a time while compressing.
+const COMPRESS_CHUNK_SIZE: usize = 64 * 1024;
+/// Brotli quality used for pushes; higher levels cost more host CPU than a USB link saves.
+const BROTLI_QUALITY: u32 = 1;
+const BROTLI_WINDOW_BITS: u32 = 22;
+
+/// Compression applied to file contents by the `sendrecv_v2` sync requests (`SND2`/`RCV2`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SyncCompression {
+    Brotli,
+    Lz4,
+    Zstd,
+}
+
+impl SyncCompression {
+    /// Preferred first: zstd compresses best for its speed, LZ4 is cheapest on the device.
+    const PREFERENCE: [SyncCompression; 3] = [
+        SyncCompression::Zstd,
+        SyncCompression::Lz4,
+        SyncCompression::Brotli,
+    ];
+
+    /// Best compression both sides support, given the features from the device banner.
+    pub(crate) fn negotiate(has_feature: impl Fn(&str) -> bool) -> Option<Self> {
+        if !has_feature("sendrecv_v2") {
+            return None;
+        }
+        Self::PREFERENCE
+            .into_iter()
+            .find(|compression| has_feature(compression.feature()))
+    }
+
+    fn feature(self) -> &'static str {
+        match self {
+            SyncCompression::Brotli => "sendrecv_v2_brotli",
+            SyncCompression::Lz4 => "sendrecv_v2_lz4",
+            SyncCompression::Zstd => "sendrecv_v2_zstd",
+        }
+    }
+
+    /// Value for the `flags` field of a `SND2`/`RCV2` setup packet.
+    pub(crate) fn flag(self) -> u32 {
+        match self {
+            SyncCompression::Brotli => 1,
+            SyncCompression::Lz4 => 2,
+            SyncCompression::Zstd => 4,
+        }
+    }
+
+    /// Reads `reader` compressed.
+    pub(crate) fn encoder<'a>(self, reader: impl Read + 'a) -> io::Result<Box<dyn Read + 'a>> {
+        Ok(match self {
+            SyncCompression::Brotli => Box::new(brotli::CompressorReader::new(
+                reader,
+                COMPRESS_CHUNK_SIZE,
+                BROTLI_QUALITY,
+                BROTLI_WINDOW_BITS,
+            )),
+            SyncCompression::Lz4 => Box::new(Lz4FrameReader::new(reader)),
+            SyncCompression::Zstd => Box::new(zstd::stream::read::Encoder::new(reader, 0)?),
+        })
+    }
+
+    /// Reads the compressed `reader` decompressed.
+    pub(crate) fn decoder<'a>(self, reader: impl Read + 'a) -> io::Result<Box<dyn Read + 'a>> {
+        Ok(match self {
+            SyncCompression::Brotli => {
+                Box::new(brotli::Decompressor::new(reader, COMPRESS_CHUNK_SIZE))
+            }
+            SyncCompression::Lz4 => Box::new(FrameDecoder::new(reader)),
+            SyncCompression::Zstd => Box::new(zstd::stream::read::Decoder::new(reader)?),
+        })
+    }
+}
+
+/// LZ4 frame compression of a reader; `lz4_flex` only compresses into a writer.
+struct Lz4FrameReader<R> {
+    inner: R,
+    /// `None` once `inner` is exhausted and the frame has been closed.
+    encoder: Option<FrameEncoder<Vec<u8>>>,
+    chunk: Vec<u8>,
+    output: Vec<u8>,
+    offset: usize,
+}
+
+impl<R: Read> Lz4FrameReader<R> {
+    fn new(inner: R) -> Self {
+        Self {
+            inner,
+            encoder: Some(FrameEncoder::new(Vec::new())),
+            chunk: vec![0; COMPRESS_CHUNK_SIZE],
+            output: Vec::new(),
+            offset: 0,
+        }
+    }
+}
+
+impl<R: Read> Read for Lz4FrameReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.offset == self.output.len() {
+            if self.encoder.is_none() {
+                return Ok(0);
+            }
+            self.offset = 0;
+            let read = self.inner.read(&mut self.chunk)?;
+            if read == 0 {
+                if let Some(encoder) = self.encoder.take() {
+                    self.output = encoder.finish().map_err(io::Error::other)?;
+                }
+            } else if let Some(encoder) = self.encoder.as_mut() {
+                encoder.write_all(&self.chunk[..read])?;
+                self.output = std::mem::take(encoder.get_mut());
+            }
+        }
+
+        let len = buf.len().min(self.output.len() - self.offset);
+        buf[..len].copy_from_slice(&self.output[self.offset..self.offset + len]);
+        self.offset += len;
+        Ok(len)
+    }
+}
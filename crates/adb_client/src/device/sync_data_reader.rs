@@ -0,0 +1,112 @@
+use std::io::{self, Read};
+
+use byteorder::{ByteOrder, LittleEndian};
+
+use crate::{
+    ADBMessageTransport, Result, RustADBError,
+    device::{MessageCommand, MessageSubcommand, adb_message_device::ADBMessageDevice},
+};
+
+/// Contents of the `DATA` chunks of a sync receive, read up to its `DONE`.
+///
+/// Chunks are not aligned to messages, so a chunk or its header may continue in the
+/// next payload. A `FAIL` from the device is returned as an [`io::Error`] wrapping
+/// [`RustADBError::ADBRequestFailed`].
+pub(crate) struct SyncDataReader<'a, T: ADBMessageTransport> {
+    device: &'a mut ADBMessageDevice<T>,
+    payload: Vec<u8>,
+    offset: usize,
+    /// Bytes of the current `DATA` chunk not read yet.
+    chunk_left: usize,
+    done: bool,
+}
+
+impl<'a, T: ADBMessageTransport> SyncDataReader<'a, T> {
+    pub(crate) fn new(device: &'a mut ADBMessageDevice<T>) -> Self {
+        Self {
+            device,
+            payload: Vec::new(),
+            offset: 0,
+            chunk_left: 0,
+            done: false,
+        }
+    }
+
+    /// Copies buffered stream bytes into `buf`, receiving the next payload when empty.
+    fn read_stream(&mut self, buf: &mut [u8]) -> Result<usize> {
+        while self.offset == self.payload.len() {
+            let message = self.device.recv_and_reply_okay()?;
+            match message.header().command() {
+                MessageCommand::Write => {
+                    self.payload = message.into_payload();
+                    self.offset = 0;
+                }
+                MessageCommand::Clse => {
+                    return Err(RustADBError::ADBRequestFailed(
+                        "connection closed while receiving file".into(),
+                    ));
+                }
+                _ => {}
+            }
+        }
+        let len = buf.len().min(self.payload.len() - self.offset);
+        buf[..len].copy_from_slice(&self.payload[self.offset..self.offset + len]);
+        self.offset += len;
+        Ok(len)
+    }
+
+    fn read_stream_exact(&mut self, mut buf: &mut [u8]) -> Result<()> {
+        while !buf.is_empty() {
+            let read = self.read_stream(buf)?;
+            buf = &mut buf[read..];
+        }
+        Ok(())
+    }
+
+    /// Reads the next chunk header, returning `false` once `DONE` is reached.
+    fn next_chunk(&mut self) -> Result<bool> {
+        let mut header = [0; 8];
+        self.read_stream_exact(&mut header)?;
+        let id = LittleEndian::read_u32(&header[0..4]);
+        let len = LittleEndian::read_u32(&header[4..8]) as usize;
+        if id == MessageSubcommand::Data as u32 {
+            self.chunk_left = len;
+            Ok(true)
+        } else if id == MessageSubcommand::Done as u32 {
+            Ok(false)
+        } else if id == MessageSubcommand::Fail as u32 {
+            let mut message = vec![0; len];
+            self.read_stream_exact(&mut message)?;
+            Err(RustADBError::ADBRequestFailed(
+                String::from_utf8_lossy(&message).into_owned(),
+            ))
+        } else {
+            Err(RustADBError::WrongResponseReceived(
+                String::from_utf8_lossy(&header[0..4]).into_owned(),
+                "DATA".to_string(),
+            ))
+        }
+    }
+
+    fn read_data(&mut self, buf: &mut [u8]) -> Result<usize> {
+        while self.chunk_left == 0 {
+            if self.done || !self.next_chunk()? {
+                self.done = true;
+                return Ok(0);
+            }
+        }
+        let len = buf.len().min(self.chunk_left);
+        let read = self.read_stream(&mut buf[..len])?;
+        self.chunk_left -= read;
+        Ok(read)
+    }
+}
+
+impl<T: ADBMessageTransport> Read for SyncDataReader<'_, T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.read_data(buf).map_err(|error| match error {
+            RustADBError::IOError(error) => error,
+            other => io::Error::other(other),
+        })
+    }
+}
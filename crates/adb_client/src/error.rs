@@ -123,6 +123,22 @@ pub enum RustADBError {
     /// An unknown transport has been provided
     #[error("unknown transport: {0}")]
     UnknownTransport(String),
+    /// Wireless debugging pairing failed, most often because of a wrong pairing code
+    #[error("pairing failed: {0}")]
+    PairingFailed(String),
+    /// A command run on the device exited with a non-zero status, with the command and what
+    /// it printed
+    #[error("{0} failed: {1}")]
+    RemoteCommandFailed(String, String),
+    /// The path does not exist on the device
+    #[error("No such file or directory on the device: {0}")]
+    RemoteFileNotFound(String),
+    /// The device denied access to the path
+    #[error("Permission denied on the device: {0}")]
+    PermissionDenied(String),
+    /// The device could not stat the path, for the reason given by its `errno`
+    #[error("Cannot stat {0} on the device: errno {1}")]
+    RemoteStatFailed(String, u32),
 }
 
 impl<T> From<std::sync::PoisonError<T>> for RustADBError {
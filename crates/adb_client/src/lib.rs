@@ -5,23 +5,39 @@
 #![doc = include_str!("../README.md")]
 
 mod adb_device_ext;
+#[cfg(feature = "tokio")]
+mod async_device;
 mod constants;
 mod device;
 mod emulator_device;
 mod error;
 mod mdns;
 mod models;
+mod progress;
+mod remote_walk;
 mod server;
 mod server_device;
+mod shell_stream;
 mod transports;
 mod utils;
 
 pub use adb_device_ext::ADBDeviceExt;
-pub use device::{ADBTcpDevice, ADBUSBDevice, is_adb_device, search_adb_devices};
+#[cfg(feature = "tokio")]
+pub use async_device::{AsyncADBDevice, AsyncADBMessageTransport};
+pub use device::{
+    ADBRsaKey, ADBShellSession, ADBStream, ADBStreamManager, ADBTcpDevice, ADBUSBDevice,
+    DeviceEvent, DeviceWatcher, UsbDeviceId, get_default_adb_key_path, is_adb_device,
+    search_adb_devices,
+};
 pub use emulator_device::ADBEmulatorDevice;
 pub use error::{Result, RustADBError};
 pub use mdns::*;
-pub use models::{AdbStatResponse, RebootType};
+pub use models::{
+    AdbStatResponse, BackupOptions, BatteryStatus, ChargingState, ConnectionPhase, DeviceBanner,
+    DeviceInfo, InstallOptions, RebootType, RemoteEntry, RemoteEntryKind, ShellOutput,
+    ShellSessionOutput, ShellWindowSize, StorageVolume, TransportTimeouts,
+};
+pub use remote_walk::RemoteWalk;
 pub use server::*;
 pub use server_device::ADBServerDevice;
 pub use transports::*;
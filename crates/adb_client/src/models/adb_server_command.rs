@@ -22,8 +22,16 @@ pub(crate) enum AdbServerCommand {
     MDNSServices,
     ServerStatus,
     ReconnectOffline,
-    Uninstall(String),
-    Install(u64),
+    Uninstall(String, bool),
+    Install(u64, String),
+    PackageManager(String),
+    Backup(String),
+    Restore,
+    SideloadHost(u64, u64),
+    Root,
+    Unroot,
+    Remount,
+    WaitForSerial(String),
     WaitForDevice(WaitForDeviceState, WaitForDeviceTransport),
     // Local commands
     ShellCommand(String),
@@ -86,9 +94,24 @@ impl Display for AdbServerCommand {
                 write!(f, "tcpip:{port}")
             }
             AdbServerCommand::Usb => write!(f, "usb:"),
-            AdbServerCommand::Install(size) => write!(f, "exec:cmd package 'install' -S {size}"),
-            AdbServerCommand::Uninstall(package) => {
-                write!(f, "exec:cmd package 'uninstall' {package}")
+            AdbServerCommand::Install(size, args) => {
+                write!(f, "exec:cmd package 'install'{args} -S {size}")
+            }
+            AdbServerCommand::PackageManager(args) => write!(f, "exec:cmd package {args}"),
+            AdbServerCommand::Backup(args) => write!(f, "backup:{args}"),
+            AdbServerCommand::Restore => write!(f, "restore:"),
+            AdbServerCommand::Root => write!(f, "root:"),
+            AdbServerCommand::Unroot => write!(f, "unroot:"),
+            AdbServerCommand::Remount => write!(f, "remount:"),
+            AdbServerCommand::WaitForSerial(serial) => {
+                write!(f, "host-serial:{serial}:wait-for-any-device")
+            }
+            AdbServerCommand::SideloadHost(size, block_size) => {
+                write!(f, "sideload-host:{size}:{block_size}")
+            }
+            AdbServerCommand::Uninstall(package, keep_data) => {
+                let keep_data = if *keep_data { " -k" } else { "" };
+                write!(f, "exec:cmd package 'uninstall'{keep_data} {package}")
             }
             AdbServerCommand::WaitForDevice(wait_for_device_state, wait_for_device_transport) => {
                 write!(
@@ -8,35 +8,132 @@ use std::{
 use byteorder::LittleEndian;
 use serde::{Deserialize, Serialize};
 
+use crate::models::RemoteEntryKind;
+use crate::{Result, RustADBError};
+
+/// Bytes of an `LST2` reply after its id.
+pub(crate) const STAT_V2_LEN: usize = 68;
+
+// `errno` values reported by `stat_v2`, as defined on Linux.
+const EPERM: u32 = 1;
+const ENOENT: u32 = 2;
+const EACCES: u32 = 13;
+const ENOTDIR: u32 = 20;
+
 /// Represents a `stat` response
 #[derive(Debug, Deserialize, Serialize)]
 pub struct AdbStatResponse {
     /// File permissions
     pub file_perm: u32,
-    /// File size, in bytes
-    pub file_size: u32,
-    /// File modification time
+    /// File size, in bytes. Truncated to 32 bits by devices without `stat_v2`.
+    pub file_size: u64,
+    /// File modification time, in seconds since the Unix epoch. Clamped to 32 bits; see
+    /// [`AdbStatResponse::modified`] for the full value.
     pub mod_time: u32,
+    /// `errno` of the failed `lstat`, 0 on success. Only reported with `stat_v2`.
+    pub error: Option<u32>,
+    /// Owner user id. Only reported with `stat_v2`.
+    pub uid: Option<u32>,
+    /// Owner group id. Only reported with `stat_v2`.
+    pub gid: Option<u32>,
+    /// Id of the filesystem holding the file. Only reported with `stat_v2`.
+    pub dev: Option<u64>,
+    /// Inode number. Only reported with `stat_v2`.
+    pub ino: Option<u64>,
+    /// Number of hard links. Only reported with `stat_v2`.
+    pub nlink: Option<u32>,
+    /// Last access time, in seconds since the Unix epoch. Only reported with `stat_v2`.
+    pub access_time: Option<i64>,
+    /// Modification time with 64 bits, in seconds since the Unix epoch. Only reported with
+    /// `stat_v2`.
+    pub modification_time: Option<i64>,
+    /// Last status change time, in seconds since the Unix epoch. Only reported with
+    /// `stat_v2`.
+    pub change_time: Option<i64>,
 }
 
 impl From<[u8; 12]> for AdbStatResponse {
     fn from(value: [u8; 12]) -> Self {
         Self {
             file_perm: LittleEndian::read_u32(&value[0..4]),
-            file_size: LittleEndian::read_u32(&value[4..8]),
+            file_size: u64::from(LittleEndian::read_u32(&value[4..8])),
             mod_time: LittleEndian::read_u32(&value[8..]),
+            error: None,
+            uid: None,
+            gid: None,
+            dev: None,
+            ino: None,
+            nlink: None,
+            access_time: None,
+            modification_time: None,
+            change_time: None,
+        }
+    }
+}
+
+impl From<[u8; STAT_V2_LEN]> for AdbStatResponse {
+    /// Fields of an `LST2` reply following its id: error, dev, ino, mode, nlink, uid,
+    /// gid, size, atime, mtime and ctime.
+    fn from(value: [u8; STAT_V2_LEN]) -> Self {
+        let mod_time = LittleEndian::read_i64(&value[52..60]);
+        Self {
+            file_perm: LittleEndian::read_u32(&value[20..24]),
+            file_size: LittleEndian::read_u64(&value[36..44]),
+            mod_time: u32::try_from(mod_time.max(0)).unwrap_or(u32::MAX),
+            error: Some(LittleEndian::read_u32(&value[0..4])),
+            uid: Some(LittleEndian::read_u32(&value[28..32])),
+            gid: Some(LittleEndian::read_u32(&value[32..36])),
+            dev: Some(LittleEndian::read_u64(&value[4..12])),
+            ino: Some(LittleEndian::read_u64(&value[12..20])),
+            nlink: Some(LittleEndian::read_u32(&value[24..28])),
+            access_time: Some(LittleEndian::read_i64(&value[44..52])),
+            modification_time: Some(mod_time),
+            change_time: Some(LittleEndian::read_i64(&value[60..68])),
         }
     }
 }
 
+impl AdbStatResponse {
+    /// Turns a reply to a failed stat of `path` into the matching error. Devices without
+    /// `stat_v2` answer failures with zeros only, taken to mean that `path` does not exist.
+    pub(crate) fn into_result(self, path: &str) -> Result<Self> {
+        match self.error {
+            None | Some(0) if self.file_perm != 0 => Ok(self),
+            None | Some(0) | Some(ENOENT | ENOTDIR) => {
+                Err(RustADBError::RemoteFileNotFound(path.to_string()))
+            }
+            Some(EACCES | EPERM) => Err(RustADBError::PermissionDenied(path.to_string())),
+            Some(errno) => Err(RustADBError::RemoteStatFailed(path.to_string(), errno)),
+        }
+    }
+
+    /// What the stat'ed path points to.
+    pub fn kind(&self) -> RemoteEntryKind {
+        RemoteEntryKind::from_mode(self.file_perm)
+    }
+
+    /// Modification time in seconds since the Unix epoch, with 64 bits when the device
+    /// supports `stat_v2`.
+    pub fn modified(&self) -> i64 {
+        self.modification_time
+            .unwrap_or_else(|| i64::from(self.mod_time))
+    }
+}
+
 impl Display for AdbStatResponse {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let d = UNIX_EPOCH + Duration::from_secs(self.mod_time.into());
+        let d = UNIX_EPOCH + Duration::from_secs(self.modified().max(0).unsigned_abs());
         // Create DateTime from SystemTime
         let datetime = DateTime::<Utc>::from(d);
 
         writeln!(f, "File permissions: {}", self.file_perm)?;
         writeln!(f, "File size: {} bytes", self.file_size)?;
+        if let (Some(uid), Some(gid)) = (self.uid, self.gid) {
+            writeln!(f, "Owner: {uid}:{gid}")?;
+        }
+        if let Some(nlink) = self.nlink {
+            writeln!(f, "Links: {nlink}")?;
+        }
         write!(
             f,
             "Modification time: {}",
@@ -45,3 +142,53 @@ impl Display for AdbStatResponse {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failed_stat_into_error() {
+        let missing = AdbStatResponse::from([0; 12]);
+        assert!(matches!(
+            missing.into_result("/sdcard/gone"),
+            Err(RustADBError::RemoteFileNotFound(path)) if path == "/sdcard/gone"
+        ));
+
+        let mut reply = [0; STAT_V2_LEN];
+        reply[..4].copy_from_slice(&EACCES.to_le_bytes());
+        assert!(matches!(
+            AdbStatResponse::from(reply).into_result("/data/data"),
+            Err(RustADBError::PermissionDenied(_))
+        ));
+
+        reply[..4].copy_from_slice(&0u32.to_le_bytes());
+        reply[20..24].copy_from_slice(&0o100_644u32.to_le_bytes());
+        assert!(
+            AdbStatResponse::from(reply)
+                .into_result("/sdcard/a")
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn stat_v2_metadata() {
+        let mut reply = [0; STAT_V2_LEN];
+        reply[12..20].copy_from_slice(&4242u64.to_le_bytes());
+        reply[20..24].copy_from_slice(&0o100_660u32.to_le_bytes());
+        reply[24..28].copy_from_slice(&2u32.to_le_bytes());
+        reply[28..32].copy_from_slice(&10_123u32.to_le_bytes());
+        reply[32..36].copy_from_slice(&9997u32.to_le_bytes());
+        // 2110-01-01, past the 32-bit range.
+        reply[52..60].copy_from_slice(&4_417_977_600i64.to_le_bytes());
+
+        let stat = AdbStatResponse::from(reply);
+        assert_eq!(stat.ino, Some(4242));
+        assert_eq!(stat.nlink, Some(2));
+        assert_eq!((stat.uid, stat.gid), (Some(10_123), Some(9997)));
+        assert_eq!(stat.modified(), 4_417_977_600);
+        assert_eq!(stat.mod_time, u32::MAX);
+
+        assert_eq!(AdbStatResponse::from([0; 12]).nlink, None);
+    }
+}
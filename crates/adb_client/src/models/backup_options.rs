@@ -0,0 +1,69 @@
+use crate::utils::shell_quote;
+
+/// What [`crate::ADBDeviceExt::backup`] puts in the archive besides app data, as `bu backup`
+/// flags.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BackupOptions {
+    /// Include the APKs of the apps (`-apk`).
+    pub apks: bool,
+    /// Include the apps' expansion files (`-obb`).
+    pub obb: bool,
+    /// Include shared storage, such as the contents of `/sdcard` (`-shared`).
+    pub shared: bool,
+    /// Back up every installed app instead of only the listed packages (`-all`).
+    pub all: bool,
+    /// With [`BackupOptions::all`], leave out system apps (`-nosystem`).
+    pub no_system: bool,
+    /// Include apps that use the key/value backup API (`-keyvalue`).
+    pub key_value: bool,
+}
+
+impl BackupOptions {
+    /// Flags to add to the backup command.
+    pub(crate) fn flags(&self) -> Vec<&'static str> {
+        [
+            (self.apks, "-apk"),
+            (self.obb, "-obb"),
+            (self.shared, "-shared"),
+            (self.all, "-all"),
+            (self.all && self.no_system, "-nosystem"),
+            (self.key_value, "-keyvalue"),
+        ]
+        .into_iter()
+        .filter_map(|(set, flag)| set.then_some(flag))
+        .collect()
+    }
+
+    /// Arguments of the `backup:` service for `packages`, each preceded by a space. `adbd`
+    /// runs `bu` through the shell, so package names are quoted.
+    pub(crate) fn args(&self, packages: &[&str]) -> String {
+        let flags = self.flags().into_iter().map(str::to_string);
+        let packages = packages.iter().map(|package| shell_quote(package));
+        flags.chain(packages).map(|arg| format!(" {arg}")).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backup_args() {
+        let options = BackupOptions {
+            apks: true,
+            no_system: true,
+            ..BackupOptions::default()
+        };
+        assert_eq!(
+            options.args(&["com.example.app", "org.example"]),
+            " -apk 'com.example.app' 'org.example'"
+        );
+
+        let options = BackupOptions {
+            all: true,
+            no_system: true,
+            ..BackupOptions::default()
+        };
+        assert_eq!(options.args(&[]), " -all -nosystem");
+    }
+}
@@ -0,0 +1,110 @@
+use serde::{Deserialize, Serialize};
+
+/// Whether the battery is taking charge, from the `status` that `dumpsys battery` reports.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChargingState {
+    /// The device doesn't say
+    #[default]
+    Unknown,
+    /// Plugged in and charging
+    Charging,
+    /// Running on battery
+    Discharging,
+    /// Plugged in but not charging, e.g. because the battery is too hot
+    NotCharging,
+    /// Plugged in and fully charged
+    Full,
+}
+
+impl ChargingState {
+    fn from_status(status: u32) -> Self {
+        // BatteryManager.BATTERY_STATUS_* values.
+        match status {
+            2 => Self::Charging,
+            3 => Self::Discharging,
+            4 => Self::NotCharging,
+            5 => Self::Full,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+/// Battery of a device, returned by [`crate::ADBDeviceExt::battery_status`]. Values the
+/// device doesn't report, as on some emulators and TV boxes, are `None`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatteryStatus {
+    /// Charge left, in percent.
+    pub level: Option<u8>,
+    /// Whether the battery is charging.
+    pub state: ChargingState,
+    /// Whether the device is plugged into an AC, USB, wireless or dock charger.
+    pub powered: bool,
+    /// Battery temperature, in tenths of a degree Celsius.
+    pub temperature: Option<i32>,
+}
+
+impl BatteryStatus {
+    /// Parses the `key: value` lines of `dumpsys battery`, scaling `level` by `scale` when
+    /// the device doesn't count in percent.
+    pub(crate) fn parse_dumpsys(output: &str) -> Self {
+        let value = |key: &str| {
+            output.lines().find_map(|line| {
+                let (name, value) = line.split_once(':')?;
+                (name.trim() == key).then(|| value.trim())
+            })
+        };
+        let number = |key: &str| value(key).and_then(|value| value.parse::<i64>().ok());
+
+        let scale = number("scale").filter(|scale| *scale > 0).unwrap_or(100);
+        let level = number("level")
+            .map(|level| (level * 100 / scale).clamp(0, 100))
+            .and_then(|level| u8::try_from(level).ok());
+        let state = number("status")
+            .and_then(|status| u32::try_from(status).ok())
+            .map_or(ChargingState::Unknown, ChargingState::from_status);
+        let powered = [
+            "AC powered",
+            "USB powered",
+            "Wireless powered",
+            "Dock powered",
+        ]
+        .iter()
+        .any(|key| value(key) == Some("true"));
+        let temperature = number("temperature").and_then(|value| i32::try_from(value).ok());
+        Self {
+            level,
+            state,
+            powered,
+            temperature,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_dumpsys_battery() {
+        let output = "Current Battery Service state:\n  AC powered: false\n  USB powered: true\n  \
+                      Wireless powered: false\n  Max charging current: 500000\n  status: 2\n  \
+                      health: 2\n  present: true\n  level: 85\n  scale: 100\n  voltage: 4213\n  \
+                      temperature: 291\n  technology: Li-ion\n";
+        assert_eq!(
+            BatteryStatus::parse_dumpsys(output),
+            BatteryStatus {
+                level: Some(85),
+                state: ChargingState::Charging,
+                powered: true,
+                temperature: Some(291),
+            }
+        );
+
+        let scaled = BatteryStatus::parse_dumpsys("  status: 3\n  level: 120\n  scale: 200\n");
+        assert_eq!(scaled.level, Some(60));
+        assert_eq!(scaled.state, ChargingState::Discharging);
+        assert!(!scaled.powered);
+        assert_eq!(BatteryStatus::parse_dumpsys(""), BatteryStatus::default());
+    }
+}
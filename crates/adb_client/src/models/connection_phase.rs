@@ -0,0 +1,26 @@
+use std::fmt::Display;
+
+/// Steps reported while a connection with a device is being established.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionPhase {
+    /// Transport is open and `CNXN` has been sent, waiting for the device to answer
+    Connecting,
+    /// Device requested authentication, its challenge is being signed with our key
+    Authenticating,
+    /// Device did not accept our signature: our public key has been sent and the
+    /// user has to accept it on the device
+    AwaitingUserApproval,
+    /// Device accepted the connection
+    Connected,
+}
+
+impl Display for ConnectionPhase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConnectionPhase::Connecting => write!(f, "connecting"),
+            ConnectionPhase::Authenticating => write!(f, "authenticating"),
+            ConnectionPhase::AwaitingUserApproval => write!(f, "awaiting user approval"),
+            ConnectionPhase::Connected => write!(f, "connected"),
+        }
+    }
+}
@@ -0,0 +1,81 @@
+/// What a device announced about itself in the `CNXN` message accepting our connection,
+/// such as `device::ro.product.name=x;ro.product.model=y;ro.product.device=z;features=a,b`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeviceBanner {
+    /// State the device is in, such as `device`, `recovery`, `rescue` or `sideload`
+    pub state: String,
+    /// `ro.product.name`
+    pub product_name: Option<String>,
+    /// `ro.product.model`
+    pub product_model: Option<String>,
+    /// `ro.product.device`
+    pub product_device: Option<String>,
+    /// Protocol features the device supports, such as `shell_v2`, `stat_v2`, `abb` or
+    /// `sendrecv_v2_zstd`
+    pub features: Vec<String>,
+    /// Largest message payload the device accepts, in bytes
+    pub max_payload: u32,
+}
+
+impl DeviceBanner {
+    /// Parses the payload of a `CNXN` whose `arg1` was `max_payload`.
+    pub(crate) fn parse(banner: &[u8], max_payload: u32) -> Self {
+        let banner = String::from_utf8_lossy(banner);
+        let banner = banner.trim_end_matches('\0');
+        let (state, properties) = banner.split_once("::").unwrap_or((banner, ""));
+
+        let mut parsed = Self {
+            state: state.to_string(),
+            max_payload,
+            ..Self::default()
+        };
+        for property in properties.split(';') {
+            let Some((name, value)) = property.split_once('=') else {
+                continue;
+            };
+            match name {
+                "ro.product.name" => parsed.product_name = Some(value.to_string()),
+                "ro.product.model" => parsed.product_model = Some(value.to_string()),
+                "ro.product.device" => parsed.product_device = Some(value.to_string()),
+                "features" => {
+                    parsed.features = value
+                        .split(',')
+                        .filter(|feature| !feature.is_empty())
+                        .map(str::to_string)
+                        .collect();
+                }
+                _ => {}
+            }
+        }
+        parsed
+    }
+
+    /// Whether the device listed `feature`.
+    pub fn has_feature(&self, feature: &str) -> bool {
+        self.features.iter().any(|f| f == feature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_device_banner() {
+        let banner = DeviceBanner::parse(
+            b"device::ro.product.name=panther;ro.product.model=Pixel 7;ro.product.device=panther;features=shell_v2,stat_v2,abb\0",
+            1_048_576,
+        );
+        assert_eq!(banner.state, "device");
+        assert_eq!(banner.product_model.as_deref(), Some("Pixel 7"));
+        assert_eq!(banner.product_device.as_deref(), Some("panther"));
+        assert_eq!(banner.features, ["shell_v2", "stat_v2", "abb"]);
+        assert!(banner.has_feature("abb"));
+        assert!(!banner.has_feature("ls_v2"));
+        assert_eq!(banner.max_payload, 1_048_576);
+
+        let banner = DeviceBanner::parse(b"recovery::", 4096);
+        assert_eq!(banner.state, "recovery");
+        assert!(banner.features.is_empty());
+    }
+}
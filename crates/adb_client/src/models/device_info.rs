@@ -0,0 +1,48 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Identity of a device as its system properties describe it, returned by
+/// [`crate::ADBDeviceExt::device_info`]. Properties the device doesn't set are `None`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeviceInfo {
+    /// Serial number, as `adb devices` lists it for USB devices.
+    pub serial: Option<String>,
+    /// Marketing model name, such as `Pixel 7`.
+    pub model: Option<String>,
+    /// Manufacturer, such as `Google`.
+    pub manufacturer: Option<String>,
+    /// User-visible Android version, such as `14`.
+    pub android_version: Option<String>,
+    /// API level, such as 34 for Android 14.
+    pub sdk_level: Option<u32>,
+    /// Primary ABI, such as `arm64-v8a`.
+    pub abi: Option<String>,
+    /// Every ABI the device runs, most preferred first.
+    pub abis: Vec<String>,
+}
+
+impl DeviceInfo {
+    pub(crate) fn from_props(props: &BTreeMap<String, String>) -> Self {
+        let prop = |names: &[&str]| {
+            names
+                .iter()
+                .filter_map(|name| props.get(*name))
+                .map(|value| value.trim())
+                .find(|value| !value.is_empty())
+                .map(str::to_string)
+        };
+        let abis = prop(&["ro.product.cpu.abilist"])
+            .map(|list| list.split(',').map(str::to_string).collect())
+            .unwrap_or_default();
+        Self {
+            serial: prop(&["ro.serialno", "ro.boot.serialno"]),
+            model: prop(&["ro.product.model"]),
+            manufacturer: prop(&["ro.product.manufacturer"]),
+            android_version: prop(&["ro.build.version.release"]),
+            sdk_level: prop(&["ro.build.version.sdk"]).and_then(|sdk| sdk.parse().ok()),
+            abi: prop(&["ro.product.cpu.abi"]),
+            abis,
+        }
+    }
+}
@@ -0,0 +1,34 @@
+/// Package manager flags for [`crate::ADBDeviceExt::install_from_reader`] and
+/// [`crate::ADBDeviceExt::install_multiple`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InstallOptions {
+    /// Replace an installed app with the same package name (`-r`). Recent Android versions
+    /// always do.
+    pub replace: bool,
+    /// Allow installing an older version over a newer one (`-d`).
+    pub allow_downgrade: bool,
+    /// Grant every runtime permission listed in the manifest (`-g`).
+    pub grant_permissions: bool,
+    /// Allow packages marked `testOnly` (`-t`).
+    pub allow_test: bool,
+}
+
+impl InstallOptions {
+    /// Flags to add to the install command.
+    pub(crate) fn flags(&self) -> Vec<&'static str> {
+        [
+            (self.replace, "-r"),
+            (self.allow_downgrade, "-d"),
+            (self.grant_permissions, "-g"),
+            (self.allow_test, "-t"),
+        ]
+        .into_iter()
+        .filter_map(|(set, flag)| set.then_some(flag))
+        .collect()
+    }
+
+    /// [`InstallOptions::flags`] for a command line, each preceded by a space.
+    pub(crate) fn args(&self) -> String {
+        self.flags().iter().map(|flag| format!(" {flag}")).collect()
+    }
+}
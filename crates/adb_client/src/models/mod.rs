@@ -1,15 +1,36 @@
 mod adb_request_status;
 mod adb_server_command;
 mod adb_stat_response;
+mod backup_options;
+mod battery_status;
+mod connection_phase;
+mod device_banner;
+mod device_info;
 mod framebuffer_info;
 mod host_features;
+mod install_options;
 mod reboot_type;
+mod remote_entry;
+mod shell_output;
+mod storage_volume;
 mod sync_command;
+mod transport_timeouts;
 
 pub use adb_request_status::AdbRequestStatus;
 pub(crate) use adb_server_command::AdbServerCommand;
 pub use adb_stat_response::AdbStatResponse;
+pub(crate) use adb_stat_response::STAT_V2_LEN;
+pub use backup_options::BackupOptions;
+pub use battery_status::{BatteryStatus, ChargingState};
+pub use connection_phase::ConnectionPhase;
+pub use device_banner::DeviceBanner;
+pub use device_info::DeviceInfo;
 pub(crate) use framebuffer_info::{FrameBufferInfoV1, FrameBufferInfoV2};
 pub use host_features::HostFeatures;
+pub use install_options::InstallOptions;
 pub use reboot_type::RebootType;
+pub use remote_entry::{RemoteEntry, RemoteEntryKind};
+pub use shell_output::{ShellOutput, ShellSessionOutput, ShellWindowSize};
+pub use storage_volume::StorageVolume;
 pub use sync_command::SyncCommand;
+pub use transport_timeouts::TransportTimeouts;
@@ -0,0 +1,119 @@
+use serde::{Deserialize, Serialize};
+
+use crate::models::AdbStatResponse;
+
+/// File type bits of a POSIX mode (`S_IFMT`).
+const MODE_TYPE_MASK: u32 = 0o170_000;
+const MODE_REGULAR: u32 = 0o100_000;
+const MODE_DIRECTORY: u32 = 0o040_000;
+const MODE_SYMLINK: u32 = 0o120_000;
+
+/// What a remote path points to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RemoteEntryKind {
+    /// Regular file
+    File,
+    /// Directory
+    Directory,
+    /// Symbolic link
+    Symlink,
+    /// Device node, socket, pipe, or a path that does not exist
+    Other,
+}
+
+impl RemoteEntryKind {
+    /// Kind encoded in the file type bits of `mode`.
+    pub fn from_mode(mode: u32) -> Self {
+        match mode & MODE_TYPE_MASK {
+            MODE_REGULAR => Self::File,
+            MODE_DIRECTORY => Self::Directory,
+            MODE_SYMLINK => Self::Symlink,
+            _ => Self::Other,
+        }
+    }
+}
+
+/// Metadata of one remote path, as reported by a sync `STAT` or a directory listing entry.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RemoteEntry {
+    /// Path of the entry; for listing entries, its name within the listed directory
+    pub path: String,
+    /// What the path points to
+    pub kind: RemoteEntryKind,
+    /// Full POSIX mode, including the file type bits
+    pub mode: u32,
+    /// Size, in bytes
+    pub size: u64,
+    /// Modification time, in seconds since the Unix epoch
+    pub mtime: u64,
+    /// Owner user id, when the device reports it (`stat_v2` and `ls_v2`)
+    pub uid: Option<u32>,
+    /// Owner group id, when the device reports it (`stat_v2` and `ls_v2`)
+    pub gid: Option<u32>,
+}
+
+impl RemoteEntry {
+    /// Builds an entry for `path` from its mode, size and mtime.
+    pub fn new(path: impl Into<String>, mode: u32, size: u64, mtime: u64) -> Self {
+        Self {
+            path: path.into(),
+            kind: RemoteEntryKind::from_mode(mode),
+            mode,
+            size,
+            mtime,
+            uid: None,
+            gid: None,
+        }
+    }
+
+    /// Same entry, owned by `uid` and `gid`.
+    pub fn with_owner(mut self, uid: u32, gid: u32) -> Self {
+        self.uid = Some(uid);
+        self.gid = Some(gid);
+        self
+    }
+
+    /// Builds an entry for `path` from its `stat` response.
+    pub fn from_stat(path: impl Into<String>, stat: &AdbStatResponse) -> Self {
+        Self {
+            uid: stat.uid,
+            gid: stat.gid,
+            ..Self::new(
+                path,
+                stat.file_perm,
+                stat.file_size,
+                u64::try_from(stat.modified()).unwrap_or_default(),
+            )
+        }
+    }
+
+    /// Whether the entry is a regular file.
+    pub fn is_file(&self) -> bool {
+        self.kind == RemoteEntryKind::File
+    }
+
+    /// Whether the entry is a directory.
+    pub fn is_dir(&self) -> bool {
+        self.kind == RemoteEntryKind::Directory
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kind_from_mode() {
+        assert_eq!(RemoteEntryKind::from_mode(0o100_644), RemoteEntryKind::File);
+        assert_eq!(
+            RemoteEntryKind::from_mode(0o040_771),
+            RemoteEntryKind::Directory
+        );
+        assert_eq!(
+            RemoteEntryKind::from_mode(0o120_777),
+            RemoteEntryKind::Symlink
+        );
+        assert_eq!(RemoteEntryKind::from_mode(0), RemoteEntryKind::Other);
+    }
+}
@@ -0,0 +1,55 @@
+/// Output of a command run with [`crate::ADBDeviceExt::shell_command_output`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShellOutput {
+    /// Standard output. Also holds standard error when `exit_code` is `None`.
+    pub stdout: Vec<u8>,
+    /// Standard error. Always empty on devices without `shell_v2`.
+    pub stderr: Vec<u8>,
+    /// Exit status, or `None` on devices without `shell_v2`, which don't report it.
+    pub exit_code: Option<u8>,
+}
+
+impl ShellOutput {
+    /// Whether the command is known to have exited with a non-zero status.
+    pub fn failed(&self) -> bool {
+        self.exit_code.is_some_and(|code| code != 0)
+    }
+
+    /// Standard output as text, with invalid UTF-8 replaced.
+    pub fn stdout_lossy(&self) -> String {
+        String::from_utf8_lossy(&self.stdout).into_owned()
+    }
+
+    /// Standard error as text, with invalid UTF-8 replaced.
+    pub fn stderr_lossy(&self) -> String {
+        String::from_utf8_lossy(&self.stderr).into_owned()
+    }
+}
+
+/// Terminal size of an [`crate::ADBShellSession`] running in a pseudo-terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShellWindowSize {
+    /// Height, in characters
+    pub rows: u16,
+    /// Width, in characters
+    pub cols: u16,
+}
+
+impl ShellWindowSize {
+    /// Value of a shell v2 window size change packet, `rowsxcols,xpixelsxypixels`.
+    pub(crate) fn to_packet_data(self) -> String {
+        format!("{}x{},0x0", self.rows, self.cols)
+    }
+}
+
+/// Something an [`crate::ADBShellSession`] received from the device.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShellSessionOutput {
+    /// Bytes written to standard output. Also holds standard error on devices without
+    /// `shell_v2`, or when the session runs in a pseudo-terminal.
+    Stdout(Vec<u8>),
+    /// Bytes written to standard error.
+    Stderr(Vec<u8>),
+    /// The shell ended, with its exit status when the device reports it.
+    Exited(Option<u8>),
+}
@@ -0,0 +1,71 @@
+use serde::{Deserialize, Serialize};
+
+/// Space on one mounted filesystem of the device, as `df` reports it.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct StorageVolume {
+    /// What is mounted, such as `/dev/block/dm-5` or `/dev/fuse`
+    pub filesystem: String,
+    /// Where it is mounted, such as `/data` or `/storage/emulated`
+    pub mount_point: String,
+    /// Size, in bytes
+    pub total_bytes: u64,
+    /// Space in use, in bytes
+    pub used_bytes: u64,
+    /// Space left for apps and the shell user, in bytes. Less than `total_bytes` minus
+    /// `used_bytes` when some is reserved for root.
+    pub free_bytes: u64,
+}
+
+impl StorageVolume {
+    /// Parses the output of `df -k`: a header, then one line per filesystem with its
+    /// 1 KiB blocks, used and available blocks, use percentage and mount point.
+    /// Filesystems without any size, such as `proc` or empty `tmpfs`, are left out.
+    pub(crate) fn parse_df(output: &str) -> Vec<Self> {
+        output
+            .lines()
+            .skip(1)
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace();
+                let filesystem = fields.next()?;
+                let mut kib = || fields.next()?.parse::<u64>().ok();
+                let (total, used, free) = (kib()?, kib()?, kib()?);
+                let _use_percent = fields.next()?;
+                let mount_point = fields.collect::<Vec<_>>().join(" ");
+                (total > 0 && !mount_point.is_empty()).then(|| Self {
+                    filesystem: filesystem.to_string(),
+                    mount_point,
+                    total_bytes: total * 1024,
+                    used_bytes: used * 1024,
+                    free_bytes: free * 1024,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_df_output() {
+        let output = "Filesystem      1K-blocks     Used Available Use% Mounted on\n\
+                      /dev/block/dm-5   5079888  4967144     96360  99% /\n\
+                      tmpfs                   0        0         0   0% /mnt\n\
+                      /dev/block/dm-42 115451384 61216564  54103748  54% /data\n\
+                      /dev/fuse        115451384 61216564  54103748  54% /storage/emulated\n";
+        let volumes = StorageVolume::parse_df(output);
+        assert_eq!(volumes.len(), 3);
+        assert_eq!(
+            volumes[2],
+            StorageVolume {
+                filesystem: "/dev/fuse".to_string(),
+                mount_point: "/storage/emulated".to_string(),
+                total_bytes: 115_451_384 * 1024,
+                used_bytes: 61_216_564 * 1024,
+                free_bytes: 54_103_748 * 1024,
+            }
+        );
+        assert!(StorageVolume::parse_df("df: /mnt/secure: Permission denied\n").is_empty());
+    }
+}
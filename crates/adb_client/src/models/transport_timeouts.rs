@@ -0,0 +1,35 @@
+use std::time::Duration;
+
+use crate::transports::{DEFAULT_READ_TIMEOUT, DEFAULT_WRITE_TIMEOUT};
+
+/// How long a device reached over an [`crate::ADBMessageTransport`] is waited on, per
+/// operation. Defaults wait as long as it takes for the device to answer, except for
+/// writes and authentication steps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransportTimeouts {
+    /// Wait for the device to answer our `CNXN`. Short values fail fast when the device
+    /// is unplugged or its adbd is not running.
+    pub connect: Duration,
+    /// Wait for each message of the authentication handshake, until the user is asked
+    /// to accept our key.
+    pub auth: Duration,
+    /// Wait for the user to accept our key on the device.
+    pub user_approval: Duration,
+    /// Wait for each message once connected, such as the next chunk of a pull or the
+    /// listing of a huge directory.
+    pub read: Duration,
+    /// Wait for the device to take each message we send.
+    pub write: Duration,
+}
+
+impl Default for TransportTimeouts {
+    fn default() -> Self {
+        Self {
+            connect: DEFAULT_READ_TIMEOUT,
+            auth: Duration::from_secs(10),
+            user_approval: Duration::from_secs(60),
+            read: DEFAULT_READ_TIMEOUT,
+            write: DEFAULT_WRITE_TIMEOUT,
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use std::io::{self, Read, Write};
+
+/// [`Read`] adapter reporting how many bytes were read so far after each read, used to
+/// follow a push as the device takes each chunk.
+pub(crate) struct ProgressReader<'a, R> {
+    inner: R,
+    total: u64,
+    on_progress: &'a mut dyn FnMut(u64),
+}
+
+impl<'a, R: Read> ProgressReader<'a, R> {
+    pub(crate) fn new(inner: R, on_progress: &'a mut dyn FnMut(u64)) -> Self {
+        Self {
+            inner,
+            total: 0,
+            on_progress,
+        }
+    }
+}
+
+impl<R: Read> Read for ProgressReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        if read > 0 {
+            self.total = self.total.saturating_add(read as u64);
+            (self.on_progress)(self.total);
+        }
+        Ok(read)
+    }
+}
+
+/// [`Write`] adapter reporting how many bytes were written so far after each write, used to
+/// follow a pull as data arrives from the device.
+pub(crate) struct ProgressWriter<'a, W> {
+    inner: W,
+    total: u64,
+    on_progress: &'a mut dyn FnMut(u64),
+}
+
+impl<'a, W: Write> ProgressWriter<'a, W> {
+    pub(crate) fn new(inner: W, on_progress: &'a mut dyn FnMut(u64)) -> Self {
+        Self {
+            inner,
+            total: 0,
+            on_progress,
+        }
+    }
+}
+
+impl<W: Write> Write for ProgressWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        if written > 0 {
+            self.total = self.total.saturating_add(written as u64);
+            (self.on_progress)(self.total);
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
@@ -0,0 +1,112 @@
+use std::fmt;
+
+use crate::{ADBDeviceExt, RemoteEntry, Result, RustADBError};
+
+/// Depth-first walk of a remote directory tree, built on [`ADBDeviceExt::list_dir`].
+///
+/// Each directory is yielded before its contents, with [`RemoteEntry::path`] relative to the
+/// walked root. Symbolic links are reported but never followed.
+///
+/// A directory that can't be listed, usually for lack of permission, yields one `Err` after its
+/// own entry and the walk carries on with its siblings. adbd lists most unreadable directories
+/// as empty rather than failing, so those simply have no children. Any other error, such as a
+/// lost connection, is yielded once and ends the walk.
+pub struct RemoteWalk<'a> {
+    device: &'a mut dyn ADBDeviceExt,
+    root: String,
+    /// Entries still to yield, the next one last.
+    pending: Vec<RemoteEntry>,
+    /// Error listing the directory yielded last, reported before moving on.
+    deferred: Option<RustADBError>,
+    started: bool,
+    finished: bool,
+}
+
+impl<'a> RemoteWalk<'a> {
+    /// Walk the tree below `root` on `device`. Nothing is listed until the first call to
+    /// [`Iterator::next`].
+    pub fn new(device: &'a mut dyn ADBDeviceExt, root: &str) -> Self {
+        Self {
+            device,
+            root: root.trim_end_matches('/').to_string(),
+            pending: Vec::new(),
+            deferred: None,
+            started: false,
+            finished: false,
+        }
+    }
+
+    /// Lists `relative` (empty for the root) and queues its entries in order.
+    fn descend(&mut self, relative: &str) -> Result<()> {
+        let path = match relative {
+            "" if self.root.is_empty() => "/".to_string(),
+            "" => self.root.clone(),
+            relative => format!("{}/{relative}", self.root),
+        };
+        let children = self.device.list_dir(&path)?;
+        self.pending
+            .extend(children.into_iter().rev().map(|mut child| {
+                if !relative.is_empty() {
+                    child.path = format!("{relative}/{}", child.path);
+                }
+                child
+            }));
+        Ok(())
+    }
+
+    /// Errors that only concern the directory being listed, after which walking the
+    /// rest of the tree still makes sense.
+    fn is_local_to_directory(error: &RustADBError) -> bool {
+        matches!(error, RustADBError::ADBRequestFailed(_))
+    }
+
+    fn fail(&mut self, error: RustADBError) -> Option<Result<RemoteEntry>> {
+        if Self::is_local_to_directory(&error) {
+            return Some(Err(error));
+        }
+        self.finished = true;
+        self.pending.clear();
+        Some(Err(error))
+    }
+}
+
+impl Iterator for RemoteWalk<'_> {
+    type Item = Result<RemoteEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(error) = self.deferred.take() {
+            return self.fail(error);
+        }
+        if self.finished {
+            return None;
+        }
+        if !self.started {
+            self.started = true;
+            if let Err(error) = self.descend("") {
+                self.finished = true;
+                return Some(Err(error));
+            }
+        }
+
+        let Some(entry) = self.pending.pop() else {
+            self.finished = true;
+            return None;
+        };
+        if entry.is_dir() {
+            if let Err(error) = self.descend(&entry.path) {
+                self.deferred = Some(error);
+            }
+        }
+        Some(Ok(entry))
+    }
+}
+
+impl fmt::Debug for RemoteWalk<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RemoteWalk")
+            .field("root", &self.root)
+            .field("pending", &self.pending.len())
+            .field("finished", &self.finished)
+            .finish_non_exhaustive()
+    }
+}
@@ -6,7 +6,7 @@ use std::{
 use crate::{
     ADBDeviceExt, Result, RustADBError,
     constants::BUFFER_SIZE,
-    models::{AdbServerCommand, AdbStatResponse, HostFeatures},
+    models::{AdbServerCommand, AdbStatResponse, HostFeatures, RemoteEntry},
 };
 
 use super::ADBServerDevice;
@@ -45,6 +45,10 @@ impl ADBDeviceExt for ADBServerDevice {
         self.stat(remote_path)
     }
 
+    fn list_dir(&mut self, remote_path: &str) -> Result<Vec<RemoteEntry>> {
+        self.list_dir(remote_path)
+    }
+
     fn shell(
         &mut self,
         mut reader: &mut dyn Read,
@@ -112,8 +116,54 @@ impl ADBDeviceExt for ADBServerDevice {
         self.install(apk_path)
     }
 
-    fn uninstall(&mut self, package: &str) -> Result<()> {
-        self.uninstall(package)
+    fn install_from_reader(
+        &mut self,
+        apk: &mut dyn Read,
+        size: u64,
+        options: &crate::InstallOptions,
+    ) -> Result<()> {
+        self.install_from_reader(apk, size, options)
+    }
+
+    fn install_multiple(
+        &mut self,
+        apk_paths: &[&Path],
+        options: &crate::InstallOptions,
+    ) -> Result<()> {
+        self.install_multiple(apk_paths, options)
+    }
+
+    fn uninstall(&mut self, package: &str, keep_data: bool) -> Result<()> {
+        self.uninstall(package, keep_data)
+    }
+
+    fn root(&mut self) -> Result<()> {
+        self.root()
+    }
+
+    fn unroot(&mut self) -> Result<()> {
+        self.unroot()
+    }
+
+    fn remount(&mut self) -> Result<String> {
+        self.remount()
+    }
+
+    fn sideload(&mut self, package_path: &dyn AsRef<Path>) -> Result<()> {
+        self.sideload(package_path)
+    }
+
+    fn backup(
+        &mut self,
+        packages: &[&str],
+        options: &crate::BackupOptions,
+        output: &mut dyn Write,
+    ) -> Result<()> {
+        self.backup(packages, options, output)
+    }
+
+    fn restore(&mut self, input: &mut dyn Read) -> Result<()> {
+        self.restore(input)
     }
 
     fn framebuffer_inner(&mut self) -> Result<image::ImageBuffer<image::Rgba<u8>, Vec<u8>>> {
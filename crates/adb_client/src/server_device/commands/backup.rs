@@ -0,0 +1,46 @@
+use std::io::{Read, Write};
+
+use crate::{BackupOptions, Result, models::AdbServerCommand, server_device::ADBServerDevice};
+
+/// Zeroed tar blocks sent after a restore archive, marking its end even when it is truncated.
+const RESTORE_END_MARKER: [u8; 1024] = [0; 1024];
+
+impl ADBServerDevice {
+    /// Back up the data of `packages` to `output`, once the user confirms on the device
+    pub fn backup(
+        &mut self,
+        packages: &[&str],
+        options: &BackupOptions,
+        output: &mut dyn Write,
+    ) -> Result<()> {
+        self.set_serial_transport()?;
+
+        self.transport
+            .send_adb_request(AdbServerCommand::Backup(options.args(packages)))?;
+
+        // The server closes the connection once `bu` has written the whole archive.
+        std::io::copy(&mut self.transport.get_raw_connection()?, output)?;
+        output.flush()?;
+        Ok(())
+    }
+
+    /// Restore a backup read from `input`, once the user confirms on the device
+    pub fn restore(&mut self, input: &mut dyn Read) -> Result<()> {
+        self.set_serial_transport()?;
+
+        self.transport.send_adb_request(AdbServerCommand::Restore)?;
+
+        let mut raw_connection = self.transport.get_raw_connection()?;
+        std::io::copy(input, &mut raw_connection)?;
+        raw_connection.write_all(&RESTORE_END_MARKER)?;
+
+        // Closing before `bu` exits would interrupt the restore.
+        let mut output = Vec::new();
+        self.transport
+            .get_raw_connection()?
+            .read_to_end(&mut output)?;
+        log::debug!("bu restore: {}", String::from_utf8_lossy(&output).trim());
+        log::info!("Backup successfully restored");
+        Ok(())
+    }
+}
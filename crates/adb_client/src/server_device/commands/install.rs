@@ -1,7 +1,13 @@
 use std::{fs::File, io::Read, path::Path};
 
 use crate::{
-    Result, models::AdbServerCommand, server_device::ADBServerDevice, utils::check_extension_is_apk,
+    InstallOptions, Result,
+    models::AdbServerCommand,
+    server_device::ADBServerDevice,
+    utils::{
+        SplitApk, check_extension_is_apk, check_package_manager_output, open_split_apks,
+        parse_install_session,
+    },
 };
 
 impl ADBServerDevice {
@@ -13,29 +19,88 @@ impl ADBServerDevice {
 
         let file_size = apk_file.metadata()?.len();
 
+        self.install_from_reader(&mut apk_file, file_size, &InstallOptions::default())?;
+        log::info!(
+            "APK file {} successfully installed",
+            apk_path.as_ref().display()
+        );
+        Ok(())
+    }
+
+    /// Install an APK of `size` bytes read from `apk` on device
+    pub fn install_from_reader(
+        &mut self,
+        apk: &mut dyn Read,
+        size: u64,
+        options: &InstallOptions,
+    ) -> Result<()> {
         self.set_serial_transport()?;
 
         self.transport
-            .send_adb_request(AdbServerCommand::Install(file_size))?;
+            .send_adb_request(AdbServerCommand::Install(size, options.args()))?;
 
         let mut raw_connection = self.transport.get_raw_connection()?;
 
-        std::io::copy(&mut apk_file, &mut raw_connection)?;
+        std::io::copy(apk, &mut raw_connection)?;
 
-        let mut data = [0; 1024];
-        let read_amount = self.transport.get_raw_connection()?.read(&mut data)?;
+        // The server closes the connection once the package manager is done.
+        let mut output = Vec::new();
+        self.transport
+            .get_raw_connection()?
+            .read_to_end(&mut output)?;
+
+        check_package_manager_output(&String::from_utf8_lossy(&output))
+    }
 
-        match &data[0..read_amount] {
-            b"Success\n" => {
-                log::info!(
-                    "APK file {} successfully installed",
-                    apk_path.as_ref().display()
-                );
-                Ok(())
+    /// Install an app split across several APKs in one package manager session
+    pub fn install_multiple(
+        &mut self,
+        apk_paths: &[&Path],
+        options: &InstallOptions,
+    ) -> Result<()> {
+        let mut apks = open_split_apks(apk_paths)?;
+        let total_size: u64 = apks.iter().map(|apk| apk.size).sum();
+
+        let session = parse_install_session(
+            &self.package_command(&format!("install-create{} -S {total_size}", options.args()))?,
+        )?;
+
+        let result = self.write_and_commit(session, &mut apks);
+        if result.is_err() {
+            if let Err(error) = self.package_command(&format!("install-abandon {session}")) {
+                log::warn!("could not abandon install session {session}: {error}");
             }
-            d => Err(crate::RustADBError::ADBRequestFailed(String::from_utf8(
-                d.to_vec(),
-            )?)),
         }
+        result
+    }
+
+    fn write_and_commit(&mut self, session: u32, apks: &mut [SplitApk]) -> Result<()> {
+        for apk in apks {
+            self.set_serial_transport()?;
+            self.transport
+                .send_adb_request(AdbServerCommand::PackageManager(format!(
+                    "install-write -S {} {session} {} -",
+                    apk.size, apk.name
+                )))?;
+            std::io::copy(&mut apk.file, &mut self.transport.get_raw_connection()?)?;
+            check_package_manager_output(&self.read_package_output()?)?;
+        }
+        check_package_manager_output(&self.package_command(&format!("install-commit {session}"))?)
+    }
+
+    /// Runs `cmd package {args}` and returns its output.
+    fn package_command(&mut self, args: &str) -> Result<String> {
+        self.set_serial_transport()?;
+        self.transport
+            .send_adb_request(AdbServerCommand::PackageManager(args.to_string()))?;
+        self.read_package_output()
+    }
+
+    fn read_package_output(&mut self) -> Result<String> {
+        let mut output = Vec::new();
+        self.transport
+            .get_raw_connection()?
+            .read_to_end(&mut output)?;
+        Ok(String::from_utf8_lossy(&output).into_owned())
     }
 }
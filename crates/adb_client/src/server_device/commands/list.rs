@@ -1,6 +1,6 @@
 use crate::{
     ADBServerDevice, Result,
-    models::{AdbServerCommand, SyncCommand},
+    models::{AdbServerCommand, RemoteEntry, SyncCommand},
 };
 use byteorder::{ByteOrder, LittleEndian};
 use std::{
@@ -11,6 +11,11 @@ use std::{
 impl ADBServerDevice {
     /// Lists files in path on the device.
     pub fn list<A: AsRef<str>>(&mut self, path: A) -> Result<()> {
+        self.list_dir(path.as_ref()).map(|_| ())
+    }
+
+    /// Lists the entries of the directory at `path`, without `.` and `..`.
+    pub fn list_dir(&mut self, path: &str) -> Result<Vec<RemoteEntry>> {
         self.set_serial_transport()?;
 
         // Set device in SYNC mode
@@ -22,11 +27,9 @@ impl ADBServerDevice {
         self.handle_list_command(path)
     }
 
-    // This command does not seem to work correctly. The devices I test it on just resturn
-    // 'DONE' directly without listing anything.
-    fn handle_list_command<S: AsRef<str>>(&mut self, path: S) -> Result<()> {
+    fn handle_list_command(&mut self, path: &str) -> Result<Vec<RemoteEntry>> {
         let mut len_buf = [0_u8; 4];
-        LittleEndian::write_u32(&mut len_buf, u32::try_from(path.as_ref().len())?);
+        LittleEndian::write_u32(&mut len_buf, u32::try_from(path.len())?);
 
         // 4 bytes of command name is already sent by send_sync_request
         self.transport.get_raw_connection()?.write_all(&len_buf)?;
@@ -34,35 +37,40 @@ impl ADBServerDevice {
         // List send the string of the directory to list, and then the server send a list of files
         self.transport
             .get_raw_connection()?
-            .write_all(path.as_ref().to_string().as_bytes())?;
+            .write_all(path.as_bytes())?;
 
-        // Reads returned status code from ADB server
+        // Each record is a status code followed by mode, size, mtime and name length; `DONE`
+        // carries the same four (zeroed) fields.
+        let mut entries = Vec::new();
         let mut response = [0_u8; 4];
         loop {
             self.transport
                 .get_raw_connection()?
                 .read_exact(&mut response)?;
+            let mut fields = [0_u8; 16];
+            self.transport
+                .get_raw_connection()?
+                .read_exact(&mut fields)?;
             match str::from_utf8(response.as_ref())? {
                 "DENT" => {
-                    // TODO: Move this to a struct that extract this data, but as the device
-                    // I test this on does not return anything, I can't test it.
-                    let mut file_mod = [0_u8; 4];
-                    let mut file_size = [0_u8; 4];
-                    let mut mod_time = [0_u8; 4];
-                    let mut name_len = [0_u8; 4];
-
-                    let mut connection = self.transport.get_raw_connection()?;
-                    connection.read_exact(&mut file_mod)?;
-                    connection.read_exact(&mut file_size)?;
-                    connection.read_exact(&mut mod_time)?;
-                    connection.read_exact(&mut name_len)?;
-
-                    let name_len = LittleEndian::read_u32(&name_len);
+                    let name_len = LittleEndian::read_u32(&fields[12..16]);
                     let mut name_buf = vec![0_u8; name_len as usize];
-                    connection.read_exact(&mut name_buf)?;
+                    self.transport
+                        .get_raw_connection()?
+                        .read_exact(&mut name_buf)?;
+
+                    let name = String::from_utf8_lossy(&name_buf);
+                    if name != "." && name != ".." {
+                        entries.push(RemoteEntry::new(
+                            name,
+                            LittleEndian::read_u32(&fields[0..4]),
+                            u64::from(LittleEndian::read_u32(&fields[4..8])),
+                            u64::from(LittleEndian::read_u32(&fields[8..12])),
+                        ));
+                    }
                 }
                 "DONE" => {
-                    return Ok(());
+                    return Ok(entries);
                 }
                 x => log::error!("Got an unknown response {x}"),
             }
@@ -1,3 +1,4 @@
+mod backup;
 mod forward;
 mod framebuffer;
 mod host_features;
@@ -8,7 +9,9 @@ mod reboot;
 mod reconnect;
 mod recv;
 mod reverse;
+mod root;
 mod send;
+mod sideload;
 mod stat;
 mod tcpip;
 mod transport;
@@ -0,0 +1,61 @@
+use std::{io::Read, time::Duration};
+
+use crate::{
+    Result, WaitForDeviceState, WaitForDeviceTransport,
+    models::AdbServerCommand,
+    server_device::ADBServerDevice,
+    utils::{check_remount_output, parse_adbd_restart},
+};
+
+/// How long adbd may take to exit after answering, while the server still lists the device.
+const ADBD_EXIT_DELAY: Duration = Duration::from_secs(1);
+
+impl ADBServerDevice {
+    /// Restart adbd as root, and wait for the server to see the device again
+    pub fn root(&mut self) -> Result<()> {
+        self.restart_adbd(AdbServerCommand::Root)
+    }
+
+    /// Restart adbd without root permissions, and wait for the server to see the device again
+    pub fn unroot(&mut self) -> Result<()> {
+        self.restart_adbd(AdbServerCommand::Unroot)
+    }
+
+    /// Remount system partitions read-write, returning what `remount` printed
+    pub fn remount(&mut self) -> Result<String> {
+        self.set_serial_transport()?;
+
+        self.transport.send_adb_request(AdbServerCommand::Remount)?;
+
+        let mut output = Vec::new();
+        self.transport
+            .get_raw_connection()?
+            .read_to_end(&mut output)?;
+        check_remount_output(&String::from_utf8_lossy(&output))
+    }
+
+    fn restart_adbd(&mut self, command: AdbServerCommand) -> Result<()> {
+        self.set_serial_transport()?;
+
+        self.transport.send_adb_request(command)?;
+
+        let mut output = Vec::new();
+        self.transport
+            .get_raw_connection()?
+            .read_to_end(&mut output)?;
+        if !parse_adbd_restart(&String::from_utf8_lossy(&output))? {
+            return Ok(());
+        }
+
+        std::thread::sleep(ADBD_EXIT_DELAY);
+        let wait = match &self.identifier {
+            Some(serial) => AdbServerCommand::WaitForSerial(serial.clone()),
+            None => AdbServerCommand::WaitForDevice(
+                WaitForDeviceState::Device,
+                WaitForDeviceTransport::Any,
+            ),
+        };
+        self.connect()?.send_adb_request(wait)?;
+        self.transport.read_adb_response()
+    }
+}
@@ -0,0 +1,46 @@
+use std::{
+    fs::File,
+    io::{Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+use crate::{
+    Result,
+    models::AdbServerCommand,
+    server_device::ADBServerDevice,
+    utils::{SIDELOAD_BLOCK_SIZE, parse_sideload_request},
+};
+
+impl ADBServerDevice {
+    /// Send an OTA package to a device in recovery waiting for `adb sideload`
+    pub fn sideload(&mut self, package_path: &dyn AsRef<Path>) -> Result<()> {
+        let mut package = File::open(package_path)?;
+        let size = package.metadata()?.len();
+
+        self.set_serial_transport()?;
+
+        self.transport
+            .send_adb_request(AdbServerCommand::SideloadHost(size, SIDELOAD_BLOCK_SIZE))?;
+
+        let mut raw_connection = self.transport.get_raw_connection()?;
+        let mut request = [0; 8];
+        let mut block = Vec::new();
+        loop {
+            raw_connection.read_exact(&mut request)?;
+            let Some(range) = parse_sideload_request(&request, size)? else {
+                break;
+            };
+
+            package.seek(SeekFrom::Start(range.start))?;
+            block.resize(usize::try_from(range.end - range.start)?, 0);
+            package.read_exact(&mut block)?;
+            raw_connection.write_all(&block)?;
+        }
+
+        log::info!(
+            "package {} successfully sideloaded",
+            package_path.as_ref().display()
+        );
+        Ok(())
+    }
+}
@@ -46,6 +46,6 @@ impl ADBServerDevice {
         // Send a "Stat" command
         self.transport.send_sync_request(SyncCommand::Stat)?;
 
-        self.handle_stat_command(path)
+        self.handle_stat_command(&path)?.into_result(path.as_ref())
     }
 }
@@ -1,4 +1,9 @@
-use crate::{ADBServerDevice, Result, models::AdbServerCommand};
+use std::net::SocketAddr;
+
+use crate::{
+    ADBDeviceExt, ADBServerDevice, Result, RustADBError, models::AdbServerCommand,
+    utils::parse_wifi_address,
+};
 
 impl ADBServerDevice {
     /// Set adb daemon to tcp/ip mode
@@ -9,4 +14,18 @@ impl ADBServerDevice {
             .proxy_connection(AdbServerCommand::TcpIp(port), false)
             .map(|_| ())
     }
+
+    /// Same as [`ADBServerDevice::tcpip`], returning the device's Wi-Fi address to reach it
+    /// at, looked up before adbd restarts.
+    pub fn enable_tcpip(&mut self, port: u16) -> Result<SocketAddr> {
+        let mut routes = Vec::new();
+        self.shell_command(&["ip", "route"], &mut routes)?;
+        let address = parse_wifi_address(&String::from_utf8_lossy(&routes)).ok_or(
+            RustADBError::ADBRequestFailed("device has no network address, is Wi-Fi on?".into()),
+        )?;
+
+        self.tcpip(port)?;
+
+        Ok(SocketAddr::new(address, port))
+    }
 }
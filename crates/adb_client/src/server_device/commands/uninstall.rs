@@ -1,26 +1,29 @@
 use std::io::Read;
 
-use crate::{Result, models::AdbServerCommand, server_device::ADBServerDevice};
+use crate::{
+    Result, models::AdbServerCommand, server_device::ADBServerDevice,
+    utils::check_package_manager_output,
+};
 
 impl ADBServerDevice {
-    /// Uninstall a package from device
-    pub fn uninstall(&mut self, package_name: &str) -> Result<()> {
+    /// Uninstall a package from device, keeping its data if `keep_data` is set
+    pub fn uninstall(&mut self, package_name: &str, keep_data: bool) -> Result<()> {
         self.set_serial_transport()?;
 
         self.transport
-            .send_adb_request(AdbServerCommand::Uninstall(package_name.to_string()))?;
+            .send_adb_request(AdbServerCommand::Uninstall(
+                package_name.to_string(),
+                keep_data,
+            ))?;
 
-        let mut data = [0; 1024];
-        let read_amount = self.transport.get_raw_connection()?.read(&mut data)?;
+        // The server closes the connection once the package manager is done.
+        let mut output = Vec::new();
+        self.transport
+            .get_raw_connection()?
+            .read_to_end(&mut output)?;
 
-        match &data[0..read_amount] {
-            b"Success\n" => {
-                log::info!("Package {package_name} successfully uninstalled");
-                Ok(())
-            }
-            d => Err(crate::RustADBError::ADBRequestFailed(String::from_utf8(
-                d.to_vec(),
-            )?)),
-        }
+        check_package_manager_output(&String::from_utf8_lossy(&output))?;
+        log::info!("Package {package_name} successfully uninstalled");
+        Ok(())
     }
 }
@@ -0,0 +1,100 @@
+use std::io::{self, Write};
+use std::ops::ControlFlow;
+
+/// [`Write`] sink handing every write to a chunk callback, used to stream
+/// output of devices that only offer a [`Write`] based shell.
+///
+/// When the callback breaks, further writes fail so the underlying command
+/// stops reading; [`ChunkWriter::stopped`] tells that failure apart from a
+/// real I/O error.
+pub(crate) struct ChunkWriter<'a> {
+    on_chunk: &'a mut dyn FnMut(&[u8]) -> ControlFlow<()>,
+    stopped: bool,
+}
+
+impl<'a> ChunkWriter<'a> {
+    pub(crate) fn new(on_chunk: &'a mut dyn FnMut(&[u8]) -> ControlFlow<()>) -> Self {
+        Self {
+            on_chunk,
+            stopped: false,
+        }
+    }
+
+    pub(crate) fn stopped(&self) -> bool {
+        self.stopped
+    }
+}
+
+impl Write for ChunkWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.stopped || (self.on_chunk)(buf).is_break() {
+            self.stopped = true;
+            return Err(io::Error::other("shell output stream stopped by consumer"));
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Reassembles shell output chunks into lines.
+#[derive(Default)]
+pub(crate) struct LineBuffer {
+    pending: Vec<u8>,
+}
+
+impl LineBuffer {
+    /// Appends `chunk` and hands every completed line, without its `\n` or
+    /// `\r\n` terminator, to `on_line`.
+    pub(crate) fn push(
+        &mut self,
+        chunk: &[u8],
+        on_line: &mut dyn FnMut(&str) -> ControlFlow<()>,
+    ) -> ControlFlow<()> {
+        self.pending.extend_from_slice(chunk);
+        let mut start = 0;
+        while let Some(offset) = self.pending[start..].iter().position(|byte| *byte == b'\n') {
+            let end = start + offset;
+            let line = &self.pending[start..end];
+            let line = line.strip_suffix(b"\r").unwrap_or(line);
+            let flow = on_line(&String::from_utf8_lossy(line));
+            start = end + 1;
+            if flow.is_break() {
+                self.pending.drain(..start);
+                return flow;
+            }
+        }
+        self.pending.drain(..start);
+        ControlFlow::Continue(())
+    }
+
+    /// Hands over a trailing line that was not terminated.
+    pub(crate) fn finish(self, on_line: &mut dyn FnMut(&str) -> ControlFlow<()>) {
+        if !self.pending.is_empty() {
+            let line = self.pending.strip_suffix(b"\r").unwrap_or(&self.pending);
+            let _ = on_line(&String::from_utf8_lossy(line));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_split_across_chunks() {
+        let mut lines = Vec::new();
+        let mut on_line = |line: &str| {
+            lines.push(line.to_string());
+            ControlFlow::Continue(())
+        };
+        let mut buffer = LineBuffer::default();
+        let _ = buffer.push(b"first\r\nsec", &mut on_line);
+        let _ = buffer.push(b"ond\n\nlast", &mut on_line);
+        buffer.finish(&mut on_line);
+
+        assert_eq!(lines, ["first", "second", "", "last"]);
+    }
+}
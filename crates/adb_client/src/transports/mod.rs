@@ -7,5 +7,7 @@ mod usb_transport;
 pub use tcp_emulator_transport::TCPEmulatorTransport;
 pub use tcp_server_transport::TCPServerTransport;
 pub use tcp_transport::TcpTransport;
+pub(crate) use tcp_transport::tls_client_config;
 pub use traits::{ADBMessageTransport, ADBTransport};
+pub(crate) use traits::{DEFAULT_READ_TIMEOUT, DEFAULT_WRITE_TIMEOUT};
 pub use usb_transport::USBTransport;
@@ -8,9 +8,7 @@ use rustls::{
 use super::{ADBMessageTransport, ADBTransport};
 use crate::{
     Result, RustADBError,
-    device::{
-        ADBTransportMessage, ADBTransportMessageHeader, MessageCommand, get_default_adb_key_path,
-    },
+    device::{ADBTransportMessage, ADBTransportMessageHeader, get_default_adb_key_path},
 };
 use std::{
     fs::read_to_string,
@@ -90,6 +88,25 @@ fn certificate_from_pk(key_pair: &KeyPair) -> Result<Vec<CertificateDer<'static>
     Ok(vec![certificate.der().to_owned()])
 }
 
+/// Builds the TLS configuration used to reach adbd, authenticating with a certificate
+/// self-signed by the given PKCS#8 PEM private key. adbd's certificate is not verified: its
+/// identity is established by the pairing code instead.
+pub(crate) fn tls_client_config(pk_content: &str) -> Result<ClientConfig> {
+    let key_pair = KeyPair::from_pkcs8_pem_and_sign_algo(pk_content, &PKCS_RSA_SHA256)?;
+
+    let certificate = certificate_from_pk(&key_pair)?;
+    let private_key = PrivatePkcs8KeyDer::from_pem_slice(pk_content.as_bytes())?;
+
+    let mut client_config = ClientConfig::builder()
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(NoCertificateVerification {}))
+        .with_client_auth_cert(certificate, private_key.into())?;
+
+    client_config.key_log = Arc::new(KeyLogFile::new());
+
+    Ok(client_config)
+}
+
 impl TcpTransport {
     /// Instantiate a new [`TcpTransport`]
     pub fn new(address: SocketAddr) -> Result<Self> {
@@ -132,21 +149,7 @@ impl TcpTransport {
                     // TODO: Check if we cannot be more precise
 
                     let pk_content = read_to_string(&self.private_key_path)?;
-
-                    let key_pair =
-                        KeyPair::from_pkcs8_pem_and_sign_algo(&pk_content, &PKCS_RSA_SHA256)?;
-
-                    let certificate = certificate_from_pk(&key_pair)?;
-                    let private_key = PrivatePkcs8KeyDer::from_pem_file(&self.private_key_path)?;
-
-                    let mut client_config = ClientConfig::builder()
-                        .dangerous()
-                        .with_custom_certificate_verifier(Arc::new(NoCertificateVerification {}))
-                        .with_client_auth_cert(certificate, private_key.into())?;
-
-                    client_config.key_log = Arc::new(KeyLogFile::new());
-
-                    let rc_config = Arc::new(client_config);
+                    let rc_config = Arc::new(tls_client_config(&pk_content)?);
                     let server_name = self.address.ip().into();
                     let conn = ClientConnection::new(rc_config, server_name)?;
                     let owned = tcp_stream.try_clone()?;
@@ -163,17 +166,7 @@ impl TcpTransport {
             }
         }
 
-        let message = self.read_message()?;
-        match message.header().command() {
-            MessageCommand::Cnxn => {
-                let device_infos = String::from_utf8(message.into_payload())?;
-                log::debug!("received device info: {device_infos}");
-                Ok(())
-            }
-            c => Err(RustADBError::ADBRequestFailed(format!(
-                "Wrong command received {c}"
-            ))),
-        }
+        Ok(())
     }
 }
 
@@ -3,8 +3,8 @@ use std::time::Duration;
 use super::ADBTransport;
 use crate::{Result, device::ADBTransportMessage};
 
-const DEFAULT_READ_TIMEOUT: Duration = Duration::from_secs(u64::MAX);
-const DEFAULT_WRITE_TIMEOUT: Duration = Duration::from_secs(2);
+pub(crate) const DEFAULT_READ_TIMEOUT: Duration = Duration::from_secs(u64::MAX);
+pub(crate) const DEFAULT_WRITE_TIMEOUT: Duration = Duration::from_secs(2);
 
 /// Trait representing a transport able to read and write messages.
 pub trait ADBMessageTransport: ADBTransport + Clone + Send + 'static {
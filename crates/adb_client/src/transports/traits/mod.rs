@@ -2,4 +2,5 @@ mod adb_message_transport;
 mod adb_transport;
 
 pub use adb_message_transport::ADBMessageTransport;
+pub(crate) use adb_message_transport::{DEFAULT_READ_TIMEOUT, DEFAULT_WRITE_TIMEOUT};
 pub use adb_transport::ADBTransport;
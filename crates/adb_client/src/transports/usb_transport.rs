@@ -44,6 +44,35 @@ impl USBTransport {
         )))
     }
 
+    /// Instantiate a new [`USBTransport`] for the device with given `vendor_id`, `product_id`
+    /// and USB serial number, telling apart several devices of the same model.
+    ///
+    /// Reading the serial number opens each candidate, so devices that cannot be opened are
+    /// skipped.
+    pub fn new_with_serial(vendor_id: u16, product_id: u16, serial: &str) -> Result<Self> {
+        for device in rusb::devices()?.iter() {
+            let Ok(descriptor) = device.device_descriptor() else {
+                continue;
+            };
+            if descriptor.vendor_id() != vendor_id || descriptor.product_id() != product_id {
+                continue;
+            }
+            let Ok(handle) = device.open() else {
+                continue;
+            };
+            if handle
+                .read_serial_number_string_ascii(&descriptor)
+                .is_ok_and(|device_serial| device_serial == serial)
+            {
+                return Ok(Self::new_from_device(device));
+            }
+        }
+
+        Err(RustADBError::DeviceNotFound(format!(
+            "cannot find USB device with vendor_id={vendor_id}, product_id={product_id} and serial={serial}",
+        )))
+    }
+
     /// Instantiate a new [`USBTransport`] from a [`rusb::Device`].
     ///
     /// Devices can be enumerated using [`rusb::devices()`] and then filtered out to get desired device.
@@ -56,6 +85,33 @@ impl USBTransport {
         }
     }
 
+    /// Looks the device up again on the bus, on the same port, after it re-enumerated, e.g.
+    /// because adbd restarted. Its previous [`rusb::Device`] can no longer be opened then.
+    pub(crate) fn rediscover(&mut self) -> Result<()> {
+        let bus_number = self.device.bus_number();
+        let port_numbers = self.device.port_numbers()?;
+        let vendor_id = self.device.device_descriptor()?.vendor_id();
+        for device in rusb::devices()?.iter() {
+            if device.bus_number() != bus_number
+                || device.port_numbers().ok().as_ref() != Some(&port_numbers)
+            {
+                continue;
+            }
+            if device
+                .device_descriptor()
+                .is_ok_and(|descriptor| descriptor.vendor_id() == vendor_id)
+            {
+                self.device = device;
+                self.handle = None;
+                return Ok(());
+            }
+        }
+
+        Err(RustADBError::DeviceNotFound(format!(
+            "no USB device on bus {bus_number} port {port_numbers:?} anymore",
+        )))
+    }
+
     pub(crate) fn get_raw_connection(&self) -> Result<Arc<DeviceHandle<GlobalContext>>> {
         self.handle
             .as_ref()
@@ -87,9 +143,32 @@ impl USBTransport {
 
     fn configure_endpoint(handle: &DeviceHandle<GlobalContext>, endpoint: &Endpoint) -> Result<()> {
         handle.claim_interface(endpoint.iface)?;
+        // An endpoint left halted by a stall in a previous connection fails every transfer
+        // until cleared.
+        if let Err(e) = handle.clear_halt(endpoint.address) {
+            log::debug!(
+                "could not clear halt on endpoint {:#x}: {e}",
+                endpoint.address
+            );
+        }
         Ok(())
     }
 
+    /// Resets the device's USB port, as if it were unplugged and plugged back, for a device
+    /// stuck after babble or stall errors. Must be disconnected first. A device whose
+    /// descriptors changed meanwhile comes back as a new one, looked up on the same port.
+    pub(crate) fn reset_port(&mut self) -> Result<()> {
+        let handle = self.device.open()?;
+        match handle.reset() {
+            Ok(()) => Ok(()),
+            Err(rusb::Error::NotFound) => {
+                log::debug!("device re-enumerated after port reset");
+                self.rediscover()
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
     fn find_endpoints(handle: &DeviceHandle<GlobalContext>) -> Result<(Endpoint, Endpoint)> {
         let mut read_endpoint: Option<Endpoint> = None;
         let mut write_endpoint: Option<Endpoint> = None;
@@ -193,6 +272,7 @@ impl ADBTransport for USBTransport {
                 }
             }
         }
+        self.handle = None;
 
         Ok(())
     }
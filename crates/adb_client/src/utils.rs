@@ -1,4 +1,4 @@
-use std::{ffi::OsStr, path::Path};
+use std::{collections::BTreeMap, ffi::OsStr, fs::File, net::IpAddr, path::Path};
 
 use crate::{Result, RustADBError};
 
@@ -16,3 +16,328 @@ pub fn check_extension_is_apk<P: AsRef<Path>>(path: P) -> Result<()> {
 
     Ok(())
 }
+
+/// Checks the output of a package manager install or uninstall, which ends with either
+/// `Success` or a `Failure [REASON]` line. Session commands follow `Success` with details,
+/// as in `Success: streamed 1024 bytes`.
+pub fn check_package_manager_output(output: &str) -> Result<()> {
+    if output
+        .lines()
+        .any(|line| line.trim_start().starts_with("Success"))
+    {
+        return Ok(());
+    }
+    let failure = output
+        .lines()
+        .find(|line| line.trim_start().starts_with("Failure"))
+        .unwrap_or(output);
+    Err(RustADBError::ADBRequestFailed(failure.trim().to_string()))
+}
+
+/// Reads the session id out of `install-create` output, `Success: created install session [ID]`.
+pub(crate) fn parse_install_session(output: &str) -> Result<u32> {
+    check_package_manager_output(output)?;
+    output
+        .split_once('[')
+        .and_then(|(_, rest)| rest.split_once(']'))
+        .and_then(|(id, _)| id.trim().parse().ok())
+        .ok_or_else(|| {
+            RustADBError::ADBRequestFailed(format!("no install session in '{}'", output.trim()))
+        })
+}
+
+/// One APK of a split install, opened and sized up front so the session can be created
+/// with the total size.
+#[derive(Debug)]
+pub(crate) struct SplitApk {
+    pub(crate) file: File,
+    pub(crate) size: u64,
+    /// Name within the session, `{index}_{file name}` like `adb install-multiple` uses.
+    pub(crate) name: String,
+}
+
+pub(crate) fn open_split_apks(apk_paths: &[&Path]) -> Result<Vec<SplitApk>> {
+    if apk_paths.is_empty() {
+        return Err(RustADBError::ADBRequestFailed("no APK to install".into()));
+    }
+    apk_paths
+        .iter()
+        .enumerate()
+        .map(|(index, path)| {
+            check_extension_is_apk(path)?;
+            let file = File::open(path)?;
+            let size = file.metadata()?.len();
+            let file_name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().replace(' ', "_"))
+                .unwrap_or_else(|| "split.apk".to_string());
+            Ok(SplitApk {
+                file,
+                size,
+                name: format!("{index}_{file_name}"),
+            })
+        })
+        .collect()
+}
+
+/// Single-quotes `arg` for the device shell, so it reaches the command as one word with no
+/// globbing or expansion.
+pub(crate) fn shell_quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
+
+/// Parses `getprop` output, one `[name]: [value]` per property. Values may span lines.
+pub(crate) fn parse_getprop(output: &str) -> BTreeMap<String, String> {
+    let mut props = BTreeMap::new();
+    // Name and value so far of a property whose value continues on the next line.
+    let mut pending: Option<(String, String)> = None;
+    for line in output.lines() {
+        if let Some((name, mut value)) = pending.take() {
+            value.push('\n');
+            match line.strip_suffix(']') {
+                Some(end) => {
+                    value.push_str(end);
+                    props.insert(name, value);
+                }
+                None => {
+                    value.push_str(line);
+                    pending = Some((name, value));
+                }
+            }
+            continue;
+        }
+        let Some((name, value)) = line
+            .strip_prefix('[')
+            .and_then(|line| line.split_once("]: ["))
+        else {
+            continue;
+        };
+        match value.strip_suffix(']') {
+            Some(value) => {
+                props.insert(name.to_string(), value.to_string());
+            }
+            None => pending = Some((name.to_string(), value.to_string())),
+        }
+    }
+    props
+}
+
+/// Picks the device's own address out of `ip route` output, preferring a Wi-Fi interface.
+/// Each local network route names it as `src`, e.g.
+/// `192.168.1.0/24 dev wlan0 proto kernel scope link src 192.168.1.23`.
+pub(crate) fn parse_wifi_address(routes: &str) -> Option<IpAddr> {
+    let mut fallback = None;
+    for line in routes.lines() {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let after = |key: &str| {
+            words
+                .iter()
+                .position(|word| *word == key)
+                .and_then(|index| words.get(index + 1))
+        };
+        let Some(address) = after("src").and_then(|src| src.parse::<IpAddr>().ok()) else {
+            continue;
+        };
+        if address.is_loopback() {
+            continue;
+        }
+        if after("dev").is_some_and(|dev| dev.starts_with("wlan")) {
+            return Some(address);
+        }
+        fallback.get_or_insert(address);
+    }
+    fallback
+}
+
+/// Appended to commands run with [`crate::ADBDeviceExt::shell_command_checked`], followed by
+/// their exit status, which devices without shell v2 don't report otherwise.
+pub(crate) const EXIT_STATUS_MARKER: &str = "adb_client_exit_status=";
+
+/// Splits the output of a command followed by `echo` of [`EXIT_STATUS_MARKER`] into what the
+/// command printed and its exit status.
+pub(crate) fn split_exit_status(output: &str) -> Option<(&str, u8)> {
+    let (printed, status) = output.trim_end().rsplit_once(EXIT_STATUS_MARKER)?;
+    Some((printed, status.parse().ok()?))
+}
+
+/// A line of `bugreportz -p` output worth acting on.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum BugreportzLine<'a> {
+    /// `PROGRESS:done/total`, in units only meaningful relative to each other
+    Progress(u64, u64),
+    /// `OK:path`, naming the finished zip on the device
+    Ok(&'a str),
+    /// `FAIL:reason`
+    Fail(&'a str),
+}
+
+/// Parses a line of `bugreportz -p` output. `BEGIN:` and unknown lines read as `None`.
+pub(crate) fn parse_bugreportz_line(line: &str) -> Option<BugreportzLine<'_>> {
+    let (kind, value) = line.trim_end().split_once(':')?;
+    match kind {
+        "PROGRESS" => {
+            let (done, total) = value.split_once('/')?;
+            Some(BugreportzLine::Progress(
+                done.parse().ok()?,
+                total.parse().ok()?,
+            ))
+        }
+        "OK" => Some(BugreportzLine::Ok(value)),
+        "FAIL" => Some(BugreportzLine::Fail(value)),
+        _ => None,
+    }
+}
+
+/// Interprets adbd's reply to `root:` or `unroot:`, telling whether it is restarting and so
+/// dropping the connection. It doesn't when it already runs as requested.
+pub(crate) fn parse_adbd_restart(output: &str) -> Result<bool> {
+    let output = output.trim();
+    if output.starts_with("restarting") {
+        Ok(true)
+    } else if output.contains("cannot") {
+        // `adbd cannot run as root in production builds`
+        Err(RustADBError::ADBRequestFailed(output.to_string()))
+    } else {
+        Ok(false)
+    }
+}
+
+/// Checks the output of the `remount:` service, returning it when the remount worked, as it
+/// may ask for a reboot before taking effect.
+pub(crate) fn check_remount_output(output: &str) -> Result<String> {
+    let output = output.trim();
+    let lowercase = output.to_lowercase();
+    if lowercase.contains("fail") || lowercase.contains("not running as root") {
+        return Err(RustADBError::ADBRequestFailed(output.to_string()));
+    }
+    Ok(output.to_string())
+}
+
+/// Size of the blocks a device in sideload mode requests an OTA package in, announced in
+/// the `sideload-host:` service.
+pub(crate) const SIDELOAD_BLOCK_SIZE: u64 = 64 * 1024;
+
+/// Sent by a device in place of a block number once it has the whole package.
+const SIDELOAD_DONE: &[u8] = b"DONEDONE";
+
+/// Parses an 8-byte `sideload-host:` request into the byte range of a `size`-byte package it
+/// asks for, or `None` once the device is done.
+pub(crate) fn parse_sideload_request(
+    request: &[u8],
+    size: u64,
+) -> Result<Option<std::ops::Range<u64>>> {
+    if request == SIDELOAD_DONE {
+        return Ok(None);
+    }
+    let block = std::str::from_utf8(request)
+        .ok()
+        .and_then(|block| block.parse::<u64>().ok())
+        .ok_or_else(|| {
+            RustADBError::ADBRequestFailed(format!(
+                "invalid sideload request {:?}",
+                String::from_utf8_lossy(request)
+            ))
+        })?;
+    let start = block.saturating_mul(SIDELOAD_BLOCK_SIZE);
+    if start >= size {
+        return Err(RustADBError::ADBRequestFailed(format!(
+            "device requested block {block} past the end of the package"
+        )));
+    }
+    Ok(Some(start..(start + SIDELOAD_BLOCK_SIZE).min(size)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        BugreportzLine, check_remount_output, parse_adbd_restart, parse_bugreportz_line,
+        parse_getprop, parse_sideload_request, parse_wifi_address, split_exit_status,
+    };
+
+    #[test]
+    fn parse_getprop_multiline_values() {
+        let output = "[ro.product.model]: [Pixel 7]\n\
+                      [ro.build.description]: [first\nsecond]\n\
+                      [ro.empty]: []\n";
+        let props = parse_getprop(output);
+        assert_eq!(props["ro.product.model"], "Pixel 7");
+        assert_eq!(props["ro.build.description"], "first\nsecond");
+        assert_eq!(props["ro.empty"], "");
+        assert_eq!(props.len(), 3);
+    }
+
+    #[test]
+    fn parse_wifi_address_prefers_wlan() {
+        let routes = "10.0.0.0/8 dev rmnet_data0 proto kernel scope link src 10.12.4.7\n\
+                      192.168.1.0/24 dev wlan0 proto kernel scope link src 192.168.1.23\n";
+        assert_eq!(
+            parse_wifi_address(routes),
+            Some("192.168.1.23".parse().unwrap())
+        );
+        assert_eq!(parse_wifi_address(""), None);
+    }
+
+    #[test]
+    fn split_exit_status_after_output() {
+        let output = "rm: /sdcard/a: No such file or directory\nadb_client_exit_status=1\n";
+        assert_eq!(
+            split_exit_status(output),
+            Some(("rm: /sdcard/a: No such file or directory\n", 1))
+        );
+        assert_eq!(split_exit_status("adb_client_exit_status=0"), Some(("", 0)));
+        assert_eq!(split_exit_status("killed\n"), None);
+    }
+
+    #[test]
+    fn parse_bugreportz_output() {
+        assert_eq!(
+            parse_bugreportz_line("PROGRESS:1250/10000"),
+            Some(BugreportzLine::Progress(1250, 10000))
+        );
+        assert_eq!(
+            parse_bugreportz_line("OK:/bugreports/bugreport-panther-2026-10-16.zip\r"),
+            Some(BugreportzLine::Ok(
+                "/bugreports/bugreport-panther-2026-10-16.zip"
+            ))
+        );
+        assert_eq!(
+            parse_bugreportz_line("FAIL:Could not open file"),
+            Some(BugreportzLine::Fail("Could not open file"))
+        );
+        assert_eq!(
+            parse_bugreportz_line("BEGIN:/bugreports/bugreport.zip"),
+            None
+        );
+    }
+
+    #[test]
+    fn parse_sideload_requests() {
+        let size = 3 * 65536 + 100;
+        assert_eq!(
+            parse_sideload_request(b"00000001", size).unwrap(),
+            Some(65536..131072)
+        );
+        assert_eq!(
+            parse_sideload_request(b"00000003", size).unwrap(),
+            Some(196608..196708)
+        );
+        assert_eq!(parse_sideload_request(b"DONEDONE", size).unwrap(), None);
+        assert!(parse_sideload_request(b"00000004", size).is_err());
+        assert!(parse_sideload_request(b"garbage!", size).is_err());
+    }
+
+    #[test]
+    fn parse_root_and_remount_replies() {
+        assert!(parse_adbd_restart("restarting adbd as root\n").unwrap());
+        assert!(!parse_adbd_restart("adbd is already running as root\n").unwrap());
+        assert!(!parse_adbd_restart("adbd not running as root\n").unwrap());
+        assert!(parse_adbd_restart("adbd cannot run as root in production builds\n").is_err());
+
+        assert_eq!(
+            check_remount_output("remount succeeded\n").unwrap(),
+            "remount succeeded"
+        );
+        assert!(check_remount_output("Not running as root. Try \"adb root\" first.").is_err());
+        assert!(check_remount_output("remount failed").is_err());
+    }
+}
@@ -0,0 +1,327 @@
+use adb_client::RemoteEntry;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use tauri::{State, Window};
+
+use crate::backend::ConnectedDevice;
+use crate::capabilities::{self, DeviceCapabilities};
+use crate::compare::{self, ComparisonStrategy, MTIME_TOLERANCE_SECS};
+use crate::hashes::{remote_sha256, LocalHashCache};
+use crate::manifest::RemoteManifest;
+use crate::naming::{self, RemoteNameRegistry};
+use crate::placeholders::PathPlaceholders;
+use crate::routing::{ExtensionRoute, RemoteDestinations};
+use crate::rules::ExcludeSet;
+use crate::symlinks::{self, DirectoryTrail};
+use crate::{
+    apply_profile, canonicalize_local_root, connect_device, emit_connection_stage,
+    normalize_remote_path, remote_metadata, skipped, ActiveSync, DeviceDetails, FolderPair,
+    SyncError, SyncOptions,
+};
+
+/// How a file differs between the local folder and the device.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditDifference {
+    /// A local file with no device copy.
+    MissingOnDevice,
+    SizeDiffers,
+    /// Same size, but modified at different times.
+    MtimeDiffers,
+    /// Same size, but different SHA-256.
+    ContentDiffers,
+    /// A device file under the remote root with no local counterpart.
+    OnlyOnDevice,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditEntry {
+    /// Relative to the local root, or to the remote root for
+    /// [`AuditDifference::OnlyOnDevice`].
+    path: String,
+    remote_path: String,
+    difference: AuditDifference,
+    local_size: Option<u64>,
+    remote_size: Option<u64>,
+    local_mtime: Option<u64>,
+    remote_mtime: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PairAudit {
+    local_root: String,
+    remote_path: String,
+    /// The strategy actually used, which falls back when the device can't
+    /// hash files.
+    comparison: ComparisonStrategy,
+    files_checked: usize,
+    files_matching: usize,
+    skipped_entries: usize,
+    /// Whether device-only files were looked for, which needs `find` on the
+    /// device.
+    listed_device_files: bool,
+    differences: Vec<AuditEntry>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditReport {
+    device: DeviceDetails,
+    pairs: Vec<PairAudit>,
+}
+
+struct Auditor<'a> {
+    device: &'a mut ConnectedDevice,
+    options: &'a SyncOptions,
+    comparison: ComparisonStrategy,
+    hashes: LocalHashCache,
+    names: RemoteNameRegistry,
+    manifest: Option<RemoteManifest>,
+    audit: PairAudit,
+}
+
+impl Auditor<'_> {
+    fn walk(
+        &mut self,
+        root: &Path,
+        current: &Path,
+        trail: &mut DirectoryTrail,
+        destinations: &RemoteDestinations,
+    ) -> Result<(), SyncError> {
+        for entry in fs::read_dir(current)? {
+            self.options.cancel.check()?;
+            let entry = entry?;
+            let path = entry.path();
+            let relative = path.strip_prefix(root).unwrap_or_else(|_| Path::new(""));
+            if self.options.skips_entry(root, &path) {
+                self.audit.skipped_entries += 1;
+                continue;
+            }
+            let Ok(metadata) = symlinks::entry_metadata(&entry, self.options.follow_symlinks)
+            else {
+                self.audit.skipped_entries += 1;
+                continue;
+            };
+            if skipped::os_metadata_kind(&path, &metadata).is_some() {
+                self.audit.skipped_entries += 1;
+                continue;
+            }
+
+            if metadata.is_dir() {
+                if self.options.descends_into(relative) && trail.enter(&path)? {
+                    self.walk(root, &path, trail, destinations)?;
+                    trail.leave();
+                } else {
+                    self.audit.skipped_entries += 1;
+                }
+            } else if metadata.is_file()
+                && !self.options.exceeds_max_size(metadata.len())
+                && !self.options.outside_age_window(&metadata)
+            {
+                let Some(remote_path) = self.names.claim(
+                    &relative.display().to_string(),
+                    destinations.file(relative),
+                    naming::needs_sanitizing(relative),
+                )?
+                else {
+                    self.audit.skipped_entries += 1;
+                    continue;
+                };
+                self.check_file(&path, relative, &remote_path, &metadata)?;
+            } else {
+                self.audit.skipped_entries += 1;
+            }
+        }
+        Ok(())
+    }
+
+    fn check_file(
+        &mut self,
+        local_path: &Path,
+        relative: &Path,
+        remote_path: &str,
+        metadata: &fs::Metadata,
+    ) -> Result<(), SyncError> {
+        self.audit.files_checked += 1;
+        let remote = match self
+            .manifest
+            .as_ref()
+            .and_then(|manifest| manifest.get(remote_path))
+        {
+            Some(listed) => listed,
+            None => remote_metadata(self.device, remote_path)?
+                .map(|stat| RemoteEntry::from_stat(remote_path, &stat)),
+        };
+        let local_mtime = compare::file_modified_seconds(metadata);
+        let difference = match &remote {
+            None => Some(AuditDifference::MissingOnDevice),
+            Some(remote) => self.compare(local_path, metadata, local_mtime, remote)?,
+        };
+
+        match difference {
+            None => self.audit.files_matching += 1,
+            Some(difference) => self.audit.differences.push(AuditEntry {
+                path: relative.display().to_string(),
+                remote_path: remote_path.to_string(),
+                difference,
+                local_size: Some(metadata.len()),
+                remote_size: remote.as_ref().map(|remote| remote.size),
+                local_mtime,
+                remote_mtime: remote.as_ref().map(|remote| remote.mtime),
+            }),
+        }
+        Ok(())
+    }
+
+    /// The same checks [`compare::FileComparer`] makes before a push, without
+    /// touching the sync baseline.
+    fn compare(
+        &mut self,
+        local_path: &Path,
+        metadata: &fs::Metadata,
+        local_mtime: Option<u64>,
+        remote: &RemoteEntry,
+    ) -> Result<Option<AuditDifference>, SyncError> {
+        if remote.size != metadata.len() {
+            return Ok(Some(AuditDifference::SizeDiffers));
+        }
+        let differs = match self.comparison {
+            ComparisonStrategy::SizeOnly => None,
+            ComparisonStrategy::SizeAndMtime => local_mtime
+                .is_none_or(|local| local.abs_diff(remote.mtime) > MTIME_TOLERANCE_SECS)
+                .then_some(AuditDifference::MtimeDiffers),
+            ComparisonStrategy::Checksum => {
+                let local = self.hashes.hash(local_path)?;
+                (remote_sha256(self.device, &remote.path).as_deref() != Some(local.as_str()))
+                    .then_some(AuditDifference::ContentDiffers)
+            }
+        };
+        Ok(differs)
+    }
+
+    /// Device files under the remote root that no local file maps to.
+    fn collect_device_only(&mut self) {
+        let Some(manifest) = &self.manifest else {
+            return;
+        };
+        for (remote_path, relative, size) in manifest.files() {
+            if !self.names.is_claimed(remote_path) {
+                self.audit.differences.push(AuditEntry {
+                    path: relative.to_string(),
+                    remote_path: remote_path.to_string(),
+                    difference: AuditDifference::OnlyOnDevice,
+                    local_size: None,
+                    remote_size: Some(size),
+                    local_mtime: None,
+                    remote_mtime: None,
+                });
+            }
+        }
+    }
+}
+
+fn audit_pair(
+    window: &Window,
+    device: &mut ConnectedDevice,
+    pair: &FolderPair,
+    routes: &[ExtensionRoute],
+    options: &SyncOptions,
+    capabilities: &DeviceCapabilities,
+) -> Result<PairAudit, SyncError> {
+    let local_root = canonicalize_local_root(&pair.local)?;
+    let remote_root = normalize_remote_path(&pair.remote)?;
+    let destinations = RemoteDestinations::new(remote_root, routes)?;
+    let manifest = if capabilities.can_list() {
+        Some(RemoteManifest::scan(
+            device,
+            destinations.root(),
+            &options.cancel,
+        )?)
+    } else {
+        None
+    };
+    let comparison = capabilities.comparison(options.comparison);
+
+    let mut auditor = Auditor {
+        device,
+        options,
+        comparison,
+        hashes: LocalHashCache::open(window, &local_root),
+        names: RemoteNameRegistry::new(options.name_collisions),
+        audit: PairAudit {
+            local_root: local_root.display().to_string(),
+            remote_path: destinations.root().to_string(),
+            comparison,
+            files_checked: 0,
+            files_matching: 0,
+            skipped_entries: 0,
+            listed_device_files: manifest.is_some(),
+            differences: Vec::new(),
+        },
+        manifest,
+    };
+    auditor.walk(
+        &local_root,
+        &local_root,
+        &mut DirectoryTrail::new(&local_root, options.follow_symlinks)?,
+        &destinations,
+    )?;
+    auditor.collect_device_only();
+    // Hashes are only a cache; keeping them makes the next sync cheaper.
+    auditor.hashes.save();
+    log::info!(
+        "audited {}: {} checked, {} differ",
+        auditor.audit.local_root,
+        auditor.audit.files_checked,
+        auditor.audit.differences.len()
+    );
+    Ok(auditor.audit)
+}
+
+fn audit(
+    window: &Window,
+    pairs: &[FolderPair],
+    profile_id: Option<&str>,
+    mut options: SyncOptions,
+) -> Result<AuditReport, SyncError> {
+    if pairs.is_empty() {
+        return Err(SyncError::Config("No folder pairs to audit".into()));
+    }
+    let (routes, profile_name) = apply_profile(window, profile_id, &mut options)?;
+    let pairs = PathPlaceholders::for_run(profile_name.as_deref()).expand_pairs(pairs)?;
+    options.excludes = ExcludeSet::new(&options.exclude)?;
+
+    let (device_info, mut device) =
+        connect_device(&mut |stage| emit_connection_stage(window, stage))?;
+    let capabilities = capabilities::for_device(window, &mut device)?;
+    let pairs = pairs
+        .iter()
+        .map(|pair| audit_pair(window, &mut device, pair, &routes, &options, &capabilities))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(AuditReport {
+        device: DeviceDetails::probe(device_info, &mut device),
+        pairs,
+    })
+}
+
+/// Compares each pair with the device using the run's comparison strategy
+/// and reports every file that differs, without creating, uploading or
+/// deleting anything and without updating the sync baseline. Unlike a dry
+/// run, the report also lists device files that have no local counterpart.
+#[tauri::command]
+pub async fn audit_folders(
+    window: Window,
+    active: State<'_, ActiveSync>,
+    pairs: Vec<FolderPair>,
+    profile_id: Option<String>,
+    options: Option<SyncOptions>,
+) -> Result<AuditReport, String> {
+    let mut options = options.unwrap_or_default();
+    options.cancel = active.begin();
+    tauri::async_runtime::spawn_blocking(move || {
+        audit(&window, &pairs, profile_id.as_deref(), options)
+    })
+    .await
+    .map_err(|e| format!("audit task failed: {e}"))?
+    .map_err(|e| e.to_string())
+}
@@ -0,0 +1,204 @@
+use std::io::{Read, Write};
+use std::net::SocketAddr;
+use std::ops::ControlFlow;
+use std::path::Path;
+
+use adb_client::{
+    ADBDeviceExt, ADBServer, ADBServerDevice, ADBTcpDevice, ADBUSBDevice, AdbStatResponse,
+    BackupOptions, InstallOptions, RebootType, RemoteEntry, Result, RustADBError, ShellOutput,
+};
+use image::{ImageBuffer, Rgba};
+
+use crate::throughput::Transport;
+
+/// A device the app talks to: either claimed directly over USB, reached
+/// through the adb server already running on this computer, which owns the
+/// USB interface while it runs, or a saved wireless device reached over the
+/// network.
+#[derive(Debug)]
+pub(crate) enum ConnectedDevice {
+    Usb(ADBUSBDevice),
+    Server(ADBServerDevice),
+    Tcp(ADBTcpDevice),
+}
+
+impl ConnectedDevice {
+    /// Reaches the device through the host adb server (`host:transport:<serial>`),
+    /// by its USB serial number, or as the only device the server lists.
+    pub(crate) fn through_server(serial: Option<&str>) -> Result<Self> {
+        let mut server = ADBServer::default();
+        let device = match serial {
+            Some(serial) => server.get_device_by_name(serial)?,
+            None => server.get_device()?,
+        };
+        Ok(Self::Server(device))
+    }
+
+    /// Repeats the USB or TCP handshake. The adb server opens a new
+    /// connection for every request, so through it this only checks the
+    /// device is still listed.
+    pub(crate) fn reconnect(&mut self) -> Result<()> {
+        self.reconnect_with_reset(false)
+    }
+
+    /// Same as [`ConnectedDevice::reconnect`], resetting the USB port first
+    /// when `reset_port` is set, for a device left stalled. The adb server
+    /// manages the port itself, and a wireless device has none.
+    pub(crate) fn reconnect_with_reset(&mut self, reset_port: bool) -> Result<()> {
+        match self {
+            Self::Usb(device) => device.reconnect_with_reset(reset_port),
+            Self::Tcp(device) => device.connect(),
+            Self::Server(device) => match &device.identifier {
+                Some(serial) => ADBServer::default().get_device_by_name(serial).map(drop),
+                None => ADBServer::default().get_device().map(drop),
+            },
+        }
+    }
+
+    /// See [`ADBUSBDevice::set_sync_chunk_size`]. The adb server picks its own
+    /// chunk size.
+    pub(crate) fn set_sync_chunk_size(&mut self, size: usize) {
+        if let Self::Usb(device) = self {
+            device.set_sync_chunk_size(size);
+        }
+    }
+
+    pub(crate) fn enable_tcpip(&mut self, port: u16) -> Result<SocketAddr> {
+        match self {
+            Self::Usb(device) => device.enable_tcpip(port),
+            Self::Server(device) => device.enable_tcpip(port),
+            Self::Tcp(_) => Err(RustADBError::ADBRequestFailed(
+                "the device is already connected over the network".into(),
+            )),
+        }
+    }
+
+    /// How the device is reached, as run history records it.
+    pub(crate) fn transport(&self) -> Transport {
+        match self {
+            Self::Usb(_) | Self::Server(_) => Transport::Usb,
+            Self::Tcp(_) => Transport::Wifi,
+        }
+    }
+
+    fn inner(&mut self) -> &mut dyn ADBDeviceExt {
+        match self {
+            Self::Usb(device) => device,
+            Self::Server(device) => device,
+            Self::Tcp(device) => device,
+        }
+    }
+}
+
+impl ADBDeviceExt for ConnectedDevice {
+    fn shell_command(&mut self, command: &[&str], output: &mut dyn Write) -> Result<()> {
+        self.inner().shell_command(command, output)
+    }
+
+    fn shell_command_stream(
+        &mut self,
+        command: &[&str],
+        on_chunk: &mut dyn FnMut(&[u8]) -> ControlFlow<()>,
+    ) -> Result<()> {
+        self.inner().shell_command_stream(command, on_chunk)
+    }
+
+    fn shell_command_output(&mut self, command: &[&str]) -> Result<ShellOutput> {
+        self.inner().shell_command_output(command)
+    }
+
+    fn binder_command_output(&mut self, service: &str, args: &[&str]) -> Result<ShellOutput> {
+        self.inner().binder_command_output(service, args)
+    }
+
+    fn shell(&mut self, reader: &mut dyn Read, writer: Box<dyn Write + Send>) -> Result<()> {
+        self.inner().shell(reader, writer)
+    }
+
+    fn stat(&mut self, remote_path: &str) -> Result<AdbStatResponse> {
+        self.inner().stat(remote_path)
+    }
+
+    fn list_dir(&mut self, remote_path: &str) -> Result<Vec<RemoteEntry>> {
+        self.inner().list_dir(remote_path)
+    }
+
+    fn pull(&mut self, source: &dyn AsRef<str>, output: &mut dyn Write) -> Result<()> {
+        self.inner().pull(source, output)
+    }
+
+    fn push(&mut self, stream: &mut dyn Read, path: &dyn AsRef<str>) -> Result<()> {
+        self.inner().push(stream, path)
+    }
+
+    fn push_with_mtime(
+        &mut self,
+        stream: &mut dyn Read,
+        path: &dyn AsRef<str>,
+        mtime: u32,
+    ) -> Result<()> {
+        self.inner().push_with_mtime(stream, path, mtime)
+    }
+
+    fn reboot(&mut self, reboot_type: RebootType) -> Result<()> {
+        self.inner().reboot(reboot_type)
+    }
+
+    fn install(&mut self, apk_path: &dyn AsRef<Path>) -> Result<()> {
+        self.inner().install(apk_path)
+    }
+
+    fn install_from_reader(
+        &mut self,
+        apk: &mut dyn Read,
+        size: u64,
+        options: &InstallOptions,
+    ) -> Result<()> {
+        self.inner().install_from_reader(apk, size, options)
+    }
+
+    fn install_multiple(&mut self, apk_paths: &[&Path], options: &InstallOptions) -> Result<()> {
+        self.inner().install_multiple(apk_paths, options)
+    }
+
+    fn uninstall(&mut self, package: &str, keep_data: bool) -> Result<()> {
+        self.inner().uninstall(package, keep_data)
+    }
+
+    fn root(&mut self) -> Result<()> {
+        self.inner().root()
+    }
+
+    fn unroot(&mut self) -> Result<()> {
+        self.inner().unroot()
+    }
+
+    fn remount(&mut self) -> Result<String> {
+        self.inner().remount()
+    }
+
+    fn sideload(&mut self, package_path: &dyn AsRef<Path>) -> Result<()> {
+        self.inner().sideload(package_path)
+    }
+
+    fn backup(
+        &mut self,
+        packages: &[&str],
+        options: &BackupOptions,
+        output: &mut dyn Write,
+    ) -> Result<()> {
+        self.inner().backup(packages, options, output)
+    }
+
+    fn restore(&mut self, input: &mut dyn Read) -> Result<()> {
+        self.inner().restore(input)
+    }
+
+    fn framebuffer_inner(&mut self) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>> {
+        self.inner().framebuffer_inner()
+    }
+
+    fn screencap(&mut self) -> Result<Vec<u8>> {
+        self.inner().screencap()
+    }
+}
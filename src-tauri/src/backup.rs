@@ -0,0 +1,93 @@
+use adb_client::{ADBDeviceExt, BackupOptions};
+use serde::Deserialize;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter};
+use std::path::PathBuf;
+
+use crate::{connect_device, SyncError};
+
+/// What [`backup_packages`] includes besides the apps' data.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct BackupRequest {
+    /// Include each app's APK, so the backup restores onto a phone without it.
+    pub apks: bool,
+    /// Include the apps' expansion files.
+    pub obb: bool,
+    /// Include shared storage, which file sync usually covers already.
+    pub shared: bool,
+}
+
+impl From<BackupRequest> for BackupOptions {
+    fn from(request: BackupRequest) -> Self {
+        BackupOptions {
+            apks: request.apks,
+            obb: request.obb,
+            shared: request.shared,
+            ..BackupOptions::default()
+        }
+    }
+}
+
+fn local_target(local_path: &str) -> Result<PathBuf, SyncError> {
+    let target = PathBuf::from(local_path.trim());
+    if target.as_os_str().is_empty() {
+        return Err(SyncError::InvalidLocalPath(
+            "Local path cannot be empty".into(),
+        ));
+    }
+    Ok(target)
+}
+
+fn backup(packages: &[String], request: BackupRequest, local_path: &str) -> Result<u64, SyncError> {
+    let target = local_target(local_path)?;
+    let packages: Vec<&str> = packages.iter().map(String::as_str).collect();
+    let (_, mut device) = connect_device(&mut |_| {})?;
+    log::info!("backing up {} to {}", packages.join(", "), target.display());
+    let result: Result<u64, SyncError> = (|| {
+        let mut file = BufWriter::new(File::create(&target)?);
+        device.backup(&packages, &request.into(), &mut file)?;
+        Ok(file.get_ref().metadata()?.len())
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&target);
+    }
+    result
+}
+
+/// Backs up the data of `packages` into an `adb backup` archive at
+/// `local_path`, returning its size in bytes. Waits for the user to confirm
+/// on the device, which may not happen at all; apps that opt out of backup
+/// come out empty.
+#[tauri::command]
+pub async fn backup_packages(
+    packages: Vec<String>,
+    options: Option<BackupRequest>,
+    local_path: String,
+) -> Result<u64, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        backup(&packages, options.unwrap_or_default(), &local_path)
+    })
+    .await
+    .map_err(|e| format!("backup task failed: {e}"))?
+    .map_err(|e| e.to_string())
+}
+
+fn restore(local_path: &str) -> Result<(), SyncError> {
+    let source = local_target(local_path)?;
+    let mut file = BufReader::new(File::open(&source)?);
+    let (_, mut device) = connect_device(&mut |_| {})?;
+    log::info!("restoring backup {}", source.display());
+    device.restore(&mut file)?;
+    Ok(())
+}
+
+/// Restores an archive made by [`backup_packages`] from `local_path`, once
+/// the user confirms on the device.
+#[tauri::command]
+pub async fn restore_backup(local_path: String) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || restore(&local_path))
+        .await
+        .map_err(|e| format!("restore task failed: {e}"))?
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,134 @@
+use adb_client::{ADBDeviceExt, BatteryStatus};
+use serde::{Deserialize, Serialize};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Window};
+
+use crate::backend::ConnectedDevice;
+use crate::{CancelToken, SyncError};
+
+const BATTERY_EVENT: &str = "sync-low-battery";
+/// How often the battery is read while files are being pushed.
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+/// How often a paused run reads the battery to see whether it can go on.
+const PAUSED_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+/// A paused run sleeps in steps this long so cancelling stays responsive.
+const PAUSED_SLEEP_STEP: Duration = Duration::from_secs(1);
+
+/// What a run does when the device battery falls below `min_battery`.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LowBatteryAction {
+    /// Wait until the battery is back at the threshold, then carry on.
+    #[default]
+    Pause,
+    /// Stop the run with an error.
+    Abort,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct LowBatteryPayload {
+    level: u8,
+    threshold: u8,
+    action: LowBatteryAction,
+    /// `false` once a paused run has enough charge to continue.
+    paused: bool,
+    /// Whether the device is plugged in, so a paused run will pick up again.
+    powered: bool,
+}
+
+/// Battery of the device, or `None` when `dumpsys battery` can't be read.
+fn read_status(device: &mut ConnectedDevice) -> Option<BatteryStatus> {
+    device
+        .battery_status()
+        .inspect_err(|e| log::debug!("could not read battery status: {e}"))
+        .ok()
+}
+
+/// Reads the battery before the run and then at most once a minute, pausing
+/// or aborting while it is below the threshold. Every stop and restart is
+/// reported with a `sync-low-battery` event so the UI can say why the run is
+/// waiting or why it ended.
+pub(crate) struct BatteryGuard {
+    threshold: u8,
+    action: LowBatteryAction,
+    last_check: Option<Instant>,
+}
+
+impl BatteryGuard {
+    pub(crate) fn new(threshold: u8, action: LowBatteryAction) -> Self {
+        Self {
+            threshold,
+            action,
+            last_check: None,
+        }
+    }
+
+    pub(crate) fn check(
+        &mut self,
+        device: &mut ConnectedDevice,
+        window: &Window,
+        cancel: &CancelToken,
+    ) -> Result<(), SyncError> {
+        if self
+            .last_check
+            .is_some_and(|last| last.elapsed() < CHECK_INTERVAL)
+        {
+            return Ok(());
+        }
+        self.last_check = Some(Instant::now());
+        let Some(status) = read_status(device) else {
+            return Ok(());
+        };
+        let Some(level) = status.level.filter(|level| *level < self.threshold) else {
+            return Ok(());
+        };
+
+        log::warn!(
+            "device battery at {level}%, below {}%: {:?}",
+            self.threshold,
+            self.action
+        );
+        self.emit(window, level, true, status.powered);
+        if self.action == LowBatteryAction::Abort {
+            return Err(SyncError::LowBattery {
+                level,
+                threshold: self.threshold,
+            });
+        }
+
+        let (mut level, mut powered) = (level, status.powered);
+        while level < self.threshold {
+            let resume_check = Instant::now() + PAUSED_CHECK_INTERVAL;
+            while Instant::now() < resume_check {
+                cancel.check()?;
+                thread::sleep(PAUSED_SLEEP_STEP);
+            }
+            // A device that stops reporting its level is let go rather
+            // than held paused forever.
+            let status = read_status(device).unwrap_or_default();
+            let previously_powered = std::mem::replace(&mut powered, status.powered);
+            level = status.level.unwrap_or(self.threshold);
+            if level < self.threshold && powered != previously_powered {
+                self.emit(window, level, true, powered);
+            }
+        }
+        log::info!("device battery at {level}%, resuming");
+        self.emit(window, level, false, powered);
+        self.last_check = Some(Instant::now());
+        Ok(())
+    }
+
+    fn emit(&self, window: &Window, level: u8, paused: bool, powered: bool) {
+        let _ = window.emit(
+            BATTERY_EVENT,
+            LowBatteryPayload {
+                level,
+                threshold: self.threshold,
+                action: self.action,
+                paused,
+                powered,
+            },
+        );
+    }
+}
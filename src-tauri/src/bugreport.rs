@@ -0,0 +1,52 @@
+use adb_client::ADBDeviceExt;
+use serde::Serialize;
+use std::fs::{self, File};
+use std::path::PathBuf;
+use tauri::{Emitter, Window};
+
+use crate::{connect_device, SyncError};
+
+const BUGREPORT_PROGRESS_EVENT: &str = "device-bugreport-progress";
+
+#[derive(Debug, Clone, Serialize)]
+struct BugreportProgressPayload {
+    done: u64,
+    total: u64,
+}
+
+fn capture(window: &Window, local_path: &str) -> Result<u64, SyncError> {
+    let target = PathBuf::from(local_path.trim());
+    if target.as_os_str().is_empty() {
+        return Err(SyncError::InvalidLocalPath(
+            "Local path cannot be empty".into(),
+        ));
+    }
+    let (_, mut device) = connect_device(&mut |_| {})?;
+    log::info!("capturing bugreport into {}", target.display());
+    let result: Result<u64, SyncError> = (|| {
+        let mut file = File::create(&target)?;
+        device.bugreport_with_progress(&mut file, &mut |done, total| {
+            let _ = window.emit(
+                BUGREPORT_PROGRESS_EVENT,
+                BugreportProgressPayload { done, total },
+            );
+        })?;
+        Ok(file.metadata()?.len())
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&target);
+    }
+    result
+}
+
+/// Captures a bugreport from the connected device into a zip at
+/// `local_path`, for attaching to sync failure reports, returning its size in
+/// bytes. Takes a few minutes; progress is emitted as
+/// `device-bugreport-progress` events.
+#[tauri::command]
+pub async fn capture_bugreport(window: Window, local_path: String) -> Result<u64, String> {
+    tauri::async_runtime::spawn_blocking(move || capture(&window, &local_path))
+        .await
+        .map_err(|e| format!("bugreport task failed: {e}"))?
+        .map_err(|e| e.to_string())
+}
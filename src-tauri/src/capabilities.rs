@@ -0,0 +1,158 @@
+use adb_client::ADBDeviceExt;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tauri::Window;
+
+use crate::backend::ConnectedDevice;
+use crate::clock::unix_seconds;
+use crate::compare::ComparisonStrategy;
+use crate::config_store::ConfigFile;
+use crate::{run_shell, SyncError};
+
+const CAPABILITIES_FILE: &str = "capabilities.json";
+
+/// Runs every check in one shell round-trip. Each line is `key=value`; a
+/// check that fails prints nothing, which reads back as unsupported.
+const PROBE_SCRIPT: &str = "\
+for bin in toybox sha256sum md5sum du readlink; do \
+command -v $bin >/dev/null 2>&1 && echo bin=$bin; \
+done; \
+stat -c '%f %s %Y %n' / >/dev/null 2>&1 && echo flag=stat_format; \
+find / -maxdepth 0 -exec stat -c '%s' {} + >/dev/null 2>&1 && echo flag=find_exec_plus; \
+find / -maxdepth 0 -printf '' >/dev/null 2>&1 && echo flag=find_printf; \
+mv -n /proc/.adbsync-a /proc/.adbsync-b 2>&1 | grep -qiE 'unknown|invalid|usage' \
+|| echo flag=mv_no_clobber";
+
+/// Which binaries and flags a device's shell supports, probed once per
+/// device build and cached by serial, so features can pick a strategy the
+/// device supports instead of failing mid-run.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeviceCapabilities {
+    serial: String,
+    /// Build fingerprint at probe time; a system update invalidates the cache.
+    fingerprint: String,
+    probed_at: u64,
+    toybox: bool,
+    sha256sum: bool,
+    md5sum: bool,
+    du: bool,
+    readlink: bool,
+    /// `stat -c` with a custom format.
+    stat_format: bool,
+    /// `find ... -exec cmd {} +`.
+    find_exec_plus: bool,
+    find_printf: bool,
+    /// `mv -n`.
+    mv_no_clobber: bool,
+}
+
+impl DeviceCapabilities {
+    fn parse(serial: &str, fingerprint: &str, output: &str) -> Self {
+        let mut capabilities = Self {
+            serial: serial.to_string(),
+            fingerprint: fingerprint.to_string(),
+            probed_at: unix_seconds(),
+            ..Self::default()
+        };
+        for line in output.lines().map(str::trim) {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            match (key, value) {
+                ("bin", "toybox") => capabilities.toybox = true,
+                ("bin", "sha256sum") => capabilities.sha256sum = true,
+                ("bin", "md5sum") => capabilities.md5sum = true,
+                ("bin", "du") => capabilities.du = true,
+                ("bin", "readlink") => capabilities.readlink = true,
+                ("flag", "stat_format") => capabilities.stat_format = true,
+                ("flag", "find_exec_plus") => capabilities.find_exec_plus = true,
+                ("flag", "find_printf") => capabilities.find_printf = true,
+                ("flag", "mv_no_clobber") => capabilities.mv_no_clobber = true,
+                _ => {}
+            }
+        }
+        capabilities
+    }
+
+    /// Whether the remote root can be listed in one `find`/`stat` pass.
+    pub(crate) fn can_list(&self) -> bool {
+        self.stat_format && self.find_exec_plus
+    }
+
+    /// Whether remote files can be matched by content, as move detection and
+    /// checksum comparison do.
+    pub(crate) fn can_hash(&self) -> bool {
+        self.sha256sum
+    }
+
+    /// `requested`, or the closest strategy the device supports.
+    pub(crate) fn comparison(&self, requested: ComparisonStrategy) -> ComparisonStrategy {
+        match requested {
+            ComparisonStrategy::Checksum if !self.can_hash() => {
+                log::warn!(
+                    "device {} has no sha256sum; comparing by size and mtime",
+                    self.serial
+                );
+                ComparisonStrategy::SizeAndMtime
+            }
+            strategy => strategy,
+        }
+    }
+}
+
+fn records_file(
+    window: &Window,
+) -> Result<ConfigFile<HashMap<String, DeviceCapabilities>>, SyncError> {
+    ConfigFile::open(window, CAPABILITIES_FILE)
+}
+
+fn load_records(window: &Window) -> Result<HashMap<String, DeviceCapabilities>, SyncError> {
+    records_file(window)?.load()
+}
+
+fn save_record(window: &Window, record: &DeviceCapabilities) -> Result<(), SyncError> {
+    let file = records_file(window)?;
+    let mut records = file.load()?;
+    records.insert(record.serial.clone(), record.clone());
+    file.write(&records)
+}
+
+/// Capabilities of the connected device, from the cache when its serial and
+/// build fingerprint match, otherwise probed and cached. A cache that can't
+/// be read or written only costs a re-probe.
+pub(crate) fn for_device(
+    window: &Window,
+    device: &mut ConnectedDevice,
+) -> Result<DeviceCapabilities, SyncError> {
+    let serial = device.get_prop("ro.serialno")?.unwrap_or_default();
+    let fingerprint = device.get_prop("ro.build.fingerprint")?.unwrap_or_default();
+    let (serial, fingerprint) = (serial.as_str(), fingerprint.as_str());
+
+    if let Some(cached) = load_records(window)
+        .ok()
+        .and_then(|mut records| records.remove(serial))
+        .filter(|cached| !serial.is_empty() && cached.fingerprint == fingerprint)
+    {
+        return Ok(cached);
+    }
+
+    let probe = run_shell(device, &[PROBE_SCRIPT])?;
+    let capabilities = DeviceCapabilities::parse(serial, fingerprint, &probe);
+    log::info!(
+        "probed device {}: {}",
+        capabilities.serial,
+        serde_json::to_string(&capabilities).unwrap_or_default()
+    );
+    if let Err(error) = save_record(window, &capabilities) {
+        log::warn!("could not cache device capabilities: {error}");
+    }
+    Ok(capabilities)
+}
+
+#[tauri::command]
+pub fn list_device_capabilities(window: Window) -> Result<Vec<DeviceCapabilities>, String> {
+    load_records(&window)
+        .map(|records| records.into_values().collect())
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,101 @@
+use adb_client::RemoteEntry;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use crate::backend::ConnectedDevice;
+use crate::conflicts::SyncBaseline;
+use crate::hashes::{remote_sha256, LocalHashCache};
+use crate::SyncError;
+
+/// Remote mtimes are allowed this much drift, since FAT-formatted storage
+/// only keeps them to two seconds.
+pub(crate) const MTIME_TOLERANCE_SECS: u64 = 2;
+
+/// How a file already on the device is judged to match the local one.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ComparisonStrategy {
+    /// Same size. Fastest, but misses edits that keep the size.
+    #[default]
+    SizeOnly,
+    /// Same size and modification time.
+    SizeAndMtime,
+    /// Same size and SHA-256. Reads every candidate on both sides, so local
+    /// hashes are cached between runs. Devices without `sha256sum` get every
+    /// file uploaded again.
+    Checksum,
+}
+
+/// Applies the run's comparison strategy to files that exist on both sides,
+/// and keeps the baseline of files found in step.
+pub(crate) struct FileComparer {
+    strategy: ComparisonStrategy,
+    local_hashes: LocalHashCache,
+    baseline: SyncBaseline,
+}
+
+impl FileComparer {
+    pub(crate) fn new(
+        strategy: ComparisonStrategy,
+        local_hashes: LocalHashCache,
+        baseline: SyncBaseline,
+    ) -> Self {
+        Self {
+            strategy,
+            local_hashes,
+            baseline,
+        }
+    }
+
+    pub(crate) fn local_hashes(&mut self) -> &mut LocalHashCache {
+        &mut self.local_hashes
+    }
+
+    pub(crate) fn baseline(&mut self) -> &mut SyncBaseline {
+        &mut self.baseline
+    }
+
+    pub(crate) fn is_unchanged(
+        &mut self,
+        device: &mut ConnectedDevice,
+        local_path: &Path,
+        metadata: &fs::Metadata,
+        remote: &RemoteEntry,
+    ) -> Result<bool, SyncError> {
+        if remote.size != metadata.len() {
+            return Ok(false);
+        }
+
+        let unchanged = match self.strategy {
+            ComparisonStrategy::SizeOnly => true,
+            ComparisonStrategy::SizeAndMtime => file_modified_seconds(metadata)
+                .is_some_and(|local| local.abs_diff(remote.mtime) <= MTIME_TOLERANCE_SECS),
+            ComparisonStrategy::Checksum => {
+                let local = self.local_hashes.hash(local_path)?;
+                remote_sha256(device, &remote.path).as_deref() == Some(local.as_str())
+            }
+        };
+        if unchanged {
+            self.baseline
+                .record(&remote.path, remote.size, remote.mtime);
+        }
+        Ok(unchanged)
+    }
+
+    /// Persists the local hashes and baseline gathered during the run.
+    pub(crate) fn finish(&mut self) {
+        self.local_hashes.save();
+        self.baseline.save();
+    }
+}
+
+/// Modification time in seconds since the Unix epoch.
+pub(crate) fn file_modified_seconds(metadata: &fs::Metadata) -> Option<u64> {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_secs())
+}
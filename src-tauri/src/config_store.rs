@@ -0,0 +1,52 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs;
+use std::io;
+use std::marker::PhantomData;
+use std::path::PathBuf;
+use tauri::{Manager, Window};
+
+use crate::SyncError;
+
+/// A JSON document in the app config directory. A missing file reads as
+/// `T::default()`.
+pub(crate) struct ConfigFile<T> {
+    path: PathBuf,
+    contents: PhantomData<T>,
+}
+
+impl<T: Serialize + DeserializeOwned + Default> ConfigFile<T> {
+    pub(crate) fn open(window: &Window, file_name: &str) -> Result<Self, SyncError> {
+        let dir = window
+            .path()
+            .app_config_dir()
+            .map_err(|e| SyncError::Config(e.to_string()))?;
+        Ok(Self {
+            path: dir.join(file_name),
+            contents: PhantomData,
+        })
+    }
+
+    pub(crate) fn load(&self) -> Result<T, SyncError> {
+        match fs::read_to_string(&self.path) {
+            Ok(contents) => {
+                serde_json::from_str(&contents).map_err(|e| SyncError::Config(e.to_string()))
+            }
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(T::default()),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    pub(crate) fn write(&self, value: &T) -> Result<(), SyncError> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let contents =
+            serde_json::to_string_pretty(value).map_err(|e| SyncError::Config(e.to_string()))?;
+        // Write then rename so a crash mid-write can't truncate the file.
+        let staging = self.path.with_extension("json.tmp");
+        fs::write(&staging, contents)?;
+        fs::rename(staging, &self.path)?;
+        Ok(())
+    }
+}
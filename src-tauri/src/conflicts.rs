@@ -0,0 +1,289 @@
+use adb_client::{ADBDeviceExt, RemoteEntry};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{Manager, Window};
+
+use crate::backend::ConnectedDevice;
+use crate::clock::unix_seconds;
+use crate::compare::{self, MTIME_TOLERANCE_SECS};
+use crate::hashes::cache_file_name;
+use crate::pull::download;
+use crate::stats::SyncStats;
+use crate::{remote_metadata, SyncError, SyncOptions};
+
+const BASELINE_DIR: &str = "baselines";
+
+/// What to do with a file that changed on the device since the last sync and
+/// no longer matches the local copy.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictPolicy {
+    /// Keep whichever copy was modified last.
+    NewerWins,
+    /// Upload the local file over the device's, as sync always has.
+    #[default]
+    LocalWins,
+    /// Copy the device's file over the local one.
+    RemoteWins,
+    /// Leave both alone and report the conflict until it is settled by hand.
+    Ask,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictResolution {
+    KeptLocal,
+    KeptRemote,
+    Unresolved,
+}
+
+/// One conflict met during a run and how it was settled.
+#[derive(Debug, Clone, Serialize)]
+pub struct FileConflict {
+    remote_path: String,
+    local_mtime: Option<u64>,
+    remote_mtime: u64,
+    resolution: ConflictResolution,
+    /// Where the losing side was kept, when conflict copies are on.
+    conflict_copy: Option<String>,
+}
+
+impl ConflictPolicy {
+    fn resolve(self, local_mtime: Option<u64>, remote_mtime: u64) -> ConflictResolution {
+        match self {
+            ConflictPolicy::LocalWins => ConflictResolution::KeptLocal,
+            ConflictPolicy::RemoteWins => ConflictResolution::KeptRemote,
+            ConflictPolicy::Ask => ConflictResolution::Unresolved,
+            ConflictPolicy::NewerWins => match local_mtime {
+                Some(local) if local >= remote_mtime => ConflictResolution::KeptLocal,
+                _ => ConflictResolution::KeptRemote,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct BaselineEntry {
+    size: u64,
+    mtime: u64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct BaselineFile {
+    root: String,
+    entries: HashMap<String, BaselineEntry>,
+}
+
+/// The size and mtime each device file had when a run last left it in step
+/// with the local one, so a later run can tell a file edited on the device
+/// from one that is merely out of date. Kept in the app cache directory, one
+/// file per local root; losing it only means no conflicts are detected until
+/// files have been synced once more.
+pub(crate) struct SyncBaseline {
+    path: Option<PathBuf>,
+    file: BaselineFile,
+    dirty: bool,
+}
+
+impl SyncBaseline {
+    pub(crate) fn open(window: &Window, local_root: &Path) -> Self {
+        let path = window
+            .path()
+            .app_cache_dir()
+            .ok()
+            .map(|dir| dir.join(BASELINE_DIR).join(cache_file_name(local_root)));
+        let root = local_root.display().to_string();
+        let file = path
+            .as_deref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|contents| serde_json::from_str::<BaselineFile>(&contents).ok())
+            .filter(|file| file.root == root)
+            .unwrap_or_else(|| BaselineFile {
+                root,
+                entries: HashMap::new(),
+            });
+
+        Self {
+            path,
+            file,
+            dirty: false,
+        }
+    }
+
+    /// Notes that `remote_path` and its local file are in step.
+    pub(crate) fn record(&mut self, remote_path: &str, size: u64, mtime: u64) {
+        self.file
+            .entries
+            .insert(remote_path.to_string(), BaselineEntry { size, mtime });
+        self.dirty = true;
+    }
+
+    /// Whether a local file with this size and mtime would still be in step
+    /// with `remote_path`, judging by the last run alone.
+    pub(crate) fn in_step(&self, remote_path: &str, size: u64, mtime: Option<u64>) -> bool {
+        self.file.entries.get(remote_path).is_some_and(|entry| {
+            entry.size == size
+                && mtime.is_some_and(|mtime| entry.mtime.abs_diff(mtime) <= MTIME_TOLERANCE_SECS)
+        })
+    }
+
+    /// Whether the device file was modified since it was last in step. A file
+    /// never synced before has nothing to differ from.
+    fn changed_on_device(&self, remote: &RemoteEntry) -> bool {
+        self.file.entries.get(&remote.path).is_some_and(|entry| {
+            entry.size != remote.size || entry.mtime.abs_diff(remote.mtime) > MTIME_TOLERANCE_SECS
+        })
+    }
+
+    pub(crate) fn save(&mut self) {
+        if !self.dirty {
+            return;
+        }
+        let Some(path) = self.path.as_deref() else {
+            return;
+        };
+        let Ok(contents) = serde_json::to_string(&self.file) else {
+            return;
+        };
+        if let Some(parent) = path.parent() {
+            if fs::create_dir_all(parent).is_err() {
+                return;
+            }
+        }
+        let staging = path.with_extension("json.tmp");
+        if fs::write(&staging, contents).is_ok() && fs::rename(&staging, path).is_ok() {
+            self.dirty = false;
+        } else {
+            log::warn!("could not write sync baseline {}", path.display());
+        }
+    }
+}
+
+/// Settles a device file that differs from `local_path`. Returns `true` when
+/// the local file should be uploaded as usual, which is also the case when
+/// the device copy is just out of date rather than in conflict.
+///
+/// With conflict copies on, the side that loses is renamed to
+/// `name (conflict YYYY-MM-DD).ext` next to the original instead of being
+/// overwritten; the copy is picked up like any other file by later runs.
+pub(crate) fn settle(
+    device: &mut ConnectedDevice,
+    options: &SyncOptions,
+    baseline: &mut SyncBaseline,
+    local_path: &Path,
+    metadata: &fs::Metadata,
+    remote: &RemoteEntry,
+    stats: &SyncStats,
+) -> Result<bool, SyncError> {
+    if !baseline.changed_on_device(remote) {
+        return Ok(true);
+    }
+
+    let local_mtime = compare::file_modified_seconds(metadata);
+    let resolution = options.conflicts.resolve(local_mtime, remote.mtime);
+    let date = utc_date(unix_seconds());
+    let conflict_copy = match resolution {
+        ConflictResolution::KeptLocal if options.conflict_copies => {
+            let copy = free_remote_name(device, &remote.path, &date)?;
+            if !options.dry_run {
+                device.mv(&remote.path, &copy)?;
+            }
+            Some(copy)
+        }
+        ConflictResolution::KeptRemote if options.conflict_copies => {
+            let copy = free_local_name(local_path, &date);
+            if !options.dry_run {
+                fs::rename(local_path, &copy)?;
+            }
+            Some(copy.display().to_string())
+        }
+        _ => None,
+    };
+    log::info!(
+        "conflict on {}: {resolution:?} ({:?}), copy {conflict_copy:?}",
+        remote.path,
+        options.conflicts
+    );
+    stats.record_conflict(FileConflict {
+        remote_path: remote.path.clone(),
+        local_mtime,
+        remote_mtime: remote.mtime,
+        resolution,
+        conflict_copy,
+    });
+
+    match resolution {
+        ConflictResolution::KeptLocal => Ok(true),
+        ConflictResolution::KeptRemote => {
+            if !options.dry_run {
+                download(device, &remote.path, local_path, remote.mtime)?;
+                baseline.record(&remote.path, remote.size, remote.mtime);
+            }
+            Ok(false)
+        }
+        ConflictResolution::Unresolved => Ok(false),
+    }
+}
+
+/// `name (conflict 2024-05-01).ext` for `name.ext`, with a counter added
+/// when that is taken too.
+fn conflict_name(name: &str, date: &str, attempt: usize) -> String {
+    let tag = match attempt {
+        0 => format!(" (conflict {date})"),
+        n => format!(" (conflict {date} {})", n + 1),
+    };
+    match name.rfind('.') {
+        Some(dot) if dot > 0 => format!("{}{tag}{}", &name[..dot], &name[dot..]),
+        _ => format!("{name}{tag}"),
+    }
+}
+
+fn free_remote_name(
+    device: &mut ConnectedDevice,
+    remote_path: &str,
+    date: &str,
+) -> Result<String, SyncError> {
+    let (dir, name) = match remote_path.rfind('/') {
+        Some(index) => remote_path.split_at(index + 1),
+        None => ("", remote_path),
+    };
+    let mut attempt = 0;
+    loop {
+        let candidate = format!("{dir}{}", conflict_name(name, date, attempt));
+        if remote_metadata(device, &candidate)?.is_none() {
+            return Ok(candidate);
+        }
+        attempt += 1;
+    }
+}
+
+fn free_local_name(local_path: &Path, date: &str) -> PathBuf {
+    let name = local_path.file_name().unwrap_or_default().to_string_lossy();
+    (0..)
+        .map(|attempt| local_path.with_file_name(conflict_name(&name, date, attempt)))
+        .find(|candidate| !candidate.exists())
+        .unwrap_or_else(|| local_path.to_path_buf())
+}
+
+/// `YYYY-MM-DD` of a Unix time, in UTC.
+pub(crate) fn utc_date(secs: u64) -> String {
+    // Days since the epoch to a proleptic Gregorian date, counting eras of
+    // 400 years from 0000-03-01 so leap days fall at the end of each year.
+    let days = (secs / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
@@ -0,0 +1,265 @@
+use adb_client::{ADBDeviceExt, RemoteEntryKind};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use tauri::{State, Window};
+
+use crate::backend::ConnectedDevice;
+use crate::clock::{unix_nanos, unix_seconds};
+use crate::config_store::ConfigFile;
+use crate::mirror::{DeletionMode, TRASH_ROOT};
+use crate::{
+    connect_device, normalize_remote_path, remote_metadata, run_shell, run_shell_checked,
+    shell_quote, SyncError,
+};
+
+const JOURNAL_FILE: &str = "deletions.json";
+/// Oldest entries are dropped once the journal holds this many.
+const MAX_ENTRIES: usize = 500;
+/// How long a preview's confirmation token stays valid.
+const CONFIRMATION_TTL: Duration = Duration::from_secs(120);
+
+/// What deleting a remote path would remove, shown to the user before they
+/// confirm. `token` must be passed back to [`delete_remote_path`].
+#[derive(Debug, Clone, Serialize)]
+pub struct DeletionPreview {
+    path: String,
+    kind: RemoteEntryKind,
+    files: usize,
+    bytes: u64,
+    token: String,
+    expires_in_secs: u64,
+}
+
+struct PendingDeletion {
+    path: String,
+    kind: RemoteEntryKind,
+    files: usize,
+    bytes: u64,
+    issued_at: Instant,
+}
+
+/// Previews awaiting confirmation, by token. A token is used up by the
+/// deletion it confirms, whether or not that deletion succeeds.
+#[derive(Default)]
+pub struct PendingDeletions(Mutex<HashMap<String, PendingDeletion>>);
+
+impl PendingDeletions {
+    fn issue(&self, pending: PendingDeletion) -> String {
+        let token = format!("{:x}", unix_nanos());
+        let mut map = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        map.retain(|_, pending| pending.issued_at.elapsed() < CONFIRMATION_TTL);
+        map.insert(token.clone(), pending);
+        token
+    }
+
+    fn redeem(&self, token: &str) -> Result<PendingDeletion, SyncError> {
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(token)
+            .filter(|pending| pending.issued_at.elapsed() < CONFIRMATION_TTL)
+            .ok_or(SyncError::DeletionNotConfirmed)
+    }
+}
+
+/// One deletion made from the remote browser.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeletionRecord {
+    path: String,
+    kind: RemoteEntryKind,
+    files: usize,
+    bytes: u64,
+    mode: DeletionMode,
+    /// Where the path was moved to, for [`DeletionMode::Trash`].
+    trash_path: Option<String>,
+    deleted_at: u64,
+}
+
+pub(crate) struct DeletionJournal {
+    file: ConfigFile<Vec<DeletionRecord>>,
+}
+
+impl DeletionJournal {
+    pub(crate) fn open(window: &Window) -> Result<Self, SyncError> {
+        Ok(Self {
+            file: ConfigFile::open(window, JOURNAL_FILE)?,
+        })
+    }
+
+    pub(crate) fn load(&self) -> Result<Vec<DeletionRecord>, SyncError> {
+        self.file.load()
+    }
+
+    fn append(&self, record: DeletionRecord) -> Result<(), SyncError> {
+        let mut records = self.load()?;
+        records.push(record);
+        if records.len() > MAX_ENTRIES {
+            records.drain(..records.len() - MAX_ENTRIES);
+        }
+        self.file.write(&records)
+    }
+}
+
+/// Refuses `/` and top-level folders such as `/sdcard`, which no one means
+/// to delete from a file browser.
+fn deletable_path(device_path: &str) -> Result<String, SyncError> {
+    let path = normalize_remote_path(device_path)?;
+    if path.matches('/').count() < 2 {
+        return Err(SyncError::InvalidRemotePath(format!(
+            "Refusing to delete top-level path '{path}'"
+        )));
+    }
+    Ok(path)
+}
+
+fn remote_kind(device: &mut ConnectedDevice, path: &str) -> Result<RemoteEntryKind, SyncError> {
+    match remote_metadata(device, path)?.map(|stat| stat.kind()) {
+        Some(kind @ (RemoteEntryKind::File | RemoteEntryKind::Directory)) => Ok(kind),
+        Some(_) => Err(SyncError::InvalidRemotePath(format!(
+            "'{path}' is not a regular file or folder"
+        ))),
+        None => Err(SyncError::InvalidRemotePath(format!(
+            "'{path}' does not exist on the device"
+        ))),
+    }
+}
+
+/// Number and total size of the files at or below `path`.
+fn measure(device: &mut ConnectedDevice, path: &str) -> Result<(usize, u64), SyncError> {
+    let sizes = run_shell(
+        device,
+        &[
+            "find",
+            &shell_quote(path),
+            "-type",
+            "f",
+            "-exec",
+            "stat",
+            "-c",
+            "%s",
+            "{}",
+            "+",
+            "2>/dev/null",
+        ],
+    )?;
+    Ok(sizes
+        .lines()
+        .filter_map(|line| line.trim().parse::<u64>().ok())
+        .fold((0, 0), |(files, bytes), size| (files + 1, bytes + size)))
+}
+
+fn preview(device_path: &str) -> Result<PendingDeletion, SyncError> {
+    let path = deletable_path(device_path)?;
+    let (_, mut device) = connect_device(&mut |_| {})?;
+    let kind = remote_kind(&mut device, &path)?;
+    let (files, bytes) = measure(&mut device, &path)?;
+    Ok(PendingDeletion {
+        path,
+        kind,
+        files,
+        bytes,
+        issued_at: Instant::now(),
+    })
+}
+
+fn delete(pending: &PendingDeletion, mode: DeletionMode) -> Result<DeletionRecord, SyncError> {
+    let (_, mut device) = connect_device(&mut |_| {})?;
+    // The path may have changed since the preview; never remove a file where
+    // the user confirmed a folder or the other way round.
+    if remote_kind(&mut device, &pending.path)? != pending.kind {
+        return Err(SyncError::DeletionNotConfirmed);
+    }
+
+    let deleted_at = unix_seconds();
+    let trash_path = match mode {
+        DeletionMode::Trash => {
+            let target = format!("{TRASH_ROOT}/manual-{deleted_at}{}", pending.path);
+            let target_parent = target
+                .rsplit_once('/')
+                .map(|(parent, _)| parent)
+                .unwrap_or(TRASH_ROOT);
+            run_shell_checked(&mut device, &["mkdir", "-p", &shell_quote(target_parent)])?;
+            device.mv(&pending.path, &target)?;
+            Some(target)
+        }
+        DeletionMode::Permanent => {
+            match pending.kind {
+                RemoteEntryKind::Directory => device.rm_dir(&pending.path, true)?,
+                _ => device.rm(&pending.path)?,
+            }
+            None
+        }
+    };
+    log::info!("deleted {} from the device ({mode:?})", pending.path);
+
+    Ok(DeletionRecord {
+        path: pending.path.clone(),
+        kind: pending.kind,
+        files: pending.files,
+        bytes: pending.bytes,
+        mode,
+        trash_path,
+        deleted_at,
+    })
+}
+
+/// Measures a remote file or folder and issues a token that
+/// [`delete_remote_path`] requires, so nothing is deleted without the user
+/// having seen what it contains.
+#[tauri::command]
+pub async fn prepare_remote_delete(
+    pending: State<'_, PendingDeletions>,
+    device_path: String,
+) -> Result<DeletionPreview, String> {
+    let deletion = tauri::async_runtime::spawn_blocking(move || preview(&device_path))
+        .await
+        .map_err(|e| format!("delete preview task failed: {e}"))?
+        .map_err(|e| e.to_string())?;
+    let path = deletion.path.clone();
+    let (kind, files, bytes) = (deletion.kind, deletion.files, deletion.bytes);
+    let token = pending.issue(deletion);
+    Ok(DeletionPreview {
+        path,
+        kind,
+        files,
+        bytes,
+        token,
+        expires_in_secs: CONFIRMATION_TTL.as_secs(),
+    })
+}
+
+/// Deletes the path previewed under `token`, moving it to the device trash
+/// unless `mode` says otherwise, and records it in the deletion journal.
+#[tauri::command]
+pub async fn delete_remote_path(
+    window: Window,
+    pending: State<'_, PendingDeletions>,
+    token: String,
+    mode: Option<DeletionMode>,
+) -> Result<DeletionRecord, String> {
+    let deletion = pending.redeem(&token).map_err(|e| e.to_string())?;
+    let record =
+        tauri::async_runtime::spawn_blocking(move || delete(&deletion, mode.unwrap_or_default()))
+            .await
+            .map_err(|e| format!("delete task failed: {e}"))?
+            .map_err(|e| e.to_string())?;
+
+    // The deletion already happened; a journal that can't be written is
+    // logged rather than reported as a failed delete.
+    if let Err(error) =
+        DeletionJournal::open(&window).and_then(|journal| journal.append(record.clone()))
+    {
+        log::warn!("could not journal deletion of {}: {error}", record.path);
+    }
+    Ok(record)
+}
+
+#[tauri::command]
+pub fn list_deletions(window: Window) -> Result<Vec<DeletionRecord>, String> {
+    let records = DeletionJournal::open(&window)
+        .and_then(|journal| journal.load())
+        .map_err(|e| e.to_string())?;
+    Ok(records.into_iter().rev().collect())
+}
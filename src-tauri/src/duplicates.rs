@@ -0,0 +1,135 @@
+use adb_client::ADBDeviceExt;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::backend::ConnectedDevice;
+use crate::hashes::LocalHashCache;
+use crate::stats::SyncStats;
+use crate::{shell_quote, CancelToken, SyncError};
+
+/// Local files with identical content under one root.
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicateGroup {
+    size: u64,
+    sha256: String,
+    /// Paths relative to the local root, sorted.
+    paths: Vec<String>,
+}
+
+/// Groups files by content. Only files sharing a size with another file are
+/// hashed, so the common case of no duplicates reads nothing; empty files are
+/// left out since they are trivially equal.
+pub(crate) fn find_duplicates(
+    root: &Path,
+    by_size: &HashMap<u64, Vec<PathBuf>>,
+    hashes: &mut LocalHashCache,
+    cancel: &CancelToken,
+) -> Result<Vec<DuplicateGroup>, SyncError> {
+    let mut groups = Vec::new();
+    for (&size, paths) in by_size {
+        if size == 0 || paths.len() < 2 {
+            continue;
+        }
+
+        let mut by_hash: HashMap<String, Vec<String>> = HashMap::new();
+        for path in paths {
+            cancel.check()?;
+            let relative = path.strip_prefix(root).unwrap_or(path);
+            by_hash
+                .entry(hashes.hash(path)?)
+                .or_default()
+                .push(relative.display().to_string());
+        }
+        groups.extend(
+            by_hash
+                .into_iter()
+                .filter(|(_, paths)| paths.len() > 1)
+                .map(|(sha256, mut paths)| {
+                    paths.sort();
+                    DuplicateGroup {
+                        size,
+                        sha256,
+                        paths,
+                    }
+                }),
+        );
+    }
+    hashes.save();
+
+    groups.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.paths.cmp(&b.paths)));
+    Ok(groups)
+}
+
+/// Tracks which duplicate groups already have a copy on the device during a
+/// run, so further members can be copied device-side with `cp` instead of
+/// being uploaded again.
+pub(crate) struct DuplicateCopier {
+    group_of: HashMap<PathBuf, usize>,
+    on_device: HashMap<usize, String>,
+}
+
+impl DuplicateCopier {
+    /// `enabled` off gives a copier that never copies.
+    pub(crate) fn new(root: &Path, groups: &[DuplicateGroup], enabled: bool) -> Self {
+        let mut group_of = HashMap::new();
+        if enabled {
+            for (index, group) in groups.iter().enumerate() {
+                for relative in &group.paths {
+                    group_of.insert(root.join(relative), index);
+                }
+            }
+        }
+        Self {
+            group_of,
+            on_device: HashMap::new(),
+        }
+    }
+
+    /// Notes that `remote_path` now holds the content of `local_path`.
+    pub(crate) fn record(&mut self, local_path: &Path, remote_path: &str) {
+        if let Some(&group) = self.group_of.get(local_path) {
+            self.on_device
+                .entry(group)
+                .or_insert_with(|| remote_path.to_string());
+        }
+    }
+
+    /// Copies an identical file already on the device to `remote_path`.
+    /// Returns `false` when no member of the group has reached the device yet,
+    /// or when the copy fails, so that the file is uploaded instead.
+    pub(crate) fn try_copy(
+        &self,
+        device: &mut ConnectedDevice,
+        local_path: &Path,
+        remote_path: &str,
+        stats: &SyncStats,
+        dry_run: bool,
+    ) -> Result<bool, SyncError> {
+        let Some(source) = self
+            .group_of
+            .get(local_path)
+            .and_then(|group| self.on_device.get(group))
+            .filter(|source| source.as_str() != remote_path)
+        else {
+            return Ok(false);
+        };
+
+        if !dry_run {
+            if let Err(error) = device.shell_command_checked(&[
+                "cp",
+                "-f",
+                &shell_quote(source),
+                &shell_quote(remote_path),
+            ]) {
+                log::warn!(
+                    "device copy {source} -> {remote_path} failed, uploading instead: {error}"
+                );
+                return Ok(false);
+            }
+        }
+        log::info!("copied {source} -> {remote_path} on the device instead of uploading");
+        stats.record_copied();
+        Ok(true)
+    }
+}
@@ -0,0 +1,180 @@
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use tauri::Window;
+
+use crate::compare;
+use crate::conflicts::SyncBaseline;
+use crate::placeholders::PathPlaceholders;
+use crate::routing::{ExtensionRoute, RemoteDestinations};
+use crate::rules::ExcludeSet;
+use crate::symlinks::{self, DirectoryTrail};
+use crate::throughput::latest_usb_rate;
+use crate::{
+    apply_profile, canonicalize_local_root, normalize_remote_path, skipped, FolderPair, SyncError,
+    SyncOptions,
+};
+
+/// Assumed USB push rate when no device has been probed yet.
+const DEFAULT_BYTES_PER_SECOND: f64 = 20.0 * 1024.0 * 1024.0;
+/// Protocol round-trips per file (stat, send, done), which dominate when
+/// syncing many small files.
+const PER_FILE_SECS: f64 = 0.02;
+
+/// Scope of a sync worked out from the local tree alone, without a device.
+#[derive(Debug, Default, Serialize)]
+pub struct SyncEstimate {
+    files: usize,
+    bytes: u64,
+    /// Files the last run left in step and that haven't changed locally since,
+    /// when the cache was consulted. A change made on the device can't be
+    /// seen without it, so these are likely rather than certain skips.
+    likely_unchanged_files: usize,
+    likely_unchanged_bytes: u64,
+    files_to_transfer: usize,
+    bytes_to_transfer: u64,
+    estimated_secs: u64,
+    bytes_per_second: f64,
+    /// Whether the rate comes from a throughput probe rather than a default.
+    rate_measured: bool,
+}
+
+struct EstimateWalk<'a> {
+    options: &'a SyncOptions,
+    destinations: RemoteDestinations,
+    baseline: Option<SyncBaseline>,
+    estimate: &'a mut SyncEstimate,
+}
+
+impl EstimateWalk<'_> {
+    fn walk(
+        &mut self,
+        root: &Path,
+        current: &Path,
+        trail: &mut DirectoryTrail,
+    ) -> Result<(), SyncError> {
+        for entry in fs::read_dir(current)? {
+            let entry = entry?;
+            let path = entry.path();
+            if self.options.skips_entry(root, &path) {
+                continue;
+            }
+            let Ok(metadata) = symlinks::entry_metadata(&entry, self.options.follow_symlinks)
+            else {
+                continue;
+            };
+            if skipped::os_metadata_kind(&path, &metadata).is_some() {
+                continue;
+            }
+            let relative = path.strip_prefix(root).unwrap_or_else(|_| Path::new(""));
+            if metadata.is_dir() {
+                if self.options.descends_into(relative) && trail.enter(&path)? {
+                    self.walk(root, &path, trail)?;
+                    trail.leave();
+                }
+            } else if metadata.is_file()
+                && !self.options.exceeds_max_size(metadata.len())
+                && !self.options.outside_age_window(&metadata)
+            {
+                self.count(relative, &metadata);
+            }
+        }
+        Ok(())
+    }
+
+    fn count(&mut self, relative: &Path, metadata: &fs::Metadata) {
+        let size = metadata.len();
+        self.estimate.files += 1;
+        self.estimate.bytes = self.estimate.bytes.saturating_add(size);
+        let unchanged = self.baseline.as_ref().is_some_and(|baseline| {
+            baseline.in_step(
+                &self.destinations.file(relative),
+                size,
+                compare::file_modified_seconds(metadata),
+            )
+        });
+        if unchanged {
+            self.estimate.likely_unchanged_files += 1;
+            self.estimate.likely_unchanged_bytes =
+                self.estimate.likely_unchanged_bytes.saturating_add(size);
+        } else {
+            self.estimate.files_to_transfer += 1;
+            self.estimate.bytes_to_transfer = self.estimate.bytes_to_transfer.saturating_add(size);
+        }
+    }
+}
+
+fn estimate_pair(
+    window: &Window,
+    pair: &FolderPair,
+    routes: &[ExtensionRoute],
+    options: &SyncOptions,
+    use_cache: bool,
+    estimate: &mut SyncEstimate,
+) -> Result<(), SyncError> {
+    let local_root = canonicalize_local_root(&pair.local)?;
+    let remote_root = normalize_remote_path(&pair.remote)?;
+    let mut walk = EstimateWalk {
+        options,
+        destinations: RemoteDestinations::new(remote_root, routes)?,
+        baseline: use_cache.then(|| SyncBaseline::open(window, &local_root)),
+        estimate,
+    };
+    walk.walk(
+        &local_root,
+        &local_root,
+        &mut DirectoryTrail::new(&local_root, options.follow_symlinks)?,
+    )
+}
+
+fn estimate(
+    window: &Window,
+    pairs: &[FolderPair],
+    profile_id: Option<&str>,
+    mut options: SyncOptions,
+    use_cache: bool,
+) -> Result<SyncEstimate, SyncError> {
+    let (routes, profile_name) = apply_profile(window, profile_id, &mut options)?;
+    let pairs = PathPlaceholders::for_run(profile_name.as_deref()).expand_pairs(pairs)?;
+    options.excludes = ExcludeSet::new(&options.exclude)?;
+
+    let mut estimate = SyncEstimate::default();
+    for pair in &pairs {
+        estimate_pair(window, pair, &routes, &options, use_cache, &mut estimate)?;
+    }
+
+    let measured = latest_usb_rate(window).filter(|rate| *rate > 0.0);
+    estimate.rate_measured = measured.is_some();
+    estimate.bytes_per_second = measured.unwrap_or(DEFAULT_BYTES_PER_SECOND);
+    let secs = estimate.bytes_to_transfer as f64 / estimate.bytes_per_second
+        + estimate.files_to_transfer as f64 * PER_FILE_SECS;
+    estimate.estimated_secs = secs.ceil() as u64;
+    Ok(estimate)
+}
+
+/// Counts what a sync of `pairs` would cover by walking only the local tree,
+/// so the UI can show the scope before the user starts. With `use_cache`,
+/// files the sync baseline says are already on the device are left out of
+/// the transfer estimate. The duration uses the last USB throughput probe.
+#[tauri::command]
+pub async fn estimate_sync(
+    window: Window,
+    pairs: Vec<FolderPair>,
+    profile_id: Option<String>,
+    options: Option<SyncOptions>,
+    use_cache: Option<bool>,
+) -> Result<SyncEstimate, String> {
+    let options = options.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || {
+        estimate(
+            &window,
+            &pairs,
+            profile_id.as_deref(),
+            options,
+            use_cache.unwrap_or(true),
+        )
+    })
+    .await
+    .map_err(|e| format!("estimate task failed: {e}"))?
+    .map_err(|e| e.to_string())
+}
@@ -0,0 +1,169 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use tauri::{Manager, Window};
+
+use crate::backend::ConnectedDevice;
+use crate::{run_shell, shell_quote, SyncError};
+
+const CACHE_DIR: &str = "hashes";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedHash {
+    size: u64,
+    modified_ns: u64,
+    sha256: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheFile {
+    root: String,
+    entries: HashMap<String, CachedHash>,
+}
+
+/// SHA-256 of files under one local root, remembered across runs together
+/// with the size and mtime they were computed from, so a file is only read
+/// again once it changes. Kept in the app cache directory, one file per root.
+///
+/// The cache is best-effort: if it can't be read or written, hashes are simply
+/// computed from scratch. It is only read once a hash is first asked for.
+pub(crate) struct LocalHashCache {
+    path: Option<PathBuf>,
+    root: PathBuf,
+    cache: Option<CacheFile>,
+    dirty: bool,
+}
+
+impl LocalHashCache {
+    pub(crate) fn open(window: &Window, local_root: &Path) -> Self {
+        let path = window
+            .path()
+            .app_cache_dir()
+            .ok()
+            .map(|dir| dir.join(CACHE_DIR).join(cache_file_name(local_root)));
+
+        Self {
+            path,
+            root: local_root.to_path_buf(),
+            cache: None,
+            dirty: false,
+        }
+    }
+
+    fn entries(&mut self) -> &mut HashMap<String, CachedHash> {
+        let (path, root) = (&self.path, &self.root);
+        &mut self
+            .cache
+            .get_or_insert_with(|| {
+                path.as_deref()
+                    .and_then(|path| fs::read_to_string(path).ok())
+                    .and_then(|contents| serde_json::from_str::<CacheFile>(&contents).ok())
+                    .filter(|cache| Path::new(&cache.root) == root)
+                    .unwrap_or_else(|| CacheFile {
+                        root: root.display().to_string(),
+                        entries: HashMap::new(),
+                    })
+            })
+            .entries
+    }
+
+    /// Hex SHA-256 of `path`, read from the cache when its size and mtime
+    /// still match.
+    pub(crate) fn hash(&mut self, path: &Path) -> Result<String, SyncError> {
+        let metadata = fs::metadata(path)?;
+        let size = metadata.len();
+        let modified_ns = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map(|duration| duration.as_nanos() as u64)
+            .unwrap_or_default();
+        let key = path
+            .strip_prefix(&self.root)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .into_owned();
+
+        if let Some(cached) = self.entries().get(&key) {
+            if cached.size == size && cached.modified_ns == modified_ns && modified_ns != 0 {
+                return Ok(cached.sha256.clone());
+            }
+        }
+
+        let sha256 = hash_file(path)?;
+        self.entries().insert(
+            key,
+            CachedHash {
+                size,
+                modified_ns,
+                sha256: sha256.clone(),
+            },
+        );
+        self.dirty = true;
+        Ok(sha256)
+    }
+
+    /// Writes the cache back if anything was hashed, dropping entries for
+    /// files that are gone.
+    pub(crate) fn save(&mut self) {
+        if !self.dirty {
+            return;
+        }
+        let (Some(path), Some(cache)) = (self.path.as_deref(), self.cache.as_mut()) else {
+            return;
+        };
+
+        let root = &self.root;
+        cache
+            .entries
+            .retain(|relative, _| root.join(relative).is_file());
+        let Ok(contents) = serde_json::to_string(cache) else {
+            return;
+        };
+        if let Some(parent) = path.parent() {
+            if fs::create_dir_all(parent).is_err() {
+                return;
+            }
+        }
+        let staging = path.with_extension("json.tmp");
+        if fs::write(&staging, contents).is_ok() && fs::rename(&staging, path).is_ok() {
+            self.dirty = false;
+        } else {
+            log::warn!("could not write hash cache {}", path.display());
+        }
+    }
+}
+
+/// Hex SHA-256 of a device file, or `None` if it can't be read or the device
+/// has no `sha256sum`.
+pub(crate) fn remote_sha256(device: &mut ConnectedDevice, remote_file: &str) -> Option<String> {
+    let output = run_shell(
+        device,
+        &["sha256sum", &shell_quote(remote_file), "2>/dev/null"],
+    )
+    .ok()?;
+    output
+        .split_whitespace()
+        .next()
+        .filter(|hash| hash.len() == 64)
+        .map(str::to_ascii_lowercase)
+}
+
+fn hash_file(path: &Path) -> Result<String, SyncError> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hex(&hasher.finalize()))
+}
+
+pub(crate) fn cache_file_name(local_root: &Path) -> String {
+    let digest = Sha256::digest(local_root.to_string_lossy().as_bytes());
+    format!("{}.json", &hex(&digest)[..16])
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
@@ -0,0 +1,150 @@
+use adb_client::{ADBDeviceExt, BatteryStatus, StorageVolume};
+use serde::Serialize;
+
+use crate::backend::ConnectedDevice;
+use crate::clock::unix_seconds;
+use crate::{connect_device, run_shell, DeviceDetails, SyncError};
+
+/// Everything the diagnostics panel shows about the connected device.
+#[derive(Debug, Serialize)]
+pub struct DeviceHealth {
+    device: DeviceDetails,
+    adbd_version: Option<String>,
+    clock_skew_seconds: Option<i64>,
+    battery: Option<BatteryStatus>,
+    volumes: Vec<VolumeStatus>,
+    /// Space on each mounted filesystem, as `df` reports it.
+    storage: Vec<StorageVolume>,
+    sd_card_mounted: Option<bool>,
+    disks: Vec<String>,
+    storage_wear: Vec<WearHint>,
+}
+
+#[derive(Debug, Serialize)]
+struct VolumeStatus {
+    id: String,
+    state: String,
+    fs_uuid: Option<String>,
+    removable: bool,
+}
+
+/// eMMC/UFS life-time estimates as exposed by the kernel, when readable.
+#[derive(Debug, Serialize)]
+struct WearHint {
+    source: String,
+    value: String,
+}
+
+#[tauri::command]
+pub async fn device_health() -> Result<DeviceHealth, String> {
+    tauri::async_runtime::spawn_blocking(collect_device_health)
+        .await
+        .map_err(|e| format!("device health task failed: {e}"))?
+        .map_err(|e| e.to_string())
+}
+
+fn collect_device_health() -> Result<DeviceHealth, SyncError> {
+    let (device_info, mut device) = connect_device(&mut |_| {})?;
+
+    let adbd_version = read_adbd_version(&mut device);
+    let clock_skew_seconds = read_clock_skew(&mut device);
+    let battery = device
+        .battery_status()
+        .inspect_err(|e| log::warn!("could not read battery status: {e}"))
+        .ok();
+    let volumes = read_volumes(&mut device);
+    let storage = device.storage_info().unwrap_or_default();
+    let sd_card_mounted = volumes
+        .iter()
+        .filter(|volume| volume.removable)
+        .map(|volume| volume.state == "mounted")
+        .reduce(|any, mounted| any || mounted);
+    let disks = run_shell(&mut device, &["sm", "list-disks"])
+        .map(|output| non_empty_lines(&output))
+        .unwrap_or_default();
+    let storage_wear = read_storage_wear(&mut device);
+
+    Ok(DeviceHealth {
+        device: DeviceDetails::probe(device_info, &mut device),
+        adbd_version,
+        clock_skew_seconds,
+        battery,
+        volumes,
+        storage,
+        sd_card_mounted,
+        disks,
+        storage_wear,
+    })
+}
+
+fn read_adbd_version(device: &mut ConnectedDevice) -> Option<String> {
+    let output = run_shell(device, &["adbd", "--version", "2>/dev/null"]).ok()?;
+    output
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(str::to_string)
+}
+
+fn read_clock_skew(device: &mut ConnectedDevice) -> Option<i64> {
+    let output = run_shell(device, &["date", "+%s"]).ok()?;
+    let device_seconds: i64 = output.trim().parse().ok()?;
+    let host_seconds = i64::try_from(unix_seconds()).ok()?;
+    Some(device_seconds - host_seconds)
+}
+
+fn read_volumes(device: &mut ConnectedDevice) -> Vec<VolumeStatus> {
+    let Ok(output) = run_shell(device, &["sm", "list-volumes", "all"]) else {
+        return Vec::new();
+    };
+
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let id = fields.next()?.to_string();
+            let state = fields.next()?.to_string();
+            let fs_uuid = fields
+                .next()
+                .filter(|uuid| *uuid != "null")
+                .map(str::to_string);
+            Some(VolumeStatus {
+                removable: id.starts_with("public:"),
+                id,
+                state,
+                fs_uuid,
+            })
+        })
+        .collect()
+}
+
+fn read_storage_wear(device: &mut ConnectedDevice) -> Vec<WearHint> {
+    let command = [
+        "for f in /sys/block/*/device/life_time /sys/block/*/device/pre_eol_info",
+        "/sys/devices/platform/*/*/health_descriptor/life_time_estimation_*;",
+        "do [ -r \"$f\" ] && echo \"$f $(cat \"$f\")\"; done",
+    ];
+    let Ok(output) = run_shell(device, &command) else {
+        return Vec::new();
+    };
+
+    output
+        .lines()
+        .filter_map(|line| {
+            let (source, value) = line.trim().split_once(' ')?;
+            Some(WearHint {
+                source: source.to_string(),
+                value: value.trim().to_string(),
+            })
+        })
+        .collect()
+}
+
+fn non_empty_lines(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect()
+}
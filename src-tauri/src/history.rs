@@ -0,0 +1,225 @@
+use adb_client::ADBDeviceExt;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use tauri::{Manager, Window};
+
+use crate::backend::ConnectedDevice;
+use crate::clock::{unix_nanos, unix_seconds};
+use crate::config_store::ConfigFile;
+use crate::throughput::Transport;
+use crate::{AndroidDeviceInfo, FolderPair, PairSummary, SyncError, SyncOptions};
+
+const HISTORY_FILE: &str = "history.json";
+/// Oldest runs are dropped once the history holds this many.
+const MAX_RUNS: usize = 200;
+/// How often an active run's counters are written while it progresses.
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RunStatus {
+    Running,
+    Completed,
+    Failed,
+    /// Still marked running by an earlier app session, so the app went away
+    /// mid-run; the counters show the last checkpoint.
+    Interrupted,
+}
+
+/// The device a run talked to, as far as it could be identified.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RunDevice {
+    manufacturer: Option<String>,
+    product: Option<String>,
+    model: Option<String>,
+    serial: Option<String>,
+}
+
+impl RunDevice {
+    /// Reads the model and serial from the device; a property that can't be
+    /// read is left empty rather than failing the run.
+    pub(crate) fn probe(info: &AndroidDeviceInfo, device: &mut ConnectedDevice) -> Self {
+        let mut property = |name: &str| device.get_prop(name).ok().flatten();
+        Self {
+            manufacturer: info.manufacturer.clone(),
+            product: info.product.clone(),
+            model: property("ro.product.model"),
+            serial: property("ro.serialno"),
+        }
+    }
+}
+
+/// What a run was made with, so two runs of the same pairs can be compared
+/// when one of them behaves differently.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RunEnvironment {
+    app_version: String,
+    os: String,
+    arch: String,
+    device: Option<RunDevice>,
+    transport: Option<Transport>,
+    options: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunRecord {
+    id: String,
+    session: String,
+    status: RunStatus,
+    pairs: Vec<FolderPair>,
+    dry_run: bool,
+    started_at: u64,
+    updated_at: u64,
+    finished_at: Option<u64>,
+    processed_files: usize,
+    total_files: usize,
+    current_file: Option<String>,
+    files_synced: Option<usize>,
+    bytes_uploaded: Option<u64>,
+    error: Option<String>,
+    #[serde(default)]
+    environment: RunEnvironment,
+}
+
+pub(crate) struct HistoryStore {
+    file: ConfigFile<Vec<RunRecord>>,
+}
+
+impl HistoryStore {
+    pub(crate) fn open(window: &Window) -> Result<Self, SyncError> {
+        Ok(Self {
+            file: ConfigFile::open(window, HISTORY_FILE)?,
+        })
+    }
+
+    pub(crate) fn load(&self) -> Result<Vec<RunRecord>, SyncError> {
+        self.file.load()
+    }
+
+    fn upsert(&self, record: &RunRecord) -> Result<(), SyncError> {
+        let mut runs = self.load()?;
+        match runs.iter_mut().find(|existing| existing.id == record.id) {
+            Some(existing) => *existing = record.clone(),
+            None => runs.push(record.clone()),
+        }
+        if runs.len() > MAX_RUNS {
+            runs.drain(..runs.len() - MAX_RUNS);
+        }
+        self.file.write(&runs)
+    }
+}
+
+/// Keeps the history entry of the active run up to date. History is
+/// best-effort: a failure to write it never fails the sync itself.
+pub(crate) struct RunHistory {
+    store: HistoryStore,
+    record: RunRecord,
+    last_write: Instant,
+}
+
+impl RunHistory {
+    pub(crate) fn start(
+        window: &Window,
+        pairs: &[FolderPair],
+        options: &SyncOptions,
+        total_files: usize,
+    ) -> Option<Self> {
+        let store = HistoryStore::open(window).ok()?;
+        let now = unix_seconds();
+        let environment = RunEnvironment {
+            app_version: window.app_handle().package_info().version.to_string(),
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            device: None,
+            transport: None,
+            options: serde_json::to_value(options).unwrap_or_default(),
+        };
+        let record = RunRecord {
+            id: format!("run-{:x}", unix_nanos()),
+            session: session_id().to_string(),
+            status: RunStatus::Running,
+            pairs: pairs.to_vec(),
+            dry_run: options.dry_run,
+            started_at: now,
+            updated_at: now,
+            finished_at: None,
+            processed_files: 0,
+            total_files,
+            current_file: None,
+            files_synced: None,
+            bytes_uploaded: None,
+            error: None,
+            environment,
+        };
+        let _ = store.upsert(&record);
+        Some(Self {
+            store,
+            record,
+            last_write: Instant::now(),
+        })
+    }
+
+    /// Records the device once connected; written with the next checkpoint.
+    pub(crate) fn record_device(&mut self, device: RunDevice, transport: Transport) {
+        self.record.environment.device = Some(device);
+        self.record.environment.transport = Some(transport);
+    }
+
+    pub(crate) fn checkpoint(&mut self, processed_files: usize, current_file: Option<&str>) {
+        self.record.processed_files = processed_files;
+        self.record.current_file = current_file.map(str::to_string);
+        if self.last_write.elapsed() < CHECKPOINT_INTERVAL {
+            return;
+        }
+        self.record.updated_at = unix_seconds();
+        self.last_write = Instant::now();
+        let _ = self.store.upsert(&self.record);
+    }
+
+    pub(crate) fn finish(mut self, result: Result<&[PairSummary], &SyncError>) {
+        let now = unix_seconds();
+        self.record.updated_at = now;
+        self.record.finished_at = Some(now);
+        self.record.current_file = None;
+        match result {
+            Ok(pairs) => {
+                self.record.status = RunStatus::Completed;
+                self.record.files_synced = Some(pairs.iter().map(|pair| pair.files_synced).sum());
+                self.record.bytes_uploaded =
+                    Some(pairs.iter().map(|pair| pair.bytes_uploaded).sum());
+            }
+            Err(error) => {
+                self.record.status = RunStatus::Failed;
+                self.record.error = Some(error.to_string());
+            }
+        }
+        let _ = self.store.upsert(&self.record);
+    }
+}
+
+/// Identifies this process so runs left `Running` by a previous one can be
+/// told apart from runs still in progress.
+fn session_id() -> &'static str {
+    static SESSION: OnceLock<String> = OnceLock::new();
+    SESSION.get_or_init(|| format!("{:x}", unix_nanos()))
+}
+
+#[tauri::command]
+pub fn list_history(window: Window) -> Result<Vec<RunRecord>, String> {
+    let runs = HistoryStore::open(&window)
+        .and_then(|store| store.load())
+        .map_err(|e| e.to_string())?;
+    Ok(runs
+        .into_iter()
+        .rev()
+        .map(|mut run| {
+            if run.status == RunStatus::Running && run.session != session_id() {
+                run.status = RunStatus::Interrupted;
+            }
+            run
+        })
+        .collect())
+}
@@ -0,0 +1,140 @@
+use adb_client::{DeviceEvent, DeviceWatcher, UsbDeviceId};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+use std::time::Duration;
+use tauri::{Emitter, State, Window};
+
+use crate::profiles::ProfileStore;
+use crate::queue::{JobKind, JobQueue};
+use crate::{enumerate_android_devices, trigger};
+
+const CONNECTED_EVENT: &str = "usb-device-connected";
+const DISCONNECTED_EVENT: &str = "usb-device-disconnected";
+/// How long a freshly plugged device gets for adbd to come up before a
+/// plug-triggered sync connects to it.
+const PLUG_SETTLE: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Serialize)]
+struct UsbDevicePayload {
+    vendor_id: u16,
+    product_id: u16,
+    bus_number: u8,
+    address: u8,
+}
+
+impl From<UsbDeviceId> for UsbDevicePayload {
+    fn from(id: UsbDeviceId) -> Self {
+        Self {
+            vendor_id: id.vendor_id,
+            product_id: id.product_id,
+            bus_number: id.bus_number,
+            address: id.address,
+        }
+    }
+}
+
+/// The USB watcher, started once, and the profile synced whenever an ADB
+/// device is plugged in.
+#[derive(Default)]
+pub struct UsbWatch {
+    started: AtomicBool,
+    plug_profile: Arc<Mutex<Option<String>>>,
+}
+
+fn on_event(
+    window: &Window,
+    queue: &JobQueue,
+    plug_profile: &Mutex<Option<String>>,
+    already_attached: &mut HashSet<(u8, u8)>,
+    event: DeviceEvent,
+) {
+    match event {
+        DeviceEvent::Connected(id) => {
+            let _ = window.emit(CONNECTED_EVENT, UsbDevicePayload::from(id));
+            // Devices attached before watching started weren't just plugged in.
+            if already_attached.remove(&(id.bus_number, id.address)) {
+                return;
+            }
+            let profile = plug_profile
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .clone();
+            if let Some(profile_id) = profile {
+                log::info!(
+                    "device {:04x}:{:04x} plugged in, queueing profile {profile_id}",
+                    id.vendor_id,
+                    id.product_id
+                );
+                let window = window.clone();
+                let queue = queue.clone();
+                thread::spawn(move || {
+                    thread::sleep(PLUG_SETTLE);
+                    trigger::queue_triggered(&window, &queue, &profile_id, JobKind::Sync);
+                });
+            }
+        }
+        DeviceEvent::Disconnected(id) => {
+            already_attached.remove(&(id.bus_number, id.address));
+            let _ = window.emit(DISCONNECTED_EVENT, UsbDevicePayload::from(id));
+        }
+    }
+}
+
+/// Starts reporting ADB devices being plugged in and unplugged through
+/// `usb-device-connected` and `usb-device-disconnected` events, starting with
+/// those already attached. Called once by the frontend; later calls do nothing.
+#[tauri::command]
+pub fn start_usb_watch(
+    window: Window,
+    queue: State<'_, JobQueue>,
+    watch: State<'_, UsbWatch>,
+) -> Result<(), String> {
+    if watch.started.swap(true, Ordering::SeqCst) {
+        return Ok(());
+    }
+    let mut already_attached: HashSet<(u8, u8)> = enumerate_android_devices()
+        .map(|devices| devices.iter().filter_map(|info| info.port()).collect())
+        .unwrap_or_default();
+
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = DeviceWatcher::new();
+    if let Err(error) = watcher.start(sender) {
+        watch.started.store(false, Ordering::SeqCst);
+        return Err(error.to_string());
+    }
+
+    let queue = queue.inner().clone();
+    let plug_profile = watch.plug_profile.clone();
+    thread::spawn(move || {
+        // Events stop once the watcher fails; it is dropped with this thread.
+        let _watcher = watcher;
+        for event in receiver {
+            on_event(&window, &queue, &plug_profile, &mut already_attached, event);
+        }
+    });
+    Ok(())
+}
+
+/// Sets the profile queued each time an ADB device is plugged in, or turns
+/// plug-triggered syncs off with `None`. Needs [`start_usb_watch`] running.
+#[tauri::command]
+pub fn set_plug_sync_profile(
+    window: Window,
+    watch: State<'_, UsbWatch>,
+    profile_id: Option<String>,
+) -> Result<(), String> {
+    if let Some(profile_id) = &profile_id {
+        ProfileStore::open(&window)
+            .and_then(|store| store.get(profile_id))
+            .map_err(|e| e.to_string())?;
+    }
+    *watch
+        .plug_profile
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner) = profile_id;
+    Ok(())
+}
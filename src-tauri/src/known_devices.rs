@@ -0,0 +1,350 @@
+use adb_client::{ADBDeviceExt, ADBTcpDevice};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::net::{IpAddr, SocketAddr, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, PoisonError};
+use std::thread;
+use std::time::Duration;
+use tauri::{Emitter, Manager, State, Window};
+
+use crate::clock::unix_seconds;
+use crate::config_store::ConfigFile;
+use crate::{connect_device, SyncError};
+
+const KNOWN_DEVICES_FILE: &str = "known_devices.json";
+const STATUS_EVENT: &str = "wireless-device-status";
+/// Port adbd listens on after `adb tcpip` when none is given.
+pub(crate) const ADB_TCP_PORT: u16 = 5555;
+/// How long a saved device gets to answer before it counts as unreachable.
+const REACH_TIMEOUT: Duration = Duration::from_secs(2);
+/// How many times, a second apart, a device switched to TCP mode is tried
+/// while its adbd restarts.
+const TCPIP_CONNECT_ATTEMPTS: u32 = 10;
+
+/// A device added by its network address rather than discovered over USB or
+/// mDNS, remembered with the key it accepted so it can be reconnected to
+/// without prompting again.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KnownDevice {
+    pub(crate) address: String,
+    pub(crate) serial: Option<String>,
+    pub(crate) model: Option<String>,
+    /// The private key the device authorized, used both for RSA `AUTH` and
+    /// as the client certificate when adbd upgrades to TLS.
+    key_path: String,
+    /// SHA-256 of the key file, so a replaced key is noticed before the
+    /// device would reject it.
+    key_fingerprint: Option<String>,
+    added_at: u64,
+    last_connected: Option<u64>,
+    #[serde(default = "auto_connect_by_default")]
+    auto_connect: bool,
+}
+
+fn auto_connect_by_default() -> bool {
+    true
+}
+
+/// Saved devices marked `auto_connect`, as last read from or written to the
+/// store, which [`connect_auto`] falls back to when no USB device is attached.
+static AUTO_CONNECT: Mutex<Vec<KnownDevice>> = Mutex::new(Vec::new());
+
+fn remember_auto_connect(devices: &[KnownDevice]) {
+    *AUTO_CONNECT.lock().unwrap_or_else(PoisonError::into_inner) = devices
+        .iter()
+        .filter(|device| device.auto_connect)
+        .cloned()
+        .collect();
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct StatusPayload {
+    address: String,
+    online: bool,
+    error: Option<String>,
+}
+
+pub(crate) struct KnownDeviceStore {
+    file: ConfigFile<Vec<KnownDevice>>,
+}
+
+impl KnownDeviceStore {
+    pub(crate) fn open(window: &Window) -> Result<Self, SyncError> {
+        Ok(Self {
+            file: ConfigFile::open(window, KNOWN_DEVICES_FILE)?,
+        })
+    }
+
+    pub(crate) fn load(&self) -> Result<Vec<KnownDevice>, SyncError> {
+        self.file.load()
+    }
+
+    fn upsert(&self, device: &KnownDevice) -> Result<(), SyncError> {
+        let mut devices = self.load()?;
+        match devices
+            .iter_mut()
+            .find(|existing| existing.address == device.address)
+        {
+            Some(existing) => *existing = device.clone(),
+            None => devices.push(device.clone()),
+        }
+        self.write(devices)
+    }
+
+    fn remove(&self, address: &str) -> Result<bool, SyncError> {
+        let mut devices = self.load()?;
+        let before = devices.len();
+        devices.retain(|device| device.address != address);
+        if devices.len() == before {
+            return Ok(false);
+        }
+        self.write(devices)?;
+        Ok(true)
+    }
+
+    fn write(&self, devices: Vec<KnownDevice>) -> Result<(), SyncError> {
+        self.file.write(&devices)?;
+        remember_auto_connect(&devices);
+        Ok(())
+    }
+}
+
+/// `host:port`, or a bare IP address on the default adb port.
+pub(crate) fn parse_address(address: &str) -> Result<SocketAddr, SyncError> {
+    let address = address.trim();
+    address
+        .parse::<SocketAddr>()
+        .or_else(|_| {
+            address
+                .parse::<IpAddr>()
+                .map(|ip| SocketAddr::new(ip, ADB_TCP_PORT))
+        })
+        .map_err(|_| SyncError::Config(format!("'{address}' is not a valid address")))
+}
+
+fn default_key_path(window: &Window) -> Result<PathBuf, SyncError> {
+    window
+        .path()
+        .home_dir()
+        .map(|home| home.join(".android").join("adbkey"))
+        .map_err(|e| SyncError::Config(e.to_string()))
+}
+
+fn key_fingerprint(key_path: &Path) -> Option<String> {
+    let digest = Sha256::digest(fs::read(key_path).ok()?);
+    Some(digest.iter().map(|byte| format!("{byte:02x}")).collect())
+}
+
+/// Connects and authenticates, which also makes the device show its
+/// authorization prompt the first time this key is seen.
+fn connect(address: SocketAddr, key_path: &Path) -> Result<ADBTcpDevice, SyncError> {
+    Ok(ADBTcpDevice::new_with_custom_private_key(
+        address,
+        key_path.to_path_buf(),
+    )?)
+}
+
+fn add(window: &Window, address: &str) -> Result<KnownDevice, SyncError> {
+    let socket = parse_address(address)?;
+    let key_path = default_key_path(window)?;
+    let mut device = connect(socket, &key_path)?;
+    let mut property = |name: &str| device.get_prop(name).ok().flatten();
+    let now = unix_seconds();
+    let known = KnownDevice {
+        address: socket.to_string(),
+        serial: property("ro.serialno"),
+        model: property("ro.product.model"),
+        key_fingerprint: key_fingerprint(&key_path),
+        key_path: key_path.display().to_string(),
+        added_at: now,
+        last_connected: Some(now),
+        auto_connect: true,
+    };
+    KnownDeviceStore::open(window)?.upsert(&known)?;
+    log::info!("added wireless device {}", known.address);
+    Ok(known)
+}
+
+/// Pairs with the code shown under "Pair device with pairing code" on
+/// Android 11+, which authorizes this computer's key without a prompt, then
+/// adds the device at its connect `address`.
+fn pair(
+    window: &Window,
+    pairing_address: &str,
+    code: &str,
+    address: &str,
+) -> Result<KnownDevice, SyncError> {
+    // The pairing port is random, so unlike the connect address it has no default.
+    let pairing_socket = pairing_address.trim().parse::<SocketAddr>().map_err(|_| {
+        SyncError::Config(format!(
+            "'{pairing_address}' is not a valid pairing address"
+        ))
+    })?;
+    ADBTcpDevice::pair_with_custom_private_key(
+        pairing_socket,
+        code.trim(),
+        &default_key_path(window)?,
+    )?;
+    log::info!("paired with wireless device at {pairing_socket}");
+    add(window, address)
+}
+
+/// Switches the USB device to listening on `port` too, then adds it at its
+/// Wi-Fi address once adbd is back up. The key it already authorized over USB
+/// is accepted there as well.
+fn switch_to_wireless(window: &Window, port: u16) -> Result<KnownDevice, SyncError> {
+    let (_, mut device) = connect_device(&mut |_| {})?;
+    let address = device.enable_tcpip(port)?.to_string();
+    drop(device);
+    log::info!("switched device to TCP mode, reachable at {address}");
+
+    let mut attempt = 1;
+    loop {
+        thread::sleep(Duration::from_secs(1));
+        match add(window, &address) {
+            Err(error) if attempt < TCPIP_CONNECT_ATTEMPTS => {
+                log::debug!("device at {address} not ready yet: {error}");
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Reconnects to one saved device if it answers, with the key it accepted.
+fn reconnect(known: &mut KnownDevice) -> Result<ADBTcpDevice, SyncError> {
+    let socket = parse_address(&known.address)?;
+    TcpStream::connect_timeout(&socket, REACH_TIMEOUT)?;
+    let key_path = PathBuf::from(&known.key_path);
+    let fingerprint = key_fingerprint(&key_path);
+    if known.key_fingerprint.is_some() && fingerprint != known.key_fingerprint {
+        return Err(SyncError::Config(format!(
+            "The adb key at {} changed since {} was added; add the device again to authorize the new key",
+            known.key_path, known.address
+        )));
+    }
+    let device = connect(socket, &key_path)?;
+    known.last_connected = Some(unix_seconds());
+    Ok(device)
+}
+
+/// The first saved `auto_connect` device that answers, connected, for runs
+/// started while no USB device is attached. With `serial`, only that device
+/// is tried.
+pub(crate) fn connect_auto(serial: Option<&str>) -> Option<(KnownDevice, ADBTcpDevice)> {
+    let candidates = AUTO_CONNECT
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone();
+    candidates
+        .into_iter()
+        .filter(|known| serial.is_none() || known.serial.as_deref() == serial)
+        .find_map(|mut known| match reconnect(&mut known) {
+            Ok(device) => Some((known, device)),
+            Err(error) => {
+                log::debug!("wireless device {} unavailable: {error}", known.address);
+                None
+            }
+        })
+}
+
+fn reconnect_all(window: &Window) -> Result<(), SyncError> {
+    let store = KnownDeviceStore::open(window)?;
+    let devices = store.load()?;
+    remember_auto_connect(&devices);
+    for mut known in devices {
+        if !known.auto_connect {
+            continue;
+        }
+        let result = reconnect(&mut known);
+        if result.is_ok() {
+            store.upsert(&known)?;
+        }
+        let _ = window.emit(
+            STATUS_EVENT,
+            StatusPayload {
+                address: known.address.clone(),
+                online: result.is_ok(),
+                error: result.err().map(|error| error.to_string()),
+            },
+        );
+    }
+    Ok(())
+}
+
+#[derive(Default)]
+pub struct WirelessReconnect {
+    started: AtomicBool,
+}
+
+/// Connects to a device at `address` (`ip` or `ip:port`) and saves it once
+/// the device has authorized this computer's adb key.
+#[tauri::command]
+pub async fn add_wireless_device(window: Window, address: String) -> Result<KnownDevice, String> {
+    tauri::async_runtime::spawn_blocking(move || add(&window, &address))
+        .await
+        .map_err(|e| format!("add device task failed: {e}"))?
+        .map_err(|e| e.to_string())
+}
+
+/// Pairs with a device through wireless debugging using the `code` and
+/// `pairing_address` it shows, then connects to it at `address` and saves it.
+#[tauri::command]
+pub async fn pair_wireless_device(
+    window: Window,
+    pairing_address: String,
+    code: String,
+    address: String,
+) -> Result<KnownDevice, String> {
+    tauri::async_runtime::spawn_blocking(move || pair(&window, &pairing_address, &code, &address))
+        .await
+        .map_err(|e| format!("pair device task failed: {e}"))?
+        .map_err(|e| e.to_string())
+}
+
+/// Restarts the USB-connected device's adbd in TCP mode on `port` (5555 by
+/// default), like `adb tcpip`, then connects to it over Wi-Fi and saves it so
+/// syncing can continue without the cable.
+#[tauri::command]
+pub async fn switch_device_to_wireless(
+    window: Window,
+    port: Option<u16>,
+) -> Result<KnownDevice, String> {
+    let port = port.unwrap_or(ADB_TCP_PORT);
+    tauri::async_runtime::spawn_blocking(move || switch_to_wireless(&window, port))
+        .await
+        .map_err(|e| format!("switch to wireless task failed: {e}"))?
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn list_known_devices(window: Window) -> Result<Vec<KnownDevice>, String> {
+    KnownDeviceStore::open(&window)
+        .and_then(|store| store.load())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn remove_known_device(window: Window, address: String) -> Result<bool, String> {
+    KnownDeviceStore::open(&window)
+        .and_then(|store| store.remove(&address))
+        .map_err(|e| e.to_string())
+}
+
+/// Reconnects to every saved wireless device that is reachable, reporting
+/// each through a `wireless-device-status` event. Called once by the
+/// frontend after its main window has loaded; later calls do nothing.
+#[tauri::command]
+pub fn reconnect_known_devices(window: Window, reconnect: State<'_, WirelessReconnect>) {
+    if reconnect.started.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    thread::spawn(move || {
+        if let Err(error) = reconnect_all(&window) {
+            log::warn!("could not reconnect wireless devices: {error}");
+        }
+    });
+}
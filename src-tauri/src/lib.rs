@@ -1,33 +1,386 @@
-use adb_client::{is_adb_device, ADBDeviceExt, ADBUSBDevice, AdbStatResponse, RustADBError};
-use rusb::{Device, UsbContext};
-use serde::Serialize;
-use std::collections::HashSet;
+use adb_client::{
+    is_adb_device, ADBDeviceExt, ADBUSBDevice, AdbStatResponse, ConnectionPhase, DeviceInfo,
+    RemoteEntry, RustADBError, USBTransport,
+};
+use rusb::{Device, GlobalContext};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs::{self, File};
-use std::io;
+use std::io::{self, Read};
 use std::path::{Component, Path, PathBuf};
-use std::time::UNIX_EPOCH;
-use tauri::{Emitter, Window};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use tauri::{Emitter, State, Window};
+
+mod audit;
+mod backend;
+mod backup;
+mod battery;
+mod bugreport;
+mod capabilities;
+mod clock;
+mod compare;
+mod config_store;
+mod conflicts;
+mod deletion;
+mod duplicates;
+mod estimate;
+mod hashes;
+mod health;
+mod history;
+mod hotplug;
+mod known_devices;
+mod logcat;
+mod logging;
+mod manifest;
+mod mirror;
+mod moves;
+mod naming;
+mod overlap;
+mod placeholders;
+mod profiles;
+mod pull;
+mod queue;
+mod reboot;
+mod recovery;
+mod root;
+mod routing;
+mod rules;
+mod schedule;
+mod screen;
+mod shell;
+mod skipped;
+mod space;
+mod stats;
+mod symlinks;
+mod throughput;
+mod trigger;
+mod usage;
+
+use backend::ConnectedDevice;
+use battery::{BatteryGuard, LowBatteryAction};
+use capabilities::DeviceCapabilities;
+use compare::{ComparisonStrategy, FileComparer};
+use conflicts::{ConflictPolicy, FileConflict, SyncBaseline};
+use duplicates::{DuplicateCopier, DuplicateGroup};
+use hashes::LocalHashCache;
+use history::{RunDevice, RunHistory};
+use known_devices::KnownDevice;
+use manifest::RemoteManifest;
+use mirror::{DeletionMode, RemoteSymlink};
+use moves::{MoveDetector, MovedFile};
+use naming::{NameCollisionPolicy, RemoteNameMapping, RemoteNameRegistry};
+use placeholders::PathPlaceholders;
+use profiles::ProfileStore;
+use recovery::{TransportDegradation, TransportRecovery};
+use routing::{ExtensionRoute, RemoteDestinations};
+use rules::{ExcludeSet, HiddenFiles, SkipRules};
+use skipped::{SkipReason, SkippedEntry};
+use stats::SyncStats;
+use symlinks::DirectoryTrail;
 
 #[derive(Debug, Serialize)]
 pub struct SyncSummary {
+    device: DeviceDetails,
+    #[serde(flatten)]
+    pair: PairSummary,
+}
+
+/// Combined result of [`sync_pairs`], with each pair's own breakdown.
+#[derive(Debug, Serialize)]
+pub struct SyncPairsSummary {
     device: DeviceDetails,
     files_synced: usize,
     files_deleted: usize,
+    files_moved: usize,
+    files_copied: usize,
     skipped_entries: usize,
     directories_created: usize,
     bytes_uploaded: u64,
+    dry_run: bool,
+    pairs: Vec<PairSummary>,
+}
+
+impl SyncPairsSummary {
+    fn new(device: DeviceDetails, pairs: Vec<PairSummary>, dry_run: bool) -> Self {
+        Self {
+            device,
+            files_synced: pairs.iter().map(|pair| pair.files_synced).sum(),
+            files_deleted: pairs.iter().map(|pair| pair.files_deleted).sum(),
+            files_moved: pairs.iter().map(|pair| pair.files_moved).sum(),
+            files_copied: pairs.iter().map(|pair| pair.files_copied).sum(),
+            skipped_entries: pairs.iter().map(|pair| pair.skipped_entries).sum(),
+            directories_created: pairs.iter().map(|pair| pair.directories_created).sum(),
+            bytes_uploaded: pairs.iter().map(|pair| pair.bytes_uploaded).sum(),
+            dry_run,
+            pairs,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct PairSummary {
+    files_synced: usize,
+    files_deleted: usize,
+    deleted_files: Vec<String>,
+    trash_path: Option<String>,
+    files_moved: usize,
+    moved_files: Vec<MovedFile>,
+    files_copied: usize,
+    duplicate_files: Vec<DuplicateGroup>,
+    remote_symlinks: Vec<RemoteSymlink>,
+    skipped_entries: usize,
+    skipped_details: Vec<SkippedEntry>,
+    directories_created: usize,
+    bytes_uploaded: u64,
+    oversized_files: Vec<OversizedFile>,
+    conflicts: Vec<FileConflict>,
+    renamed_files: Vec<RemoteNameMapping>,
     remote_path: String,
     local_root: String,
+    comparison: ComparisonStrategy,
+    /// Set when repeated transport errors slowed the run's transfers down.
+    transport_degradation: Option<TransportDegradation>,
+    dry_run: bool,
+}
+
+/// One local folder and the device folder it syncs into.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FolderPair {
+    local: String,
+    remote: String,
+}
+
+/// Per-run tuning supplied by the frontend alongside the paths.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SyncOptions {
+    /// Files larger than this many bytes are left out of the run.
+    max_file_size: Option<u64>,
+    /// Number of directory levels below the local root to descend into.
+    max_depth: Option<usize>,
+    /// Only sync files modified at least this many days ago.
+    min_age: Option<u64>,
+    /// Only sync files modified at most this many days ago, e.g. 90 for
+    /// recent photos. Older files are skipped but never pruned by mirror mode.
+    max_age: Option<u64>,
+    /// How to resolve two local files mapping to the same remote name.
+    name_collisions: NameCollisionPolicy,
+    /// Remove remote files under the remote root that no longer exist locally.
+    mirror: bool,
+    /// Whether mirror mode moves files to the device trash or deletes them.
+    deletion: DeletionMode,
+    /// In mirror mode, `mv` remote files whose content reappears under a new
+    /// local path instead of uploading the new path and pruning the old one.
+    detect_moves: bool,
+    /// Sync a local root even when it has no files to offer. Off by default,
+    /// since an accidentally empty folder in mirror mode would empty the device.
+    allow_empty_source: bool,
+    /// Glob patterns for local paths, relative to the root, to leave out.
+    exclude: Vec<String>,
+    /// Whether dot-files and dot-directories are synced.
+    hidden: HiddenFiles,
+    /// How a file already on the device is judged up to date.
+    comparison: ComparisonStrategy,
+    /// Hash local files that share a size and report identical ones.
+    detect_duplicates: bool,
+    /// Create further copies of a duplicate with `cp` on the device once one
+    /// of them is there, instead of uploading each. Implies `detect_duplicates`.
+    copy_duplicates: bool,
+    /// Sync what local symlinks point to, descending into linked directories,
+    /// instead of skipping the links.
+    follow_symlinks: bool,
+    /// What to do with files edited on the device since they were last synced.
+    conflicts: ConflictPolicy,
+    /// Keep the losing side of a conflict as a dated copy instead of
+    /// overwriting it.
+    conflict_copies: bool,
+    /// Pause or abort once the device battery is below this percentage.
+    min_battery: Option<u8>,
+    /// Whether a battery below `min_battery` pauses the run or ends it.
+    low_battery: LowBatteryAction,
+    #[serde(skip)]
+    excludes: ExcludeSet,
+    #[serde(skip)]
     dry_run: bool,
+    #[serde(skip)]
+    cancel: CancelToken,
+}
+
+/// Flag polled by the planner and the sync loop so a run can be stopped
+/// between entries, including while it is still scanning.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub(crate) fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub(crate) fn check(&self) -> Result<(), SyncError> {
+        if self.0.load(Ordering::Relaxed) {
+            return Err(SyncError::Cancelled);
+        }
+        Ok(())
+    }
+}
+
+/// Cancellation handle of the sync started directly by `sync_folders` or
+/// `sync_pairs`; queued jobs keep their own.
+#[derive(Default)]
+pub struct ActiveSync {
+    current: Mutex<Option<CancelToken>>,
+}
+
+impl ActiveSync {
+    fn begin(&self) -> CancelToken {
+        let token = CancelToken::default();
+        *self.current.lock().unwrap_or_else(PoisonError::into_inner) = Some(token.clone());
+        token
+    }
+}
+
+impl SyncOptions {
+    /// Folds a profile's skip rules into the options sent with the run: the
+    /// profile's patterns are added to any given here, and its hidden-file
+    /// policy and limits win where set.
+    fn apply_rules(&mut self, rules: SkipRules) {
+        self.exclude.extend(rules.exclude);
+        if rules.hidden == HiddenFiles::Include {
+            self.hidden = HiddenFiles::Include;
+        }
+        if rules.max_file_size.is_some() {
+            self.max_file_size = rules.max_file_size;
+        }
+        if rules.max_depth.is_some() {
+            self.max_depth = rules.max_depth;
+        }
+        if rules.min_age.is_some() {
+            self.min_age = rules.min_age;
+        }
+        if rules.max_age.is_some() {
+            self.max_age = rules.max_age;
+        }
+    }
+
+    /// Rejects an age window that no file could fall into, whether it came
+    /// from the request, the profile, or a mix of both.
+    fn check_age_window(&self) -> Result<(), SyncError> {
+        match (self.min_age, self.max_age) {
+            (Some(min), Some(max)) if min > max => Err(SyncError::Config(format!(
+                "Minimum age ({min} days) is greater than maximum age ({max} days)"
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    fn is_hidden(&self, path: &Path) -> bool {
+        self.hidden == HiddenFiles::Skip
+            && path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+    }
+
+    fn is_excluded(&self, relative: &Path) -> bool {
+        self.excludes.matches(relative)
+    }
+
+    fn skips_entry(&self, root: &Path, path: &Path) -> bool {
+        self.is_hidden(path) || self.is_excluded(path.strip_prefix(root).unwrap_or(path))
+    }
+
+    fn finds_duplicates(&self) -> bool {
+        self.detect_duplicates || self.copy_duplicates
+    }
+
+    fn exceeds_max_size(&self, size: u64) -> bool {
+        self.max_file_size.is_some_and(|limit| size > limit)
+    }
+
+    /// Whether a file's mtime falls outside `min_age`/`max_age`. Files whose
+    /// mtime can't be read are kept.
+    fn outside_age_window(&self, metadata: &fs::Metadata) -> bool {
+        if self.min_age.is_none() && self.max_age.is_none() {
+            return false;
+        }
+        let Some(modified) = compare::file_modified_seconds(metadata) else {
+            return false;
+        };
+        let age_days = clock::unix_seconds().saturating_sub(modified) / SECS_PER_DAY;
+        self.min_age.is_some_and(|min| age_days < min)
+            || self.max_age.is_some_and(|max| age_days > max)
+    }
+
+    fn descends_into(&self, relative_dir: &Path) -> bool {
+        self.max_depth
+            .is_none_or(|max| relative_dir.components().count() <= max)
+    }
 }
 
+#[derive(Debug, Clone, Serialize)]
+struct OversizedFile {
+    path: String,
+    size: u64,
+}
+
+const SECS_PER_DAY: u64 = 86_400;
 const PROGRESS_EVENT: &str = "sync-progress";
+const CONNECTION_EVENT: &str = "sync-connection";
+/// Minimum gap between byte-level progress events for the same file.
+const BYTE_PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+/// Below this many local files, stat'ing each one is cheaper than listing a
+/// remote root that may hold far more; mirror mode always lists.
+const MANIFEST_MIN_FILES: usize = 32;
+/// How far back transfer samples are kept when computing throughput.
+const THROUGHPUT_WINDOW: Duration = Duration::from_secs(10);
+/// Idle time after which a USB connection is checked, and reopened if it went
+/// stale, before it is used again: hashing large local trees can take minutes.
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Where the run is in getting hold of the device, before any file moves.
+#[derive(Debug, Serialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+enum ConnectionStage {
+    Detecting,
+    Connecting,
+    Authenticating,
+    WaitingForApproval,
+    Connected,
+    /// The device dropped off mid-run and the run is waiting for it to return.
+    Reconnecting,
+}
+
+impl From<ConnectionPhase> for ConnectionStage {
+    fn from(value: ConnectionPhase) -> Self {
+        match value {
+            ConnectionPhase::Connecting => Self::Connecting,
+            ConnectionPhase::Authenticating => Self::Authenticating,
+            ConnectionPhase::AwaitingUserApproval => Self::WaitingForApproval,
+            ConnectionPhase::Connected => Self::Connected,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+struct ConnectionPayload {
+    stage: ConnectionStage,
+}
+
+fn emit_connection_stage(window: &Window, stage: ConnectionStage) {
+    let _ = window.emit(CONNECTION_EVENT, ConnectionPayload { stage });
+}
 
 #[derive(Debug, Serialize, Clone)]
 struct SyncProgressPayload {
     processed_files: usize,
     total_files: usize,
     current_file: Option<String>,
+    current_file_bytes_sent: Option<u64>,
+    current_file_size: Option<u64>,
+    bytes_transferred: u64,
+    throughput_bps: Option<f64>,
+    eta_seconds: Option<u64>,
     dry_run: bool,
 }
 
@@ -36,21 +389,42 @@ struct ProgressReporter {
     total_files: usize,
     processed_files: usize,
     dry_run: bool,
+    last_byte_emit: Option<Instant>,
+    history: Option<RunHistory>,
+    battery: Option<BatteryGuard>,
+    /// Size of every file the run may upload, whether or not it ends up sent.
+    total_bytes: u64,
+    /// Bytes of files already handled, uploaded or found unchanged.
+    completed_bytes: u64,
+    current_file_sent: u64,
+    bytes_transferred: u64,
+    /// `(when, bytes_transferred)` pairs covering the last [`THROUGHPUT_WINDOW`].
+    samples: VecDeque<(Instant, u64)>,
 }
 
 impl ProgressReporter {
-    fn new(window: Window, total_files: usize, dry_run: bool) -> Self {
+    fn new(window: Window, total_files: usize, total_bytes: u64, dry_run: bool) -> Self {
         let reporter = Self {
             window,
             total_files,
             processed_files: 0,
             dry_run,
+            last_byte_emit: None,
+            history: None,
+            battery: None,
+            total_bytes,
+            completed_bytes: 0,
+            current_file_sent: 0,
+            bytes_transferred: 0,
+            samples: VecDeque::new(),
         };
-        reporter.emit(None);
+        reporter.emit(None, None);
         reporter
     }
 
-    fn file_processed(&mut self, current_file: Option<&str>) {
+    fn file_processed(&mut self, current_file: Option<&str>, file_size: u64) {
+        self.completed_bytes = self.completed_bytes.saturating_add(file_size);
+        self.current_file_sent = 0;
         self.advance(current_file);
     }
 
@@ -58,11 +432,55 @@ impl ProgressReporter {
         self.advance(Some(directory));
     }
 
-    fn emit(&self, current_file: Option<&str>) {
+    /// Reports how much of an in-flight upload has been sent, throttled so a
+    /// large file doesn't flood the frontend with events.
+    fn file_bytes(&mut self, current_file: &str, bytes_sent: u64, file_size: u64) {
+        let now = Instant::now();
+        self.bytes_transferred = self
+            .bytes_transferred
+            .saturating_add(bytes_sent.saturating_sub(self.current_file_sent));
+        self.current_file_sent = bytes_sent;
+        self.samples.push_back((now, self.bytes_transferred));
+        while self
+            .samples
+            .front()
+            .is_some_and(|(when, _)| now.duration_since(*when) > THROUGHPUT_WINDOW)
+        {
+            self.samples.pop_front();
+        }
+
+        let due = self
+            .last_byte_emit
+            .is_none_or(|last| now.duration_since(last) >= BYTE_PROGRESS_INTERVAL);
+        if !due && bytes_sent < file_size {
+            return;
+        }
+        self.last_byte_emit = Some(now);
+        self.emit(Some(current_file), Some((bytes_sent, file_size)));
+    }
+
+    /// Bytes per second across the retained samples.
+    fn throughput(&self) -> Option<f64> {
+        let (first_at, first_bytes) = self.samples.front()?;
+        let (last_at, last_bytes) = self.samples.back()?;
+        let elapsed = last_at.duration_since(*first_at).as_secs_f64();
+        (elapsed > 0.0).then(|| (last_bytes - first_bytes) as f64 / elapsed)
+    }
+
+    fn emit(&self, current_file: Option<&str>, bytes: Option<(u64, u64)>) {
+        let throughput_bps = self.throughput().filter(|rate| *rate > 0.0);
+        let remaining = self
+            .total_bytes
+            .saturating_sub(self.completed_bytes.saturating_add(self.current_file_sent));
         let payload = SyncProgressPayload {
             processed_files: self.processed_files,
             total_files: self.total_files,
             current_file: current_file.map(|value| value.to_string()),
+            current_file_bytes_sent: bytes.map(|(sent, _)| sent),
+            current_file_size: bytes.map(|(_, size)| size),
+            bytes_transferred: self.bytes_transferred,
+            throughput_bps,
+            eta_seconds: throughput_bps.map(|rate| (remaining as f64 / rate).ceil() as u64),
             dry_run: self.dry_run,
         };
         let _ = self.window.emit(PROGRESS_EVENT, payload);
@@ -70,7 +488,30 @@ impl ProgressReporter {
 
     fn advance(&mut self, current_file: Option<&str>) {
         self.processed_files = self.processed_files.saturating_add(1);
-        self.emit(current_file);
+        self.last_byte_emit = None;
+        self.emit(current_file, None);
+        if let Some(history) = self.history.as_mut() {
+            history.checkpoint(self.processed_files, current_file);
+        }
+    }
+}
+
+/// Wraps a local file being pushed and reports each chunk the device pulls.
+struct ProgressReader<'a, R> {
+    inner: R,
+    remote_path: &'a str,
+    bytes_sent: u64,
+    file_size: u64,
+    progress: &'a mut ProgressReporter,
+}
+
+impl<R: Read> Read for ProgressReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.bytes_sent = self.bytes_sent.saturating_add(read as u64);
+        self.progress
+            .file_bytes(self.remote_path, self.bytes_sent, self.file_size);
+        Ok(read)
     }
 }
 
@@ -80,15 +521,23 @@ struct DeviceDetails {
     product_id: u16,
     manufacturer: Option<String>,
     product: Option<String>,
+    /// What the device reports about itself; the USB strings above are often
+    /// blank or generic. `None` when its properties couldn't be read.
+    info: Option<DeviceInfo>,
 }
 
-impl From<AndroidDeviceInfo> for DeviceDetails {
-    fn from(value: AndroidDeviceInfo) -> Self {
+impl DeviceDetails {
+    fn probe(usb: AndroidDeviceInfo, device: &mut impl ADBDeviceExt) -> Self {
+        let info = device
+            .device_info()
+            .inspect_err(|e| log::warn!("could not read device properties: {e}"))
+            .ok();
         Self {
-            vendor_id: value.vendor_id,
-            product_id: value.product_id,
-            manufacturer: value.manufacturer,
-            product: value.product,
+            vendor_id: usb.vendor_id,
+            product_id: usb.product_id,
+            manufacturer: usb.manufacturer,
+            product: usb.product,
+            info,
         }
     }
 }
@@ -98,20 +547,118 @@ pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
-        .invoke_handler(tauri::generate_handler![sync_folders])
+        .plugin(logging::plugin())
+        .manage(ActiveSync::default())
+        .manage(queue::JobQueue::default())
+        .manage(trigger::DeviceTrigger::default())
+        .manage(schedule::Scheduler::default())
+        .manage(deletion::PendingDeletions::default())
+        .manage(known_devices::WirelessReconnect::default())
+        .manage(hotplug::UsbWatch::default())
+        .invoke_handler(tauri::generate_handler![
+            sync_folders,
+            sync_pairs,
+            cancel_sync,
+            audit::audit_folders,
+            estimate::estimate_sync,
+            pull::pull_remote_path,
+            shell::stream_shell_command,
+            logcat::stream_logcat,
+            reboot::reboot_device,
+            reboot::sideload_package,
+            root::set_device_root,
+            root::remount_device,
+            backup::backup_packages,
+            backup::restore_backup,
+            bugreport::capture_bugreport,
+            screen::capture_screen,
+            screen::record_screen,
+            health::device_health,
+            profiles::list_profiles,
+            profiles::save_profile,
+            profiles::delete_profile,
+            history::list_history,
+            logging::get_log_path,
+            queue::enqueue_sync,
+            queue::list_sync_jobs,
+            queue::move_sync_job,
+            queue::cancel_sync_job,
+            trigger::start_device_trigger,
+            trigger::stop_device_trigger,
+            schedule::list_schedules,
+            schedule::save_schedule,
+            schedule::delete_schedule,
+            schedule::start_scheduler,
+            throughput::probe_transports,
+            throughput::list_throughput,
+            capabilities::list_device_capabilities,
+            usage::remote_disk_usage,
+            deletion::prepare_remote_delete,
+            deletion::delete_remote_path,
+            deletion::list_deletions,
+            list_usb_devices,
+            select_usb_device,
+            hotplug::start_usb_watch,
+            hotplug::set_plug_sync_profile,
+            known_devices::add_wireless_device,
+            known_devices::pair_wireless_device,
+            known_devices::switch_device_to_wireless,
+            known_devices::list_known_devices,
+            known_devices::remove_known_device,
+            known_devices::reconnect_known_devices
+        ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
 
+#[tauri::command]
+fn cancel_sync(active: State<'_, ActiveSync>) {
+    if let Some(token) = active
+        .current
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .as_ref()
+    {
+        token.cancel();
+    }
+}
+
 #[tauri::command]
 async fn sync_folders(
     window: Window,
+    active: State<'_, ActiveSync>,
     local_path: String,
     device_path: String,
     dry_run: bool,
+    profile_id: Option<String>,
+    options: Option<SyncOptions>,
 ) -> Result<SyncSummary, String> {
+    let mut options = options.unwrap_or_default();
+    options.dry_run = dry_run;
+    options.cancel = active.begin();
+    tauri::async_runtime::spawn_blocking(move || {
+        perform_sync(window, local_path, device_path, profile_id, options)
+    })
+    .await
+    .map_err(|e| format!("sync task failed: {e}"))?
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn sync_pairs(
+    window: Window,
+    active: State<'_, ActiveSync>,
+    pairs: Vec<FolderPair>,
+    dry_run: bool,
+    profile_id: Option<String>,
+    options: Option<SyncOptions>,
+) -> Result<SyncPairsSummary, String> {
+    let mut options = options.unwrap_or_default();
+    options.dry_run = dry_run;
+    options.cancel = active.begin();
     tauri::async_runtime::spawn_blocking(move || {
-        perform_sync(window, local_path, device_path, dry_run)
+        let (device, summaries) = perform_sync_pairs(window, &pairs, profile_id, options)?;
+        Ok::<_, SyncError>(SyncPairsSummary::new(device, summaries, dry_run))
     })
     .await
     .map_err(|e| format!("sync task failed: {e}"))?
@@ -122,65 +669,329 @@ fn perform_sync(
     window: Window,
     local_path: String,
     device_path: String,
-    dry_run: bool,
+    profile_id: Option<String>,
+    options: SyncOptions,
 ) -> Result<SyncSummary, SyncError> {
-    let local_root = canonicalize_local_root(&local_path)?;
-    let remote_root = normalize_remote_path(&device_path)?;
-    let total_files = count_local_files(&local_root)?;
-    let remote_directories = collect_remote_directories(&local_root, &remote_root)?;
-    let directories_to_create = remote_directories
+    let pairs = vec![FolderPair {
+        local: local_path,
+        remote: device_path,
+    }];
+    let (device, mut pairs) = perform_sync_pairs(window, &pairs, profile_id, options)?;
+    Ok(SyncSummary {
+        device,
+        pair: pairs.remove(0),
+    })
+}
+
+/// Syncs every pair in order over one device connection, with a single
+/// progress total covering all of them.
+fn perform_sync_pairs(
+    window: Window,
+    pairs: &[FolderPair],
+    profile_id: Option<String>,
+    mut options: SyncOptions,
+) -> Result<(DeviceDetails, Vec<PairSummary>), SyncError> {
+    if pairs.is_empty() {
+        return Err(SyncError::Config("No folder pairs to sync".into()));
+    }
+
+    let (routes, profile_name) = apply_profile(&window, profile_id.as_deref(), &mut options)?;
+    let pairs = &PathPlaceholders::for_run(profile_name.as_deref()).expand_pairs(pairs)?;
+    overlap::check_pairs(pairs, options.mirror)?;
+    options.check_age_window()?;
+    options.excludes = ExcludeSet::new(&options.exclude)?;
+    let prepared = pairs
+        .iter()
+        .map(|pair| PreparedPair::new(&window, pair, &routes, &options))
+        .collect::<Result<Vec<_>, _>>()?;
+    let total_work = prepared.iter().fold(0usize, |total, pair| {
+        total.saturating_add(pair.total_work())
+    });
+    let total_bytes = prepared
+        .iter()
+        .fold(0u64, |total, pair| total.saturating_add(pair.local.bytes));
+
+    options.cancel.check()?;
+
+    let history = RunHistory::start(&window, pairs, &options, total_work);
+    let mut progress =
+        ProgressReporter::new(window.clone(), total_work, total_bytes, options.dry_run);
+    progress.history = history;
+    progress.battery = options
+        .min_battery
+        .filter(|_| !options.dry_run)
+        .map(|threshold| BatteryGuard::new(threshold, options.low_battery));
+
+    log::info!(
+        "sync started: {} pair(s), {} item(s), {} bytes{}",
+        prepared.len(),
+        total_work,
+        total_bytes,
+        if options.dry_run { " (dry run)" } else { "" }
+    );
+    let result = sync_prepared_pairs(&window, &prepared, &options, &mut progress);
+    match &result {
+        Ok((_, summaries)) => log::info!(
+            "sync finished: {} uploaded, {} deleted, {} skipped",
+            summaries.iter().map(|pair| pair.files_synced).sum::<usize>(),
+            summaries.iter().map(|pair| pair.files_deleted).sum::<usize>(),
+            summaries.iter().map(|pair| pair.skipped_entries).sum::<usize>()
+        ),
+        Err(error) => log::error!("sync failed: {error}"),
+    }
+    if let Some(history) = progress.history.take() {
+        history.finish(result.as_ref().map(|(_, summaries)| summaries.as_slice()));
+    }
+    result
+}
+
+/// Merges a profile's skip rules and mirror flag into `options`, returning
+/// its extension routes and name. Without a profile there are neither.
+fn apply_profile(
+    window: &Window,
+    profile_id: Option<&str>,
+    options: &mut SyncOptions,
+) -> Result<(Vec<ExtensionRoute>, Option<String>), SyncError> {
+    let Some(id) = profile_id else {
+        return Ok((Vec::new(), None));
+    };
+    let profile = ProfileStore::open(window)?.get(id)?;
+    options.apply_rules(profile.rules);
+    options.mirror |= profile.mirror;
+    Ok((profile.routes, Some(profile.name)))
+}
+
+fn sync_prepared_pairs(
+    window: &Window,
+    prepared: &[PreparedPair],
+    options: &SyncOptions,
+    progress: &mut ProgressReporter,
+) -> Result<(DeviceDetails, Vec<PairSummary>), SyncError> {
+    let (device_info, mut adb_device) =
+        connect_device(&mut |stage| emit_connection_stage(window, stage))?;
+    if let Some(history) = progress.history.as_mut() {
+        history.record_device(
+            RunDevice::probe(&device_info, &mut adb_device),
+            adb_device.transport(),
+        );
+    }
+    let capabilities = capabilities::for_device(window, &mut adb_device)?;
+    if let Some(battery) = progress.battery.as_mut() {
+        battery.check(&mut adb_device, window, &options.cancel)?;
+    }
+    if !options.dry_run {
+        space::check_free_space(&mut adb_device, prepared)?;
+    }
+    let mut recovery = TransportRecovery::new(device_info.serial.clone());
+
+    let summaries = prepared
         .iter()
-        .filter(|dir| normalize_remote_dir_path(dir.as_str()) != "/")
-        .count();
+        .map(|pair| {
+            sync_pair(
+                &mut adb_device,
+                pair,
+                options,
+                &capabilities,
+                &mut recovery,
+                progress,
+            )
+        })
+        .collect::<Result<Vec<_>, _>>()?;
 
-    let device_info = detect_android_device()?;
+    let device = DeviceDetails::probe(device_info, &mut adb_device);
+    Ok((device, summaries))
+}
 
-    let mut created_dirs = HashSet::new();
-    let mut stats = SyncStats::default();
-    let mut progress = ProgressReporter::new(
-        window,
-        total_files.saturating_add(directories_to_create),
-        dry_run,
+/// A pair's paths and local scan, resolved before the device is touched.
+struct PreparedPair {
+    local_root: PathBuf,
+    remote_root: String,
+    destinations: RemoteDestinations,
+    remote_directories: Vec<String>,
+    local: LocalTotals,
+    duplicates: Vec<DuplicateGroup>,
+}
+
+impl PreparedPair {
+    fn new(
+        window: &Window,
+        pair: &FolderPair,
+        routes: &[ExtensionRoute],
+        options: &SyncOptions,
+    ) -> Result<Self, SyncError> {
+        let local_root = canonicalize_local_root(&pair.local)?;
+        let remote_root = normalize_remote_path(&pair.remote)?;
+        let destinations = RemoteDestinations::new(remote_root.clone(), routes)?;
+        let local = count_local_files(&local_root, options)?;
+        if local.files == 0 && !options.allow_empty_source {
+            return Err(SyncError::EmptyLocalRoot(local_root.display().to_string()));
+        }
+        // With routes in play a local folder may hold nothing that lands under
+        // the root, so its remote parents are created as files arrive instead.
+        let remote_directories = if destinations.is_routed() {
+            vec![normalize_remote_dir_path(&remote_root)]
+        } else {
+            collect_remote_directories(&local_root, &remote_root, options)?
+        };
+        let duplicates = if options.finds_duplicates() {
+            duplicates::find_duplicates(
+                &local_root,
+                &local.by_size,
+                &mut LocalHashCache::open(window, &local_root),
+                &options.cancel,
+            )?
+        } else {
+            Vec::new()
+        };
+        Ok(Self {
+            local_root,
+            remote_root,
+            destinations,
+            remote_directories,
+            local,
+            duplicates,
+        })
+    }
+
+    fn total_work(&self) -> usize {
+        let directories_to_create = self
+            .remote_directories
+            .iter()
+            .filter(|dir| normalize_remote_dir_path(dir.as_str()) != "/")
+            .count();
+        self.local.files.saturating_add(directories_to_create)
+    }
+}
+
+fn sync_pair(
+    adb_device: &mut ConnectedDevice,
+    pair: &PreparedPair,
+    options: &SyncOptions,
+    capabilities: &DeviceCapabilities,
+    recovery: &mut TransportRecovery,
+    progress: &mut ProgressReporter,
+) -> Result<PairSummary, SyncError> {
+    let dry_run = options.dry_run;
+    let PreparedPair {
+        local_root,
+        remote_root,
+        destinations,
+        remote_directories,
+        local,
+        duplicates,
+    } = pair;
+
+    if options.mirror && !capabilities.can_list() {
+        return Err(SyncError::DeviceUnsupported(
+            "listing files with `find -exec stat -c`".into(),
+        ));
+    }
+    let manifest =
+        if capabilities.can_list() && (options.mirror || local.files >= MANIFEST_MIN_FILES) {
+            Some(RemoteManifest::scan(
+                adb_device,
+                destinations.root(),
+                &options.cancel,
+            )?)
+        } else {
+            None
+        };
+    let mut moves = match &manifest {
+        Some(manifest) if options.mirror && options.detect_moves && capabilities.can_hash() => {
+            Some(MoveDetector::new(manifest, local_root))
+        }
+        _ => None,
+    };
+
+    let comparison = capabilities.comparison(options.comparison);
+    let mut comparer = FileComparer::new(
+        comparison,
+        LocalHashCache::open(&progress.window, local_root),
+        SyncBaseline::open(&progress.window, local_root),
     );
+    let mut copier = DuplicateCopier::new(local_root, duplicates, options.copy_duplicates);
+    let mut created_dirs = HashSet::new();
+    let mut names = RemoteNameRegistry::new(options.name_collisions);
+    let stats = SyncStats::default();
 
     create_remote_directories(
-        &device_info,
-        &remote_directories,
+        adb_device,
+        remote_directories,
         dry_run,
         &mut created_dirs,
-        &mut stats,
-        &mut progress,
+        &stats,
+        progress,
     )?;
 
-    let mut adb_device = ADBUSBDevice::new(device_info.vendor_id, device_info.product_id)?;
-
-    ensure_remote_dir(
-        &mut adb_device,
-        &remote_root,
-        &mut created_dirs,
-        &mut stats,
-        dry_run,
-    )?;
-    sync_directory(
-        &mut adb_device,
-        &local_root,
-        &local_root,
-        &remote_root,
-        &mut created_dirs,
-        &mut stats,
-        &mut progress,
-        dry_run,
-    )?;
+    ensure_remote_dir(adb_device, remote_root, &mut created_dirs, &stats, dry_run)?;
+    // Entries already dealt with, so a pass resumed after the device came
+    // back carries on from the first file the lost pass didn't finish.
+    let mut completed = HashSet::new();
+    let walked = loop {
+        let result = sync_directory(
+            adb_device,
+            local_root,
+            local_root,
+            &mut DirectoryTrail::new(local_root, options.follow_symlinks)?,
+            destinations,
+            options,
+            &mut created_dirs,
+            &mut names,
+            manifest.as_ref(),
+            &mut moves,
+            &mut comparer,
+            &mut copier,
+            &mut completed,
+            recovery,
+            &stats,
+            progress,
+        );
+        match result {
+            Err(error) if recovery.can_resume(&error) => {
+                log::warn!("lost the device mid-sync ({error}); waiting for it to return");
+                recovery.resume(adb_device, &progress.window, &options.cancel)?;
+            }
+            result => break result,
+        }
+    };
+    comparer.finish();
+    walked?;
+
+    if let Some(manifest) = manifest.as_ref().filter(|_| options.mirror) {
+        mirror::prune_remote(
+            adb_device,
+            local_root,
+            destinations.root(),
+            manifest,
+            &names,
+            moves.as_ref(),
+            options,
+            &stats,
+        )?;
+    }
 
-    Ok(SyncSummary {
-        device: device_info.into(),
+    let stats = stats.snapshot();
+    Ok(PairSummary {
         files_synced: stats.files_synced,
         files_deleted: stats.files_deleted,
+        deleted_files: stats.deleted_files,
+        trash_path: stats.trash_path,
+        files_moved: stats.files_moved,
+        moved_files: stats.moved_files,
+        files_copied: stats.files_copied,
+        duplicate_files: duplicates.clone(),
+        remote_symlinks: stats.remote_symlinks,
         skipped_entries: stats.skipped_entries,
+        skipped_details: stats.skipped_details,
         directories_created: stats.directories_created,
         bytes_uploaded: stats.bytes_uploaded,
-        remote_path: remote_root,
+        oversized_files: stats.oversized_files,
+        conflicts: stats.conflicts,
+        renamed_files: names.into_mappings(),
+        remote_path: destinations.root().to_string(),
         local_root: local_root.display().to_string(),
+        comparison,
+        transport_degradation: recovery.degradation(),
         dry_run,
     })
 }
@@ -237,86 +1048,306 @@ fn normalize_remote_path(path: &str) -> Result<String, SyncError> {
     Ok(format!("/{}", parts.join("/")))
 }
 
+#[allow(clippy::too_many_arguments)]
 fn sync_directory(
-    device: &mut ADBUSBDevice,
+    device: &mut ConnectedDevice,
     root: &Path,
     current: &Path,
-    remote_root: &str,
+    trail: &mut DirectoryTrail,
+    destinations: &RemoteDestinations,
+    options: &SyncOptions,
     created_dirs: &mut HashSet<String>,
-    stats: &mut SyncStats,
+    names: &mut RemoteNameRegistry,
+    manifest: Option<&RemoteManifest>,
+    moves: &mut Option<MoveDetector>,
+    comparer: &mut FileComparer,
+    copier: &mut DuplicateCopier,
+    completed: &mut HashSet<PathBuf>,
+    recovery: &mut TransportRecovery,
+    stats: &SyncStats,
     progress: &mut ProgressReporter,
-    dry_run: bool,
 ) -> Result<(), SyncError> {
     for entry in fs::read_dir(current)? {
+        options.cancel.check()?;
         let entry = entry?;
         let entry_path = entry.path();
-        let metadata = entry.metadata()?;
-
-        if should_skip_entry(&entry_path) {
-            stats.skipped_entries += 1;
+        if completed.contains(&entry_path) {
             continue;
         }
+        sync_entry(
+            device,
+            root,
+            &entry,
+            &entry_path,
+            trail,
+            destinations,
+            options,
+            created_dirs,
+            names,
+            manifest,
+            moves,
+            comparer,
+            copier,
+            completed,
+            recovery,
+            stats,
+            progress,
+        )?;
+        completed.insert(entry_path);
+    }
 
-        let relative_path = entry_path
-            .strip_prefix(root)
-            .unwrap_or_else(|_| Path::new(""));
+    Ok(())
+}
 
-        if metadata.is_dir() {
-            let remote_dir = build_remote_path(remote_root, relative_path);
-            ensure_remote_dir(device, &remote_dir, created_dirs, stats, dry_run)?;
-            sync_directory(
-                device,
-                root,
-                &entry_path,
-                remote_root,
-                created_dirs,
-                stats,
-                progress,
-                dry_run,
-            )?;
-        } else if metadata.is_file() {
-            let remote_file = build_remote_path(remote_root, relative_path);
-            let parent = relative_path
-                .parent()
-                .map(|p| build_remote_path(remote_root, p))
-                .unwrap_or_else(|| remote_root.to_string());
-            ensure_remote_dir(device, &parent, created_dirs, stats, dry_run)?;
-            push_file(device, &entry_path, &remote_file, &metadata, stats, dry_run)?;
-            progress.file_processed(Some(remote_file.as_str()));
-        } else {
-            stats.skipped_entries += 1;
+/// Syncs one directory entry, descending into directories.
+#[allow(clippy::too_many_arguments)]
+fn sync_entry(
+    device: &mut ConnectedDevice,
+    root: &Path,
+    entry: &fs::DirEntry,
+    entry_path: &Path,
+    trail: &mut DirectoryTrail,
+    destinations: &RemoteDestinations,
+    options: &SyncOptions,
+    created_dirs: &mut HashSet<String>,
+    names: &mut RemoteNameRegistry,
+    manifest: Option<&RemoteManifest>,
+    moves: &mut Option<MoveDetector>,
+    comparer: &mut FileComparer,
+    copier: &mut DuplicateCopier,
+    completed: &mut HashSet<PathBuf>,
+    recovery: &mut TransportRecovery,
+    stats: &SyncStats,
+    progress: &mut ProgressReporter,
+) -> Result<(), SyncError> {
+    let relative_path = entry_path
+        .strip_prefix(root)
+        .unwrap_or_else(|_| Path::new(""));
+
+    let metadata = match symlinks::entry_metadata(entry, options.follow_symlinks) {
+        Ok(metadata) => metadata,
+        Err(error) => {
+            stats.skip(
+                relative_path,
+                SkipReason::MetadataUnreadable,
+                Some(error.to_string()),
+            );
+            return Ok(());
         }
+    };
+
+    if let Some(kind) = skipped::os_metadata_kind(entry_path, &metadata) {
+        stats.skip(relative_path, SkipReason::OsMetadata, Some(kind));
+        return Ok(());
+    }
+
+    if options.is_hidden(entry_path) {
+        stats.skip(relative_path, SkipReason::Hidden, None);
+        return Ok(());
+    }
+
+    if options.is_excluded(relative_path) {
+        stats.skip(relative_path, SkipReason::Excluded, None);
+        return Ok(());
+    }
+
+    if metadata.is_dir() {
+        if !options.descends_into(relative_path) {
+            stats.skip(relative_path, SkipReason::BeyondMaxDepth, None);
+            return Ok(());
+        }
+        if !trail.enter(entry_path)? {
+            stats.skip(relative_path, SkipReason::SymlinkLoop, None);
+            return Ok(());
+        }
+
+        sync_directory(
+            device,
+            root,
+            entry_path,
+            trail,
+            destinations,
+            options,
+            created_dirs,
+            names,
+            manifest,
+            moves,
+            comparer,
+            copier,
+            completed,
+            recovery,
+            stats,
+            progress,
+        )?;
+        trail.leave();
+    } else if metadata.is_file() {
+        if options.exceeds_max_size(metadata.len()) {
+            stats.record_oversized(OversizedFile {
+                path: relative_path.display().to_string(),
+                size: metadata.len(),
+            });
+            return Ok(());
+        }
+        if options.outside_age_window(&metadata) {
+            stats.skip(relative_path, SkipReason::OutsideAgeWindow, None);
+            return Ok(());
+        }
+
+        let Some(remote_file) = names.claim(
+            &relative_path.display().to_string(),
+            destinations.file(relative_path),
+            naming::needs_sanitizing(relative_path),
+        )?
+        else {
+            stats.skip(relative_path, SkipReason::NameCollision, None);
+            progress.file_processed(None, metadata.len());
+            return Ok(());
+        };
+        let parent = destinations.file_parent(relative_path);
+        ensure_remote_dir(device, &parent, created_dirs, stats, options.dry_run)?;
+        if let Some(battery) = progress.battery.as_mut() {
+            battery.check(device, &progress.window, &options.cancel)?;
+        }
+        push_file(
+            device,
+            entry_path,
+            &remote_file,
+            &metadata,
+            names,
+            manifest,
+            moves,
+            comparer,
+            copier,
+            recovery,
+            stats,
+            progress,
+            options,
+        )?;
+        progress.file_processed(Some(remote_file.as_str()), metadata.len());
+    } else {
+        let (reason, detail) = skipped::classify_unsupported(entry_path, &metadata);
+        stats.skip(relative_path, reason, detail);
     }
 
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 fn push_file(
-    device: &mut ADBUSBDevice,
+    device: &mut ConnectedDevice,
     local_path: &Path,
     remote_path: &str,
     metadata: &fs::Metadata,
-    stats: &mut SyncStats,
-    dry_run: bool,
+    names: &RemoteNameRegistry,
+    manifest: Option<&RemoteManifest>,
+    moves: &mut Option<MoveDetector>,
+    comparer: &mut FileComparer,
+    copier: &mut DuplicateCopier,
+    recovery: &mut TransportRecovery,
+    stats: &SyncStats,
+    progress: &mut ProgressReporter,
+    options: &SyncOptions,
 ) -> Result<(), SyncError> {
-    if file_is_unchanged(device, remote_path, metadata)? {
-        return Ok(());
+    let dry_run = options.dry_run;
+    let remote = match manifest.and_then(|manifest| manifest.get(remote_path)) {
+        Some(listed) => listed,
+        None => remote_metadata(device, remote_path)?
+            .map(|stat| RemoteEntry::from_stat(remote_path, &stat)),
+    };
+    let in_place = match (remote, moves.as_mut()) {
+        (Some(remote), _) => {
+            let unchanged = comparer.is_unchanged(device, local_path, metadata, &remote)?;
+            if !unchanged
+                && !conflicts::settle(
+                    device,
+                    options,
+                    comparer.baseline(),
+                    local_path,
+                    metadata,
+                    &remote,
+                    stats,
+                )?
+            {
+                return Ok(());
+            }
+            unchanged
+        }
+        (None, Some(moves)) => moves.try_move(
+            device,
+            local_path,
+            remote_path,
+            metadata.len(),
+            names,
+            comparer.local_hashes(),
+            stats,
+            dry_run,
+        )?,
+        (None, None) => false,
+    };
+
+    if !in_place && !copier.try_copy(device, local_path, remote_path, stats, dry_run)? {
+        upload_file(
+            device,
+            local_path,
+            remote_path,
+            metadata,
+            recovery,
+            stats,
+            progress,
+            dry_run,
+        )?;
+        if !dry_run {
+            let mtime = compare::file_modified_seconds(metadata).unwrap_or_default();
+            comparer
+                .baseline()
+                .record(remote_path, metadata.len(), mtime);
+        }
     }
+    copier.record(local_path, remote_path);
+    Ok(())
+}
 
+#[allow(clippy::too_many_arguments)]
+fn upload_file(
+    device: &mut ConnectedDevice,
+    local_path: &Path,
+    remote_path: &str,
+    metadata: &fs::Metadata,
+    recovery: &mut TransportRecovery,
+    stats: &SyncStats,
+    progress: &mut ProgressReporter,
+    dry_run: bool,
+) -> Result<(), SyncError> {
     if !dry_run {
-        let mut file = File::open(local_path)?;
-        device.push(&mut file, &remote_path)?;
+        let mtime = compare::file_modified_seconds(metadata)
+            .and_then(|mtime| u32::try_from(mtime).ok())
+            .unwrap_or_default();
+        recovery.push(device, remote_path, |device| {
+            let mut reader = ProgressReader {
+                inner: File::open(local_path)?,
+                remote_path,
+                bytes_sent: 0,
+                file_size: metadata.len(),
+                progress: &mut *progress,
+            };
+            device.push_with_mtime(&mut reader, &remote_path, mtime)?;
+            Ok(())
+        })?;
     }
-    stats.files_synced += 1;
-    stats.bytes_uploaded += metadata.len();
+    log::debug!(
+        "uploaded {} -> {remote_path} ({} bytes)",
+        local_path.display(),
+        metadata.len()
+    );
+    stats.record_upload(metadata.len());
     Ok(())
 }
 
 fn ensure_remote_dir(
-    device: &mut ADBUSBDevice,
+    device: &mut ConnectedDevice,
     remote_dir: &str,
     created_dirs: &mut HashSet<String>,
-    stats: &mut SyncStats,
+    stats: &SyncStats,
     dry_run: bool,
 ) -> Result<(), SyncError> {
     let normalized = normalize_remote_dir_path(remote_dir);
@@ -327,10 +1358,9 @@ fn ensure_remote_dir(
 
     if normalized != "/" {
         if !dry_run {
-            let mut sink = io::sink();
-            device.shell_command(&["mkdir", "-p", normalized.as_str()], &mut sink)?;
+            run_shell_checked(device, &["mkdir", "-p", &shell_quote(&normalized)])?;
         }
-        stats.directories_created += 1;
+        stats.record_directory_created();
     }
 
     Ok(())
@@ -344,10 +1374,21 @@ fn normalize_remote_dir_path(path: &str) -> String {
     }
 }
 
-fn collect_remote_directories(local_root: &Path, remote_root: &str) -> Result<Vec<String>, SyncError> {
+fn collect_remote_directories(
+    local_root: &Path,
+    remote_root: &str,
+    options: &SyncOptions,
+) -> Result<Vec<String>, SyncError> {
     let mut directories = HashSet::new();
     directories.insert(normalize_remote_dir_path(remote_root));
-    collect_remote_directories_recursive(local_root, local_root, remote_root, &mut directories)?;
+    collect_remote_directories_recursive(
+        local_root,
+        local_root,
+        &mut DirectoryTrail::new(local_root, options.follow_symlinks)?,
+        remote_root,
+        options,
+        &mut directories,
+    )?;
 
     let mut list: Vec<_> = directories.into_iter().collect();
     list.sort_by(|a, b| {
@@ -361,24 +1402,46 @@ fn collect_remote_directories(local_root: &Path, remote_root: &str) -> Result<Ve
 fn collect_remote_directories_recursive(
     root: &Path,
     current: &Path,
+    trail: &mut DirectoryTrail,
     remote_root: &str,
+    options: &SyncOptions,
     directories: &mut HashSet<String>,
 ) -> Result<(), SyncError> {
     for entry in fs::read_dir(current)? {
+        options.cancel.check()?;
         let entry = entry?;
         let path = entry.path();
-        if should_skip_entry(&path) {
+        if options.skips_entry(root, &path) {
             continue;
         }
 
-        let metadata = entry.metadata()?;
+        let Ok(metadata) = symlinks::entry_metadata(&entry, options.follow_symlinks) else {
+            continue;
+        };
         if metadata.is_dir() {
+            if skipped::os_metadata_kind(&path, &metadata).is_some() {
+                continue;
+            }
             let relative = path
                 .strip_prefix(root)
                 .unwrap_or_else(|_| Path::new(""));
+            if !options.descends_into(relative) {
+                continue;
+            }
+            if !trail.enter(&path)? {
+                continue;
+            }
             let remote_dir = build_remote_path(remote_root, relative);
             directories.insert(normalize_remote_dir_path(remote_dir.as_str()));
-            collect_remote_directories_recursive(root, &path, remote_root, directories)?;
+            collect_remote_directories_recursive(
+                root,
+                &path,
+                trail,
+                remote_root,
+                options,
+                directories,
+            )?;
+            trail.leave();
         }
     }
 
@@ -393,27 +1456,13 @@ fn directory_depth(path: &str) -> usize {
 }
 
 fn create_remote_directories(
-    device_info: &AndroidDeviceInfo,
+    device: &mut ConnectedDevice,
     directories: &[String],
     dry_run: bool,
     created_dirs: &mut HashSet<String>,
-    stats: &mut SyncStats,
+    stats: &SyncStats,
     progress: &mut ProgressReporter,
 ) -> Result<(), SyncError> {
-    let needs_device = !dry_run
-        && directories
-            .iter()
-            .any(|dir| normalize_remote_dir_path(dir.as_str()) != "/");
-
-    let mut shell_device = if needs_device {
-        Some(ADBUSBDevice::new(
-            device_info.vendor_id,
-            device_info.product_id,
-        )?)
-    } else {
-        None
-    };
-
     for dir in directories {
         let normalized = normalize_remote_dir_path(dir.as_str());
         if !created_dirs.insert(normalized.clone()) {
@@ -424,79 +1473,35 @@ fn create_remote_directories(
             continue;
         }
 
-        if let Some(device) = shell_device.as_mut() {
-            if !dry_run {
-                let mut sink = io::sink();
-                device.shell_command(&["mkdir", "-p", normalized.as_str()], &mut sink)?;
-            }
+        if !dry_run {
+            run_shell_checked(device, &["mkdir", "-p", &shell_quote(&normalized)])?;
         }
 
-        stats.directories_created += 1;
+        stats.record_directory_created();
         progress.directory_prepared(normalized.as_str());
     }
 
     Ok(())
 }
 
-fn file_is_unchanged(
-    device: &mut ADBUSBDevice,
-    remote_path: &str,
-    metadata: &fs::Metadata,
-) -> Result<bool, SyncError> {
-    let Some(remote) = remote_metadata(device, remote_path)? else {
-        return Ok(false);
-    };
-
-    if u64::from(remote.file_size) != metadata.len() {
-        return Ok(false);
-    }
-
-    Ok(true)
-}
-
 fn remote_metadata(
-    device: &mut ADBUSBDevice,
+    device: &mut ConnectedDevice,
     remote_path: &str,
 ) -> Result<Option<AdbStatResponse>, SyncError> {
     match device.stat(remote_path) {
         Ok(stat) => Ok(Some(stat)),
-        Err(error) => match error {
-            RustADBError::ADBRequestFailed(message) => {
-                if adb_missing_file(&message) {
-                    Ok(None)
-                } else {
-                    Err(SyncError::Adb(RustADBError::ADBRequestFailed(message)))
-                }
-            }
-            other => Err(other.into()),
-        },
+        Err(RustADBError::RemoteFileNotFound(_)) => Ok(None),
+        Err(other) => Err(other.into()),
     }
 }
 
-fn adb_missing_file(message: &str) -> bool {
-    let lower = message.to_ascii_lowercase();
-    lower.contains("no such file")
-        || lower.contains("not found")
-        || lower.contains("does not exist")
-        || lower.contains("failed to lstat")
-        || lower.contains("failed to stat")
-}
-
-fn file_modified_seconds(metadata: &fs::Metadata) -> Option<u64> {
-    metadata
-        .modified()
-        .ok()
-        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
-        .map(|duration| duration.as_secs())
-}
-
 fn build_remote_path(remote_root: &str, relative: &Path) -> String {
     let mut pieces = Vec::new();
     for component in relative.components() {
         if let Component::Normal(part) = component {
             let text = part.to_string_lossy();
             if !text.is_empty() {
-                pieces.push(text.into_owned());
+                pieces.push(naming::sanitize_component(&text));
             }
         }
     }
@@ -512,30 +1517,64 @@ fn build_remote_path(remote_root: &str, relative: &Path) -> String {
     }
 }
 
-fn count_local_files(root: &Path) -> Result<usize, SyncError> {
-    let mut total = 0;
-    for entry in fs::read_dir(root)? {
+/// Files (and their combined size) a run over a local root will consider.
+#[derive(Debug, Clone, Default)]
+struct LocalTotals {
+    files: usize,
+    bytes: u64,
+    /// Files grouped by size, only collected when looking for duplicates.
+    by_size: HashMap<u64, Vec<PathBuf>>,
+}
+
+fn count_local_files(root: &Path, options: &SyncOptions) -> Result<LocalTotals, SyncError> {
+    let mut totals = LocalTotals::default();
+    let mut trail = DirectoryTrail::new(root, options.follow_symlinks)?;
+    count_local_files_in(root, root, &mut trail, options, &mut totals)?;
+    Ok(totals)
+}
+
+fn count_local_files_in(
+    root: &Path,
+    current: &Path,
+    trail: &mut DirectoryTrail,
+    options: &SyncOptions,
+    totals: &mut LocalTotals,
+) -> Result<(), SyncError> {
+    for entry in fs::read_dir(current)? {
+        options.cancel.check()?;
         let entry = entry?;
-        if should_skip_entry(&entry.path()) {
+        let path = entry.path();
+        if options.skips_entry(root, &path) {
+            continue;
+        }
+        let Ok(metadata) = symlinks::entry_metadata(&entry, options.follow_symlinks) else {
+            continue;
+        };
+        if skipped::os_metadata_kind(&path, &metadata).is_some() {
             continue;
         }
-        let metadata = entry.metadata()?;
         if metadata.is_dir() {
-            total += count_local_files(&entry.path())?;
-        } else if metadata.is_file() {
-            total += 1;
+            let relative = path.strip_prefix(root).unwrap_or_else(|_| Path::new(""));
+            if options.descends_into(relative) && trail.enter(&path)? {
+                count_local_files_in(root, &path, trail, options, totals)?;
+                trail.leave();
+            }
+        } else if metadata.is_file()
+            && !options.exceeds_max_size(metadata.len())
+            && !options.outside_age_window(&metadata)
+        {
+            totals.files += 1;
+            totals.bytes = totals.bytes.saturating_add(metadata.len());
+            if options.finds_duplicates() {
+                totals.by_size.entry(metadata.len()).or_default().push(path);
+            }
         }
     }
-    Ok(total)
-}
-
-fn should_skip_entry(path: &Path) -> bool {
-    path.file_name()
-        .map(|name| name.to_string_lossy().starts_with('.'))
-        .unwrap_or(false)
+    Ok(())
 }
 
-fn detect_android_device() -> Result<AndroidDeviceInfo, SyncError> {
+/// Every ADB device attached over USB, each physical device listed once.
+fn enumerate_android_devices() -> Result<Vec<AndroidDeviceInfo>, SyncError> {
     let devices = rusb::devices()?;
     let mut matches = Vec::new();
 
@@ -551,68 +1590,364 @@ fn detect_android_device() -> Result<AndroidDeviceInfo, SyncError> {
         matches.push(AndroidDeviceInfo::from_usb_device(device, descriptor));
     }
 
+    Ok(dedupe_enumeration(matches))
+}
+
+/// The device to sync with: the one chosen with [`select_usb_device`], or
+/// the only one attached when none was chosen.
+fn detect_android_device() -> Result<AndroidDeviceInfo, SyncError> {
+    let mut matches = enumerate_android_devices()?;
+    let selected = SELECTED_SERIAL
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone();
+    if let Some(serial) = &selected {
+        matches.retain(|info| info.serial.as_ref() == Some(serial));
+    }
     match matches.len() {
         0 => Err(SyncError::DeviceNotFound),
         1 => Ok(matches.remove(0)),
         _ => Err(SyncError::MultipleDevices(
-            matches
-                .iter()
-                .map(|info| (info.vendor_id, info.product_id))
-                .collect(),
+            matches.iter().map(AndroidDeviceInfo::label).collect(),
         )),
     }
 }
 
+/// USB serial of the device chosen to sync with when several are attached.
+static SELECTED_SERIAL: Mutex<Option<String>> = Mutex::new(None);
+
+#[derive(Debug, Serialize)]
+struct UsbDeviceSummary {
+    vendor_id: u16,
+    product_id: u16,
+    manufacturer: Option<String>,
+    product: Option<String>,
+    serial: Option<String>,
+    selected: bool,
+}
+
+/// Lists the ADB devices attached over USB, so one can be picked by serial
+/// when several of the same model are plugged in.
+#[tauri::command]
+fn list_usb_devices() -> Result<Vec<UsbDeviceSummary>, String> {
+    let selected = SELECTED_SERIAL
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone();
+    let devices = enumerate_android_devices().map_err(|e| e.to_string())?;
+    Ok(devices
+        .into_iter()
+        .map(|info| UsbDeviceSummary {
+            selected: selected.is_some() && info.serial == selected,
+            vendor_id: info.vendor_id,
+            product_id: info.product_id,
+            manufacturer: info.manufacturer,
+            product: info.product,
+            serial: info.serial,
+        })
+        .collect())
+}
+
+/// Chooses the device every later run connects to by its USB serial, or
+/// clears the choice so the only attached device is used.
+#[tauri::command]
+fn select_usb_device(serial: Option<String>) {
+    *SELECTED_SERIAL
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner) = serial.filter(|serial| !serial.is_empty());
+}
+
+/// Bus and address of the device the last successful connection went to.
+/// That instance has authorized this computer, so it is the one kept when a
+/// hub briefly lists the same phone twice.
+static LAST_CONNECTED: Mutex<Option<(u8, u8)>> = Mutex::new(None);
+
+/// Collapses entries for one physical device, which some hubs and OSes list
+/// twice for a moment while re-enumerating it. Entries are the same device
+/// when their USB serials match, or their bus and address when a serial can't
+/// be read. An entry that couldn't be opened at all is dropped when another
+/// with the same vendor and product could, as that is the stale one.
+fn dedupe_enumeration(matches: Vec<AndroidDeviceInfo>) -> Vec<AndroidDeviceInfo> {
+    let last = *LAST_CONNECTED
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    let mut unique: Vec<AndroidDeviceInfo> = Vec::new();
+    for info in matches {
+        match unique.iter_mut().find(|kept| kept.same_device(&info)) {
+            Some(kept) if info.rank(last) > kept.rank(last) => {
+                log::debug!("ignoring duplicate enumeration at {:?}", kept.port());
+                *kept = info;
+            }
+            Some(_) => log::debug!("ignoring duplicate enumeration at {:?}", info.port()),
+            None => unique.push(info),
+        }
+    }
+
+    let opened: Vec<(u16, u16)> = unique
+        .iter()
+        .filter(|info| info.opened)
+        .map(|info| (info.vendor_id, info.product_id))
+        .collect();
+    unique.retain(|info| {
+        let stale = !info.opened && opened.contains(&(info.vendor_id, info.product_id));
+        if stale {
+            log::debug!("ignoring unopenable enumeration at {:?}", info.port());
+        }
+        !stale
+    });
+    unique
+}
+
+fn connect_device(
+    on_stage: &mut dyn FnMut(ConnectionStage),
+) -> Result<(AndroidDeviceInfo, ConnectedDevice), SyncError> {
+    on_stage(ConnectionStage::Detecting);
+    let device_info = match detect_android_device() {
+        Err(SyncError::DeviceNotFound) => return connect_wireless(on_stage),
+        result => result?,
+    };
+    log::info!(
+        "connecting to {:04x}:{:04x} ({})",
+        device_info.vendor_id,
+        device_info.product_id,
+        device_info.product.as_deref().unwrap_or("unknown product")
+    );
+    // The serial tells apart identical phones even if they re-enumerate
+    // between detecting and connecting.
+    let transport = device_info
+        .serial
+        .as_deref()
+        .and_then(|serial| {
+            USBTransport::new_with_serial(device_info.vendor_id, device_info.product_id, serial)
+                .inspect_err(|error| log::debug!("reopening by serial failed: {error}"))
+                .ok()
+        })
+        .or_else(|| {
+            device_info
+                .device
+                .clone()
+                .map(USBTransport::new_from_device)
+        })
+        .ok_or(SyncError::DeviceNotFound)?;
+    let connected = ADBUSBDevice::new_from_transport_with_progress(transport, None, &mut |phase| {
+        log::debug!("connection phase: {phase}");
+        on_stage(phase.into())
+    });
+    let device = match connected.map_err(SyncError::from) {
+        Ok(mut device) => {
+            device.set_keepalive(Some(KEEPALIVE_INTERVAL));
+            ConnectedDevice::Usb(device)
+        }
+        // A running adb server keeps the interface claimed; go through it
+        // instead of asking the user to stop it.
+        Err(SyncError::UsbBusy) => {
+            match ConnectedDevice::through_server(device_info.serial.as_deref()) {
+                Ok(device) => {
+                    log::info!("USB interface is busy, using the host adb server");
+                    on_stage(ConnectionStage::Connected);
+                    device
+                }
+                Err(error) => {
+                    log::warn!(
+                        "USB interface is busy and the host adb server is unusable: {error}"
+                    );
+                    return Err(SyncError::UsbBusy);
+                }
+            }
+        }
+        Err(error) => return Err(error),
+    };
+    *LAST_CONNECTED
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner) = device_info.port();
+    Ok((device_info, device))
+}
+
+/// Falls back to a saved wireless device marked for auto-connect when no USB
+/// device is attached, so syncing carries on without the cable.
+fn connect_wireless(
+    on_stage: &mut dyn FnMut(ConnectionStage),
+) -> Result<(AndroidDeviceInfo, ConnectedDevice), SyncError> {
+    let selected = SELECTED_SERIAL
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone();
+    let (known, device) =
+        known_devices::connect_auto(selected.as_deref()).ok_or(SyncError::DeviceNotFound)?;
+    log::info!(
+        "no USB device attached, connected to {} over Wi-Fi",
+        known.address
+    );
+    on_stage(ConnectionStage::Connected);
+    Ok((
+        AndroidDeviceInfo::wireless(&known),
+        ConnectedDevice::Tcp(device),
+    ))
+}
+
+fn run_shell<D: ADBDeviceExt>(device: &mut D, command: &[&str]) -> Result<String, SyncError> {
+    let mut output = Vec::new();
+    device.shell_command(command, &mut output)?;
+    Ok(String::from_utf8_lossy(&output).into_owned())
+}
+
+/// Like [`run_shell`], but fails with [`SyncError::RemoteCommand`] when the
+/// command exits non-zero. Devices without shell v2 don't report an exit
+/// status, so there a failure goes unnoticed as before.
+fn run_shell_checked<D: ADBDeviceExt>(
+    device: &mut D,
+    command: &[&str],
+) -> Result<String, SyncError> {
+    let output = device.shell_command_output(command)?;
+    match output.exit_code {
+        Some(code) if code != 0 => Err(SyncError::RemoteCommand {
+            command: command.join(" "),
+            code,
+            stderr: output.stderr_lossy().trim().to_string(),
+        }),
+        _ => Ok(output.stdout_lossy()),
+    }
+}
+
+/// Quotes `value` for the device shell, which receives commands as one joined string.
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
 #[derive(Debug)]
 struct AndroidDeviceInfo {
     vendor_id: u16,
     product_id: u16,
     manufacturer: Option<String>,
     product: Option<String>,
+    serial: Option<String>,
+    /// Whether the device could be opened to read its strings.
+    opened: bool,
+    /// `None` for a saved wireless device, which has no USB identity.
+    device: Option<Device<GlobalContext>>,
 }
 
 impl AndroidDeviceInfo {
-    fn from_usb_device<T: UsbContext>(
-        device: Device<T>,
-        descriptor: rusb::DeviceDescriptor,
-    ) -> Self {
+    fn from_usb_device(device: Device<GlobalContext>, descriptor: rusb::DeviceDescriptor) -> Self {
         let vendor_id = descriptor.vendor_id();
         let product_id = descriptor.product_id();
 
-        let (manufacturer, product) = device
-            .open()
-            .ok()
+        let handle = device.open().ok();
+        let (manufacturer, product, serial) = handle
+            .as_ref()
             .map(|handle| {
                 let manufacturer = handle.read_manufacturer_string_ascii(&descriptor).ok();
                 let product = handle.read_product_string_ascii(&descriptor).ok();
-                (manufacturer, product)
+                let serial = handle.read_serial_number_string_ascii(&descriptor).ok();
+                (manufacturer, product, serial)
             })
-            .unwrap_or((None, None));
+            .unwrap_or_default();
 
         Self {
             vendor_id,
             product_id,
             manufacturer,
             product,
+            serial,
+            opened: handle.is_some(),
+            device: Some(device),
         }
     }
-}
 
-#[derive(Default)]
-struct SyncStats {
-    files_synced: usize,
-    files_deleted: usize,
-    skipped_entries: usize,
-    directories_created: usize,
-    bytes_uploaded: u64,
+    fn wireless(known: &KnownDevice) -> Self {
+        Self {
+            vendor_id: 0,
+            product_id: 0,
+            manufacturer: None,
+            product: known.model.clone(),
+            serial: known.serial.clone(),
+            opened: true,
+            device: None,
+        }
+    }
+
+    /// How the device is named when asking the user to pick one.
+    fn label(&self) -> String {
+        let name = self.product.as_deref().unwrap_or("unknown device");
+        match &self.serial {
+            Some(serial) => format!("{name} ({serial})"),
+            None => format!("{name} ({:04x}:{:04x})", self.vendor_id, self.product_id),
+        }
+    }
+
+    fn port(&self) -> Option<(u8, u8)> {
+        self.device
+            .as_ref()
+            .map(|device| (device.bus_number(), device.address()))
+    }
+
+    fn same_device(&self, other: &Self) -> bool {
+        match (&self.serial, &other.serial) {
+            (Some(serial), Some(other_serial)) => {
+                serial == other_serial
+                    && (self.vendor_id, self.product_id) == (other.vendor_id, other.product_id)
+            }
+            _ => self.port() == other.port(),
+        }
+    }
+
+    /// Preference among entries for one device: the instance this session
+    /// last connected to, then one that could be opened.
+    fn rank(&self, last_connected: Option<(u8, u8)>) -> u8 {
+        2 * u8::from(last_connected.is_some() && last_connected == self.port())
+            + u8::from(self.opened)
+    }
 }
 
 #[derive(Debug)]
 enum SyncError {
     InvalidLocalPath(String),
     InvalidRemotePath(String),
+    EmptyLocalRoot(String),
+    InvalidProfile(String),
+    ProfileNotFound(String),
+    Config(String),
+    JobNotFound(u64),
+    JobRunning(u64),
+    /// A remote delete was requested with an unknown or expired confirmation.
+    DeletionNotConfirmed,
+    Cancelled,
+    /// The device battery fell below the run's `min_battery`.
+    LowBattery {
+        level: u8,
+        threshold: u8,
+    },
+    /// The device filesystem of a new remote root can't hold what the run would push.
+    InsufficientSpace {
+        mount_point: String,
+        needed: u64,
+        free: u64,
+    },
+    NameCollision {
+        first: String,
+        second: String,
+        remote: String,
+    },
     DeviceNotFound,
-    MultipleDevices(Vec<(u16, u16)>),
+    /// The device's shell lacks a command or flag the run depends on.
+    DeviceUnsupported(String),
+    /// A shell command on the device exited with a non-zero status.
+    RemoteCommand {
+        command: String,
+        code: u8,
+        stderr: String,
+    },
+    /// Several devices are attached and none was selected; holds their labels.
+    MultipleDevices(Vec<String>),
+    /// The device went away mid-run (unplugged, rebooted, or USB mode changed).
+    UsbDisconnected,
+    /// Another program, usually an `adb` server, has claimed the interface.
+    UsbBusy,
+    /// The OS refused access to the device node.
+    UsbAccessDenied,
+    UsbTimeout,
+    /// The device stalled an endpoint; the connection is unusable.
+    UsbPipe,
     Usb(rusb::Error),
     Adb(RustADBError),
     Io(io::Error),
@@ -623,17 +1958,85 @@ impl std::fmt::Display for SyncError {
         match self {
             SyncError::InvalidLocalPath(msg) => write!(f, "{msg}"),
             SyncError::InvalidRemotePath(msg) => write!(f, "{msg}"),
+            SyncError::EmptyLocalRoot(path) => write!(
+                f,
+                "Local path '{path}' has no files to sync. Enable \"allow empty source\" if this is intended."
+            ),
+            SyncError::InvalidProfile(msg) => write!(f, "Invalid profile: {msg}"),
+            SyncError::ProfileNotFound(id) => write!(f, "Sync profile '{id}' does not exist"),
+            SyncError::Config(msg) => write!(f, "Configuration error: {msg}"),
+            SyncError::JobNotFound(id) => write!(f, "Sync job {id} is not in the queue"),
+            SyncError::JobRunning(id) => write!(f, "Sync job {id} is already running"),
+            SyncError::DeletionNotConfirmed => write!(
+                f,
+                "This deletion was not confirmed or its confirmation expired. Review it again before deleting."
+            ),
+            SyncError::Cancelled => write!(f, "Sync cancelled"),
+            SyncError::LowBattery { level, threshold } => write!(
+                f,
+                "Sync stopped: the device battery is at {level}%, below the {threshold}% minimum. Charge the device and try again."
+            ),
+            SyncError::InsufficientSpace {
+                mount_point,
+                needed,
+                free,
+            } => write!(
+                f,
+                "Not enough space on the device: {needed} bytes to copy, but {mount_point} has only {free} bytes free."
+            ),
+            SyncError::NameCollision {
+                first,
+                second,
+                remote,
+            } => write!(
+                f,
+                "'{first}' and '{second}' both map to remote path '{remote}'"
+            ),
             SyncError::DeviceNotFound => write!(
                 f,
                 "No Android device detected over USB. Ensure USB debugging is enabled."
             ),
+            SyncError::DeviceUnsupported(feature) => write!(
+                f,
+                "The device does not support {feature}, which this sync needs."
+            ),
+            SyncError::RemoteCommand {
+                command,
+                code,
+                stderr,
+            } if stderr.is_empty() => write!(f, "`{command}` failed on the device (exit {code})"),
+            SyncError::RemoteCommand {
+                command,
+                code,
+                stderr,
+            } => write!(f, "`{command}` failed on the device (exit {code}): {stderr}"),
             SyncError::MultipleDevices(devs) => {
                 write!(
                     f,
-                    "Multiple Android devices detected ({:?}). Connect only one device.",
-                    devs
+                    "Multiple Android devices detected ({}). Select the one to sync with, or connect only one device.",
+                    devs.join(", ")
                 )
             }
+            SyncError::UsbDisconnected => write!(
+                f,
+                "The device was disconnected. Reconnect the cable and unlock the phone, then try again."
+            ),
+            SyncError::UsbBusy => write!(
+                f,
+                "The device is in use by another program. Stop any running adb server (`adb kill-server`) or close other Android tools, then try again."
+            ),
+            SyncError::UsbAccessDenied => write!(
+                f,
+                "Permission to open the USB device was denied. On Linux, add a udev rule for the device; on Windows, install the WinUSB driver."
+            ),
+            SyncError::UsbTimeout => write!(
+                f,
+                "The device stopped responding over USB. Check the cable, keep the screen unlocked, and try again."
+            ),
+            SyncError::UsbPipe => write!(
+                f,
+                "The USB connection stalled. Unplug and reconnect the device, then try again."
+            ),
             SyncError::Usb(err) => write!(f, "USB error: {err}"),
             SyncError::Adb(err) => write!(f, "ADB error: {err}"),
             SyncError::Io(err) => write!(f, "File system error: {err}"),
@@ -645,13 +2048,24 @@ impl std::error::Error for SyncError {}
 
 impl From<rusb::Error> for SyncError {
     fn from(value: rusb::Error) -> Self {
-        SyncError::Usb(value)
+        match value {
+            rusb::Error::NoDevice | rusb::Error::NotFound => SyncError::UsbDisconnected,
+            rusb::Error::Busy => SyncError::UsbBusy,
+            rusb::Error::Access => SyncError::UsbAccessDenied,
+            rusb::Error::Timeout => SyncError::UsbTimeout,
+            rusb::Error::Pipe => SyncError::UsbPipe,
+            other => SyncError::Usb(other),
+        }
     }
 }
 
 impl From<RustADBError> for SyncError {
     fn from(value: RustADBError) -> Self {
-        SyncError::Adb(value)
+        match value {
+            RustADBError::UsbError(error) => error.into(),
+            RustADBError::USBDeviceNotFound(..) => SyncError::UsbDisconnected,
+            other => SyncError::Adb(other),
+        }
     }
 }
 
@@ -0,0 +1,60 @@
+use adb_client::ADBDeviceExt;
+use serde::Serialize;
+use std::ops::ControlFlow;
+use tauri::{Emitter, State, Window};
+
+use crate::{connect_device, ActiveSync, CancelToken, SyncError};
+
+const LOGCAT_LINE_EVENT: &str = "device-logcat-line";
+
+#[derive(Debug, Clone, Serialize)]
+struct LogcatLinePayload<'a> {
+    line: &'a str,
+}
+
+/// How much of the device log [`stream_logcat`] forwarded before it stopped.
+#[derive(Debug, Default, Serialize)]
+pub struct LogcatSummary {
+    lines: usize,
+}
+
+fn stream(
+    window: &Window,
+    filter_specs: &[String],
+    cancel: &CancelToken,
+) -> Result<LogcatSummary, SyncError> {
+    let (_, mut device) = connect_device(&mut |_| {})?;
+    let filter_specs: Vec<&str> = filter_specs
+        .iter()
+        .map(|spec| spec.trim())
+        .filter(|spec| !spec.is_empty())
+        .collect();
+
+    let mut summary = LogcatSummary::default();
+    device.logcat(&filter_specs, &mut |line| {
+        if cancel.check().is_err() {
+            return ControlFlow::Break(());
+        }
+        let _ = window.emit(LOGCAT_LINE_EVENT, LogcatLinePayload { line });
+        summary.lines += 1;
+        ControlFlow::Continue(())
+    })?;
+    cancel.check()?;
+    Ok(summary)
+}
+
+/// Follows the device log, forwarding each entry to the frontend as a
+/// `device-logcat-line` event, until cancelled with `cancel_sync`.
+/// `filter_specs` are logcat filters such as `MediaProvider:V` and `*:S`.
+#[tauri::command]
+pub async fn stream_logcat(
+    window: Window,
+    active: State<'_, ActiveSync>,
+    filter_specs: Vec<String>,
+) -> Result<LogcatSummary, String> {
+    let cancel = active.begin();
+    tauri::async_runtime::spawn_blocking(move || stream(&window, &filter_specs, &cancel))
+        .await
+        .map_err(|e| format!("logcat task failed: {e}"))?
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,46 @@
+use log::LevelFilter;
+use tauri::plugin::TauriPlugin;
+use tauri::{Manager, Runtime, Window};
+use tauri_plugin_log::{RotationStrategy, Target, TargetKind};
+
+use crate::SyncError;
+
+const LOG_FILE_NAME: &str = "android-sync";
+/// Size at which the log file is rotated.
+const LOG_MAX_BYTES: u128 = 5 * 1024 * 1024;
+/// Rotated files kept alongside the active one.
+const LOG_KEEP_FILES: usize = 4;
+
+/// Logs to stdout and to a rotating file in the app log directory. The ADB
+/// layer logs protocol detail at debug level, which is kept for diagnosing
+/// failed syncs.
+pub(crate) fn plugin<R: Runtime>() -> TauriPlugin<R> {
+    tauri_plugin_log::Builder::new()
+        .targets([
+            Target::new(TargetKind::Stdout),
+            Target::new(TargetKind::LogDir {
+                file_name: Some(LOG_FILE_NAME.into()),
+            }),
+        ])
+        .max_file_size(LOG_MAX_BYTES)
+        .rotation_strategy(RotationStrategy::KeepSome(LOG_KEEP_FILES))
+        .level(LevelFilter::Info)
+        .level_for("adb_client", LevelFilter::Debug)
+        .build()
+}
+
+fn log_path(window: &Window) -> Result<String, SyncError> {
+    let dir = window
+        .path()
+        .app_log_dir()
+        .map_err(|e| SyncError::Config(e.to_string()))?;
+    Ok(dir
+        .join(format!("{LOG_FILE_NAME}.log"))
+        .display()
+        .to_string())
+}
+
+#[tauri::command]
+pub fn get_log_path(window: Window) -> Result<String, String> {
+    log_path(&window).map_err(|e| e.to_string())
+}
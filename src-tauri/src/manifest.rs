@@ -0,0 +1,87 @@
+use adb_client::{ADBDeviceExt, RemoteEntry};
+use std::collections::BTreeMap;
+use std::ops::ControlFlow;
+
+use crate::backend::ConnectedDevice;
+use crate::mirror::is_in_trash;
+use crate::{shell_quote, CancelToken, SyncError};
+
+/// Every regular file below a remote root with its size, taken with a single
+/// shell command before anything is pushed.
+///
+/// Looking files up here replaces one STAT round-trip per local file, which is
+/// most of the protocol chatter when syncing many small files. `find -H`
+/// resolves the root itself if it is a link (as `/sdcard` is) but never
+/// descends through links below it. The listing is parsed line by line as it
+/// streams in, so a cancel stops it mid-way.
+pub(crate) struct RemoteManifest {
+    prefix: String,
+    files: BTreeMap<String, RemoteEntry>,
+}
+
+impl RemoteManifest {
+    pub(crate) fn scan(
+        device: &mut ConnectedDevice,
+        remote_root: &str,
+        cancel: &CancelToken,
+    ) -> Result<Self, SyncError> {
+        cancel.check()?;
+        let prefix = format!("{}/", remote_root.trim_end_matches('/'));
+        let mut files = BTreeMap::new();
+        device.shell_command_lines(
+            &[
+                "find",
+                "-H",
+                &shell_quote(remote_root),
+                "-type",
+                "f",
+                "-exec",
+                "stat",
+                "-c",
+                "'%f %s %Y %n'",
+                "{}",
+                "+",
+                "2>/dev/null",
+            ],
+            &mut |line| {
+                if cancel.check().is_err() {
+                    return ControlFlow::Break(());
+                }
+                if let Some(entry) = parse_listing_line(line.trim_end()) {
+                    if entry.path.starts_with(&prefix) && !is_in_trash(&entry.path) {
+                        files.insert(entry.path.clone(), entry);
+                    }
+                }
+                ControlFlow::Continue(())
+            },
+        )?;
+        cancel.check()?;
+
+        Ok(Self { prefix, files })
+    }
+
+    /// `remote_path` as listed, `Some(None)` if it is under the root but
+    /// absent, or `None` when it lies outside the root (e.g. a routed
+    /// destination) and has to be stat'ed.
+    pub(crate) fn get(&self, remote_path: &str) -> Option<Option<RemoteEntry>> {
+        remote_path
+            .starts_with(&self.prefix)
+            .then(|| self.files.get(remote_path).cloned())
+    }
+
+    /// `(remote path, path relative to the root, size)` for every listed file.
+    pub(crate) fn files(&self) -> impl Iterator<Item = (&str, &str, u64)> {
+        self.files
+            .iter()
+            .map(|(path, file)| (path.as_str(), &path[self.prefix.len()..], file.size))
+    }
+}
+
+/// Parses a `%f %s %Y %n` line: hex mode, size, mtime and path.
+fn parse_listing_line(line: &str) -> Option<RemoteEntry> {
+    let mut fields = line.splitn(4, ' ');
+    let mode = u32::from_str_radix(fields.next()?, 16).ok()?;
+    let size = fields.next()?.parse().ok()?;
+    let mtime = fields.next()?.parse().ok()?;
+    Some(RemoteEntry::new(fields.next()?, mode, size, mtime))
+}
@@ -0,0 +1,165 @@
+use adb_client::ADBDeviceExt;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::backend::ConnectedDevice;
+use crate::clock::unix_seconds;
+use crate::manifest::RemoteManifest;
+use crate::moves::MoveDetector;
+use crate::naming::RemoteNameRegistry;
+use crate::rules::HiddenFiles;
+use crate::stats::SyncStats;
+use crate::{run_shell, run_shell_checked, shell_quote, SyncError, SyncOptions};
+
+/// Device folder that receives mirror-mode deletions in [`DeletionMode::Trash`].
+pub(crate) const TRASH_ROOT: &str = "/sdcard/.adbsync-trash";
+
+/// How mirror mode disposes of remote files that no longer exist locally.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DeletionMode {
+    /// Move them under [`TRASH_ROOT`] so they can be recovered on the device.
+    #[default]
+    Trash,
+    /// Remove them with `rm`.
+    Permanent,
+}
+
+/// Whether `remote_path` lies in [`TRASH_ROOT`], which mirror mode must neither
+/// list nor prune, or a run syncing to `/sdcard` would trash the last run's trash.
+pub(crate) fn is_in_trash(remote_path: &str) -> bool {
+    remote_path
+        .strip_prefix(TRASH_ROOT)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// A symbolic link found under the remote root. Links are never followed or
+/// deleted by mirror mode; they are only reported.
+#[derive(Debug, Clone, Serialize)]
+pub struct RemoteSymlink {
+    path: String,
+    target: Option<String>,
+}
+
+/// Removes files below `remote_root` that have no local counterpart.
+///
+/// Works from the pre-sync [`RemoteManifest`]; every file pushed during the run
+/// is claimed in `names`, so the listing doesn't need refreshing. The manifest
+/// never descends through links, so nothing reachable only through a symlink
+/// is pruned.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn prune_remote(
+    device: &mut ConnectedDevice,
+    local_root: &Path,
+    remote_root: &str,
+    manifest: &RemoteManifest,
+    names: &RemoteNameRegistry,
+    moves: Option<&MoveDetector>,
+    options: &SyncOptions,
+    stats: &SyncStats,
+) -> Result<(), SyncError> {
+    let trash_dir = format!("{TRASH_ROOT}/{}", unix_seconds());
+
+    for (remote_file, relative, _) in manifest.files() {
+        options.cancel.check()?;
+        if names.is_claimed(remote_file)
+            || moves.is_some_and(|moves| moves.was_moved(remote_file))
+            || is_protected(local_root, relative, options)
+            || is_in_trash(remote_file)
+        {
+            continue;
+        }
+
+        if !options.dry_run {
+            match options.deletion {
+                DeletionMode::Trash => {
+                    let target = format!("{trash_dir}/{relative}");
+                    let target_parent = target
+                        .rsplit_once('/')
+                        .map(|(parent, _)| parent)
+                        .unwrap_or(trash_dir.as_str());
+                    run_shell_checked(device, &["mkdir", "-p", &shell_quote(target_parent)])?;
+                    device.mv(remote_file, &target)?;
+                }
+                DeletionMode::Permanent => device.rm(remote_file)?,
+            }
+        }
+
+        log::info!("mirror: removed {remote_file} ({:?})", options.deletion);
+        stats.record_deleted(remote_file.to_string());
+    }
+
+    for link in list_remote_symlinks(device, remote_root)? {
+        stats.record_remote_symlink(link);
+    }
+
+    if stats.files_deleted() > 0 && options.deletion == DeletionMode::Trash {
+        stats.set_trash_path(trash_dir);
+    }
+
+    Ok(())
+}
+
+fn list_remote_symlinks(
+    device: &mut ConnectedDevice,
+    remote_root: &str,
+) -> Result<Vec<RemoteSymlink>, SyncError> {
+    let listing = run_shell(
+        device,
+        &[
+            "find",
+            "-H",
+            &shell_quote(remote_root),
+            "-type",
+            "l",
+            "2>/dev/null",
+            "|",
+            "while",
+            "read",
+            "-r",
+            "link;",
+            "do",
+            "echo",
+            "\"$link\t$(readlink \"$link\")\";",
+            "done",
+        ],
+    )?;
+
+    Ok(listing
+        .lines()
+        .filter_map(|line| {
+            let (path, target) = line.split_once('\t').unwrap_or((line, ""));
+            let path = path.trim_end();
+            (!path.is_empty()).then(|| RemoteSymlink {
+                path: path.to_string(),
+                target: Some(target.trim_end())
+                    .filter(|target| !target.is_empty())
+                    .map(str::to_string),
+            })
+        })
+        .collect())
+}
+
+/// Remote files that the local side deliberately leaves alone are never pruned:
+/// hidden entries unless hidden files are synced, excluded paths, anything past
+/// `max_depth`, and paths that still exist locally but were filtered out of
+/// this run.
+fn is_protected(local_root: &Path, relative: &str, options: &SyncOptions) -> bool {
+    if options.hidden == HiddenFiles::Skip
+        && relative.split('/').any(|segment| segment.starts_with('.'))
+    {
+        return true;
+    }
+
+    let relative_path = Path::new(relative);
+    if options.is_excluded(relative_path) {
+        return true;
+    }
+    if let Some(parent) = relative_path.parent() {
+        if !parent.as_os_str().is_empty() && !options.descends_into(parent) {
+            return true;
+        }
+    }
+
+    local_root.join(relative_path).symlink_metadata().is_ok()
+}
@@ -0,0 +1,114 @@
+use adb_client::ADBDeviceExt;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use crate::backend::ConnectedDevice;
+use crate::hashes::{remote_sha256, LocalHashCache};
+use crate::manifest::RemoteManifest;
+use crate::naming::RemoteNameRegistry;
+use crate::stats::SyncStats;
+use crate::SyncError;
+
+/// A remote file that was moved into place instead of being uploaded again.
+#[derive(Debug, Clone, Serialize)]
+pub struct MovedFile {
+    from: String,
+    to: String,
+}
+
+/// Remote files under the remote root with no local counterpart, grouped by
+/// size, that a newly appearing local file may have been moved or renamed from.
+pub(crate) struct MoveDetector {
+    candidates: HashMap<u64, Vec<String>>,
+    remote_hashes: HashMap<String, Option<String>>,
+    moved_from: HashSet<String>,
+}
+
+impl MoveDetector {
+    /// Collects candidates from the pre-sync listing of the remote root.
+    pub(crate) fn new(manifest: &RemoteManifest, local_root: &Path) -> Self {
+        let mut candidates: HashMap<u64, Vec<String>> = HashMap::new();
+        for (remote_file, relative, size) in manifest.files() {
+            if relative.split('/').any(|segment| segment.starts_with('.'))
+                || local_root.join(relative).symlink_metadata().is_ok()
+            {
+                continue;
+            }
+            candidates
+                .entry(size)
+                .or_default()
+                .push(remote_file.to_string());
+        }
+
+        Self {
+            candidates,
+            remote_hashes: HashMap::new(),
+            moved_from: HashSet::new(),
+        }
+    }
+
+    /// Moves an orphaned remote file with the same size and SHA-256 as
+    /// `local_path` to `remote_path`. Returns `false` when there is no match and
+    /// the file has to be uploaded.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn try_move(
+        &mut self,
+        device: &mut ConnectedDevice,
+        local_path: &Path,
+        remote_path: &str,
+        size: u64,
+        names: &RemoteNameRegistry,
+        local_hashes: &mut LocalHashCache,
+        stats: &SyncStats,
+        dry_run: bool,
+    ) -> Result<bool, SyncError> {
+        let Some(candidates) = self.candidates.get(&size) else {
+            return Ok(false);
+        };
+        let candidates: Vec<String> = candidates
+            .iter()
+            .filter(|candidate| !names.is_claimed(candidate))
+            .cloned()
+            .collect();
+        if candidates.is_empty() {
+            return Ok(false);
+        }
+
+        let local_hash = local_hashes.hash(local_path)?;
+        for candidate in candidates {
+            if self.remote_hash(device, &candidate).as_deref() != Some(local_hash.as_str()) {
+                continue;
+            }
+
+            // Anything already at `remote_path` is replaced by the same content.
+            if !dry_run {
+                device.mv(&candidate, remote_path)?;
+            }
+            if let Some(group) = self.candidates.get_mut(&size) {
+                group.retain(|remaining| *remaining != candidate);
+            }
+            log::info!("moved {candidate} -> {remote_path} instead of uploading");
+            self.moved_from.insert(candidate.clone());
+            stats.record_moved(MovedFile {
+                from: candidate,
+                to: remote_path.to_string(),
+            });
+            return Ok(true);
+        }
+
+        Ok(false)
+    }
+
+    /// Whether `remote_file` was (or, in a dry run, would have been) moved away.
+    pub(crate) fn was_moved(&self, remote_file: &str) -> bool {
+        self.moved_from.contains(remote_file)
+    }
+
+    fn remote_hash(&mut self, device: &mut ConnectedDevice, remote_file: &str) -> Option<String> {
+        self.remote_hashes
+            .entry(remote_file.to_string())
+            .or_insert_with(|| remote_sha256(device, remote_file))
+            .clone()
+    }
+}
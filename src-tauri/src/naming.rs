@@ -0,0 +1,175 @@
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::{Component, Path};
+use unicode_normalization::UnicodeNormalization;
+
+use crate::SyncError;
+
+/// Characters rejected by the FAT-style filesystems backing most shared storage.
+const RESERVED_CHARACTERS: &[char] = &['\\', ':', '*', '?', '"', '<', '>', '|'];
+
+/// What to do when two local files end up with the same remote name.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NameCollisionPolicy {
+    /// Keep both by appending a short hash of the local path to the later file.
+    #[default]
+    SuffixHash,
+    /// Keep the first file and leave the later one out of the run.
+    SkipSecond,
+    /// Abort the run.
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MappingReason {
+    Sanitized,
+    CollisionSuffixed,
+    CollisionSkipped,
+}
+
+/// A local file whose remote name differs from its local one.
+#[derive(Debug, Clone, Serialize)]
+pub struct RemoteNameMapping {
+    local_path: String,
+    remote_path: String,
+    reason: MappingReason,
+}
+
+/// Makes a single path component safe to create on the device.
+pub(crate) fn sanitize_component(name: &str) -> String {
+    let normalized: String = name
+        .nfc()
+        .map(|c| {
+            if c.is_control() || RESERVED_CHARACTERS.contains(&c) {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect();
+
+    let trimmed = normalized.trim_end_matches(['.', ' ']);
+    if trimmed.is_empty() {
+        "_".to_string()
+    } else {
+        trimmed.to_string()
+    }
+}
+
+/// Whether any component of `relative` changes when sanitized.
+pub(crate) fn needs_sanitizing(relative: &Path) -> bool {
+    relative.components().any(|component| match component {
+        Component::Normal(part) => {
+            let text = part.to_string_lossy();
+            sanitize_component(&text) != text
+        }
+        _ => false,
+    })
+}
+
+/// Tracks which remote file names have been claimed during a run.
+pub(crate) struct RemoteNameRegistry {
+    policy: NameCollisionPolicy,
+    claimed: HashMap<String, String>,
+    mappings: Vec<RemoteNameMapping>,
+}
+
+impl RemoteNameRegistry {
+    pub(crate) fn new(policy: NameCollisionPolicy) -> Self {
+        Self {
+            policy,
+            claimed: HashMap::new(),
+            mappings: Vec::new(),
+        }
+    }
+
+    /// Reserves `remote_path` for `local_path`, returning the remote path to use or
+    /// `None` when the file should be skipped. A file claiming again, as when a run
+    /// resumes after the device dropped, gets back the name it was given before.
+    pub(crate) fn claim(
+        &mut self,
+        local_path: &str,
+        remote_path: String,
+        sanitized: bool,
+    ) -> Result<Option<String>, SyncError> {
+        let Some(owner) = self.claimed.get(&remote_path) else {
+            if sanitized {
+                self.record(local_path, &remote_path, MappingReason::Sanitized);
+            }
+            self.claimed
+                .insert(remote_path.clone(), local_path.to_string());
+            return Ok(Some(remote_path));
+        };
+        if owner == local_path {
+            return Ok(Some(remote_path));
+        }
+
+        match self.policy {
+            NameCollisionPolicy::Error => Err(SyncError::NameCollision {
+                first: owner.clone(),
+                second: local_path.to_string(),
+                remote: remote_path,
+            }),
+            NameCollisionPolicy::SkipSecond => {
+                self.record(local_path, &remote_path, MappingReason::CollisionSkipped);
+                Ok(None)
+            }
+            NameCollisionPolicy::SuffixHash => {
+                let suffixed = with_hash_suffix(&remote_path, local_path);
+                match self.claimed.get(&suffixed) {
+                    Some(suffixed_owner) if suffixed_owner == local_path => {
+                        return Ok(Some(suffixed));
+                    }
+                    Some(_) => {
+                        return Err(SyncError::NameCollision {
+                            first: owner.clone(),
+                            second: local_path.to_string(),
+                            remote: suffixed,
+                        });
+                    }
+                    None => {}
+                }
+                self.record(local_path, &suffixed, MappingReason::CollisionSuffixed);
+                self.claimed
+                    .insert(suffixed.clone(), local_path.to_string());
+                Ok(Some(suffixed))
+            }
+        }
+    }
+
+    pub(crate) fn is_claimed(&self, remote_path: &str) -> bool {
+        self.claimed.contains_key(remote_path)
+    }
+
+    pub(crate) fn into_mappings(self) -> Vec<RemoteNameMapping> {
+        self.mappings
+    }
+
+    fn record(&mut self, local_path: &str, remote_path: &str, reason: MappingReason) {
+        self.mappings.push(RemoteNameMapping {
+            local_path: local_path.to_string(),
+            remote_path: remote_path.to_string(),
+            reason,
+        });
+    }
+}
+
+fn with_hash_suffix(remote_path: &str, local_path: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    local_path.hash(&mut hasher);
+    let tag = format!("{:08x}", hasher.finish() as u32);
+
+    let (dir, name) = match remote_path.rfind('/') {
+        Some(index) => remote_path.split_at(index + 1),
+        None => ("", remote_path),
+    };
+
+    match name.rfind('.') {
+        Some(dot) if dot > 0 => format!("{dir}{}~{tag}{}", &name[..dot], &name[dot..]),
+        _ => format!("{dir}{name}~{tag}"),
+    }
+}
@@ -0,0 +1,95 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::profiles::SyncProfile;
+use crate::{normalize_remote_path, FolderPair, SyncError};
+
+/// The local root as it will be synced, so `a/../b` and symlinked paths
+/// compare equal to their targets. Paths that don't exist are compared as
+/// given; the run rejects them later anyway.
+fn local_root(path: &str) -> PathBuf {
+    let path = PathBuf::from(path.trim());
+    fs::canonicalize(&path).unwrap_or(path)
+}
+
+/// Whether either local root contains the other, by whole components.
+fn locals_overlap(a: &Path, b: &Path) -> bool {
+    a.starts_with(b) || b.starts_with(a)
+}
+
+/// Whether either normalized remote root contains the other.
+fn remotes_overlap(a: &str, b: &str) -> bool {
+    let within = |inner: &str, outer: &str| {
+        inner == outer
+            || outer == "/"
+            || inner
+                .strip_prefix(outer)
+                .is_some_and(|rest| rest.starts_with('/'))
+    };
+    within(a, b) || within(b, a)
+}
+
+/// Rejects pairs in one run that would fight over the same files: a local
+/// root inside another would be uploaded twice, and in mirror mode a remote
+/// root inside another would have its files pruned by the outer pair.
+pub(crate) fn check_pairs(pairs: &[FolderPair], mirror: bool) -> Result<(), SyncError> {
+    let roots = pairs
+        .iter()
+        .map(|pair| {
+            Ok((
+                local_root(&pair.local),
+                normalize_remote_path(&pair.remote)?,
+            ))
+        })
+        .collect::<Result<Vec<_>, SyncError>>()?;
+
+    for (index, (local, remote)) in roots.iter().enumerate() {
+        for (other_local, other_remote) in &roots[index + 1..] {
+            if locals_overlap(local, other_local) {
+                return Err(SyncError::InvalidLocalPath(format!(
+                    "Local paths '{}' and '{}' overlap; sync the outer one only",
+                    local.display(),
+                    other_local.display()
+                )));
+            }
+            if mirror && remotes_overlap(remote, other_remote) {
+                return Err(SyncError::InvalidRemotePath(format!(
+                    "Device paths '{remote}' and '{other_remote}' overlap, so mirror mode would delete one pair's files while syncing the other"
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Checks a profile being saved against the other saved profiles. Nested
+/// device paths are refused when either profile mirrors, since running one
+/// would prune the other's files; nested local paths are allowed but logged.
+pub(crate) fn check_profile(
+    profile: &SyncProfile,
+    others: &[SyncProfile],
+) -> Result<(), SyncError> {
+    let local = local_root(&profile.local_path);
+    let remote = normalize_remote_path(&profile.device_path)
+        .map_err(|error| SyncError::InvalidProfile(error.to_string()))?;
+
+    for other in others.iter().filter(|other| other.id != profile.id) {
+        let Ok(other_remote) = normalize_remote_path(&other.device_path) else {
+            continue;
+        };
+        if (profile.mirror || other.mirror) && remotes_overlap(&remote, &other_remote) {
+            return Err(SyncError::InvalidProfile(format!(
+                "Device path '{remote}' overlaps '{other_remote}' of profile '{}', and mirror mode would delete one profile's files when the other runs",
+                other.name
+            )));
+        }
+        if locals_overlap(&local, &local_root(&other.local_path)) {
+            log::warn!(
+                "profile '{}' local path overlaps profile '{}'",
+                profile.name,
+                other.name
+            );
+        }
+    }
+    Ok(())
+}
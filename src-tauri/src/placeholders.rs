@@ -0,0 +1,155 @@
+use std::process::Command;
+
+use crate::clock::unix_seconds;
+use crate::conflicts::utc_date;
+use crate::naming::sanitize_component;
+use crate::{FolderPair, SyncError};
+
+/// Values substituted into `{name}` placeholders in device paths, resolved
+/// once per run so every pair sees the same date.
+pub(crate) struct PathPlaceholders {
+    date: String,
+    hostname: String,
+    profile: Option<String>,
+}
+
+impl PathPlaceholders {
+    pub(crate) fn for_run(profile: Option<&str>) -> Self {
+        Self {
+            date: utc_date(unix_seconds()),
+            hostname: path_safe(&hostname()),
+            profile: profile.map(path_safe),
+        }
+    }
+
+    /// Expands `{date}` (UTC, `YYYY-MM-DD`), `{hostname}` and `{profile}` in
+    /// `path`. `{{` and `}}` stand for literal braces.
+    pub(crate) fn expand(&self, path: &str) -> Result<String, SyncError> {
+        let mut expanded = String::with_capacity(path.len());
+        let mut rest = path;
+        while let Some(index) = rest.find(['{', '}']) {
+            expanded.push_str(&rest[..index]);
+            let tail = &rest[index..];
+            if let Some(after) = tail.strip_prefix("{{") {
+                expanded.push('{');
+                rest = after;
+                continue;
+            }
+            if let Some(after) = tail.strip_prefix("}}") {
+                expanded.push('}');
+                rest = after;
+                continue;
+            }
+            let Some(end) = tail.find('}').filter(|_| tail.starts_with('{')) else {
+                return Err(SyncError::InvalidRemotePath(format!(
+                    "Unmatched brace in device path '{path}'"
+                )));
+            };
+            let value = match &tail[1..end] {
+                "date" => self.date.as_str(),
+                "hostname" => self.hostname.as_str(),
+                "profile" => self.profile.as_deref().ok_or_else(|| {
+                    SyncError::InvalidRemotePath(format!(
+                        "Device path '{path}' uses {{profile}} but no profile is selected"
+                    ))
+                })?,
+                other => {
+                    return Err(SyncError::InvalidRemotePath(format!(
+                        "Unknown placeholder {{{other}}} in device path '{path}'"
+                    )))
+                }
+            };
+            expanded.push_str(value);
+            rest = &tail[end + 1..];
+        }
+        expanded.push_str(rest);
+        Ok(expanded)
+    }
+
+    pub(crate) fn expand_pairs(&self, pairs: &[FolderPair]) -> Result<Vec<FolderPair>, SyncError> {
+        pairs
+            .iter()
+            .map(|pair| {
+                Ok(FolderPair {
+                    local: pair.local.clone(),
+                    remote: self.expand(&pair.remote)?,
+                })
+            })
+            .collect()
+    }
+}
+
+/// A placeholder value can't add path levels or characters the device rejects.
+fn path_safe(value: &str) -> String {
+    sanitize_component(&value.trim().replace('/', "_"))
+}
+
+/// This computer's name, from the environment where shells export it and
+/// otherwise from the `hostname` command, which every desktop OS ships.
+fn hostname() -> String {
+    ["COMPUTERNAME", "HOSTNAME"]
+        .iter()
+        .find_map(|name| std::env::var(name).ok())
+        .or_else(|| {
+            Command::new("hostname")
+                .output()
+                .ok()
+                .filter(|output| output.status.success())
+                .map(|output| String::from_utf8_lossy(&output.stdout).into_owned())
+        })
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "unknown-host".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn placeholders(profile: Option<&str>) -> PathPlaceholders {
+        PathPlaceholders {
+            date: "2024-05-06".to_string(),
+            hostname: "desk".to_string(),
+            profile: profile.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn expand_placeholders() {
+        let values = placeholders(Some("Photos"));
+        assert_eq!(
+            values
+                .expand("/sdcard/{hostname}/{profile}/{date}")
+                .unwrap(),
+            "/sdcard/desk/Photos/2024-05-06"
+        );
+        assert_eq!(values.expand("/sdcard/plain").unwrap(), "/sdcard/plain");
+        assert_eq!(
+            values.expand("/sdcard/{{date}}-{date}}}").unwrap(),
+            "/sdcard/{date}-2024-05-06}"
+        );
+    }
+
+    #[test]
+    fn expand_rejects_bad_placeholders() {
+        let values = placeholders(None);
+        for path in [
+            "/sdcard/{date",
+            "/sdcard/date}",
+            "/sdcard/}date{",
+            "/sdcard/{user}",
+            "/sdcard/{profile}",
+        ] {
+            assert!(
+                matches!(values.expand(path), Err(SyncError::InvalidRemotePath(_))),
+                "{path}"
+            );
+        }
+        assert_eq!(
+            placeholders(Some("Photos"))
+                .expand("/sdcard/{profile}")
+                .unwrap(),
+            "/sdcard/Photos"
+        );
+    }
+}
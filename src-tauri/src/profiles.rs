@@ -0,0 +1,116 @@
+use serde::{Deserialize, Serialize};
+use tauri::Window;
+
+use crate::clock::unix_nanos;
+use crate::config_store::ConfigFile;
+use crate::overlap::check_profile;
+use crate::routing::{deserialize_routes, validate_routes, ExtensionRoute};
+use crate::rules::SkipRules;
+use crate::SyncError;
+
+const PROFILES_FILE: &str = "profiles.json";
+
+/// A saved sync job, persisted in the app config directory.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncProfile {
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    pub local_path: String,
+    pub device_path: String,
+    /// Either a list of routes or a map such as `{"jpg|png": "/sdcard/Pictures"}`.
+    #[serde(default, deserialize_with = "deserialize_routes")]
+    pub routes: Vec<ExtensionRoute>,
+    #[serde(default)]
+    pub rules: SkipRules,
+    /// Run the profile in mirror mode, pruning device files that are gone locally.
+    #[serde(default)]
+    pub mirror: bool,
+}
+
+pub(crate) struct ProfileStore {
+    file: ConfigFile<Vec<SyncProfile>>,
+}
+
+impl ProfileStore {
+    pub(crate) fn open(window: &Window) -> Result<Self, SyncError> {
+        Ok(Self {
+            file: ConfigFile::open(window, PROFILES_FILE)?,
+        })
+    }
+
+    pub(crate) fn load(&self) -> Result<Vec<SyncProfile>, SyncError> {
+        self.file.load()
+    }
+
+    pub(crate) fn get(&self, id: &str) -> Result<SyncProfile, SyncError> {
+        self.load()?
+            .into_iter()
+            .find(|profile| profile.id == id)
+            .ok_or_else(|| SyncError::ProfileNotFound(id.to_string()))
+    }
+
+    pub(crate) fn save(&self, mut profile: SyncProfile) -> Result<SyncProfile, SyncError> {
+        if profile.name.trim().is_empty() {
+            return Err(SyncError::InvalidProfile(
+                "Profile name cannot be empty".into(),
+            ));
+        }
+        profile.routes = validate_routes(&profile.routes)?;
+        profile.rules = profile.rules.validate()?;
+
+        if profile.id.trim().is_empty() {
+            profile.id = generate_profile_id();
+        }
+
+        let mut profiles = self.load()?;
+        check_profile(&profile, &profiles)?;
+        match profiles
+            .iter_mut()
+            .find(|existing| existing.id == profile.id)
+        {
+            Some(existing) => *existing = profile.clone(),
+            None => profiles.push(profile.clone()),
+        }
+        self.file.write(&profiles)?;
+
+        Ok(profile)
+    }
+
+    pub(crate) fn delete(&self, id: &str) -> Result<bool, SyncError> {
+        let mut profiles = self.load()?;
+        let before = profiles.len();
+        profiles.retain(|profile| profile.id != id);
+        if profiles.len() == before {
+            return Ok(false);
+        }
+
+        self.file.write(&profiles)?;
+        Ok(true)
+    }
+}
+
+fn generate_profile_id() -> String {
+    format!("profile-{:x}", unix_nanos())
+}
+
+#[tauri::command]
+pub fn list_profiles(window: Window) -> Result<Vec<SyncProfile>, String> {
+    ProfileStore::open(&window)
+        .and_then(|store| store.load())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn save_profile(window: Window, profile: SyncProfile) -> Result<SyncProfile, String> {
+    ProfileStore::open(&window)
+        .and_then(|store| store.save(profile))
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn delete_profile(window: Window, id: String) -> Result<bool, String> {
+    ProfileStore::open(&window)
+        .and_then(|store| store.delete(&id))
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,202 @@
+use adb_client::{ADBDeviceExt, RemoteEntry, RemoteEntryKind};
+use serde::Serialize;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+use tauri::State;
+
+use crate::backend::ConnectedDevice;
+use crate::compare;
+use crate::manifest::RemoteManifest;
+use crate::{
+    connect_device, normalize_remote_path, remote_metadata, ActiveSync, CancelToken, FolderPair,
+    SyncError,
+};
+
+/// What [`pull_remote_path`] or a queued pull job copied from the device.
+#[derive(Debug, Clone, Serialize)]
+pub struct PullSummary {
+    remote_path: String,
+    local_path: String,
+    files_pulled: usize,
+    bytes_pulled: u64,
+    /// Files already on the computer with the device's size and mtime.
+    files_unchanged: usize,
+}
+
+/// Replaces `local_path` with the device file, keeping its mtime so the two
+/// compare equal afterwards. Written beside the target and renamed, so a
+/// failed transfer leaves the local file as it was.
+pub(crate) fn download(
+    device: &mut ConnectedDevice,
+    remote_path: &str,
+    local_path: &Path,
+    mtime: u64,
+) -> Result<(), SyncError> {
+    let mut staging_name = local_path.file_name().unwrap_or_default().to_os_string();
+    staging_name.push(".adbsync-tmp");
+    let staging = local_path.with_file_name(staging_name);
+
+    let result: Result<(), SyncError> = (|| {
+        let mut file = File::create(&staging)?;
+        device.pull(&remote_path, &mut file)?;
+        file.set_modified(UNIX_EPOCH + Duration::from_secs(mtime))?;
+        fs::rename(&staging, local_path)?;
+        Ok(())
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&staging);
+    }
+    result
+}
+
+/// Like `adb pull`, a path pulled into an existing local folder lands inside
+/// it under its own name; otherwise `local` is the new file or folder itself.
+fn target_path(local: &Path, remote_path: &str) -> PathBuf {
+    match remote_path
+        .rsplit('/')
+        .next()
+        .filter(|name| !name.is_empty())
+    {
+        Some(name) if local.is_dir() => local.join(name),
+        _ => local.to_path_buf(),
+    }
+}
+
+/// Whether `local` already holds a file of `size` bytes modified at `mtime`,
+/// as a previous pull leaves it.
+fn is_unchanged(local: &Path, size: u64, mtime: u64) -> bool {
+    fs::metadata(local)
+        .ok()
+        .filter(|metadata| metadata.is_file() && metadata.len() == size)
+        .and_then(|metadata| compare::file_modified_seconds(&metadata))
+        == Some(mtime)
+}
+
+fn local_path_arg(local_path: &str) -> Result<PathBuf, SyncError> {
+    let local = PathBuf::from(local_path.trim());
+    if local.as_os_str().is_empty() {
+        return Err(SyncError::InvalidLocalPath(
+            "Local path cannot be empty".into(),
+        ));
+    }
+    Ok(local)
+}
+
+fn pull(
+    device_path: &str,
+    local_path: &str,
+    cancel: &CancelToken,
+) -> Result<PullSummary, SyncError> {
+    let remote_path = normalize_remote_path(device_path)?;
+    let local = local_path_arg(local_path)?;
+    let target = target_path(&local, &remote_path);
+
+    let (_, mut device) = connect_device(&mut |_| {})?;
+    pull_to(&mut device, &remote_path, &target, cancel)
+}
+
+/// Copies each pair's device folder into its local folder, for a pull job
+/// queued when the device asks for its files. The folder's contents land
+/// directly in the local folder, the reverse of a sync.
+pub(crate) fn pull_pairs(
+    pairs: &[FolderPair],
+    cancel: &CancelToken,
+) -> Result<Vec<PullSummary>, SyncError> {
+    let (_, mut device) = connect_device(&mut |_| {})?;
+    pairs
+        .iter()
+        .map(|pair| {
+            let remote_path = normalize_remote_path(&pair.remote)?;
+            let local = local_path_arg(&pair.local)?;
+            pull_to(&mut device, &remote_path, &local, cancel)
+        })
+        .collect()
+}
+
+/// Copies the device file at `remote_path` to `target`, or the folder there
+/// with everything below it into `target`. Files already copied and unchanged
+/// since are not fetched again.
+fn pull_to(
+    device: &mut ConnectedDevice,
+    remote_path: &str,
+    target: &Path,
+    cancel: &CancelToken,
+) -> Result<PullSummary, SyncError> {
+    let Some(stat) = remote_metadata(device, remote_path)? else {
+        return Err(SyncError::InvalidRemotePath(format!(
+            "'{remote_path}' does not exist on the device"
+        )));
+    };
+    let remote = RemoteEntry::from_stat(remote_path, &stat);
+
+    let mut summary = PullSummary {
+        remote_path: remote_path.to_string(),
+        local_path: target.display().to_string(),
+        files_pulled: 0,
+        bytes_pulled: 0,
+        files_unchanged: 0,
+    };
+    match remote.kind {
+        RemoteEntryKind::File if is_unchanged(target, remote.size, remote.mtime) => {
+            summary.files_unchanged = 1;
+        }
+        RemoteEntryKind::File => {
+            download(device, &remote.path, target, remote.mtime)?;
+            summary.files_pulled = 1;
+            summary.bytes_pulled = remote.size;
+        }
+        RemoteEntryKind::Directory => {
+            fs::create_dir_all(target)?;
+            let manifest = RemoteManifest::scan(device, remote_path, cancel)?;
+            for (path, relative, size) in manifest.files() {
+                cancel.check()?;
+                let local_file = target.join(relative);
+                if let Some(parent) = local_file.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                let mtime = manifest
+                    .get(path)
+                    .flatten()
+                    .map(|entry| entry.mtime)
+                    .unwrap_or_default();
+                if is_unchanged(&local_file, size, mtime) {
+                    summary.files_unchanged += 1;
+                    continue;
+                }
+                download(device, path, &local_file, mtime)?;
+                summary.files_pulled += 1;
+                summary.bytes_pulled = summary.bytes_pulled.saturating_add(size);
+            }
+        }
+        _ => {
+            return Err(SyncError::InvalidRemotePath(format!(
+                "'{remote_path}' is not a regular file or folder"
+            )))
+        }
+    }
+    log::info!(
+        "pulled {} file(s), {} bytes from {remote_path} to {} ({} unchanged)",
+        summary.files_pulled,
+        summary.bytes_pulled,
+        summary.local_path,
+        summary.files_unchanged
+    );
+    Ok(summary)
+}
+
+/// Copies a device file, or a folder with everything below it, to
+/// `local_path`, keeping modification times and skipping files copied
+/// before and unchanged since. Cancelled with `cancel_sync`.
+#[tauri::command]
+pub async fn pull_remote_path(
+    active: State<'_, ActiveSync>,
+    device_path: String,
+    local_path: String,
+) -> Result<PullSummary, String> {
+    let cancel = active.begin();
+    tauri::async_runtime::spawn_blocking(move || pull(&device_path, &local_path, &cancel))
+        .await
+        .map_err(|e| format!("pull task failed: {e}"))?
+        .map_err(|e| e.to_string())
+}
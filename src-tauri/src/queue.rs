@@ -0,0 +1,317 @@
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::thread;
+use tauri::{Emitter, State, Window};
+
+use crate::pull::{pull_pairs, PullSummary};
+use crate::{
+    perform_sync_pairs, CancelToken, FolderPair, SyncError, SyncOptions, SyncPairsSummary,
+};
+
+const QUEUE_EVENT: &str = "sync-queue";
+const JOB_FINISHED_EVENT: &str = "sync-job-finished";
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Pending,
+    Running,
+}
+
+/// Which way a job copies its pairs.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    /// Push local folders to the device, as `sync_pairs` does.
+    Sync,
+    /// Copy device folders to the computer, when the device asked for it.
+    Pull,
+}
+
+/// What the frontend sees of a queued or running job.
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncJobView {
+    id: u64,
+    pairs: Vec<FolderPair>,
+    profile_id: Option<String>,
+    kind: JobKind,
+    dry_run: bool,
+    status: JobStatus,
+}
+
+#[derive(Debug, Serialize)]
+struct JobFinishedPayload {
+    id: u64,
+    summary: Option<SyncPairsSummary>,
+    /// One entry per pair, for pull jobs.
+    pulled: Option<Vec<PullSummary>>,
+    error: Option<String>,
+}
+
+struct QueuedJob {
+    view: SyncJobView,
+    options: SyncOptions,
+    window: Window,
+}
+
+#[derive(Default)]
+struct QueueState {
+    pending: VecDeque<QueuedJob>,
+    running: Option<SyncJobView>,
+    running_cancel: Option<CancelToken>,
+    next_id: u64,
+    worker_started: bool,
+}
+
+impl QueueState {
+    fn views(&self) -> Vec<SyncJobView> {
+        self.running
+            .iter()
+            .cloned()
+            .chain(self.pending.iter().map(|job| job.view.clone()))
+            .collect()
+    }
+}
+
+/// Sync jobs run one at a time, in order, on a single background worker.
+#[derive(Default, Clone)]
+pub struct JobQueue {
+    shared: Arc<(Mutex<QueueState>, Condvar)>,
+}
+
+impl JobQueue {
+    fn state(&self) -> MutexGuard<'_, QueueState> {
+        self.shared.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub(crate) fn enqueue(
+        &self,
+        window: Window,
+        pairs: Vec<FolderPair>,
+        dry_run: bool,
+        profile_id: Option<String>,
+        options: SyncOptions,
+    ) -> u64 {
+        self.push_job(window, pairs, JobKind::Sync, dry_run, profile_id, options)
+    }
+
+    /// Queues a copy of each pair's device folder into its local folder.
+    pub(crate) fn enqueue_pull(
+        &self,
+        window: Window,
+        pairs: Vec<FolderPair>,
+        profile_id: Option<String>,
+    ) -> u64 {
+        self.push_job(
+            window,
+            pairs,
+            JobKind::Pull,
+            false,
+            profile_id,
+            SyncOptions::default(),
+        )
+    }
+
+    fn push_job(
+        &self,
+        window: Window,
+        pairs: Vec<FolderPair>,
+        kind: JobKind,
+        dry_run: bool,
+        profile_id: Option<String>,
+        mut options: SyncOptions,
+    ) -> u64 {
+        options.dry_run = dry_run;
+        let mut state = self.state();
+        state.next_id += 1;
+        let id = state.next_id;
+        state.pending.push_back(QueuedJob {
+            view: SyncJobView {
+                id,
+                pairs,
+                profile_id,
+                kind,
+                dry_run,
+                status: JobStatus::Pending,
+            },
+            options,
+            window: window.clone(),
+        });
+
+        if !state.worker_started {
+            state.worker_started = true;
+            let queue = self.clone();
+            thread::spawn(move || queue.run_worker());
+        }
+
+        emit_queue(&window, &state);
+        drop(state);
+        self.shared.1.notify_one();
+        id
+    }
+
+    /// True when nothing is running or waiting to run.
+    pub(crate) fn is_idle(&self) -> bool {
+        let state = self.state();
+        state.running.is_none() && state.pending.is_empty()
+    }
+
+    /// True when a job for `profile_id` is already running or waiting.
+    pub(crate) fn has_profile(&self, profile_id: &str) -> bool {
+        self.state()
+            .views()
+            .iter()
+            .any(|job| job.profile_id.as_deref() == Some(profile_id))
+    }
+
+    fn move_job(&self, window: &Window, id: u64, position: usize) -> Result<(), SyncError> {
+        let mut state = self.state();
+        let index = pending_index(&state, id)?;
+        if let Some(job) = state.pending.remove(index) {
+            let position = position.min(state.pending.len());
+            state.pending.insert(position, job);
+        }
+        emit_queue(window, &state);
+        Ok(())
+    }
+
+    /// Drops a pending job, or asks the running one to stop at its next check.
+    fn cancel(&self, window: &Window, id: u64) -> Result<(), SyncError> {
+        let mut state = self.state();
+        if state.running.as_ref().is_some_and(|job| job.id == id) {
+            if let Some(token) = state.running_cancel.as_ref() {
+                token.cancel();
+            }
+            return Ok(());
+        }
+        let index = pending_index(&state, id)?;
+        state.pending.remove(index);
+        emit_queue(window, &state);
+        Ok(())
+    }
+
+    fn run_worker(&self) {
+        loop {
+            let job = {
+                let mut state = self.state();
+                let mut job = loop {
+                    match state.pending.pop_front() {
+                        Some(job) => break job,
+                        None => {
+                            state = self
+                                .shared
+                                .1
+                                .wait(state)
+                                .unwrap_or_else(PoisonError::into_inner)
+                        }
+                    }
+                };
+                job.view.status = JobStatus::Running;
+                state.running = Some(job.view.clone());
+                state.running_cancel = Some(job.options.cancel.clone());
+                emit_queue(&job.window, &state);
+                job
+            };
+
+            let QueuedJob {
+                view,
+                options,
+                window,
+            } = job;
+            log::info!("queue: starting {:?} job {}", view.kind, view.id);
+            let payload = run_job(&window, &view, options);
+
+            let mut state = self.state();
+            state.running = None;
+            state.running_cancel = None;
+            emit_queue(&window, &state);
+            drop(state);
+            let _ = window.emit(JOB_FINISHED_EVENT, payload);
+        }
+    }
+}
+
+/// Runs one job to completion, in the direction its kind says.
+fn run_job(window: &Window, view: &SyncJobView, options: SyncOptions) -> JobFinishedPayload {
+    let mut payload = JobFinishedPayload {
+        id: view.id,
+        summary: None,
+        pulled: None,
+        error: None,
+    };
+    let result = match view.kind {
+        JobKind::Sync => perform_sync_pairs(
+            window.clone(),
+            &view.pairs,
+            view.profile_id.clone(),
+            options,
+        )
+        .map(|(device, pairs)| {
+            payload.summary = Some(SyncPairsSummary::new(device, pairs, view.dry_run));
+        }),
+        JobKind::Pull => pull_pairs(&view.pairs, &options.cancel).map(|pulled| {
+            payload.pulled = Some(pulled);
+        }),
+    };
+    payload.error = result.err().map(|error| error.to_string());
+    payload
+}
+
+fn pending_index(state: &QueueState, id: u64) -> Result<usize, SyncError> {
+    if state.running.as_ref().is_some_and(|job| job.id == id) {
+        return Err(SyncError::JobRunning(id));
+    }
+    state
+        .pending
+        .iter()
+        .position(|job| job.view.id == id)
+        .ok_or(SyncError::JobNotFound(id))
+}
+
+fn emit_queue(window: &Window, state: &QueueState) {
+    let _ = window.emit(QUEUE_EVENT, state.views());
+}
+
+#[tauri::command]
+pub fn enqueue_sync(
+    window: Window,
+    queue: State<'_, JobQueue>,
+    pairs: Vec<FolderPair>,
+    dry_run: bool,
+    profile_id: Option<String>,
+    options: Option<SyncOptions>,
+) -> Result<u64, String> {
+    if pairs.is_empty() {
+        return Err(SyncError::Config("No folder pairs to sync".into()).to_string());
+    }
+    Ok(queue.enqueue(
+        window,
+        pairs,
+        dry_run,
+        profile_id,
+        options.unwrap_or_default(),
+    ))
+}
+
+#[tauri::command]
+pub fn list_sync_jobs(queue: State<'_, JobQueue>) -> Vec<SyncJobView> {
+    queue.state().views()
+}
+
+#[tauri::command]
+pub fn move_sync_job(
+    window: Window,
+    queue: State<'_, JobQueue>,
+    id: u64,
+    position: usize,
+) -> Result<(), String> {
+    queue
+        .move_job(&window, id, position)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn cancel_sync_job(window: Window, queue: State<'_, JobQueue>, id: u64) -> Result<(), String> {
+    queue.cancel(&window, id).map_err(|e| e.to_string())
+}
@@ -0,0 +1,70 @@
+use adb_client::{ADBDeviceExt, RebootType};
+use serde::Deserialize;
+use std::path::PathBuf;
+
+use crate::{connect_device, SyncError};
+
+/// Where [`reboot_device`] restarts the device into.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RebootTarget {
+    System,
+    Bootloader,
+    Recovery,
+    /// Recovery, waiting for an `adb sideload`.
+    Sideload,
+}
+
+impl From<RebootTarget> for RebootType {
+    fn from(target: RebootTarget) -> Self {
+        match target {
+            RebootTarget::System => RebootType::System,
+            RebootTarget::Bootloader => RebootType::Bootloader,
+            RebootTarget::Recovery => RebootType::Recovery,
+            RebootTarget::Sideload => RebootType::Sideload,
+        }
+    }
+}
+
+fn reboot(target: RebootTarget) -> Result<(), SyncError> {
+    let (_, mut device) = connect_device(&mut |_| {})?;
+    log::info!("rebooting device into {target:?}");
+    device.reboot(target.into())?;
+    Ok(())
+}
+
+/// Restarts the connected device through adbd's `reboot:` service. The device
+/// drops off USB right away, so any transfer still running on it fails.
+#[tauri::command]
+pub async fn reboot_device(target: RebootTarget) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || reboot(target))
+        .await
+        .map_err(|e| format!("reboot task failed: {e}"))?
+        .map_err(|e| e.to_string())
+}
+
+fn sideload(local_path: &str) -> Result<(), SyncError> {
+    let package = PathBuf::from(local_path.trim());
+    if !package.is_file() {
+        return Err(SyncError::InvalidLocalPath(format!(
+            "OTA package '{}' does not exist",
+            package.display()
+        )));
+    }
+    let (_, mut device) = connect_device(&mut |_| {})?;
+    log::info!("sideloading {}", package.display());
+    device.sideload(&package)?;
+    Ok(())
+}
+
+/// Sends the OTA zip at `local_path` to a device rebooted into
+/// [`RebootTarget::Sideload`] once the user picked "Apply update from ADB" in
+/// recovery. Returns once the device has the whole package; recovery installs
+/// it afterwards.
+#[tauri::command]
+pub async fn sideload_package(local_path: String) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || sideload(&local_path))
+        .await
+        .map_err(|e| format!("sideload task failed: {e}"))?
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,158 @@
+use serde::Serialize;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::Window;
+
+use crate::backend::ConnectedDevice;
+use crate::{
+    connect_device, detect_android_device, emit_connection_stage, CancelToken, ConnectionStage,
+    SyncError,
+};
+
+/// Attempts per file, counting the first, before a transport error fails the run.
+const MAX_ATTEMPTS_PER_FILE: usize = 3;
+/// Transport errors in one run after which transfers are degraded.
+const DEGRADE_AFTER_ERRORS: usize = 2;
+/// Chunk size used once degraded. Smaller bulk transfers get through flaky
+/// cables and hubs that stall on full-size ones.
+const DEGRADED_CHUNK_SIZE: usize = 16 * 1024;
+/// Times one run waits for a lost device to come back before giving up.
+const MAX_RESUMES: usize = 3;
+/// How long a lost device has to reappear.
+const RESUME_TIMEOUT: Duration = Duration::from_secs(60);
+const RESUME_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How a run slowed down its transfers after repeated transport errors.
+#[derive(Debug, Clone, Serialize)]
+pub struct TransportDegradation {
+    /// Transport errors seen before and after degrading.
+    transport_errors: usize,
+    chunk_size: usize,
+    /// The file whose upload triggered the degradation.
+    triggered_by: String,
+}
+
+/// Retries uploads that fail mid-file with a transport error by reconnecting
+/// on the same USB port, and lowers the push chunk size for the rest of the run once
+/// errors repeat. Once a chunk size is set, pushes send one chunk per message,
+/// so it bounds the size of every bulk transfer.
+///
+/// When the device drops off altogether, [`TransportRecovery::resume`] waits
+/// for the same device, by USB serial, to return and connects to it again.
+pub(crate) struct TransportRecovery {
+    errors: usize,
+    degradation: Option<TransportDegradation>,
+    serial: Option<String>,
+    resumes: usize,
+}
+
+impl TransportRecovery {
+    /// Recovery for the device with this USB serial. Without a serial a lost
+    /// device can't be told apart from another one, so runs aren't resumed.
+    pub(crate) fn new(serial: Option<String>) -> Self {
+        Self {
+            errors: 0,
+            degradation: None,
+            serial,
+            resumes: 0,
+        }
+    }
+
+    /// Runs `push`, reconnecting and running it again from the start after a
+    /// transport error.
+    pub(crate) fn push(
+        &mut self,
+        device: &mut ConnectedDevice,
+        remote_path: &str,
+        mut push: impl FnMut(&mut ConnectedDevice) -> Result<(), SyncError>,
+    ) -> Result<(), SyncError> {
+        let mut attempt = 1;
+        loop {
+            let error = match push(device) {
+                Ok(()) => return Ok(()),
+                Err(error) if is_transport_error(&error) && attempt < MAX_ATTEMPTS_PER_FILE => {
+                    error
+                }
+                Err(error) => return Err(error),
+            };
+            self.errors += 1;
+            log::warn!("transport error pushing {remote_path} (attempt {attempt}): {error}");
+
+            // A stalled endpoint can outlast a plain reconnect.
+            device.reconnect_with_reset(matches!(error, SyncError::UsbPipe))?;
+            if self.errors >= DEGRADE_AFTER_ERRORS && self.degradation.is_none() {
+                log::warn!(
+                    "{} transport errors; pushing in {DEGRADED_CHUNK_SIZE}-byte chunks for the rest of the run",
+                    self.errors
+                );
+                self.degradation = Some(TransportDegradation {
+                    transport_errors: self.errors,
+                    chunk_size: DEGRADED_CHUNK_SIZE,
+                    triggered_by: remote_path.to_string(),
+                });
+            }
+            if let Some(degradation) = self.degradation.as_mut() {
+                degradation.transport_errors = self.errors;
+                device.set_sync_chunk_size(degradation.chunk_size);
+            }
+            attempt += 1;
+        }
+    }
+
+    /// Whether the run can wait for the device to come back after `error`.
+    pub(crate) fn can_resume(&self, error: &SyncError) -> bool {
+        self.serial.is_some()
+            && self.resumes < MAX_RESUMES
+            && (is_transport_error(error) || matches!(error, SyncError::UsbDisconnected))
+    }
+
+    /// Waits for the device to reappear and authenticates again, replacing
+    /// `device` with the new connection. A device that comes back on the same
+    /// port is reconnected in place; one that re-enumerated is found by its
+    /// serial, so a different phone plugged in meanwhile is never synced to.
+    pub(crate) fn resume(
+        &mut self,
+        device: &mut ConnectedDevice,
+        window: &Window,
+        cancel: &CancelToken,
+    ) -> Result<(), SyncError> {
+        self.resumes += 1;
+        emit_connection_stage(window, ConnectionStage::Reconnecting);
+        let deadline = Instant::now() + RESUME_TIMEOUT;
+        let mut reconnected = device.reconnect().is_ok();
+        while !reconnected {
+            cancel.check()?;
+            if Instant::now() >= deadline {
+                return Err(SyncError::UsbDisconnected);
+            }
+            thread::sleep(RESUME_POLL_INTERVAL);
+            if !detect_android_device().is_ok_and(|info| info.serial == self.serial) {
+                continue;
+            }
+            match connect_device(&mut |stage| emit_connection_stage(window, stage)) {
+                Ok((info, connected)) if info.serial == self.serial => {
+                    *device = connected;
+                    reconnected = true;
+                }
+                Ok(_) => {}
+                Err(error) => log::debug!("reconnecting to the lost device failed: {error}"),
+            }
+        }
+
+        if let Some(degradation) = &self.degradation {
+            device.set_sync_chunk_size(degradation.chunk_size);
+        }
+        log::info!("device is back (resume {} of {MAX_RESUMES})", self.resumes);
+        Ok(())
+    }
+
+    /// The degradation applied so far, if errors repeated.
+    pub(crate) fn degradation(&self) -> Option<TransportDegradation> {
+        self.degradation.clone()
+    }
+}
+
+/// Errors that lose the current transfer while the device stays attached.
+fn is_transport_error(error: &SyncError) -> bool {
+    matches!(error, SyncError::UsbTimeout | SyncError::UsbPipe)
+}
@@ -0,0 +1,41 @@
+use adb_client::ADBDeviceExt;
+
+use crate::{connect_device, SyncError};
+
+fn set_root(enabled: bool) -> Result<(), SyncError> {
+    let (_, mut device) = connect_device(&mut |_| {})?;
+    if enabled {
+        log::info!("restarting adbd as root");
+        device.root()?;
+    } else {
+        log::info!("restarting adbd without root");
+        device.unroot()?;
+    }
+    Ok(())
+}
+
+/// Restarts adbd on the connected device with or without root permissions,
+/// so syncs can reach protected paths such as `/data`. Only works on
+/// userdebug and eng builds; returns once the device is reachable again.
+#[tauri::command]
+pub async fn set_device_root(enabled: bool) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || set_root(enabled))
+        .await
+        .map_err(|e| format!("adbd restart task failed: {e}"))?
+        .map_err(|e| e.to_string())
+}
+
+fn remount() -> Result<String, SyncError> {
+    let (_, mut device) = connect_device(&mut |_| {})?;
+    Ok(device.remount()?)
+}
+
+/// Remounts the system partitions of a rooted device read-write, returning
+/// what the device printed, which may ask for a reboot first.
+#[tauri::command]
+pub async fn remount_device() -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(remount)
+        .await
+        .map_err(|e| format!("remount task failed: {e}"))?
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,141 @@
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use crate::{build_remote_path, normalize_remote_path, SyncError};
+
+/// Sends every file whose extension is listed in `extensions` to `remote_path`
+/// instead of the job's remote root. Paths below the local root are preserved.
+/// An entry may list several extensions separated by `|`, as in `"jpg|png"`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtensionRoute {
+    pub extensions: Vec<String>,
+    pub remote_path: String,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RouteTable {
+    List(Vec<ExtensionRoute>),
+    Map(BTreeMap<String, String>),
+}
+
+/// Reads routes written either as a list of [`ExtensionRoute`] or as a map
+/// from `|`-separated extensions to a remote path.
+pub(crate) fn deserialize_routes<'de, D>(deserializer: D) -> Result<Vec<ExtensionRoute>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(match RouteTable::deserialize(deserializer)? {
+        RouteTable::List(routes) => routes,
+        RouteTable::Map(map) => map
+            .into_iter()
+            .map(|(extensions, remote_path)| ExtensionRoute {
+                extensions: vec![extensions],
+                remote_path,
+            })
+            .collect(),
+    })
+}
+
+/// Resolves the remote location of local entries, honouring extension routes.
+#[derive(Debug)]
+pub(crate) struct RemoteDestinations {
+    root: String,
+    routes: HashMap<String, String>,
+}
+
+impl RemoteDestinations {
+    pub(crate) fn new(root: String, routes: &[ExtensionRoute]) -> Result<Self, SyncError> {
+        let mut table = HashMap::new();
+        for route in validate_routes(routes)? {
+            for extension in route.extensions {
+                table.insert(extension, route.remote_path.clone());
+            }
+        }
+
+        Ok(Self {
+            root,
+            routes: table,
+        })
+    }
+
+    pub(crate) fn root(&self) -> &str {
+        &self.root
+    }
+
+    /// Whether any extension is sent somewhere other than the root.
+    pub(crate) fn is_routed(&self) -> bool {
+        !self.routes.is_empty()
+    }
+
+    pub(crate) fn file(&self, relative: &Path) -> String {
+        build_remote_path(self.root_for(relative), relative)
+    }
+
+    pub(crate) fn file_parent(&self, relative: &Path) -> String {
+        let root = self.root_for(relative);
+        relative
+            .parent()
+            .map(|parent| build_remote_path(root, parent))
+            .unwrap_or_else(|| root.to_string())
+    }
+
+    fn root_for(&self, relative: &Path) -> &str {
+        relative
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+            .and_then(|ext| self.routes.get(&ext))
+            .map(String::as_str)
+            .unwrap_or(&self.root)
+    }
+}
+
+/// Normalizes extensions and destinations, rejecting empty or overlapping rules.
+pub(crate) fn validate_routes(routes: &[ExtensionRoute]) -> Result<Vec<ExtensionRoute>, SyncError> {
+    let mut seen: HashMap<String, String> = HashMap::new();
+    let mut validated = Vec::with_capacity(routes.len());
+
+    for route in routes {
+        let remote_path = normalize_remote_path(&route.remote_path)?;
+        let mut extensions = Vec::new();
+
+        for raw in route.extensions.iter().flat_map(|raw| raw.split('|')) {
+            let extension = normalize_extension(raw);
+            if extension.is_empty() {
+                return Err(SyncError::InvalidProfile(format!(
+                    "Route to '{remote_path}' contains an empty extension"
+                )));
+            }
+
+            if let Some(existing) = seen.get(&extension) {
+                return Err(SyncError::InvalidProfile(format!(
+                    "Extension '.{extension}' is routed to both '{existing}' and '{remote_path}'"
+                )));
+            }
+
+            seen.insert(extension.clone(), remote_path.clone());
+            extensions.push(extension);
+        }
+
+        if extensions.is_empty() {
+            return Err(SyncError::InvalidProfile(format!(
+                "Route to '{remote_path}' does not list any extension"
+            )));
+        }
+
+        validated.push(ExtensionRoute {
+            extensions,
+            remote_path,
+        });
+    }
+
+    Ok(validated)
+}
+
+fn normalize_extension(raw: &str) -> String {
+    raw.trim()
+        .trim_start_matches('*')
+        .trim_start_matches('.')
+        .to_ascii_lowercase()
+}
@@ -0,0 +1,79 @@
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::SyncError;
+
+/// Whether dot-files and dot-directories under the local root are synced.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HiddenFiles {
+    #[default]
+    Skip,
+    Include,
+}
+
+/// Which local entries a saved profile leaves out of its runs. Stored with the
+/// profile so every caller syncing it applies the same filters.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SkipRules {
+    /// Glob patterns matched against paths relative to the local root, e.g.
+    /// `*.tmp` or `cache/**`. `*` also matches across `/`.
+    pub exclude: Vec<String>,
+    pub hidden: HiddenFiles,
+    pub max_file_size: Option<u64>,
+    pub max_depth: Option<usize>,
+    /// Leave out files modified fewer than this many days ago.
+    pub min_age: Option<u64>,
+    /// Leave out files modified more than this many days ago.
+    pub max_age: Option<u64>,
+}
+
+impl SkipRules {
+    pub(crate) fn validate(mut self) -> Result<Self, SyncError> {
+        self.exclude = self
+            .exclude
+            .into_iter()
+            .map(|pattern| pattern.trim().to_string())
+            .filter(|pattern| !pattern.is_empty())
+            .collect();
+        ExcludeSet::new(&self.exclude)?;
+        if let (Some(min), Some(max)) = (self.min_age, self.max_age) {
+            if min > max {
+                return Err(SyncError::InvalidProfile(format!(
+                    "Minimum age ({min} days) is greater than maximum age ({max} days)"
+                )));
+            }
+        }
+        Ok(self)
+    }
+}
+
+/// Compiled exclude patterns.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ExcludeSet(Option<GlobSet>);
+
+impl ExcludeSet {
+    pub(crate) fn new(patterns: &[String]) -> Result<Self, SyncError> {
+        if patterns.is_empty() {
+            return Ok(Self(None));
+        }
+
+        let mut builder = GlobSetBuilder::new();
+        for pattern in patterns {
+            let glob = Glob::new(pattern).map_err(|e| {
+                SyncError::InvalidProfile(format!("Invalid exclude pattern '{pattern}': {e}"))
+            })?;
+            builder.add(glob);
+        }
+        let set = builder
+            .build()
+            .map_err(|e| SyncError::InvalidProfile(e.to_string()))?;
+        Ok(Self(Some(set)))
+    }
+
+    pub(crate) fn matches(&self, relative: &Path) -> bool {
+        self.0.as_ref().is_some_and(|set| set.is_match(relative))
+    }
+}
@@ -0,0 +1,198 @@
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+use tauri::{Emitter, State, Window};
+
+use crate::clock::{unix_nanos, unix_seconds};
+use crate::config_store::ConfigFile;
+use crate::profiles::ProfileStore;
+use crate::queue::JobQueue;
+use crate::{detect_android_device, FolderPair, SyncError, SyncOptions};
+
+const SCHEDULES_FILE: &str = "schedules.json";
+const SCHEDULE_EVENT: &str = "schedule-fired";
+const TICK: Duration = Duration::from_secs(30);
+
+/// Runs a saved profile every `interval_minutes` while the app is open and a
+/// device is attached.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncSchedule {
+    #[serde(default)]
+    pub id: String,
+    pub profile_id: String,
+    pub interval_minutes: u64,
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
+    /// Unix seconds of the last time the schedule queued a job.
+    #[serde(default)]
+    pub last_run: Option<u64>,
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+impl SyncSchedule {
+    fn is_due(&self, now: u64) -> bool {
+        self.enabled
+            && self.last_run.is_none_or(|last| {
+                now.saturating_sub(last) >= self.interval_minutes.saturating_mul(60)
+            })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ScheduleFiredPayload {
+    schedule_id: String,
+    profile_id: String,
+    job_id: Option<u64>,
+    error: Option<String>,
+}
+
+pub(crate) struct ScheduleStore {
+    file: ConfigFile<Vec<SyncSchedule>>,
+}
+
+impl ScheduleStore {
+    pub(crate) fn open(window: &Window) -> Result<Self, SyncError> {
+        Ok(Self {
+            file: ConfigFile::open(window, SCHEDULES_FILE)?,
+        })
+    }
+
+    pub(crate) fn load(&self) -> Result<Vec<SyncSchedule>, SyncError> {
+        self.file.load()
+    }
+
+    pub(crate) fn save(&self, mut schedule: SyncSchedule) -> Result<SyncSchedule, SyncError> {
+        if schedule.interval_minutes == 0 {
+            return Err(SyncError::Config(
+                "Schedule interval must be at least one minute".into(),
+            ));
+        }
+
+        if schedule.id.trim().is_empty() {
+            schedule.id = format!("schedule-{:x}", unix_nanos());
+        }
+
+        let mut schedules = self.load()?;
+        match schedules
+            .iter_mut()
+            .find(|existing| existing.id == schedule.id)
+        {
+            Some(existing) => *existing = schedule.clone(),
+            None => schedules.push(schedule.clone()),
+        }
+        self.file.write(&schedules)?;
+
+        Ok(schedule)
+    }
+
+    pub(crate) fn delete(&self, id: &str) -> Result<bool, SyncError> {
+        let mut schedules = self.load()?;
+        let before = schedules.len();
+        schedules.retain(|schedule| schedule.id != id);
+        if schedules.len() == before {
+            return Ok(false);
+        }
+
+        self.file.write(&schedules)?;
+        Ok(true)
+    }
+}
+
+/// Set once the scheduler thread is running so repeated starts are no-ops.
+#[derive(Default)]
+pub struct Scheduler {
+    started: AtomicBool,
+}
+
+fn run_scheduler(window: Window, queue: JobQueue) {
+    loop {
+        let _ = fire_due_schedules(&window, &queue);
+        thread::sleep(TICK);
+    }
+}
+
+fn fire_due_schedules(window: &Window, queue: &JobQueue) -> Result<(), SyncError> {
+    let store = ScheduleStore::open(window)?;
+    let mut schedules = store.load()?;
+    let now = unix_seconds();
+    let due: Vec<usize> = (0..schedules.len())
+        .filter(|&index| schedules[index].is_due(now))
+        .collect();
+    if due.is_empty() || detect_android_device().is_err() {
+        return Ok(());
+    }
+
+    let profiles = ProfileStore::open(window)?;
+    for index in due {
+        let schedule = &mut schedules[index];
+        if queue.has_profile(&schedule.profile_id) {
+            continue;
+        }
+
+        let result = profiles.get(&schedule.profile_id).map(|profile| {
+            let pairs = vec![FolderPair {
+                local: profile.local_path,
+                remote: profile.device_path,
+            }];
+            queue.enqueue(
+                window.clone(),
+                pairs,
+                false,
+                Some(profile.id),
+                SyncOptions::default(),
+            )
+        });
+        schedule.last_run = Some(now);
+        let _ = window.emit(
+            SCHEDULE_EVENT,
+            ScheduleFiredPayload {
+                schedule_id: schedule.id.clone(),
+                profile_id: schedule.profile_id.clone(),
+                job_id: result.as_ref().ok().copied(),
+                error: result.err().map(|error| error.to_string()),
+            },
+        );
+    }
+
+    store.file.write(&schedules)
+}
+
+#[tauri::command]
+pub fn list_schedules(window: Window) -> Result<Vec<SyncSchedule>, String> {
+    ScheduleStore::open(&window)
+        .and_then(|store| store.load())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn save_schedule(window: Window, schedule: SyncSchedule) -> Result<SyncSchedule, String> {
+    ScheduleStore::open(&window)
+        .and_then(|store| store.save(schedule))
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn delete_schedule(window: Window, id: String) -> Result<bool, String> {
+    ScheduleStore::open(&window)
+        .and_then(|store| store.delete(&id))
+        .map_err(|e| e.to_string())
+}
+
+/// Starts checking schedules in the background. Called once by the frontend
+/// after its main window has loaded; later calls do nothing.
+#[tauri::command]
+pub fn start_scheduler(
+    window: Window,
+    queue: State<'_, JobQueue>,
+    scheduler: State<'_, Scheduler>,
+) {
+    if scheduler.started.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let queue = queue.inner().clone();
+    thread::spawn(move || run_scheduler(window, queue));
+}
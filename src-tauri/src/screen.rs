@@ -0,0 +1,53 @@
+use adb_client::ADBDeviceExt;
+use std::fs::{self, File};
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::ipc::Response;
+
+use crate::{connect_device, SyncError};
+
+fn capture() -> Result<Vec<u8>, SyncError> {
+    let (_, mut device) = connect_device(&mut |_| {})?;
+    Ok(device.screencap()?)
+}
+
+/// Captures the connected device's screen as a PNG, so the user can check
+/// the right phone is plugged in. The image is returned as raw bytes rather
+/// than a JSON array of numbers.
+#[tauri::command]
+pub async fn capture_screen() -> Result<Response, String> {
+    tauri::async_runtime::spawn_blocking(capture)
+        .await
+        .map_err(|e| format!("screen capture task failed: {e}"))?
+        .map(Response::new)
+        .map_err(|e| e.to_string())
+}
+
+fn record(local_path: &str, seconds: u64) -> Result<u64, SyncError> {
+    let target = PathBuf::from(local_path.trim());
+    if target.as_os_str().is_empty() {
+        return Err(SyncError::InvalidLocalPath(
+            "Local path cannot be empty".into(),
+        ));
+    }
+    let (_, mut device) = connect_device(&mut |_| {})?;
+    let result: Result<u64, SyncError> = (|| {
+        let mut file = File::create(&target)?;
+        device.screenrecord(Duration::from_secs(seconds), &mut file)?;
+        Ok(file.metadata()?.len())
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&target);
+    }
+    result
+}
+
+/// Records the device screen for `seconds` (at most three minutes) into an
+/// MP4 at `local_path`, returning its size in bytes.
+#[tauri::command]
+pub async fn record_screen(local_path: String, seconds: u64) -> Result<u64, String> {
+    tauri::async_runtime::spawn_blocking(move || record(&local_path, seconds))
+        .await
+        .map_err(|e| format!("screen recording task failed: {e}"))?
+        .map_err(|e| e.to_string())
+}
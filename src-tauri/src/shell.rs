@@ -0,0 +1,93 @@
+use adb_client::ADBDeviceExt;
+use serde::Serialize;
+use std::ops::ControlFlow;
+use tauri::{Emitter, State, Window};
+
+use crate::{connect_device, ActiveSync, CancelToken, SyncError};
+
+const SHELL_OUTPUT_EVENT: &str = "device-shell-output";
+
+#[derive(Debug, Clone, Serialize)]
+struct ShellOutputPayload<'a> {
+    output: &'a str,
+}
+
+/// What [`stream_shell_command`] received before the command ended.
+#[derive(Debug, Default, Serialize)]
+pub struct ShellStreamSummary {
+    bytes: u64,
+    events: usize,
+}
+
+/// Decodes output chunks as UTF-8, holding back a character cut at the end
+/// of one chunk until the next completes it.
+#[derive(Default)]
+struct Utf8Stream {
+    partial: Vec<u8>,
+}
+
+impl Utf8Stream {
+    fn push(&mut self, chunk: &[u8]) -> String {
+        self.partial.extend_from_slice(chunk);
+        let complete = match std::str::from_utf8(&self.partial) {
+            Ok(_) => self.partial.len(),
+            Err(error) if error.error_len().is_none() => error.valid_up_to(),
+            Err(_) => self.partial.len(),
+        };
+        let text = String::from_utf8_lossy(&self.partial[..complete]).into_owned();
+        self.partial.drain(..complete);
+        text
+    }
+
+    fn finish(self) -> String {
+        String::from_utf8_lossy(&self.partial).into_owned()
+    }
+}
+
+fn stream(
+    window: &Window,
+    command: &str,
+    cancel: &CancelToken,
+) -> Result<ShellStreamSummary, SyncError> {
+    let command = command.trim();
+    if command.is_empty() {
+        return Err(SyncError::Config("No shell command given".into()));
+    }
+
+    let (_, mut device) = connect_device(&mut |_| {})?;
+    let mut summary = ShellStreamSummary::default();
+    let mut decoder = Utf8Stream::default();
+    let emit = |text: &str, summary: &mut ShellStreamSummary| {
+        if !text.is_empty() {
+            let _ = window.emit(SHELL_OUTPUT_EVENT, ShellOutputPayload { output: text });
+            summary.events += 1;
+        }
+    };
+    device.shell_command_stream(&[command], &mut |chunk| {
+        if cancel.check().is_err() {
+            return ControlFlow::Break(());
+        }
+        summary.bytes = summary.bytes.saturating_add(chunk.len() as u64);
+        emit(&decoder.push(chunk), &mut summary);
+        ControlFlow::Continue(())
+    })?;
+    cancel.check()?;
+    emit(&decoder.finish(), &mut summary);
+    Ok(summary)
+}
+
+/// Runs `command` in a device shell and forwards its output to the frontend
+/// as `device-shell-output` events while it runs, rather than all at once
+/// when it ends. Cancelled with `cancel_sync`.
+#[tauri::command]
+pub async fn stream_shell_command(
+    window: Window,
+    active: State<'_, ActiveSync>,
+    command: String,
+) -> Result<ShellStreamSummary, String> {
+    let cancel = active.begin();
+    tauri::async_runtime::spawn_blocking(move || stream(&window, &command, &cancel))
+        .await
+        .map_err(|e| format!("shell task failed: {e}"))?
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,135 @@
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::Path;
+
+/// Why an entry under the local root was left out of the run.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipReason {
+    Hidden,
+    Excluded,
+    BeyondMaxDepth,
+    NameCollision,
+    Symlink,
+    BrokenSymlink,
+    /// A followed symlink leads back into a directory being synced.
+    SymlinkLoop,
+    /// Modified outside the run's `min_age`/`max_age` window.
+    OutsideAgeWindow,
+    SpecialFile,
+    MetadataUnreadable,
+    OsMetadata,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SkippedEntry {
+    path: String,
+    reason: SkipReason,
+    detail: Option<String>,
+}
+
+impl SkippedEntry {
+    pub(crate) fn new(path: &Path, reason: SkipReason, detail: Option<String>) -> Self {
+        Self {
+            path: path.display().to_string(),
+            reason,
+            detail,
+        }
+    }
+}
+
+const APPLE_SINGLE_MAGIC: [u8; 4] = [0x00, 0x05, 0x16, 0x00];
+const APPLE_DOUBLE_MAGIC: [u8; 4] = [0x00, 0x05, 0x16, 0x07];
+const DS_STORE_MAGIC: [u8; 8] = *b"\0\0\0\x01Bud1";
+
+/// Recognises macOS metadata that travels with folders copied off a Mac:
+/// AppleDouble `._*` resource forks, `.DS_Store`, `Icon\r` and `__MACOSX`.
+/// Dotted names are only matched when their header confirms the format, so an
+/// intentional `._notes` or `.DS_Store`-named text file is not mistaken for one.
+pub(crate) fn os_metadata_kind(path: &Path, metadata: &fs::Metadata) -> Option<String> {
+    let name = path.file_name()?.to_string_lossy();
+
+    if metadata.is_dir() {
+        return (name == "__MACOSX").then(|| "macOS archive metadata folder".to_string());
+    }
+    if !metadata.is_file() {
+        return None;
+    }
+    if name == "Icon\r" {
+        return Some("macOS custom folder icon".to_string());
+    }
+
+    let sniff_apple_double = name.starts_with("._");
+    let sniff_ds_store = name == ".DS_Store";
+    if !sniff_apple_double && !sniff_ds_store {
+        return None;
+    }
+
+    let mut header = [0u8; 8];
+    let mut file = File::open(path).ok()?;
+    file.read_exact(&mut header).ok()?;
+
+    if sniff_apple_double {
+        let magic: [u8; 4] = header[..4].try_into().ok()?;
+        match magic {
+            APPLE_DOUBLE_MAGIC => Some("AppleDouble resource fork".to_string()),
+            APPLE_SINGLE_MAGIC => Some("AppleSingle file".to_string()),
+            _ => None,
+        }
+    } else {
+        (header == DS_STORE_MAGIC).then(|| "Finder .DS_Store".to_string())
+    }
+}
+
+/// Explains an entry that is neither a regular file nor a directory.
+pub(crate) fn classify_unsupported(
+    path: &Path,
+    metadata: &fs::Metadata,
+) -> (SkipReason, Option<String>) {
+    let file_type = metadata.file_type();
+
+    if file_type.is_symlink() {
+        return match fs::metadata(path) {
+            Ok(_) => (SkipReason::Symlink, read_link_target(path)),
+            Err(error) => (
+                SkipReason::BrokenSymlink,
+                Some(match read_link_target(path) {
+                    Some(target) => format!("{target}: {error}"),
+                    None => error.to_string(),
+                }),
+            ),
+        };
+    }
+
+    (SkipReason::SpecialFile, special_file_kind(&file_type))
+}
+
+fn read_link_target(path: &Path) -> Option<String> {
+    fs::read_link(path)
+        .ok()
+        .map(|target| target.display().to_string())
+}
+
+#[cfg(unix)]
+fn special_file_kind(file_type: &fs::FileType) -> Option<String> {
+    use std::os::unix::fs::FileTypeExt;
+
+    let kind = if file_type.is_socket() {
+        "socket"
+    } else if file_type.is_fifo() {
+        "named pipe"
+    } else if file_type.is_block_device() {
+        "block device"
+    } else if file_type.is_char_device() {
+        "character device"
+    } else {
+        return None;
+    };
+    Some(kind.to_string())
+}
+
+#[cfg(not(unix))]
+fn special_file_kind(_file_type: &fs::FileType) -> Option<String> {
+    None
+}
@@ -0,0 +1,89 @@
+use std::collections::BTreeMap;
+
+use adb_client::{ADBDeviceExt, StorageVolume};
+
+use crate::backend::ConnectedDevice;
+use crate::{PreparedPair, SyncError};
+
+/// What the pairs of a run would write to one device filesystem.
+struct VolumeDemand {
+    volume: StorageVolume,
+    bytes: u64,
+    /// Whether every remote root on the volume is new, so that nothing there
+    /// can be skipped as unchanged.
+    all_new: bool,
+}
+
+/// Compares the bytes each pair would push against the free space of the
+/// filesystem its remote root lives on. A run that can only fail is stopped
+/// before it starts, when none of its remote roots exist yet and everything
+/// has to be uploaded. Otherwise unchanged files may be skipped, so a
+/// shortfall is only logged. Devices whose `df` can't be read are not
+/// checked.
+pub(crate) fn check_free_space(
+    device: &mut ConnectedDevice,
+    prepared: &[PreparedPair],
+) -> Result<(), SyncError> {
+    let mut demands: BTreeMap<String, VolumeDemand> = BTreeMap::new();
+    for pair in prepared {
+        let Some((volume, exists)) = volume_for(device, &pair.remote_root) else {
+            log::debug!("no filesystem found for {}", pair.remote_root);
+            continue;
+        };
+        let demand = demands
+            .entry(volume.mount_point.clone())
+            .or_insert(VolumeDemand {
+                volume,
+                bytes: 0,
+                all_new: true,
+            });
+        demand.bytes = demand.bytes.saturating_add(pair.local.bytes);
+        demand.all_new &= !exists;
+    }
+
+    for demand in demands.into_values() {
+        let VolumeDemand {
+            volume,
+            bytes,
+            all_new,
+        } = demand;
+        if bytes <= volume.free_bytes {
+            continue;
+        }
+        if all_new {
+            return Err(SyncError::InsufficientSpace {
+                mount_point: volume.mount_point,
+                needed: bytes,
+                free: volume.free_bytes,
+            });
+        }
+        log::warn!(
+            "{} has {} bytes free, the run may push up to {} bytes",
+            volume.mount_point,
+            volume.free_bytes,
+            bytes
+        );
+    }
+    Ok(())
+}
+
+/// Filesystem of `remote_root`, or of its closest existing parent when it
+/// hasn't been created yet, along with whether `remote_root` itself exists.
+fn volume_for(device: &mut ConnectedDevice, remote_root: &str) -> Option<(StorageVolume, bool)> {
+    let mut path = remote_root.trim_end_matches('/');
+    let mut exists = true;
+    loop {
+        let lookup = if path.is_empty() { "/" } else { path };
+        match device.storage_volume(lookup) {
+            Ok(Some(volume)) => return Some((volume, exists)),
+            Ok(None) if !path.is_empty() => {}
+            Ok(None) => return None,
+            Err(error) => {
+                log::debug!("df {lookup} failed: {error}");
+                return None;
+            }
+        }
+        exists = false;
+        path = &path[..path.rfind('/')?];
+    }
+}
@@ -0,0 +1,147 @@
+use serde::Serialize;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Mutex, PoisonError, RwLock};
+
+use crate::conflicts::FileConflict;
+use crate::mirror::RemoteSymlink;
+use crate::moves::MovedFile;
+use crate::skipped::{SkipReason, SkippedEntry};
+use crate::OversizedFile;
+
+/// Run-wide counters that any number of workers can update through `&SyncStats`.
+///
+/// Updates hold the shared side of `gate` while touching the atomics, and
+/// [`SyncStats::snapshot`] holds the exclusive side, so a snapshot never sees a
+/// half-applied update (e.g. a file counted without its bytes).
+#[derive(Default)]
+pub(crate) struct SyncStats {
+    gate: RwLock<()>,
+    files_synced: AtomicUsize,
+    files_deleted: AtomicUsize,
+    files_moved: AtomicUsize,
+    files_copied: AtomicUsize,
+    skipped_entries: AtomicUsize,
+    directories_created: AtomicUsize,
+    bytes_uploaded: AtomicU64,
+    details: Mutex<StatsDetails>,
+}
+
+#[derive(Default, Clone)]
+struct StatsDetails {
+    deleted_files: Vec<String>,
+    trash_path: Option<String>,
+    moved_files: Vec<MovedFile>,
+    remote_symlinks: Vec<RemoteSymlink>,
+    skipped_details: Vec<SkippedEntry>,
+    oversized_files: Vec<OversizedFile>,
+    conflicts: Vec<FileConflict>,
+}
+
+/// Point-in-time copy of [`SyncStats`], used for progress events and the summary.
+#[derive(Debug, Clone, Default, Serialize)]
+pub(crate) struct SyncStatsSnapshot {
+    pub(crate) files_synced: usize,
+    pub(crate) files_deleted: usize,
+    pub(crate) deleted_files: Vec<String>,
+    pub(crate) trash_path: Option<String>,
+    pub(crate) files_moved: usize,
+    pub(crate) moved_files: Vec<MovedFile>,
+    pub(crate) files_copied: usize,
+    pub(crate) remote_symlinks: Vec<RemoteSymlink>,
+    pub(crate) skipped_entries: usize,
+    pub(crate) skipped_details: Vec<SkippedEntry>,
+    pub(crate) directories_created: usize,
+    pub(crate) bytes_uploaded: u64,
+    pub(crate) oversized_files: Vec<OversizedFile>,
+    pub(crate) conflicts: Vec<FileConflict>,
+}
+
+impl SyncStats {
+    pub(crate) fn record_upload(&self, bytes: u64) {
+        let _update = self.gate.read().unwrap_or_else(PoisonError::into_inner);
+        self.files_synced.fetch_add(1, Ordering::Relaxed);
+        self.bytes_uploaded.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_directory_created(&self) {
+        let _update = self.gate.read().unwrap_or_else(PoisonError::into_inner);
+        self.directories_created.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_deleted(&self, remote_path: String) {
+        let _update = self.gate.read().unwrap_or_else(PoisonError::into_inner);
+        self.files_deleted.fetch_add(1, Ordering::Relaxed);
+        self.details().deleted_files.push(remote_path);
+    }
+
+    pub(crate) fn record_moved(&self, file: MovedFile) {
+        let _update = self.gate.read().unwrap_or_else(PoisonError::into_inner);
+        self.files_moved.fetch_add(1, Ordering::Relaxed);
+        self.details().moved_files.push(file);
+    }
+
+    pub(crate) fn record_copied(&self) {
+        let _update = self.gate.read().unwrap_or_else(PoisonError::into_inner);
+        self.files_copied.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_remote_symlink(&self, link: RemoteSymlink) {
+        self.details().remote_symlinks.push(link);
+    }
+
+    pub(crate) fn set_trash_path(&self, trash_path: String) {
+        self.details().trash_path = Some(trash_path);
+    }
+
+    pub(crate) fn skip(&self, path: &Path, reason: SkipReason, detail: Option<String>) {
+        log::debug!(
+            "skipped {} ({reason:?}) {}",
+            path.display(),
+            detail.as_deref().unwrap_or("")
+        );
+        let _update = self.gate.read().unwrap_or_else(PoisonError::into_inner);
+        self.skipped_entries.fetch_add(1, Ordering::Relaxed);
+        self.details()
+            .skipped_details
+            .push(SkippedEntry::new(path, reason, detail));
+    }
+
+    pub(crate) fn record_oversized(&self, file: OversizedFile) {
+        self.details().oversized_files.push(file);
+    }
+
+    pub(crate) fn record_conflict(&self, conflict: FileConflict) {
+        self.details().conflicts.push(conflict);
+    }
+
+    pub(crate) fn files_deleted(&self) -> usize {
+        self.files_deleted.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn snapshot(&self) -> SyncStatsSnapshot {
+        let _exclusive = self.gate.write().unwrap_or_else(PoisonError::into_inner);
+        let details = self.details().clone();
+
+        SyncStatsSnapshot {
+            files_synced: self.files_synced.load(Ordering::Relaxed),
+            files_deleted: self.files_deleted.load(Ordering::Relaxed),
+            deleted_files: details.deleted_files,
+            trash_path: details.trash_path,
+            files_moved: self.files_moved.load(Ordering::Relaxed),
+            moved_files: details.moved_files,
+            files_copied: self.files_copied.load(Ordering::Relaxed),
+            remote_symlinks: details.remote_symlinks,
+            skipped_entries: self.skipped_entries.load(Ordering::Relaxed),
+            skipped_details: details.skipped_details,
+            directories_created: self.directories_created.load(Ordering::Relaxed),
+            bytes_uploaded: self.bytes_uploaded.load(Ordering::Relaxed),
+            oversized_files: details.oversized_files,
+            conflicts: details.conflicts,
+        }
+    }
+
+    fn details(&self) -> std::sync::MutexGuard<'_, StatsDetails> {
+        self.details.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
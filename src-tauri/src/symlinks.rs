@@ -0,0 +1,71 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Identifies a directory independently of the path it was reached through.
+#[cfg(unix)]
+type DirectoryId = (u64, u64);
+#[cfg(not(unix))]
+type DirectoryId = std::path::PathBuf;
+
+#[cfg(unix)]
+fn directory_id(path: &Path) -> io::Result<DirectoryId> {
+    use std::os::unix::fs::MetadataExt;
+    let metadata = fs::metadata(path)?;
+    Ok((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn directory_id(path: &Path) -> io::Result<DirectoryId> {
+    fs::canonicalize(path)
+}
+
+/// Metadata of a directory entry. When following symlinks, a link is
+/// described by its target; a link that can't be resolved keeps its own
+/// metadata so it is still reported as broken.
+pub(crate) fn entry_metadata(entry: &fs::DirEntry, follow: bool) -> io::Result<fs::Metadata> {
+    let metadata = entry.metadata()?;
+    if follow && metadata.file_type().is_symlink() {
+        return Ok(fs::metadata(entry.path()).unwrap_or(metadata));
+    }
+    Ok(metadata)
+}
+
+/// The directories a walk is currently inside, by inode, so following a
+/// symlink back into one of them is caught instead of recursing forever.
+/// Without symlink following no loop is possible and nothing is tracked.
+pub(crate) struct DirectoryTrail {
+    follow: bool,
+    ids: Vec<DirectoryId>,
+}
+
+impl DirectoryTrail {
+    pub(crate) fn new(root: &Path, follow: bool) -> io::Result<Self> {
+        let ids = if follow {
+            vec![directory_id(root)?]
+        } else {
+            Vec::new()
+        };
+        Ok(Self { follow, ids })
+    }
+
+    /// Steps into `dir`, or returns `false` if it is already on the trail.
+    /// Every `true` must be matched by a [`DirectoryTrail::leave`].
+    pub(crate) fn enter(&mut self, dir: &Path) -> io::Result<bool> {
+        if !self.follow {
+            return Ok(true);
+        }
+        let id = directory_id(dir)?;
+        if self.ids.contains(&id) {
+            return Ok(false);
+        }
+        self.ids.push(id);
+        Ok(true)
+    }
+
+    pub(crate) fn leave(&mut self) {
+        if self.follow {
+            self.ids.pop();
+        }
+    }
+}
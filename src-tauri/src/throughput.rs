@@ -0,0 +1,176 @@
+use adb_client::{ADBDeviceExt, ADBTcpDevice};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{self, Cursor};
+use std::net::{IpAddr, SocketAddr};
+use std::time::Instant;
+use tauri::Window;
+
+use crate::clock::unix_seconds;
+use crate::config_store::ConfigFile;
+use crate::known_devices::{parse_address, ADB_TCP_PORT};
+use crate::{connect_device, run_shell, SyncError};
+
+const THROUGHPUT_FILE: &str = "throughput.json";
+const PROBE_REMOTE: &str = "/data/local/tmp/.adbsync-probe";
+const PROBE_BYTES: usize = 8 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Transport {
+    Usb,
+    Wifi,
+}
+
+/// One timed push of the probe payload over a transport.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransportMeasurement {
+    bytes_per_second: Option<f64>,
+    error: Option<String>,
+}
+
+impl TransportMeasurement {
+    fn from_result(result: Result<f64, SyncError>) -> Self {
+        match result {
+            Ok(rate) => Self {
+                bytes_per_second: Some(rate),
+                error: None,
+            },
+            Err(error) => Self {
+                bytes_per_second: None,
+                error: Some(error.to_string()),
+            },
+        }
+    }
+}
+
+/// Latest probe result for a device, keyed by its serial number.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThroughputRecord {
+    serial: String,
+    measured_at: u64,
+    usb: TransportMeasurement,
+    wifi: TransportMeasurement,
+    preferred: Option<Transport>,
+}
+
+fn records_file(
+    window: &Window,
+) -> Result<ConfigFile<HashMap<String, ThroughputRecord>>, SyncError> {
+    ConfigFile::open(window, THROUGHPUT_FILE)
+}
+
+fn load_records(window: &Window) -> Result<HashMap<String, ThroughputRecord>, SyncError> {
+    records_file(window)?.load()
+}
+
+fn save_record(window: &Window, record: &ThroughputRecord) -> Result<(), SyncError> {
+    let file = records_file(window)?;
+    let mut records = file.load()?;
+    records.insert(record.serial.clone(), record.clone());
+    file.write(&records)
+}
+
+/// The most recently measured USB rate of any device, for estimates made
+/// before a device is connected.
+pub(crate) fn latest_usb_rate(window: &Window) -> Option<f64> {
+    load_records(window)
+        .ok()?
+        .into_values()
+        .filter_map(|record| Some((record.measured_at, record.usb.bytes_per_second?)))
+        .max_by_key(|(measured_at, _)| *measured_at)
+        .map(|(_, rate)| rate)
+}
+
+/// Pushes a fixed pseudo-random payload (so transport compression can't flatter
+/// the result) and returns bytes per second.
+fn measure<D: ADBDeviceExt>(device: &mut D) -> Result<f64, SyncError> {
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    let payload: Vec<u8> = (0..PROBE_BYTES)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        })
+        .collect();
+
+    let started = Instant::now();
+    device.push(&mut Cursor::new(payload), &PROBE_REMOTE)?;
+    let elapsed = started.elapsed().as_secs_f64();
+
+    let mut sink = io::sink();
+    device.shell_command(&["rm", "-f", PROBE_REMOTE], &mut sink)?;
+
+    Ok(PROBE_BYTES as f64 / elapsed.max(f64::EPSILON))
+}
+
+fn wifi_address(output: &str) -> Option<IpAddr> {
+    output
+        .split_whitespace()
+        .skip_while(|token| *token != "inet")
+        .nth(1)
+        .and_then(|cidr| cidr.split('/').next())
+        .and_then(|address| address.parse().ok())
+}
+
+fn probe(window: &Window, wifi: Option<String>) -> Result<ThroughputRecord, SyncError> {
+    let (_, mut usb_device) = connect_device(&mut |_| {})?;
+    let serial = usb_device.get_prop("ro.serialno")?.unwrap_or_default();
+    let wifi = match wifi {
+        Some(address) => parse_address(&address),
+        None => run_shell(
+            &mut usb_device,
+            &["ip", "-f", "inet", "addr", "show", "wlan0"],
+        )
+        .ok()
+        .and_then(|output| wifi_address(&output))
+        .map(|ip| SocketAddr::new(ip, ADB_TCP_PORT))
+        .ok_or_else(|| SyncError::Config("The device has no Wi-Fi address".into())),
+    };
+
+    let usb = TransportMeasurement::from_result(measure(&mut usb_device));
+    drop(usb_device);
+
+    let wifi = TransportMeasurement::from_result(wifi.and_then(|address| {
+        let mut device = ADBTcpDevice::new(address)?;
+        measure(&mut device)
+    }));
+
+    let preferred = match (usb.bytes_per_second, wifi.bytes_per_second) {
+        (Some(usb), Some(wifi)) if wifi > usb => Some(Transport::Wifi),
+        (Some(_), _) => Some(Transport::Usb),
+        (None, Some(_)) => Some(Transport::Wifi),
+        (None, None) => None,
+    };
+
+    let record = ThroughputRecord {
+        serial,
+        measured_at: unix_seconds(),
+        usb,
+        wifi,
+        preferred,
+    };
+    save_record(window, &record)?;
+    Ok(record)
+}
+
+/// Measures push throughput over USB and, when adbd is listening on the
+/// network, over Wi-Fi. `wifi_address` overrides the address read from `wlan0`.
+#[tauri::command]
+pub async fn probe_transports(
+    window: Window,
+    wifi_address: Option<String>,
+) -> Result<ThroughputRecord, String> {
+    tauri::async_runtime::spawn_blocking(move || probe(&window, wifi_address))
+        .await
+        .map_err(|e| format!("throughput probe failed: {e}"))?
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn list_throughput(window: Window) -> Result<Vec<ThroughputRecord>, String> {
+    load_records(&window)
+        .map(|records| records.into_values().collect())
+        .map_err(|e| e.to_string())
+}
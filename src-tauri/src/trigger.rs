@@ -0,0 +1,141 @@
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+use std::time::Duration;
+use tauri::{Emitter, State, Window};
+
+use crate::placeholders::PathPlaceholders;
+use crate::profiles::ProfileStore;
+use crate::queue::{JobKind, JobQueue};
+use crate::{
+    connect_device, remote_metadata, run_shell, shell_quote, FolderPair, SyncError, SyncOptions,
+};
+
+/// File the phone creates (by hand, from a shortcut or a Tasker-style task) to
+/// ask the host to pull the watched profile's device folder, e.g. to grab new
+/// photos. It is removed once the job is queued.
+pub(crate) const TRIGGER_MARKER: &str = "/sdcard/.adbsync/sync-request";
+
+const TRIGGER_EVENT: &str = "sync-triggered";
+const DEFAULT_POLL_SECONDS: u64 = 15;
+
+#[derive(Debug, Clone, Serialize)]
+struct TriggerPayload {
+    profile_id: String,
+    kind: JobKind,
+    job_id: Option<u64>,
+    error: Option<String>,
+}
+
+/// The one device-trigger watcher that may be running at a time.
+#[derive(Default)]
+pub struct DeviceTrigger {
+    stop: Mutex<Option<Arc<AtomicBool>>>,
+}
+
+impl DeviceTrigger {
+    fn replace(&self, flag: Option<Arc<AtomicBool>>) {
+        let mut stop = self.stop.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(previous) = stop.take() {
+            previous.store(true, Ordering::Relaxed);
+        }
+        *stop = flag;
+    }
+}
+
+/// Polls the device for [`TRIGGER_MARKER`] and queues a pull of `profile_id`
+/// each time it appears. The device is only held for the duration of each
+/// check so queued jobs can connect in between.
+fn watch(
+    window: Window,
+    queue: JobQueue,
+    profile_id: String,
+    interval: Duration,
+    stop: &AtomicBool,
+) {
+    while !stop.load(Ordering::Relaxed) {
+        // Leave the device alone while queued work may be using it.
+        if queue.is_idle() && take_marker().unwrap_or(false) {
+            queue_triggered(&window, &queue, &profile_id, JobKind::Pull);
+        }
+        thread::sleep(interval);
+    }
+}
+
+fn take_marker() -> Result<bool, SyncError> {
+    let (_, mut device) = connect_device(&mut |_| {})?;
+    if remote_metadata(&mut device, TRIGGER_MARKER)?.is_none() {
+        return Ok(false);
+    }
+    run_shell(&mut device, &["rm", "-f", &shell_quote(TRIGGER_MARKER)])?;
+    Ok(true)
+}
+
+/// Queues `profile_id` on behalf of the device, as a sync or a pull, and
+/// reports it with a `sync-triggered` event.
+pub(crate) fn queue_triggered(window: &Window, queue: &JobQueue, profile_id: &str, kind: JobKind) {
+    let result = enqueue_profile(window, queue, profile_id, kind);
+    let _ = window.emit(
+        TRIGGER_EVENT,
+        TriggerPayload {
+            profile_id: profile_id.to_string(),
+            kind,
+            job_id: result.as_ref().ok().copied(),
+            error: result.err().map(|error| error.to_string()),
+        },
+    );
+}
+
+fn enqueue_profile(
+    window: &Window,
+    queue: &JobQueue,
+    profile_id: &str,
+    kind: JobKind,
+) -> Result<u64, SyncError> {
+    let profile = ProfileStore::open(window)?.get(profile_id)?;
+    let pairs = vec![FolderPair {
+        local: profile.local_path,
+        remote: profile.device_path,
+    }];
+    Ok(match kind {
+        JobKind::Sync => queue.enqueue(
+            window.clone(),
+            pairs,
+            false,
+            Some(profile.id),
+            SyncOptions::default(),
+        ),
+        // Syncs expand placeholders when they start; a pull has to here.
+        JobKind::Pull => {
+            let pairs = PathPlaceholders::for_run(Some(&profile.name)).expand_pairs(&pairs)?;
+            queue.enqueue_pull(window.clone(), pairs, Some(profile.id))
+        }
+    })
+}
+
+#[tauri::command]
+pub fn start_device_trigger(
+    window: Window,
+    queue: State<'_, JobQueue>,
+    trigger: State<'_, DeviceTrigger>,
+    profile_id: String,
+    interval_seconds: Option<u64>,
+) -> Result<(), String> {
+    ProfileStore::open(&window)
+        .and_then(|store| store.get(&profile_id))
+        .map_err(|e| e.to_string())?;
+
+    let interval = Duration::from_secs(interval_seconds.unwrap_or(DEFAULT_POLL_SECONDS).max(1));
+    let stop = Arc::new(AtomicBool::new(false));
+    trigger.replace(Some(stop.clone()));
+
+    let queue = queue.inner().clone();
+    thread::spawn(move || watch(window, queue, profile_id, interval, &stop));
+    Ok(())
+}
+
+#[tauri::command]
+pub fn stop_device_trigger(trigger: State<'_, DeviceTrigger>) {
+    trigger.replace(None);
+}
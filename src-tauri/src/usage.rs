@@ -0,0 +1,107 @@
+use serde::Serialize;
+
+use crate::{connect_device, normalize_remote_path, run_shell, shell_quote, SyncError};
+
+const DEFAULT_DEPTH: usize = 3;
+const MAX_DEPTH: usize = 8;
+
+/// One directory in the remote usage tree. `bytes` includes everything below
+/// it, even levels past the requested depth.
+#[derive(Debug, Serialize)]
+pub struct DiskUsageNode {
+    name: String,
+    path: String,
+    bytes: u64,
+    children: Vec<DiskUsageNode>,
+}
+
+impl DiskUsageNode {
+    fn new(path: &str, bytes: u64) -> Self {
+        Self {
+            name: path.rsplit('/').next().unwrap_or(path).to_string(),
+            path: path.to_string(),
+            bytes,
+            children: Vec::new(),
+        }
+    }
+
+    fn insert(&mut self, segments: &[&str], bytes: u64) {
+        let Some((first, rest)) = segments.split_first() else {
+            self.bytes = bytes;
+            return;
+        };
+        let child_path = format!("{}/{first}", self.path.trim_end_matches('/'));
+        let index = match self
+            .children
+            .iter()
+            .position(|child| child.path == child_path)
+        {
+            Some(index) => index,
+            None => {
+                self.children.push(DiskUsageNode::new(&child_path, 0));
+                self.children.len() - 1
+            }
+        };
+        self.children[index].insert(rest, bytes);
+    }
+
+    fn sort(&mut self) {
+        self.children.sort_by(|a, b| b.bytes.cmp(&a.bytes));
+        self.children.iter_mut().for_each(DiskUsageNode::sort);
+    }
+}
+
+fn collect_disk_usage(remote_root: &str, depth: usize) -> Result<DiskUsageNode, SyncError> {
+    let (_, mut device) = connect_device(&mut |_| {})?;
+    let depth = depth.to_string();
+    let output = run_shell(
+        &mut device,
+        &[
+            "du",
+            "-k",
+            "-d",
+            &depth,
+            &shell_quote(remote_root),
+            "2>/dev/null",
+        ],
+    )?;
+
+    let prefix = format!("{}/", remote_root.trim_end_matches('/'));
+    let mut root = DiskUsageNode::new(remote_root, 0);
+    for line in output.lines() {
+        let Some((kilobytes, path)) = line.split_once('\t') else {
+            continue;
+        };
+        let Ok(kilobytes) = kilobytes.trim().parse::<u64>() else {
+            continue;
+        };
+        let bytes = kilobytes.saturating_mul(1024);
+        let path = path.trim_end();
+        if path.trim_end_matches('/') == remote_root.trim_end_matches('/') {
+            root.bytes = bytes;
+        } else if let Some(relative) = path.strip_prefix(&prefix) {
+            let segments: Vec<&str> = relative.split('/').collect();
+            root.insert(&segments, bytes);
+        }
+    }
+
+    root.sort();
+    Ok(root)
+}
+
+/// Sizes of the directories under `device_path`, `max_depth` levels deep
+/// (default 3), largest first, for rendering a treemap.
+#[tauri::command]
+pub async fn remote_disk_usage(
+    device_path: String,
+    max_depth: Option<usize>,
+) -> Result<DiskUsageNode, String> {
+    let depth = max_depth.unwrap_or(DEFAULT_DEPTH).clamp(1, MAX_DEPTH);
+    tauri::async_runtime::spawn_blocking(move || {
+        let remote_root = normalize_remote_path(&device_path)?;
+        collect_disk_usage(&remote_root, depth)
+    })
+    .await
+    .map_err(|e| format!("disk usage task failed: {e}"))?
+    .map_err(|e| e.to_string())
+}